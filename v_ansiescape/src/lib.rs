@@ -0,0 +1,239 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_ansiescape::escape;
+//!
+//! assert_eq!(escape("a\x1b[2Jb\r").to_string(), "a\\x1b[2Jb\\x0d");
+//! ```
+//!
+//! Untrusted text written to a terminal can move the cursor, clear the screen
+//! or change the title with its control sequences. `escape` writes every
+//! control character as `\xXX`, so the sequences are shown and never run:
+//! the C0 ones but tab and newline, `DEL` and the C1 ones, `U+0080` to
+//! `U+009F`, as some terminals take `U+009B` as the start of a sequence too.
+//!
+//! `caret` writes them in caret notation as `cat -v`, as in `^[` and `M-^[`,
+//! and `keep_sgr` keeps the SGR sequences, `ESC [ ... m`, which only change the
+//! colors and the style of the text:
+//!
+//! ```rust
+//! use v_ansiescape::escape;
+//!
+//! let s = "\x1b[1;31merror\x1b[0m\x1b[2J";
+//! assert_eq!(escape(s).keep_sgr().to_string(), "\x1b[1;31merror\x1b[0m\\x1b[2J");
+//! assert_eq!(escape("\x1b\x7f\u{9b}").caret().to_string(), "^[^?M-^[");
+//! ```
+//!
+//! The first bytes of the non-ascii characters are looked for with the ascii
+//! control characters, so the ascii runs between them are skipped with the
+//! simd loops of the escapers.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter, Write};
+
+// Every character is only looked for, `write_escaped` writes them
+macro_rules! build_controls {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            controls,
+            "0x00-0x08 || 0x0B-0x1F || 0x7F || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_ansiescape_simd, v_ansiescape_avx))] {
+        build_controls!(simd = true, avx = true);
+    } else if #[cfg(all(v_ansiescape_simd, v_ansiescape_sse))] {
+        build_controls!(simd = true, avx = false);
+    } else {
+        build_controls!(simd = false);
+    }
+}
+
+/// Length of the SGR sequence at the start of `b`, `ESC [` and the parameters
+/// ended by `m`
+#[inline]
+fn sgr_len(b: &[u8]) -> Option<usize> {
+    let params = b
+        .get(2..)?
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b';' || **b == b':')
+        .count();
+    match (b.get(1), b.get(2 + params)) {
+        (Some(b'['), Some(b'm')) => Some(params + 3),
+        _ => None,
+    }
+}
+
+/// Write control character `c`, a C0 or C1 one or `DEL`
+#[inline]
+fn write_control(c: u8, caret: bool, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+    if !caret {
+        return write!(out, "\\x{:02x}", c);
+    }
+    let (meta, c) = if c >= 0x80 { ("M-", c - 0x80) } else { ("", c) };
+    // The control characters are their letter xor 0x40, `DEL` is `^?`
+    write!(out, "{}^{}", meta, char::from(c ^ 0x40))
+}
+
+fn write_escaped(
+    s: &str,
+    caret: bool,
+    keep_sgr: bool,
+    out: &mut (impl Write + ?Sized),
+) -> fmt::Result {
+    v_escape::scan::escape_found(s, out, controls::find_first_escape_bytes, |s, i, out| {
+        let c = v_escape::scan::char_at(s, i);
+        let end = i + c.len_utf8();
+        match c {
+            '\u{1b}' if keep_sgr => {
+                if let Some(len) = sgr_len(&s.as_bytes()[i..]) {
+                    out.write_str(&s[i..i + len])?;
+                    return Ok(i + len);
+                }
+                write_control(0x1B, caret, out)?
+            }
+            '\0'..='\u{9f}' => write_control(c as u8, caret, out)?,
+            _ => out.write_str(&s[i..end])?,
+        }
+        Ok(end)
+    })
+}
+
+/// Escaped `s` of [`escape`]
+pub struct AnsiEscape<'a> {
+    s: &'a str,
+    caret: bool,
+    keep_sgr: bool,
+}
+
+impl<'a> AnsiEscape<'a> {
+    /// Write the control characters in caret notation, as in `^[`
+    #[inline]
+    pub fn caret(self) -> Self {
+        AnsiEscape {
+            caret: true,
+            ..self
+        }
+    }
+
+    /// Keep the SGR sequences, escaping everything else
+    #[inline]
+    pub fn keep_sgr(self) -> Self {
+        AnsiEscape {
+            keep_sgr: true,
+            ..self
+        }
+    }
+}
+
+impl<'a> From<&'a str> for AnsiEscape<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        escape(s)
+    }
+}
+
+impl<'a> Display for AnsiEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        v_escape::pad::pad(fmt, |fmt| {
+            write_escaped(self.s, self.caret, self.keep_sgr, fmt)
+        })
+    }
+}
+
+/// Escape the control characters of `s` as `\xXX`
+#[inline]
+pub fn escape(s: &str) -> AnsiEscape<'_> {
+    AnsiEscape {
+        s,
+        caret: false,
+        keep_sgr: false,
+    }
+}
+
+/// Escaper of `escape`, to choose it at runtime
+pub static ESCAPER: &dyn v_escape::Escaper =
+    &v_escape::FnEscaper(|s, out| write_escaped(s, false, false, out));
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("ansi", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"ansi"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, ESCAPER};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a\tb\nc é ^[").to_string(), "a\tb\nc é ^[");
+        assert_eq!(
+            escape("\0\u{7}\u{8}\u{b}\r\u{1b}\u{7f}\u{80}\u{9b}\u{9f}\u{a0}").to_string(),
+            "\\x00\\x07\\x08\\x0b\\x0d\\x1b\\x7f\\x80\\x9b\\x9f\u{a0}"
+        );
+        assert_eq!(format!("{:>6}", escape("\u{1b}")), "  \\x1b");
+    }
+
+    #[test]
+    fn test_caret() {
+        assert_eq!(
+            escape("\0\u{1}\r\u{1b}\u{1c}\u{1f}\u{7f}")
+                .caret()
+                .to_string(),
+            "^@^A^M^[^\\^_^?"
+        );
+        assert_eq!(escape("\u{80}\u{9b}").caret().to_string(), "M-^@M-^[");
+    }
+
+    #[test]
+    fn test_keep_sgr() {
+        for sgr in [
+            "\u{1b}[m",
+            "\u{1b}[0m",
+            "\u{1b}[38;5;196m",
+            "\u{1b}[38:2:1:2:3m",
+        ] {
+            assert_eq!(escape(sgr).keep_sgr().to_string(), sgr);
+        }
+        for other in [
+            "\u{1b}",
+            "\u{1b}[",
+            "\u{1b}[2J",
+            "\u{1b}[1;2",
+            "\u{1b}]0;t\u{7}",
+            "\u{1b}[?25l",
+        ] {
+            let escaped = escape(other).to_string();
+            assert_eq!(escape(other).keep_sgr().to_string(), escaped);
+        }
+        assert_eq!(
+            escape("\u{1b}[31m\u{9b}31m\u{1b}[0m")
+                .keep_sgr()
+                .caret()
+                .to_string(),
+            "\u{1b}[31mM-^[31m\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn test_escaper() {
+        let s: String = "a\u{1b}[1m\u{9b}é\r".repeat(20);
+        let mut out = String::new();
+        ESCAPER.escape_to(&s, &mut out).unwrap();
+        assert_eq!(out, escape(&s).to_string());
+        assert_eq!(ESCAPER.escaped_len(&s), out.len());
+
+        super::register();
+        assert!(v_escape::registry().get("ansi").is_some());
+    }
+}
@@ -0,0 +1,35 @@
+use v_ansiescape::{escape, AnsiEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(AnsiEscape::from("\x1b").to_string(), "\\x1b");
+
+    let all: String = (0..0xA1u32)
+        .filter_map(char::from_u32)
+        .chain("é😀".chars())
+        .collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\t' | '\n' => c.to_string(),
+                c if c.is_control() => format!("\\x{:02x}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        let escaped = escape(&s).to_string();
+        assert_eq!(escaped, expected, "{}", len);
+        assert!(!escaped
+            .chars()
+            .any(|c| c.is_control() && c != '\t' && c != '\n'));
+
+        let caret = escape(&s).caret().to_string();
+        assert!(!caret
+            .chars()
+            .any(|c| c.is_control() && c != '\t' && c != '\n'));
+    }
+
+    let colored = "\x1b[32mok\x1b[0m ".repeat(20);
+    assert_eq!(escape(&colored).keep_sgr().to_string(), colored);
+}
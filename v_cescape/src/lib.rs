@@ -0,0 +1,115 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_cescape::escape;
+//!
+//! assert_eq!(escape("say \"hi\"??!\n").to_string(), "say \\\"hi\\\"\\?\\?!\\n");
+//! ```
+//!
+//! `escape` is for the text between the `"` of a string literal of generated
+//! C or C++ source. `"` and `\` are escaped with a `\`, the control characters
+//! with their short escapes, `\a`, `\b`, `\t`, `\n`, `\v`, `\f` and `\r`, or
+//! else in octal as `\033`. Octal escapes end after their three digits, unlike
+//! the hexadecimal ones, which would take the digits after them too.
+//!
+//! Every `?` is written as `\?`, so the text never makes a trigraph such as
+//! `??/`, which is a `\` for the compilers that still read them.
+//!
+//! Non-ascii characters are written as their utf-8 bytes, for a source in
+//! utf-8. `escape_ascii` writes them in octal too, for a source which has to
+//! be ascii, the string being the same utf-8 bytes:
+//!
+//! ```rust
+//! use v_cescape::escape_ascii;
+//!
+//! assert_eq!(escape_ascii("é").to_string(), "\\303\\251");
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            CEscape,
+            "0x00-0x06->\\{:03o} || 0x07->\\a || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+             0x0B->\\v || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\{:03o} || 0x22->\\\" || \
+             0x3F->\\? || 0x5C->\\\\ || 0x7F->\\177",
+            $($t)*
+        );
+        v_escape::new!(
+            CAsciiEscape,
+            "0x00-0x06->\\{:03o} || 0x07->\\a || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+             0x0B->\\v || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\{:03o} || 0x22->\\\" || \
+             0x3F->\\? || 0x5C->\\\\ || 0x7F-0xFF->\\{:03o}",
+            module = ascii,
+            $($t)*
+        );
+
+        /// Escape `s` writing its non-ascii characters in octal too
+        #[inline]
+        pub fn escape_ascii(s: &str) -> ascii::CAsciiEscape<'_> {
+            ascii::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_cescape_simd, v_cescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_cescape_simd, v_cescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("c", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"c"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_ascii};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a'b é").to_string(), "a'b é");
+        assert_eq!(
+            escape("\0\u{7}\u{8}\t\n\u{b}\u{c}\r\u{1b}\u{7f}").to_string(),
+            "\\000\\a\\b\\t\\n\\v\\f\\r\\033\\177"
+        );
+        assert_eq!(escape("\"\\??=").to_string(), "\\\"\\\\\\?\\?=");
+        assert_eq!(escape("\u{1}2").to_string(), "\\0012");
+        assert_eq!(format!("{:>5}", escape("\n")), "   \\n");
+    }
+
+    #[test]
+    fn test_escape_ascii() {
+        assert_eq!(
+            escape_ascii("a\"é\u{7f}").to_string(),
+            "a\\\"\\303\\251\\177"
+        );
+        assert_eq!(escape_ascii("😀").to_string(), "\\360\\237\\230\\200");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("c").unwrap();
+        escaper.escape_to("a\"", &mut out).unwrap();
+        assert_eq!(out, "a\\\"");
+    }
+}
@@ -0,0 +1,42 @@
+use v_cescape::{escape, escape_ascii, CEscape};
+
+fn escape_char(c: char, ascii: bool) -> String {
+    match c {
+        '\u{7}' => "\\a".into(),
+        '\u{8}' => "\\b".into(),
+        '\t' => "\\t".into(),
+        '\n' => "\\n".into(),
+        '\u{b}' => "\\v".into(),
+        '\u{c}' => "\\f".into(),
+        '\r' => "\\r".into(),
+        '"' | '?' | '\\' => format!("\\{}", c),
+        c if c.is_ascii_control() => format!("\\{:03o}", c as u32),
+        c if ascii && !c.is_ascii() => {
+            let mut buf = [0; 4];
+            c.encode_utf8(&mut buf)
+                .bytes()
+                .map(|b| format!("\\{:03o}", b))
+                .collect()
+        }
+        c => c.to_string(),
+    }
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(CEscape::from("a\nb").to_string(), "a\\nb");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s.chars().map(|c| escape_char(c, false)).collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+
+        let expected: String = s.chars().map(|c| escape_char(c, true)).collect();
+        let escaped = escape_ascii(&s).to_string();
+        assert_eq!(escaped, expected, "{}", len);
+        assert!(escaped
+            .bytes()
+            .all(|b| b.is_ascii() && !b.is_ascii_control()));
+    }
+}
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use v_cssescape::{CSSEscape, b_escape};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = CSSEscape::new(data).to_string();
+    b_escape(data, &mut bytes::BytesMut::with_capacity(0));
+    // fuzzed code goes here
+});
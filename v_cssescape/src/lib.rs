@@ -0,0 +1,171 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_cssescape::{escape, escape_ident};
+//!
+//! assert_eq!(escape_ident("1st.item").to_string(), "\\31 st\\.item");
+//! assert_eq!(escape("say \"hi\"\n").to_string(), "say \\\"hi\\\"\\a ");
+//! ```
+//!
+//! Both escapers follow the serialization of CSSOM. `escape_ident` is
+//! `CSS.escape` of the browsers, for an identifier such as a class name in a
+//! selector: every ascii character but `-`, `_` and the alphanumerics is
+//! escaped with a `\`, and a digit at the start, as it would begin a number.
+//!
+//! `escape` is for the text between the `"` of a string. Both escape the
+//! control characters as hexadecimal escapes ended by a space, as in `\a `,
+//! and write `NUL` as `U+FFFD`.
+//!
+//! A style attribute of html is escaped for html too, after this escaping.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter};
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            CSSEscape,
+            "0x00->\u{FFFD} || 0x01-0x1F->\"\\\\{:x} \" || 0x22->\\\" || 0x5C->\\\\ || \
+             0x7F->\"\\\\7f \"",
+            $($t)*
+        );
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_cssescape_simd, v_cssescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_cssescape_simd, v_cssescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+#[allow(dead_code)]
+mod ident {
+    // An identifier after its first characters, which are written by
+    // `CSSIdentEscape`
+    macro_rules! build_ident {
+        ($($t:tt)*) => {
+            v_escape::new!(
+                Ident,
+                "0x00->\u{FFFD} || 0x01-0x1F->\"\\\\{:x} \" || 0x20->\"\\\\ \" || 0x21->\\! || \
+                 0x22->\\\" || 0x23->\\# || 0x24->\\$ || 0x25->\\% || 0x26->\\& || 0x27->\\' || \
+                 0x28->\\( || 0x29->\\) || 0x2A->\\* || 0x2B->\\+ || 0x2C->\\, || 0x2E->\\. || \
+                 0x2F->\\/ || 0x3A->\\: || 0x3B->\\; || 0x3C->\\< || 0x3D->\\= || 0x3E->\\> || \
+                 0x3F->\\? || 0x40->\\@ || 0x5B->\\[ || 0x5C->\\\\ || 0x5D->\\] || 0x5E->\\^ || \
+                 0x60->\\` || 0x7B->\\{ || 0x7C->\\| || 0x7D->\\} || 0x7E->\\~ || \
+                 0x7F->\"\\\\7f \"",
+                $($t)*
+            );
+        };
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(v_cssescape_simd, v_cssescape_avx))] {
+            build_ident!(simd = true, avx = true);
+        } else if #[cfg(all(v_cssescape_simd, v_cssescape_sse))] {
+            build_ident!(simd = true, avx = false);
+        } else {
+            build_ident!(simd = false);
+        }
+    }
+}
+
+/// Escaped `s` of [`escape_ident`]
+pub struct CSSIdentEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> CSSIdentEscape<'a> {
+    #[inline]
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        let s = self.s;
+        let b = s.as_bytes();
+        // A digit can't start an identifier, nor follow its leading `-`
+        let rest = match b {
+            [b'-'] => return fmt.write_str("\\-"),
+            [b'-', d, ..] if d.is_ascii_digit() => {
+                write!(fmt, "-\\{:x} ", d)?;
+                &s[2..]
+            }
+            [d, ..] if d.is_ascii_digit() => {
+                write!(fmt, "\\{:x} ", d)?;
+                &s[1..]
+            }
+            _ => s,
+        };
+        Display::fmt(&ident::escape(rest), fmt)
+    }
+}
+
+impl<'a> Display for CSSIdentEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Escape `s` as an identifier, as `CSS.escape`
+#[inline]
+pub fn escape_ident(s: &str) -> CSSIdentEscape<'_> {
+    CSSIdentEscape { s }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("css", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"css"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_ident};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a'b<c>").to_string(), "a'b<c>");
+        assert_eq!(
+            escape("\0\u{1}\u{1f}\u{7f}").to_string(),
+            "\u{FFFD}\\1 \\1f \\7f "
+        );
+        assert_eq!(escape("\"\\").to_string(), "\\\"\\\\");
+    }
+
+    #[test]
+    fn test_escape_ident() {
+        assert_eq!(escape_ident("").to_string(), "");
+        assert_eq!(escape_ident("-").to_string(), "\\-");
+        assert_eq!(escape_ident("--").to_string(), "--");
+        assert_eq!(escape_ident("-1a").to_string(), "-\\31 a");
+        assert_eq!(escape_ident("0").to_string(), "\\30 ");
+        assert_eq!(escape_ident("a0-_é😀").to_string(), "a0-_é😀");
+        assert_eq!(escape_ident("a b#c").to_string(), "a\\ b\\#c");
+        assert_eq!(escape_ident("\0\u{7f}").to_string(), "\u{FFFD}\\7f ");
+        assert_eq!(format!("{:>5}", escape_ident("-")), "   \\-");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("css").unwrap();
+        escaper.escape_to("a\"", &mut out).unwrap();
+        assert_eq!(out, "a\\\"");
+    }
+}
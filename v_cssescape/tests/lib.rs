@@ -0,0 +1,50 @@
+use v_cssescape::{escape, escape_ident, CSSEscape};
+
+/// `CSS.escape` as CSSOM writes it, a character at a time
+fn css_escape(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '\0' => out.push('\u{FFFD}'),
+            '\u{1}'..='\u{1f}' | '\u{7f}' => out += &format!("\\{:x} ", c as u32),
+            '0'..='9' if i == 0 || (i == 1 && chars[0] == '-') => {
+                out += &format!("\\{:x} ", c as u32)
+            }
+            '-' if i == 0 && chars.len() == 1 => out += "\\-",
+            c if !c.is_ascii() || c == '-' || c == '_' || c.is_ascii_alphanumeric() => out.push(c),
+            c => {
+                out.push('\\');
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(CSSEscape::from("a\nb").to_string(), "a\\a b");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [0, 1, 2, 15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        for skip in [0, 16, 45] {
+            let s: String = all.chars().cycle().skip(skip).take(len).collect();
+            assert_eq!(escape_ident(&s).to_string(), css_escape(&s), "{:?}", s);
+
+            let string: String = s
+                .chars()
+                .map(|c| match c {
+                    '\0' => "\u{FFFD}".into(),
+                    '\u{1}'..='\u{1f}' | '\u{7f}' => format!("\\{:x} ", c as u32),
+                    '"' | '\\' => format!("\\{}", c),
+                    c => c.to_string(),
+                })
+                .collect();
+            assert_eq!(escape(&s).to_string(), string, "{:?}", s);
+        }
+    }
+    for s in ["-", "-0", "0-", "--0", "9a"] {
+        assert_eq!(escape_ident(s).to_string(), css_escape(s), "{:?}", s);
+    }
+}
@@ -0,0 +1,182 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_csvescape::quote_field;
+//!
+//! assert_eq!(quote_field("plain").to_string(), "plain");
+//! assert_eq!(quote_field("say \"hi\"").to_string(), "\"say \"\"hi\"\"\"");
+//! assert_eq!(quote_field("a,b").to_string(), "\"a,b\"");
+//! ```
+//!
+//! The fields are written as RFC 4180 says, between quotes only when they
+//! have a `"`, a `,`, a carriage return or a newline, with every `"` doubled.
+//! The first of those is found with the simd loops of the escapers, the field
+//! is written as it is up to it and escaped from there, so it is scanned once.
+//!
+//! With the `alloc` feature, `write_row` writes a whole record to a `String`:
+//!
+//! ```rust
+//! let mut buf = String::new();
+//! v_csvescape::write_row(&["id", "name, full"], &mut buf);
+//! v_csvescape::write_row(&["1", "Ann \"A\" Lee"], &mut buf);
+//! assert_eq!(buf, "id,\"name, full\"\r\n1,\"Ann \"\"A\"\" Lee\"\r\n");
+//! ```
+//!
+//! `escape` only doubles the `"`, for fields written between quotes that are
+//! already there.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter};
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        // A bare `""` quote would be an empty string literal
+        v_escape::new!(CSVEscape, r#"34->"\"\"""#, $($t)*);
+    };
+}
+
+// The bytes a field is quoted for, only found, never escaped
+macro_rules! build_special {
+    ($($t:tt)*) => {
+        v_escape::finder!(special, "0x0A || 0x0D || 0x22 || 0x2C", $($t)*);
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_csvescape_simd, v_csvescape_avx))] {
+        build!(simd = true, avx = true);
+        build_special!(simd = true, avx = true);
+    } else if #[cfg(all(v_csvescape_simd, v_csvescape_sse))] {
+        build!(simd = true, avx = false);
+        build_special!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+        build_special!(simd = false);
+    }
+}
+
+/// Whether `s` has to be quoted as a field, it has a `"`, a `,`, a carriage
+/// return or a newline
+#[inline]
+pub fn needs_quoting(s: &str) -> bool {
+    special::find_first_escape(s).is_some()
+}
+
+/// `s` quoted of [`quote_field`], as it is or between quotes
+pub struct CSVField<'a> {
+    s: &'a str,
+}
+
+impl<'a> CSVField<'a> {
+    #[inline]
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        match special::find_first_escape(self.s) {
+            Some(i) => {
+                // No `"` before the first special byte, only the rest is escaped
+                fmt.write_str("\"")?;
+                fmt.write_str(&self.s[..i])?;
+                Display::fmt(&escape(&self.s[i..]), fmt)?;
+                fmt.write_str("\"")
+            }
+            None => fmt.write_str(self.s),
+        }
+    }
+}
+
+impl<'a> Display for CSVField<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Quote `s` as a field, only when it `needs_quoting`
+#[inline]
+pub fn quote_field(s: &str) -> CSVField<'_> {
+    CSVField { s }
+}
+
+/// Append the record of `fields` to `buf`, separated by `,` and ended by
+/// `\r\n`, every field quoted as [`quote_field`]
+#[cfg(feature = "alloc")]
+pub fn write_row<S: AsRef<str>>(fields: &[S], buf: &mut v_escape::writer::String) {
+    buf.reserve(fields.iter().map(|f| f.as_ref().len() + 1).sum::<usize>() + 1);
+    for (n, field) in fields.iter().enumerate() {
+        if n != 0 {
+            buf.push(',');
+        }
+        let s = field.as_ref();
+        match special::find_first_escape(s) {
+            Some(i) => {
+                buf.push('"');
+                buf.push_str(&s[..i]);
+                escape_into(&s[i..], buf);
+                buf.push('"');
+            }
+            None => buf.push_str(s),
+        }
+    }
+    buf.push_str("\r\n");
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("csv", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"csv"`, it doubles the `"`
+/// of a field between quotes as `escape`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{needs_quoting, quote_field};
+
+    #[test]
+    fn test_quote_field() {
+        assert!(!needs_quoting(""));
+        assert_eq!(quote_field("").to_string(), "");
+        assert_eq!(quote_field("a b;c\t").to_string(), "a b;c\t");
+        for c in ["\"", ",", "\r", "\n"] {
+            assert!(needs_quoting(c));
+            let s = format!("{}{}é", "a".repeat(40), c);
+            let expected = format!("\"{}\"", s.replace('"', "\"\""));
+            assert_eq!(quote_field(&s).to_string(), expected);
+        }
+        assert_eq!(quote_field("a,\"b\"").to_string(), "\"a,\"\"b\"\"\"");
+        assert_eq!(format!("{:>5}", quote_field("a,b")), "\"a,b\"");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_write_row() {
+        let mut buf = String::new();
+        super::write_row::<&str>(&[], &mut buf);
+        assert_eq!(buf, "\r\n");
+
+        buf.clear();
+        let fields = vec![String::new(), "x".into(), "\"".into(), "a\r\nb".into()];
+        super::write_row(&fields, &mut buf);
+        assert_eq!(buf, ",x,\"\"\"\",\"a\r\nb\"\r\n");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("csv").unwrap();
+        escaper.escape_to("a\"b", &mut out).unwrap();
+        assert_eq!(out, "a\"\"b");
+    }
+}
@@ -0,0 +1,61 @@
+use v_csvescape::{escape, quote_field, write_row, CSVEscape};
+
+/// Fields of the records of `csv`, as a RFC 4180 parser reads them
+fn parse(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut chars = csv.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (_, '"') => quoted = !quoted,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {
+                chars.next();
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (_, c) => field.push(c),
+        }
+    }
+    rows
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(CSVEscape::from("").to_string(), "");
+    assert_eq!(escape("a,b\n").to_string(), "a,b\n");
+    assert_eq!(escape("\"\"").to_string(), "\"\"\"\"");
+    assert_eq!(escape(&"\"a".repeat(64)).to_string(), "\"\"a".repeat(64));
+}
+
+#[test]
+fn test_rows() {
+    let rows: Vec<Vec<String>> = (0..50)
+        .map(|n| {
+            (0..4)
+                .map(|i| "a\",\r\nbé c".chars().cycle().skip(i).take(n * i).collect())
+                .collect()
+        })
+        .collect();
+
+    let mut buf = String::new();
+    for row in &rows {
+        write_row(row, &mut buf);
+    }
+    assert_eq!(parse(&buf), rows);
+
+    let written: String = rows
+        .iter()
+        .map(|row| {
+            let fields: Vec<_> = row.iter().map(|f| quote_field(f).to_string()).collect();
+            fields.join(",") + "\r\n"
+        })
+        .collect();
+    assert_eq!(written, buf);
+}
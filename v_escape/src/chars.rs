@@ -2,7 +2,7 @@
 #[doc(hidden)]
 macro_rules! escape_char {
     ($($t:tt)+) => {
-        pub fn escape_char(c: char, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        pub fn escape_char(c: char, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
             if c.is_ascii() {
                 macro_rules! _inside {
                     (impl one $byte:ident, $quote:ident) => {
@@ -21,25 +21,59 @@ macro_rules! escape_char {
                 _inside!(impl $($t)+);
             }
 
-            use std::fmt::Write;
+            use ::core::fmt::Write;
             fmt.write_char(c)
         }
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! escape_byte {
+    (one $byte:ident, $quote:ident) => {
+        #[inline]
+        pub fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+            if b == $byte {
+                ::core::option::Option::Some($quote)
+            } else {
+                ::core::option::Option::None
+            }
+        }
+
+        $crate::escape_byte!(quote_at);
+    };
+    ($T:ident, $Q:ident, $Q_LEN:ident) => {
+        #[inline]
+        pub fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+            $Q.get($T[b as usize] as usize).copied()
+        }
+
+        $crate::escape_byte!(quote_at);
+    };
+    (quote_at) => {
+        /// Quote and length of the escape starting `rest`, escapes of single
+        /// bytes have length one
+        #[inline]
+        pub fn quote_at(rest: &[u8]) -> ::core::option::Option<(&'static str, usize)> {
+            $crate::triggers::quote_at(rest, &[], escape_byte)
+        }
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! escape_char_ptr {
     ($($t:tt)+) => {
-        pub unsafe fn f_escape_char(c: char, buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
+        pub fn f_escape_char(c: char, buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
             let len = c.len_utf8();
+            unsafe {
             if len == 1 {
                 macro_rules! _inside {
                     (impl one $byte:ident, $quote:ident) => {
                         if $byte == c as u8 {
                             let mut buf_cur = 0;
                             $crate::write_ptr!(buf_cur, buf, ($quote.as_bytes() as *const _ as *const u8), $quote.len());
-                            return Some(buf_cur);
+                            return ::core::option::Option::Some(buf_cur);
                         }
                     };
                     (impl $T:ident, $Q:ident, $Q_LEN:ident) => {
@@ -48,7 +82,7 @@ macro_rules! escape_char_ptr {
                             let mut buf_cur = 0;
                             let quote = $Q[c];
                             $crate::write_ptr!(buf_cur, buf, (quote.as_bytes() as *const _ as *const u8), quote.len());
-                            return Some(buf_cur);
+                            return ::core::option::Option::Some(buf_cur);
                         }
                     };
                 }
@@ -56,16 +90,17 @@ macro_rules! escape_char_ptr {
                 _inside!(impl $($t)+);
                 // Ascii length is one byte
                 if 0 < buf.len() {
-                    *buf.as_mut_ptr() = std::mem::MaybeUninit::new(c as u8);
-                    Some(1)
+                    *buf.as_mut_ptr() = ::core::mem::MaybeUninit::new(c as u8);
+                    ::core::option::Option::Some(1)
                 } else {
-                    None
+                    ::core::option::Option::None
                 }
             } else if len < buf.len() {
                 // safety, encode_utf8 not read
-                Some(c.encode_utf8(std::mem::transmute(buf)).len())
+                ::core::option::Option::Some(c.encode_utf8(::core::mem::transmute(buf)).len())
             } else {
-                None
+                ::core::option::Option::None
+            }
             }
         }
     };
@@ -75,9 +110,10 @@ macro_rules! escape_char_ptr {
 #[doc(hidden)]
 macro_rules! escape_char_bytes {
     ($($t:tt)+) => {
-        pub unsafe fn b_escape_char<B: $crate::Buffer>(c: char, buf: &mut B) {
+        pub fn b_escape_char<B: $crate::Buffer>(c: char, buf: &mut B) {
             let len = c.len_utf8();
             buf.reserve(len);
+            unsafe {
             if len == 1 {
                 macro_rules! _inside {
                     (impl one $byte:ident, $quote:ident) => {
@@ -98,9 +134,10 @@ macro_rules! escape_char_bytes {
                 _inside!(impl $($t)+);
                 *buf.buf_ptr() = c as u8;
             } else {
-                c.encode_utf8(std::slice::from_raw_parts_mut(buf.buf_ptr(), len));
+                c.encode_utf8(::core::slice::from_raw_parts_mut(buf.buf_ptr(), len));
             }
             buf.advance(len);
+            }
         }
     };
 }
@@ -0,0 +1,384 @@
+//! Escapers built at runtime, from pairs only known then
+//!
+//! A `DynEscaper` uses the vector loops of the `new!` escapers, with the
+//! intervals and characters to look for in memory instead of constants. They
+//! are chosen like the generator does, consecutive characters are compared as
+//! an interval and the others on their own, and past 12 compares the closest
+//! intervals are merged and a false positive is checked against the quotes
+
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::{fmt, mem::MaybeUninit};
+
+use crate::{
+    ranges::{self, Escapes, Ranges},
+    Backend, BufferTooSmall, Escaper,
+};
+
+/// Compares in the loop, an interval needs an addition and a compare and a
+/// character only a compare
+const MAX_COMPARES: usize = 12;
+
+/// Bytes of the multi-byte characters of utf-8, 0x80-0xBF and 0xC2-0xF4
+const UTF8_BYTES: usize = 0x40 + 0x33;
+
+/// Error of `EscaperBuilder::build` for pairs that can't be escaped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The character is given more than once
+    Repeated(u8),
+    /// The character is a byte of multi-byte characters, either every one of
+    /// 0x80-0xBF and 0xC2-0xF4 is escaped or none
+    PartialUtf8(u8),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::Repeated(c) => {
+                write!(fmt, "character {:?} ({:#04x}) is repeated", c as char, c)
+            }
+            BuildError::PartialUtf8(c) => write!(
+                fmt,
+                "character {:#04x} is a byte of multi-byte characters, either every one of \
+                 0x80-0xBF and 0xC2-0xF4 is escaped or none",
+                c
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+/// Builder of a `DynEscaper` from `(character, quote)` pairs
+///
+/// ```
+/// use v_escape::dynamic::EscaperBuilder;
+///
+/// let escaper = EscaperBuilder::new()
+///     .pair(b'<', "&lt;")
+///     .pair(b'>', "&gt;")
+///     .build()
+///     .unwrap();
+/// assert_eq!(escaper.escape("<p>").to_string(), "&lt;p&gt;");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EscaperBuilder {
+    pairs: Vec<(u8, String)>,
+}
+
+impl EscaperBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        EscaperBuilder { pairs: vec![] }
+    }
+
+    /// Escape byte `c` with `quote`
+    #[inline]
+    pub fn pair(&mut self, c: u8, quote: &str) -> &mut Self {
+        self.pairs.push((c, quote.into()));
+        self
+    }
+
+    /// Escape every byte of `pairs` with its quote
+    pub fn pairs<'a, I>(&mut self, pairs: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (u8, &'a str)>,
+    {
+        for (c, quote) in pairs {
+            self.pair(c, quote);
+        }
+        self
+    }
+
+    /// Build the escaper, with the fastest loop the running cpu supports
+    pub fn build(&self) -> Result<DynEscaper, BuildError> {
+        let mut quotes: Vec<Option<Box<str>>> = vec![None; 256];
+        for (c, quote) in &self.pairs {
+            let slot = &mut quotes[*c as usize];
+            if slot.is_some() {
+                return Err(BuildError::Repeated(*c));
+            }
+            *slot = Some(quote.as_str().into());
+        }
+
+        // Escaping a byte of a multi-byte character would leave the rest of it
+        // in the escaped `str`, so these are escaped all together or not at all
+        let in_utf8 = |c: u8| matches!(c, 0x80..=0xBF | 0xC2..=0xF4);
+        let mut escaped = (0..=255u8).filter(|&c| in_utf8(c) && quotes[c as usize].is_some());
+        if let Some(c) = escaped.next() {
+            if escaped.count() + 1 != UTF8_BYTES {
+                return Err(BuildError::PartialUtf8(c));
+            }
+        }
+
+        let (intervals, chars, exact) = chain(&quotes);
+        Ok(DynEscaper {
+            quotes: quotes.into_boxed_slice(),
+            intervals: intervals.into_boxed_slice(),
+            chars: chars.into_boxed_slice(),
+            exact,
+            backend: detect(),
+        })
+    }
+}
+
+/// Intervals of consecutive escaped bytes and the bytes on their own, the
+/// neighbours with the smallest gap merged until they fit in `MAX_COMPARES`,
+/// and whether no other byte was merged in
+fn chain(quotes: &[Option<Box<str>>]) -> (Vec<[u8; 2]>, Vec<u8>, bool) {
+    let mut chain: Vec<[u8; 2]> = vec![];
+    for c in (0..=255u8).filter(|&c| quotes[c as usize].is_some()) {
+        match chain.last_mut() {
+            Some([_, r]) if *r + 1 == c => *r = c,
+            _ => chain.push([c, c]),
+        }
+    }
+    // Every byte doesn't fit in one interval
+    if chain == [[0, 255]] {
+        chain = vec![[0, 127], [128, 255]];
+    }
+
+    let compares = |chain: &[[u8; 2]]| -> usize {
+        chain.iter().map(|[l, r]| if l == r { 1 } else { 2 }).sum()
+    };
+    let mut exact = true;
+    while compares(&chain) > MAX_COMPARES {
+        let i = (0..chain.len() - 1)
+            .min_by_key(|&i| chain[i + 1][0] - chain[i][1])
+            .unwrap();
+        chain[i][1] = chain[i + 1][1];
+        chain.remove(i + 1);
+        exact = false;
+    }
+
+    let (chars, intervals): (Vec<_>, Vec<_>) = chain.into_iter().partition(|[l, r]| l == r);
+    (
+        intervals,
+        chars.into_iter().map(|[c, _]| c).collect(),
+        exact,
+    )
+}
+
+/// Escaper built at runtime, see `EscaperBuilder`
+#[derive(Clone, Debug)]
+pub struct DynEscaper {
+    /// Quote of every byte
+    quotes: Box<[Option<Box<str>>]>,
+    intervals: Box<[[u8; 2]]>,
+    chars: Box<[u8]>,
+    exact: bool,
+    backend: Backend,
+}
+
+/// Call `$fn` of the loop of `$backend`, it must be `available`
+macro_rules! dispatch {
+    ($backend:expr, $fn:ident($($arg:expr),*)) => {
+        match $backend {
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse => ranges::sse::$fn($($arg),*),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx => ranges::avx::$fn($($arg),*),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx512 => ranges::avx512::$fn($($arg),*),
+            #[cfg(target_arch = "riscv64")]
+            Backend::Rvv => ranges::rvv::$fn($($arg),*),
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            Backend::Simd128 => ranges::wasm::$fn($($arg),*),
+            #[cfg(all(
+                feature = "powerpc-simd",
+                target_arch = "powerpc64",
+                target_feature = "altivec"
+            ))]
+            Backend::Altivec => ranges::altivec::$fn($($arg),*),
+            #[cfg(all(feature = "arm-simd", target_arch = "arm", target_feature = "neon"))]
+            Backend::Neon => ranges::neon::$fn($($arg),*),
+            #[cfg(feature = "portable-simd")]
+            Backend::Portable => ranges::portable::$fn($($arg),*),
+            _ => ranges::swar::$fn($($arg),*),
+        }
+    };
+}
+
+/// Whether the running cpu has the x86 `$feature`, without `std` only the
+/// target features enabled at compile time are seen
+#[cfg(target_arch = "x86_64")]
+macro_rules! x86_detected {
+    ($feature:tt) => {{
+        #[cfg(feature = "std")]
+        {
+            std::is_x86_feature_detected!($feature)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            cfg!(target_feature = $feature)
+        }
+    }};
+}
+
+/// Whether the loop of `backend` is built for the target and the running cpu
+/// supports it
+fn available(backend: Backend) -> bool {
+    match backend {
+        Backend::Swar => true,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse => x86_detected!("sse2"),
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx => x86_detected!("avx2"),
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx512 => x86_detected!("avx2") && x86_detected!("avx512bw"),
+        #[cfg(target_arch = "riscv64")]
+        Backend::Rvv => crate::is_rvv_detected(),
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Backend::Simd128 => true,
+        #[cfg(all(
+            feature = "powerpc-simd",
+            target_arch = "powerpc64",
+            target_feature = "altivec"
+        ))]
+        Backend::Altivec => true,
+        #[cfg(all(feature = "arm-simd", target_arch = "arm", target_feature = "neon"))]
+        Backend::Neon => true,
+        #[cfg(feature = "portable-simd")]
+        Backend::Portable => true,
+        _ => false,
+    }
+}
+
+/// Fastest available backend, `avx512bw` is only used when forced as in the
+/// generated escapers
+fn detect() -> Backend {
+    const PREFERRED: [Backend; 7] = [
+        Backend::Avx,
+        Backend::Sse,
+        Backend::Rvv,
+        Backend::Simd128,
+        Backend::Altivec,
+        Backend::Neon,
+        Backend::Portable,
+    ];
+
+    PREFERRED
+        .iter()
+        .copied()
+        .find(|&backend| available(backend))
+        .unwrap_or(Backend::Swar)
+}
+
+impl DynEscaper {
+    /// Escaper of `pairs`, see `EscaperBuilder::build`
+    #[inline]
+    pub fn new<'a, I>(pairs: I) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = (u8, &'a str)>,
+    {
+        EscaperBuilder::new().pairs(pairs).build()
+    }
+
+    /// Escaped `s`, written with `Display`
+    #[inline]
+    pub fn escape<'a>(&'a self, s: &'a str) -> DynEscape<'a> {
+        DynEscape {
+            escaper: self,
+            bytes: s.as_bytes(),
+        }
+    }
+
+    /// Quote replacing `b`, or `None` when it isn't escaped
+    #[inline]
+    pub fn escape_byte(&self, b: u8) -> Option<&str> {
+        self.quotes[b as usize].as_deref()
+    }
+
+    /// Exact length in bytes of escaped `s`
+    #[inline]
+    pub fn escaped_len(&self, s: &str) -> usize {
+        crate::scan::escaped_len(|fmt| self.escape_fmt(s.as_bytes(), fmt))
+    }
+
+    /// Write escaped `s` to `buf`, returns the number of written bytes
+    #[inline]
+    pub fn escape_to_slice(&self, s: &str, buf: &mut [u8]) -> Result<usize, BufferTooSmall> {
+        self.f_escape(s.as_bytes(), crate::as_uninit(buf))
+            .ok_or(BufferTooSmall)
+    }
+
+    /// Write escaped `bytes` to the start of `buf`, returns the number of
+    /// written bytes or `None` when it doesn't fit
+    #[inline]
+    pub fn f_escape(&self, bytes: &[u8], buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
+        // SAFETY: the backend is only set to an available one
+        unsafe { dispatch!(self.backend, f_escape(self, bytes, buf)) }
+    }
+
+    /// Implementation used by the escaping functions
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Use `backend`, returns false and keeps the current one when it isn't
+    /// built for the target or supported by the running cpu
+    ///
+    /// There is no byte at a time loop, `Backend::Scalar` is never used
+    pub fn force_backend(&mut self, backend: Backend) -> bool {
+        let available = available(backend);
+        if available {
+            self.backend = backend;
+        }
+        available
+    }
+
+    #[inline]
+    fn escape_fmt(&self, bytes: &[u8], fmt: &mut fmt::Formatter) -> fmt::Result {
+        // SAFETY: the backend is only set to an available one
+        unsafe { dispatch!(self.backend, escape(self, bytes, fmt)) }
+    }
+}
+
+// Bytes of multi-byte characters are escaped all together or not at all, and
+// only the escaped bytes are in the ranges when they are exact
+unsafe impl Escapes for DynEscaper {
+    #[inline(always)]
+    fn ranges(&self) -> Ranges<'_> {
+        Ranges {
+            intervals: &self.intervals,
+            chars: &self.chars,
+            exact: self.exact,
+        }
+    }
+
+    #[inline(always)]
+    fn quote(&self, b: u8) -> Option<&str> {
+        self.escape_byte(b)
+    }
+}
+
+impl Escaper for DynEscaper {
+    #[inline]
+    fn escape_to(&self, input: &str, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(out, "{}", self.escape(input))
+    }
+
+    #[inline]
+    fn escaped_len(&self, input: &str) -> usize {
+        DynEscaper::escaped_len(self, input)
+    }
+}
+
+/// Escaped string of a `DynEscaper`, width, precision and fill are applied to
+/// the escaped string
+pub struct DynEscape<'a> {
+    escaper: &'a DynEscaper,
+    bytes: &'a [u8],
+}
+
+impl fmt::Display for DynEscape<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.escaper.escape_fmt(self.bytes, fmt)
+        } else {
+            crate::pad::pad(fmt, |fmt| self.escaper.escape_fmt(self.bytes, fmt))
+        }
+    }
+}
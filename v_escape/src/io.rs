@@ -0,0 +1,345 @@
+//! `std::io` adapters escaping what goes through them, and with feature
+//! `tokio` its `AsyncWrite` counterpart
+//!
+//! They take the `escape_bytes` function of a `new!` escaper, and every
+//! escaper has a function to build them. An escaper with triggers gives the
+//! length of its carry too, the bytes at the end of a chunk starting a
+//! trigger that the next chunk may finish, which are held back until then
+
+use std::{
+    io::{self, Read, Write},
+    vec::Vec,
+};
+
+/// Escape function of a `new!` escaper, `escape_bytes`
+pub type EscapeBytes = fn(&[u8], &mut Vec<u8>);
+
+/// Length of the carry at the end of a chunk, `carry_len` of the triggers of
+/// a `new!` escaper
+pub type CarryLen = fn(&[u8]) -> usize;
+
+const CHUNK: usize = 8 * 1024;
+
+/// Reader yielding the escaped bytes of the underlying reader
+///
+/// The input is read in chunks, and every chunk is escaped at once. The
+/// carry of a chunk is kept at the start of the buffer the next one is read
+/// in, and escaped on its own at the end of the input
+pub struct EscapingReader<R> {
+    inner: R,
+    escape: EscapeBytes,
+    carry_len: CarryLen,
+    chunk: Vec<u8>,
+    carry: usize,
+    escaped: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> EscapingReader<R> {
+    /// Reader escaping with `escape`, whose escapes are single bytes
+    pub fn new(inner: R, escape: EscapeBytes) -> Self {
+        Self::with_carry(inner, escape, crate::triggers::no_carry)
+    }
+
+    /// Reader escaping with `escape`, holding back the carry of every chunk
+    pub fn with_carry(inner: R, escape: EscapeBytes, carry_len: CarryLen) -> Self {
+        Self {
+            inner,
+            escape,
+            carry_len,
+            chunk: Vec::new(),
+            carry: 0,
+            escaped: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps this `EscapingReader`, the already escaped bytes are lost
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Escape chunks until there is some output, returns false at the end
+    fn fill(&mut self) -> io::Result<bool> {
+        self.chunk.resize(self.carry + CHUNK, 0);
+        while self.pos == self.escaped.len() {
+            let n = self.inner.read(&mut self.chunk[self.carry..])?;
+            self.escaped.clear();
+            self.pos = 0;
+            if n == 0 {
+                if self.carry == 0 {
+                    return Ok(false);
+                }
+                // The input ended, the carry can't start a trigger anymore
+                (self.escape)(&self.chunk[..self.carry], &mut self.escaped);
+                self.carry = 0;
+                continue;
+            }
+
+            let len = self.carry + n;
+            let end = len - (self.carry_len)(&self.chunk[..len]);
+            (self.escape)(&self.chunk[..end], &mut self.escaped);
+            self.chunk.copy_within(end..len, 0);
+            self.carry = len - end;
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for EscapingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || !self.fill()? {
+            return Ok(0);
+        }
+
+        let pending = &self.escaped[self.pos..];
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
+
+/// Writer escaping everything written to it before forwarding it to the
+/// underlying writer
+///
+/// A write split anywhere gives the same output, the carry of a write is
+/// escaped with the next one. `flush` escapes and forwards it, a trigger
+/// can't be finished by the writes after it. Every `write` forwards its whole
+/// escaped output, a write is never reported as partially done
+pub struct EscapingWriter<W> {
+    inner: W,
+    escape: EscapeBytes,
+    carry_len: CarryLen,
+    carry: Vec<u8>,
+    escaped: Vec<u8>,
+}
+
+impl<W: Write> EscapingWriter<W> {
+    /// Writer escaping with `escape`, whose escapes are single bytes
+    pub fn new(inner: W, escape: EscapeBytes) -> Self {
+        Self::with_carry(inner, escape, crate::triggers::no_carry)
+    }
+
+    /// Writer escaping with `escape`, holding back the carry of every write
+    /// until the next write or `flush`
+    pub fn with_carry(inner: W, escape: EscapeBytes, carry_len: CarryLen) -> Self {
+        Self {
+            inner,
+            escape,
+            carry_len,
+            carry: Vec::new(),
+            escaped: Vec::new(),
+        }
+    }
+
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this `EscapingWriter`, returning the underlying writer
+    ///
+    /// The carry isn't written, `flush` writes it
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for EscapingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.escaped.clear();
+        crate::writer::feed_chunk(
+            &mut self.carry,
+            buf,
+            &mut self.escaped,
+            self.escape,
+            self.carry_len,
+        );
+        self.inner.write_all(&self.escaped)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.carry.is_empty() {
+            self.escaped.clear();
+            (self.escape)(&self.carry, &mut self.escaped);
+            self.carry.clear();
+            self.inner.write_all(&self.escaped)?;
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use self::async_writer::*;
+
+#[cfg(feature = "tokio")]
+mod async_writer {
+    use core::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use std::{io, vec::Vec};
+
+    pub use tokio::io::AsyncWrite;
+
+    use super::{CarryLen, EscapeBytes};
+
+    /// Async writer escaping everything written to it before forwarding it
+    /// to the underlying writer
+    ///
+    /// A write is escaped and sent right away. When the underlying writer
+    /// doesn't take anything, the write is pending and nothing is consumed.
+    /// When it takes only a part, the write is done and the rest is sent
+    /// before the next write, flush or shutdown. The carry of a write is
+    /// escaped with the next one, or by a flush or the shutdown
+    pub struct AsyncEscapingWriter<W> {
+        inner: W,
+        escape: EscapeBytes,
+        carry_len: CarryLen,
+        carry: Vec<u8>,
+        escaped: Vec<u8>,
+        pos: usize,
+    }
+
+    impl<W: AsyncWrite + Unpin> AsyncEscapingWriter<W> {
+        /// Writer escaping with `escape`, whose escapes are single bytes
+        pub fn new(inner: W, escape: EscapeBytes) -> Self {
+            Self::with_carry(inner, escape, crate::triggers::no_carry)
+        }
+
+        /// Writer escaping with `escape`, holding back the carry of every
+        /// write until the next write, flush or shutdown
+        pub fn with_carry(inner: W, escape: EscapeBytes, carry_len: CarryLen) -> Self {
+            Self {
+                inner,
+                escape,
+                carry_len,
+                carry: Vec::new(),
+                escaped: Vec::new(),
+                pos: 0,
+            }
+        }
+
+        /// Gets a reference to the underlying writer
+        pub fn get_ref(&self) -> &W {
+            &self.inner
+        }
+
+        /// Gets a mutable reference to the underlying writer
+        pub fn get_mut(&mut self) -> &mut W {
+            &mut self.inner
+        }
+
+        /// Unwraps this `AsyncEscapingWriter`, the escaped bytes not sent yet
+        /// and the carry are lost
+        pub fn into_inner(self) -> W {
+            self.inner
+        }
+
+        /// Send the escaped bytes left by a partial write
+        fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            while self.pos < self.escaped.len() {
+                let pending = &self.escaped[self.pos..];
+                match Pin::new(&mut self.inner).poll_write(cx, pending) {
+                    Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Poll::Ready(Ok(n)) => self.pos += n,
+                    other => return other.map_ok(|_| ()),
+                }
+            }
+
+            self.escaped.clear();
+            self.pos = 0;
+            Poll::Ready(Ok(()))
+        }
+
+        /// Send the escaped bytes left by a partial write, then the carry
+        fn poll_drain_carry(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            match self.poll_drain(cx) {
+                Poll::Ready(Ok(())) if !self.carry.is_empty() => {
+                    (self.escape)(&self.carry, &mut self.escaped);
+                    self.carry.clear();
+                    self.poll_drain(cx)
+                }
+                other => other,
+            }
+        }
+    }
+
+    impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEscapingWriter<W> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            match this.poll_drain(cx) {
+                Poll::Ready(Ok(())) => (),
+                other => return other.map_ok(|()| 0),
+            }
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+
+            // The carry is only changed once `buf` is consumed
+            let carried = this.carry.len();
+            if carried != 0 {
+                this.carry.extend_from_slice(buf);
+            }
+            let bytes = if carried == 0 { buf } else { &this.carry[..] };
+            let end = bytes.len() - (this.carry_len)(bytes);
+            (this.escape)(&bytes[..end], &mut this.escaped);
+            match this.poll_drain(cx) {
+                // Nothing was sent, `buf` isn't consumed
+                Poll::Pending if this.pos == 0 => {
+                    this.escaped.clear();
+                    this.carry.truncate(carried);
+                    Poll::Pending
+                }
+                Poll::Ready(Err(e)) if this.pos == 0 => {
+                    this.escaped.clear();
+                    this.carry.truncate(carried);
+                    Poll::Ready(Err(e))
+                }
+                _ => {
+                    if carried == 0 {
+                        this.carry.extend_from_slice(&buf[end..]);
+                    } else {
+                        this.carry.drain(..end);
+                    }
+                    Poll::Ready(Ok(buf.len()))
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            match this.poll_drain_carry(cx) {
+                Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+                other => other,
+            }
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            match this.poll_drain_carry(cx) {
+                Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+                other => other,
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
-//! Crate v_escape provides a macro, `new!` that define a `struct` with
-//! escaping functionality. These macros are optimized using simd by default,
+//! Crate v_escape provides a macro, `new!` (or the `new_escape!` proc macro
+//! it forwards to) that define a `struct` with escaping functionality. These macros are optimized using simd by default,
 //! but this can be alter using sub-attributes.
 //!
 //! # Quick start
@@ -20,6 +20,8 @@
 //! # }
 //! ```
 //!
+//! `v_escape::new_escape!(MyEscape, "62->bar");` is the same.
+//!
 //! ## Pairs syntax
 //! v_escape uses a simple syntax to replace characters
 //! with their respective quotes. The tuple is named `Pair`,
@@ -30,10 +32,17 @@
 //!
 //!    `([character]->[quote] || )*`
 //!
-//! * `character` :   Character to substitute. Accepts`i8+` from `0` to `i8::MAX` and
-//!                 accepts the following formats: decimal (49), hexadecimal (0x31),
-//!                 octal (0o61) or character (#1).
-//!                 Note: Numbers are read in ASCII: `#6->foo`
+//! * `character` :   Character to substitute. Accepts a byte from `0` to `255` and
+//!   accepts the following formats: decimal (49), hexadecimal (0x31),
+//!   octal (0o61) or character (#1).
+//!   Note: Numbers are read in ASCII: `#6->foo`
+//!
+//!   A byte over 127 is given as a number. The bytes of the multi-byte
+//!   characters, `0x80-0xBF` and `0xC2-0xF4`, are escaped all together or
+//!   none of them, so a `str` is never split inside a character. The other
+//!   ones never appear in a `str` and are escaped on their own by the bytes
+//!   functions. `escape_char` and the other char functions only escape ascii
+//!   characters
 //!
 //! * `quote` :   Characters that will replace `character`
 //!
@@ -89,20 +98,273 @@
 //! # }
 //! ```
 //!
+//! ## Portable SIMD
+//! With the nightly only feature `portable-simd`, targets without a hand written
+//! simd implementation use `std::simd` instead of the SWAR loop.
+//!
+//! ## PowerPC
+//! With the nightly only feature `powerpc-simd`, powerpc64 targets with `altivec`
+//! enabled (ppc64le by default, ppc64 with `-C target-cpu=pwr8` or newer) use an
+//! altivec implementation.
+//!
+//! ## ARM
+//! With the nightly only feature `arm-simd`, 32 bits arm targets with `neon`
+//! enabled (`thumbv7neon-*` or `-C target-feature=+neon`) use a neon
+//! implementation.
+//!
+//! ## SWAR
+//! When simd is disabled, or not available in the target or the running cpu,
+//! a loop that checks a `u64` at a time is used instead of a byte at a time.
+//!
+//! ## Runtime escapers
+//! When the pairs are only known at runtime, as when they are read from a
+//! configuration file, `dynamic::EscaperBuilder` builds a `DynEscaper` with the
+//! same loops.
+//!
+//! ```
+//! let escaper = v_escape::dynamic::DynEscaper::new(vec![(b'<', "foo")]).unwrap();
+//! assert_eq!(escaper.escape("<").to_string(), "foo");
+//! ```
+//!
+//! ## `no_std`
+//! With `detect = "static"` the generated code has no runtime detection and only
+//! uses `core`. Disable the default features to drop `std` and `buf-min`, then
+//! `detect = "static"` is the default. The feature `alloc` keeps the functions
+//! that allocate, the io adapters need `std` and the `b_escape` functions
+//! `buf-min`. The preset crates have the same `std` and `alloc` features.
+//!
+//! ```
+//! v_escape::new!(MyEscape, "60->foo", detect = "static");
+//! # fn main() {
+//! # assert_eq!(escape("<").to_string(), "foo");
+//! # }
+//! ```
+//!
+//! ## Safe code only
+//! With the feature `forbid-unsafe` every escaper is generated as with
+//! `simd = scalar`, whatever its options, with the loop of safe code of
+//! `v_escape`. The generated code has no `unsafe`, so it builds in crates with
+//! `#![forbid(unsafe_code)]`, and `escape_raw` isn't generated. The preset
+//! crates have the same feature.
+//!
+//! ## Miri
+//! The vector loops load their last vector overlapping the bytes already
+//! processed, which Miri and the sanitizers flag. Under Miri, and with the
+//! feature `strict-provenance` for the sanitizers, the last bytes are escaped
+//! one at a time instead, so the test suites using the escapers run there as
+//! they are.
+//!
+#![no_std]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
+#![cfg_attr(
+    all(feature = "powerpc-simd", target_arch = "powerpc64"),
+    feature(stdarch_powerpc)
+)]
+#![cfg_attr(
+    all(feature = "arm-simd", target_arch = "arm"),
+    feature(stdarch_arm_neon_intrinsics)
+)]
 #![allow(unused_imports)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "buf-min")]
 pub use buf_min::Buffer;
 
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod io;
+#[doc(hidden)]
+pub mod pad;
+pub mod registry;
+#[doc(hidden)]
+pub mod scan;
+#[doc(hidden)]
+pub mod triggers;
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub mod unescape;
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub mod writer;
+
+#[allow(deprecated)]
 pub use v_escape_derive::derive;
+pub use v_escape_derive::new_escape;
+pub use v_escape_derive::new_finder;
+
+pub use registry::registry;
+#[cfg(feature = "alloc")]
+pub use unescape::{UnescapeError, UnescapeErrorKind, UnescapeStream};
+
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[doc(hidden)]
+pub use std::is_x86_feature_detected;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use std::time;
 
 #[macro_use]
 mod macros;
 #[macro_use]
 mod scalar;
-#[macro_use]
-mod ranges;
+#[doc(hidden)]
+pub mod ranges;
 #[macro_use]
 mod chars;
+#[doc(hidden)]
+pub mod safe;
+
+/// Detect the RISC-V `V` extension at runtime
+///
+/// `is_riscv_feature_detected!` is still unstable, so the hardware
+/// capabilities reported by Linux are read instead
+#[cfg(target_arch = "riscv64")]
+#[doc(hidden)]
+#[inline]
+pub fn is_rvv_detected() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        use core::ffi::c_ulong;
+
+        extern "C" {
+            fn getauxval(t: c_ulong) -> c_ulong;
+        }
+
+        const AT_HWCAP: c_ulong = 16;
+        const COMPAT_HWCAP_ISA_V: c_ulong = 1 << (b'V' - b'A');
+
+        unsafe { getauxval(AT_HWCAP) & COMPAT_HWCAP_ISA_V != 0 }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        cfg!(target_feature = "v")
+    }
+}
+
+/// Implementation used by the escaping functions of a `new!` escaper
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Byte at a time loop
+    Scalar,
+    /// `u64` at a time loop
+    Swar,
+    /// x86_64 `sse2`
+    Sse,
+    /// x86_64 `avx2`
+    Avx,
+    /// x86_64 `avx512bw`
+    Avx512,
+    /// wasm32 `simd128`
+    Simd128,
+    /// RISC-V `V` extension
+    Rvv,
+    /// powerpc64 `altivec`
+    Altivec,
+    /// arm `neon`
+    Neon,
+    /// `core::simd`
+    Portable,
+}
+
+impl Backend {
+    #[doc(hidden)]
+    pub const ALL: [Backend; 10] = [
+        Backend::Scalar,
+        Backend::Swar,
+        Backend::Sse,
+        Backend::Avx,
+        Backend::Avx512,
+        Backend::Simd128,
+        Backend::Rvv,
+        Backend::Altivec,
+        Backend::Neon,
+        Backend::Portable,
+    ];
+}
+
+/// Error of `escape_to_slice` when the escaped string doesn't fit in the buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferTooSmall;
+
+impl core::fmt::Display for BufferTooSmall {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fmt.write_str("buffer too small for the escaped string")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferTooSmall {}
+
+/// Escaper chosen at runtime, implemented by the `ESCAPER` of every `new!` escaper
+///
+/// They can be looked up by name in the `registry`
+///
+/// ```
+/// mod html {
+///     v_escape::new!(Html, "'<' -> \"&lt;\"");
+/// }
+/// mod latex {
+///     v_escape::new!(Latex, "'$' -> \"\\\\$\"");
+/// }
+///
+/// # fn main() {
+/// use v_escape::Escaper;
+///
+/// let escaper: &dyn Escaper = if true { html::ESCAPER } else { latex::ESCAPER };
+/// let mut out = String::new();
+/// escaper.escape_to("<b>", &mut out).unwrap();
+/// assert_eq!(out, "&lt;b>");
+/// assert_eq!(escaper.escaped_len("<b>"), 6);
+/// # }
+/// ```
+pub trait Escaper: Sync {
+    /// Write escaped `input` to `out`
+    fn escape_to(&self, input: &str, out: &mut dyn core::fmt::Write) -> core::fmt::Result;
+
+    /// Exact length in bytes of escaped `input`
+    fn escaped_len(&self, input: &str) -> usize;
+}
+
+/// `Escaper` of a function writing escaped input, for the escapers written by
+/// hand around a `finder!`
+///
+/// ```
+/// use core::fmt::Write;
+///
+/// static UPPER: &dyn v_escape::Escaper = &v_escape::FnEscaper(|input, out| {
+///     input.chars().try_for_each(|c| out.write_char(c.to_ascii_uppercase()))
+/// });
+///
+/// let mut out = String::new();
+/// UPPER.escape_to("<b>", &mut out).unwrap();
+/// assert_eq!(out, "<B>");
+/// assert_eq!(UPPER.escaped_len("<b>"), 3);
+/// ```
+pub struct FnEscaper(pub fn(&str, &mut dyn core::fmt::Write) -> core::fmt::Result);
+
+impl Escaper for FnEscaper {
+    #[inline]
+    fn escape_to(&self, input: &str, out: &mut dyn core::fmt::Write) -> core::fmt::Result {
+        (self.0)(input, out)
+    }
+
+    fn escaped_len(&self, input: &str) -> usize {
+        scan::escaped_len(|fmt| (self.0)(input, fmt))
+    }
+}
+
+/// View of an initialized buffer as a buffer to write to
+#[doc(hidden)]
+#[inline]
+pub fn as_uninit(buf: &mut [u8]) -> &mut [core::mem::MaybeUninit<u8>] {
+    // SAFETY: same layout, and only initialized bytes are written through it
+    unsafe { &mut *(buf as *mut [u8] as *mut [core::mem::MaybeUninit<u8>]) }
+}
 
 #[macro_export]
 /// Generates struct `$name` with escaping functionality at `fmt`
@@ -112,16 +374,79 @@ mod chars;
 /// * $__name__: Name of escape class.
 ///
 /// * $__pairs__: Pairs of `[character]->[quote] || [character]->[quote]` or
-///              `[character]->[quote]`.
+///   `[character]->[quote]`, a range of characters with the same quote is written
+///   `[character]-[character]->[quote]`. A `{:[0][width][x|X|o|b]}` placeholder in a
+///   quote is replaced by the escaped character formatted as a number, as in
+///   `0x00-0x1F->\\u00{:02x}`. A character can be given as a number, decimal, `0x`
+///   hexadecimal, `0o` octal or `#` digit, or as a char literal as in `'\n'`, and a
+///   quote as a string literal with escape sequences, `'<' -> "&lt;"`. They can be
+///   left out when a `file` or a `preset` is given. A string literal of two or more
+///   ascii bytes is a trigger, escaped as a whole, as in `"</" -> "<\\/"` for json in
+///   a `<script>` or `"]]>" -> "]]]]><![CDATA[>"` in cdata. Its first byte is looked
+///   for with simd too and the rest checked where it is found, it comes before a pair
+///   of that byte and can't start another trigger. The functions of single
+///   characters, like `escape_byte` and `escape_char`, only see the pairs. The
+///   chunked `StreamEscaper`, `escaping_reader`, `escaping_writer` and
+///   `async_escaping_writer` hold back the bytes at the end of a chunk starting a
+///   trigger, until the next chunk finishes it or not, or until `finish`, a flush or
+///   the shutdown.
 ///
-/// * $__t__: Optional boolean parameters (simd, avx, sse, print).
-///     * __simd__:  If true (by default), simd optimizations are enabled. When false,
-///         no matter value of avx, `sse4.2` will be used,
+/// * $__t__: Optional `name = value` parameters (file, module, preset, simd, avx, avx512,
+///   ranges, strategy, detect, print, tests, then, unescape, idempotent), a string value can
+///   also be written as a bare word. Unknown, repeated or invalid options are compile errors.
+///     * __file__: Path, relative to the crate root, of a file with more pairs, one
+///       per line. The crate is rebuilt when it changes.
+///     * __module__: Name of a module, generated with every item in it, only `$name`
+///       is reexported next to it. Several escapers can be defined in the same
+///       module this way, as in `new!(Attr, "'\"' -> \"&quot;\"", module = attr)`
+///       and `attr::escape(s)`.
+///     * __preset__: `"html"`, `"json"`, `"latex"` or `"shell"`, the standard pairs
+///       of that format are added to the given pairs, a given pair replaces the
+///       preset one of the same character.
+///     * __simd__:  If true (by default), simd optimizations are enabled. On x86_64 the
+///       widest loop the cpu has is used, `avx512bw` (with `avx512`), `avx2` (with `avx`)
+///       or `sse2`, checked at runtime or by the target features as `detect` says. When
+///       false, only the `swar` loop is generated, whatever the values of `avx` and
+///       `avx512`. When `scalar`, only the byte at a time loop is generated and called
+///       directly, without any `cfg` nor simd code, which also builds faster.
 ///     * __avx__:   If true (by default), avx optimization are enabled. When false,
-///         `sse2`(if `ranges=true` and `simd=true`) or `scalar`(if `simd=false`) will be used.
-///     * __ranges__:   If true (by default), ranges optimizations are enabled. When false,
-///         `sse4.2`(if `simd=true`) or `scalar`(if `simd=false`) will be used.
+///       `sse2`(if `ranges=true` and `simd=true`) or `swar`(if `simd=false`) will be used.
+///     * __avx512__:   If true (false by default), `avx512bw` optimization is enabled and
+///       used when it is detected at runtime. Inputs shorter than 64 bytes are handed over
+///       to the `avx2` loop.
+///     * __ranges__:   `auto` (by default), `true` or `false`. The ranges are the simd
+///       implementation, `auto` and `true` use them when `simd=true`, `true` with
+///       `simd=false` is an error. When false, `swar` will be used. Characters are
+///       compared by intervals, past 12 compares the closest intervals are merged
+///       and a false positive is checked against the table.
+///     * __strategy__: `auto` (by default), `eq` or `ranges`, how the characters are
+///       compared. `eq` compares every character on its own, up to 16 of them, with more
+///       it warns and falls back to `auto`. `ranges` uses at most 3
+///       ranges checking a false positive against the table and `auto` picks by how
+///       many characters there are and how close they are.
+///     * __detect__: `"runtime"` or `"static"`, by default `"runtime"` with the `std`
+///       feature, which it needs, and `"static"` without it. When `"static"`, the
+///       implementation is chosen only by the target features enabled at compile time
+///       (`-C target-feature` or `-C target-cpu`), without runtime detection code,
+///       so it can be used in `#![no_std]` crates.
 ///     * __print__: If true (false by default), prints out generated code to console.
+///     * __tests__: If true (false by default), a `#[cfg(test)]` module is generated
+///       checking every backend that can be forced against escaping byte by byte, with
+///       every single character, inputs of the lengths around the vector sizes and
+///       random inputs. Not with the deprecated `derive!`, which doesn't generate the
+///       escaper type the tests go through.
+///     * __then__: Pairs escaping the output of the pairs, in the same pass. The quotes
+///       are escaped with them, and the characters only in them with their quote, as in
+///       `new!(Html, preset = "html", then = "'\r' -> \"\" || '\n' -> \"&#10;\"")`.
+///     * __unescape__: If true (false by default), `unescape` is generated too. The
+///       characters have to be ascii, the quotes have to start with an escaped
+///       character and none can start another, else it is a compile error.
+///     * __idempotent__: If true (false by default), the quotes already in the input are
+///       passed through instead of escaping their first character again, for input that
+///       may be escaped in part, as in `&amp;` staying `&amp;` and not becoming
+///       `&amp;amp;`. The ascii quotes starting with an escaped character are added as
+///       triggers escaped as themselves, so none can start another and it can't be
+///       combined with `unescape`. Escaping its output again doesn't change it.
 ///
 /// and will:
 ///
@@ -132,9 +457,49 @@ mod chars;
 ///
 /// 3. Implements for `$name` constructors `new` and `From<&'a str>`
 ///
-/// 4. Implements trait `Display` for `$name` with escape functionality
+/// 4. Implements trait `Display` for `$name` with escape functionality, width,
+///    precision and fill are applied to the escaped string
+///
+/// 5. Implements functions `escape(&str) -> $name`, `needs_escaping(&str) -> bool`,
+///    `escape_fragments(impl IntoIterator<Item = &str>, &mut impl fmt::Write) -> fmt::Result`,
+///    `find_first_escape(&str) -> Option<usize>`,
+///    `find_first_escape_bytes(&[u8]) -> Option<usize>`, `escaped_len(&str) -> usize`,
+///    `escape_to_slice(&str, &mut [u8]) -> Result<usize, BufferTooSmall>`,
+///    `escape_raw(*const u8, usize, *mut u8, usize) -> isize` for FFI callers
+///    (not with feature `forbid-unsafe`),
+///    `escape_cow(&str) -> Cow<str>`, `escape_into(&str, &mut String) -> bool`,
+///    `escape_truncated(&str, usize, &mut String) -> bool`,
+///    `escape_bytes(&[u8], &mut Vec<u8>)`, `escape_owned(String) -> String`,
+///    `escape_cow_in(Cow<str>) -> Cow<str>`,
+///    `escape_vec(Vec<u8>) -> Vec<u8>`, the chunked escaper `StreamEscaper`,
+///    `escaping_reader(impl io::Read) -> EscapingReader`,
+///    `escaping_writer(impl io::Write) -> EscapingWriter`,
+///    `escape_to_writer(&str, &mut impl io::Write) -> io::Result<usize>`,
+///    with feature `bytes`, `escape_bytes_mut(&str, &mut BytesMut)` and
+///    `$name::to_bytes() -> Bytes` and, with feature `tokio`,
+///    `async_escaping_writer(impl AsyncWrite) -> AsyncEscapingWriter`
 ///
-/// 5. Implements function `escape(&str) -> $name`
+/// 6. Implements functions `escape_byte(u8) -> Option<&'static str>` and
+///    `escape_char_quote(char) -> Option<&'static str>` looking up the quote,
+///    `escape_iter(&str) -> impl Iterator<Item = &str>` yielding the
+///    escaped output in chunks and
+///    `escape_positions(&str) -> impl Iterator<Item = (usize, &'static str)>`
+///    yielding the offsets of the escaped characters
+///
+/// 7. Implements functions `current_backend() -> Backend`,
+///    `force_backend(Backend) -> bool` and `tune() -> Backend`
+///
+/// 8. Defines `ESCAPER: &dyn Escaper`, the escaper as a trait object to choose
+///    between escapers at runtime
+///
+/// 9. With `unescape = true`, implements function
+///    `unescape(&str) -> Result<Cow<str>, UnescapeError>` replacing the quotes by
+///    their characters, it looks for the first bytes of the quotes with simd too,
+///    `unescape_lossy(&str) -> Cow<str>` writing `U+FFFD` in place of the errors
+///    and `unescape_stream() -> UnescapeStream` doing it on chunks
+///
+/// `new!` forwards to the `new_escape!` proc macro, which can be called
+/// directly with the same arguments.
 ///
 /// #### Example
 ///
@@ -149,18 +514,156 @@ mod chars;
 /// ```
 ///
 macro_rules! new {
-    // Macro called without attributes
-    ($name:ident, $pairs:expr) => {
-        $crate::derive!($pairs);
-        $crate::escape_new!($name);
+    ($name:ident, $($t:tt)+) => {
+        $crate::new_escape!($name, $($t)+);
+    };
+}
+
+#[macro_export]
+/// Generates module `$module` looking for characters with the loops of `new!`,
+/// the finder of an escaping loop written by hand
+///
+/// It takes the pairs and the options of `new!`, but a character or a range
+/// can be given without a quote, as in `0x00-0x1F || 0x80-0xBF || 0xC2-0xF4`.
+/// The same bytes of the multi-byte characters have to be given, every one of
+/// 0x80-0xBF and 0xC2-0xF4 or none. Triggers and the options of the escaping
+/// functions, `module`, `tests`, `then`, `unescape` and `idempotent`, can't
+/// be given.
+///
+/// The module has functions `find_first_escape(&str) -> Option<usize>` and
+/// `find_first_escape_bytes(&[u8]) -> Option<usize>`, the position of the
+/// first byte looked for, `escape_byte(u8) -> Option<&'static str>`, the
+/// quote of a character given with one, and `current_backend() -> Backend`.
+///
+/// `finder!` forwards to the `new_finder!` proc macro, which can be called
+/// directly with the same arguments.
+///
+/// #### Example
+///
+/// ```
+/// v_escape::finder!(special, "'\\n' || '\\r' || ',' || '\"'->&quot;");
+///
+/// # fn main() {
+/// assert_eq!(special::find_first_escape("a,\"b"), Some(1));
+/// assert_eq!(special::escape_byte(b'"'), Some("&quot;"));
+/// assert_eq!(special::escape_byte(b','), None);
+/// # }
+/// ```
+///
+macro_rules! finder {
+    ($module:ident, $($t:tt)+) => {
+        $crate::new_finder!($module, $($t)+);
+    };
+}
+
+#[cfg(feature = "buf-min")]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the items that need `Buffer`
+macro_rules! cfg_buffer {
+    ($($t:tt)*) => {
+        $($t)*
+    };
+}
+
+#[cfg(not(feature = "buf-min"))]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the items that need `Buffer`, it isn't available without `buf-min`
+macro_rules! cfg_buffer {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(not(feature = "forbid-unsafe"))]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the unsafe items
+macro_rules! cfg_unsafe {
+    ($($t:tt)*) => {
+        $($t)*
+    };
+}
+
+#[cfg(feature = "forbid-unsafe")]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the unsafe items, the escapers have none with `forbid-unsafe`
+macro_rules! cfg_unsafe {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(feature = "alloc")]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the items that need `alloc`
+macro_rules! cfg_alloc {
+    ($($t:tt)*) => {
+        $($t)*
+    };
+}
+
+#[cfg(not(feature = "alloc"))]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the items that need `alloc`
+macro_rules! cfg_alloc {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(feature = "std")]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the items that need `std`
+macro_rules! cfg_std {
+    ($($t:tt)*) => {
+        $($t)*
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the items that need `std`
+macro_rules! cfg_std {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(feature = "tokio")]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the items that need `tokio`
+macro_rules! cfg_tokio {
+    ($($t:tt)*) => {
+        $($t)*
     };
-    // Macro called with attributes
-    ($name:ident, $pairs:expr, $($t:tt)+) => {
-        $crate::derive!($pairs, $($t)+);
-        $crate::escape_new!($name);
+}
+
+#[cfg(not(feature = "tokio"))]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the items that need `tokio`
+macro_rules! cfg_tokio {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(feature = "bytes")]
+#[macro_export]
+#[doc(hidden)]
+/// Keep the items that need `bytes`
+macro_rules! cfg_bytes {
+    ($($t:tt)*) => {
+        $($t)*
     };
 }
 
+#[cfg(not(feature = "bytes"))]
+#[macro_export]
+#[doc(hidden)]
+/// Drop the items that need `bytes`
+macro_rules! cfg_bytes {
+    ($($t:tt)*) => {};
+}
+
 #[macro_export]
 #[doc(hidden)]
 /// Escape implementation
@@ -168,6 +671,18 @@ macro_rules! new {
 /// Generates function new, and traits From and Display, for class `$name`
 macro_rules! escape_new {
     ($name:ident) => {
+        $crate::escape_new!(@ $name);
+        $crate::escape_fragments!(_escape_fn(), $crate::triggers::no_carry, []);
+        $crate::escape_chunks!($crate::triggers::no_carry);
+    };
+    // A trigger can be cut between chunks or fragments, its start is carried
+    // to the next one
+    ($name:ident, triggers) => {
+        $crate::escape_new!(@ $name);
+        $crate::escape_fragments!(_escape_fn(), _carry_len, [0; V_ESCAPE_FRAGMENTS_BUF]);
+        $crate::escape_chunks!(_carry_len);
+    };
+    (@ $name:ident) => {
         pub struct $name<'a> {
             bytes: &'a [u8],
         }
@@ -179,15 +694,12 @@ macro_rules! escape_new {
             }
 
             #[inline]
-            pub fn f_escape(&self, buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-                #[allow(unused_unsafe)]
-                unsafe {
-                    _f_escape(self.bytes, buf)
-                }
+            pub fn f_escape(&self, buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
+                _f_escape(self.bytes, buf)
             }
         }
 
-        impl<'a> From<&'a str> for $name<'a> {
+        impl<'a> ::core::convert::From<&'a str> for $name<'a> {
             #[inline]
             fn from(s: &str) -> $name {
                 $name {
@@ -198,241 +710,1226 @@ macro_rules! escape_new {
 
         #[inline]
         pub fn escape(s: &str) -> $name {
-            $name::from(s)
+            $name {
+                bytes: s.as_bytes(),
+            }
         }
 
-        impl<'a> std::fmt::Display for $name<'a> {
-            fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-                #[allow(unused_unsafe)]
-                unsafe {
+        impl<'a> ::core::fmt::Display for $name<'a> {
+            fn fmt(&self, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                if fmt.width().is_none() && fmt.precision().is_none() {
                     _escape(self.bytes, fmt)
+                } else {
+                    $crate::pad::pad(fmt, |fmt| _escape(self.bytes, fmt))
                 }
             }
         }
 
+        /// Position of the first byte of `s` that needs escaping
         #[inline]
-        pub fn escape_char(c: char) -> impl std::fmt::Display {
-            struct EscapeChar(char);
-
-            impl std::fmt::Display for EscapeChar {
-                fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-                    chars::escape_char(self.0, fmt)
-                }
-            }
+        pub fn find_first_escape(s: &str) -> ::core::option::Option<usize> {
+            find_first_escape_bytes(s.as_bytes())
+        }
 
-            EscapeChar(c)
+        /// Position of the first byte of `bytes` that needs escaping
+        ///
+        /// An escaped byte may be inside a character, the scans go on from
+        /// there with the bytes. With an escaper only used to find bytes, as
+        /// `0x00-0x1F->?`, it is the search of the vector loops
+        #[inline]
+        pub fn find_first_escape_bytes(bytes: &[u8]) -> ::core::option::Option<usize> {
+            $crate::scan::find_first(bytes, |fmt| _escape(bytes, fmt))
         }
 
+        /// Write escaped `s` to `buf`, returns the number of written bytes
+        ///
+        /// It doesn't allocate, when the escaped string doesn't fit the content
+        /// of `buf` is unspecified
         #[inline]
-        pub fn f_escape(s: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-            #[allow(unused_unsafe)]
-            unsafe {
-                _f_escape(s, buf)
+        pub fn escape_to_slice(s: &str, buf: &mut [u8]) -> ::core::result::Result<usize, $crate::BufferTooSmall> {
+            f_escape(s.as_bytes(), $crate::as_uninit(buf)).ok_or($crate::BufferTooSmall)
+        }
+
+        $crate::cfg_unsafe! {
+            /// Write the `len` escaped bytes at `src` to the `cap` bytes at `dst`,
+            /// returns the number of written bytes or, when they don't fit, the
+            /// needed capacity negated
+            ///
+            /// It has the C calling convention to back escapers in other languages,
+            /// export it from a `#[no_mangle]` function of your own
+            ///
+            /// # Safety
+            /// `src` must be valid for reads of `len` bytes and `dst` for writes of
+            /// `cap` bytes, they can be null when their length is zero. The input
+            /// isn't checked to be utf-8, and invalid utf-8 stays invalid
+            #[inline]
+            pub unsafe extern "C" fn escape_raw(src: *const u8, len: usize, dst: *mut u8, cap: usize) -> isize {
+                let src = if len == 0 {
+                    &[]
+                } else {
+                    ::core::slice::from_raw_parts(src, len)
+                };
+                let dst = if cap == 0 {
+                    &mut []
+                } else {
+                    ::core::slice::from_raw_parts_mut(dst as *mut ::core::mem::MaybeUninit<u8>, cap)
+                };
+                match f_escape(src, dst) {
+                    ::core::option::Option::Some(written) => written as isize,
+                    ::core::option::Option::None => {
+                        -($crate::scan::quoted_len(src, chars::quote_at) as isize)
+                    }
+                }
             }
         }
 
+        /// Exact length in bytes of escaped `s`, to allocate the output once
         #[inline]
-        pub fn f_escape_char(c: char, buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-            #[allow(unused_unsafe)]
-            unsafe {
-                chars::f_escape_char(c, buf)
-            }
+        pub fn escaped_len(s: &str) -> usize {
+            $crate::scan::escaped_len(|fmt| _escape(s.as_bytes(), fmt))
         }
 
-        /// Escape byte slice to `Buffer`
+        /// Escaped `s` in chunks, unescaped runs of `s` alternating with quotes
         ///
-        /// # SIGILL
-        /// Can produce **SIGILL** if compile with `sse2` or `avx2` and execute without they
-        /// Because not exist way to build multiple static allocations by type
-        /// And it's very expensive check it in runtime
-        /// https://github.com/rust-lang/rust/issues/57775
-        #[inline]
-        pub fn b_escape<B: $crate::Buffer>(s: &[u8], buf: &mut B) {
-            #[allow(unused_unsafe)]
-            unsafe {
-                _b_escape(s, buf)
-            }
+        /// Nothing is allocated and `s` is scanned lazily, as the chunks are taken
+        #[inline]
+        pub fn escape_iter(s: &str) -> impl ::core::iter::Iterator<Item = &str> {
+            $crate::scan::EscapeIter::new(s, find_first_escape_bytes, chars::quote_at)
         }
 
-        /// Escape char to `buf-min::Buffer`
+        /// Byte offsets in `s` of the escaped characters, with their quotes
         #[inline]
-        pub fn b_escape_char<B: $crate::Buffer>(s: char, buf: &mut B) {
-            #[allow(unused_unsafe)]
-            unsafe {
-                chars::b_escape_char(s, buf)
-            }
+        pub fn escape_positions(s: &str) -> impl ::core::iter::Iterator<Item = (usize, &'static str)> + '_ {
+            $crate::scan::EscapePositions::new(s, find_first_escape_bytes, chars::quote_at)
         }
-    };
-}
 
-#[macro_export]
-#[doc(hidden)]
-/// cfg_if for escape function
-macro_rules! cfg_escape {
-    (false, $($t:tt)+) => {
-        $crate::cfg_escape!(fn);
-    };
-    (true, $($t:tt)+) => {
-        #[cfg(target_arch = "x86_64")]
-        #[inline(always)]
-        // https://github.com/BurntSushi/rust-memchr/blob/master/src/x86/mod.rs#L9-L29
-        fn _escape(bytes: &[u8], fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-            use std::mem;
-            use std::sync::atomic::{AtomicUsize, Ordering};
-            use std::fmt::{self, Formatter};
-            static mut FN: fn(&[u8], &mut Formatter) -> fmt::Result = detect;
-
-            fn detect(bytes: &[u8], fmt: &mut Formatter) -> fmt::Result {
-                let fun = $crate::cfg_escape!(if $($t)+);
-
-                let slot = unsafe { &*(&FN as *const _ as *const AtomicUsize) };
-                slot.store(fun, Ordering::Relaxed);
-                unsafe {
-                    mem::transmute::<usize, fn(&[u8], &mut Formatter) -> fmt::Result>(fun)(
-                        bytes, fmt,
-                    )
+        /// Returns true if any byte of `s` needs escaping
+        #[inline]
+        pub fn needs_escaping(s: &str) -> bool {
+            find_first_escape(s).is_some()
+        }
+
+        $crate::cfg_alloc! {
+            /// Escape `s`, borrowing it when there is nothing to escape
+            #[inline]
+            pub fn escape_cow(s: &str) -> $crate::writer::Cow<'_, str> {
+                use ::core::fmt::Write;
+
+                let mut writer = $crate::writer::CowWriter::new(s);
+                // Never fails, writing to a `String` is infallible
+                let _ = ::core::write!(writer, "{}", escape(s));
+                writer.into_cow()
+            }
+
+            /// Append escaped `s` to `buf`, returns true if anything was escaped
+            #[inline]
+            pub fn escape_into(s: &str, buf: &mut $crate::writer::String) -> bool {
+                use ::core::fmt::Write;
+
+                let mut writer = $crate::writer::StringWriter::new(s, buf);
+                // Never fails, writing to a `String` is infallible
+                let _ = ::core::write!(writer, "{}", escape(s));
+                writer.escaped()
+            }
+
+            /// Append escaped `s` to `buf`, at most `max_len` bytes, returns true
+            /// if the output was truncated
+            ///
+            /// Quotes are never cut, the output stops before the first one that
+            /// doesn't fit
+            #[inline]
+            pub fn escape_truncated(s: &str, max_len: usize, buf: &mut $crate::writer::String) -> bool {
+                use ::core::fmt::Write;
+
+                let mut writer = $crate::writer::BudgetWriter::new(s, max_len, buf);
+                // An error means the budget was reached
+                let _ = ::core::write!(writer, "{}", escape(s));
+                writer.truncated()
+            }
+
+            /// Append escaped `s` to `buf`, without `fmt` nor utf-8 checks
+            #[inline]
+            pub fn escape_bytes(s: &[u8], buf: &mut $crate::writer::Vec<u8>) {
+                $crate::writer::extend_vec(s, buf, V_ESCAPE_QUOTES_MAX, f_escape)
+            }
+
+            /// Escape `s` reusing its allocation, it is returned unchanged when
+            /// there is nothing to escape
+            #[inline]
+            pub fn escape_owned(s: $crate::writer::String) -> $crate::writer::String {
+                match find_first_escape(&s) {
+                    ::core::option::Option::Some(first) => {
+                        $crate::writer::escape_string_from(s, first, V_ESCAPE_QUOTES_MAX, f_escape)
+                    }
+                    ::core::option::Option::None => s,
                 }
             }
 
-            unsafe {
-                let slot = &*(&FN as *const _ as *const AtomicUsize);
-                let fun = slot.load(Ordering::Relaxed);
-                mem::transmute::<usize, fn(&[u8], &mut Formatter) -> fmt::Result>(fun)(bytes, fmt)
+            /// Escape `s` keeping its ownership, a borrowed `s` stays borrowed when
+            /// there is nothing to escape and an owned one reuses its allocation
+            #[inline]
+            pub fn escape_cow_in(s: $crate::writer::Cow<'_, str>) -> $crate::writer::Cow<'_, str> {
+                match s {
+                    $crate::writer::Cow::Borrowed(s) => escape_cow(s),
+                    $crate::writer::Cow::Owned(s) => $crate::writer::Cow::Owned(escape_owned(s)),
+                }
             }
-        }
 
-        #[cfg(not(target_arch = "x86_64"))]
-        $crate::cfg_escape!(fn);
-    };
-    (fn) => {
-        #[inline(always)]
-        fn _escape(bytes: &[u8], fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-            scalar::escape(bytes, fmt)
-        }
-    };
-    (if true) => {
-        if is_x86_feature_detected!("avx2") {
-            ranges::avx::escape as usize
-        } else if is_x86_feature_detected!("sse2") {
-            ranges::sse::escape as usize
-        } else {
-            scalar::escape as usize
-        }
-    };
-    (if false) => {
-        if is_x86_feature_detected!("sse2") {
-            ranges::sse::escape as usize
-        } else {
-            scalar::escape as usize
+            /// Escape `bytes` reusing its allocation, it is returned unchanged when
+            /// there is nothing to escape
+            #[inline]
+            pub fn escape_vec(mut bytes: $crate::writer::Vec<u8>) -> $crate::writer::Vec<u8> {
+                if let ::core::option::Option::Some(first) = find_first_escape_bytes(&bytes) {
+                    $crate::writer::escape_from(&mut bytes, first, V_ESCAPE_QUOTES_MAX, f_escape);
+                }
+                bytes
+            }
         }
-    };
-}
 
-#[macro_export]
-#[doc(hidden)]
-/// cfg_if for escape function
-macro_rules! cfg_escape_ptr {
-    (false, $($t:tt)+) => {
-        $crate::cfg_escape_ptr!(fn);
-    };
-    (true, $($t:tt)+) => {
-        #[cfg(target_arch = "x86_64")]
-        #[inline(always)]
-        #[allow(unreachable_code)]
-        // https://github.com/BurntSushi/rust-memchr/blob/master/src/x86/mod.rs#L9-L29
-        pub unsafe fn _f_escape(bytes: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-            use std::mem;
-            use std::sync::atomic::{AtomicUsize, Ordering};
-            static mut FN: fn(&[u8], &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> = detect;
-
-            fn detect(bytes: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-                let fun = $crate::cfg_escape_ptr!(if $($t)+);
-
-                let slot = unsafe { &*(&FN as *const _ as *const AtomicUsize) };
-                slot.store(fun, Ordering::Relaxed);
-                unsafe {
-                    mem::transmute::<usize, fn(&[u8], &mut [std::mem::MaybeUninit<u8>]) -> Option<usize>>(fun)(
-                        bytes, buf,
-                    )
+        $crate::cfg_std! {
+            $crate::cfg_bytes! {
+                /// Append escaped `s` to `buf`
+                #[inline]
+                pub fn escape_bytes_mut(s: &str, buf: &mut $crate::writer::BytesMut) {
+                    $crate::writer::extend_bytes_mut(s.as_bytes(), buf, V_ESCAPE_QUOTES_MAX, f_escape)
+                }
+
+                impl<'a> $name<'a> {
+                    /// Escaped output as `Bytes`, without an intermediate `String`
+                    #[inline]
+                    pub fn to_bytes(&self) -> $crate::writer::Bytes {
+                        let mut buf = $crate::writer::BytesMut::new();
+                        $crate::writer::extend_bytes_mut(self.bytes, &mut buf, V_ESCAPE_QUOTES_MAX, f_escape);
+                        buf.freeze()
+                    }
                 }
             }
 
-            unsafe {
-                let slot = &*(&FN as *const _ as *const AtomicUsize);
-                let fun = slot.load(Ordering::Relaxed);
-                mem::transmute::<usize, fn(&[u8], &mut [std::mem::MaybeUninit<u8>]) -> Option<usize>>(fun)(bytes, buf)
+            /// Write escaped `s` to `writer`, returns the number of written bytes
+            ///
+            /// Unescaped runs are written at once, without an intermediate buffer
+            #[inline]
+            pub fn escape_to_writer<W: $crate::writer::io::Write + ?::core::marker::Sized>(
+                s: &str,
+                writer: &mut W,
+            ) -> $crate::writer::io::Result<usize> {
+                use ::core::fmt::Write;
+
+                let mut writer = $crate::writer::IoWriter::new(writer);
+                let result = ::core::write!(writer, "{}", escape(s));
+                writer.finish(result)
             }
         }
 
-        #[cfg(not(target_arch = "x86_64"))]
-        $crate::cfg_escape_ptr!(fn);
-    };
-    (fn) => {
-        #[inline(always)]
-        pub unsafe fn _f_escape(bytes: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-            scalar::f_escape(bytes, buf)
+        #[inline]
+        pub fn escape_char(c: char) -> impl ::core::fmt::Display {
+            struct EscapeChar(char);
+
+            impl ::core::fmt::Display for EscapeChar {
+                fn fmt(&self, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    if fmt.width().is_none() && fmt.precision().is_none() {
+                        chars::escape_char(self.0, fmt)
+                    } else {
+                        $crate::pad::pad(fmt, |fmt| chars::escape_char(self.0, fmt))
+                    }
+                }
+            }
+
+            EscapeChar(c)
         }
-    };
-    (if true) => {
-        if is_x86_feature_detected!("avx2") {
-            ranges::avx::f_escape as usize
-        } else if is_x86_feature_detected!("sse2") {
-            ranges::sse::f_escape as usize
-        } else {
-            scalar::f_escape as usize
+
+        impl $crate::Escaper for V_ESCAPE {
+            #[inline]
+            fn escape_to(&self, input: &str, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                ::core::write!(out, "{}", escape(input))
+            }
+
+            #[inline]
+            fn escaped_len(&self, input: &str) -> usize {
+                escaped_len(input)
+            }
         }
-    };
-    (if false) => {
-        if is_x86_feature_detected!("sse2") {
-            ranges::sse::f_escape as usize
-        } else {
-            scalar::f_escape as usize
+
+        /// This escaper as a trait object, to choose between escapers at runtime
+        pub const ESCAPER: &dyn $crate::Escaper = &V_ESCAPE;
+
+        /// Implementation used by `escape` and `f_escape`
+        #[inline]
+        pub fn current_backend() -> $crate::Backend {
+            _backend()
         }
-    };
-}
 
-#[macro_export]
-#[doc(hidden)]
-/// cfg_if for escape function
-macro_rules! cfg_escape_bytes {
-    (false, $($t:tt)+) => {
-        $crate::cfg_escape_bytes!(fn);
+        /// Use `backend` in `escape` and `f_escape`, returns false and keeps the
+        /// current one when it isn't generated or supported by the running cpu
+        ///
+        /// Only x86_64 and riscv64 with runtime detection can change it,
+        /// `b_escape` is always chosen at compile time
+        #[inline]
+        pub fn force_backend(backend: $crate::Backend) -> bool {
+            _force_backend(backend)
+        }
+
+        /// Measure every backend available in `escape` and `f_escape` with a short
+        /// input, and force the fastest one. Returns the chosen backend
+        ///
+        /// On some cpus `avx2` downclocking makes `sse2` faster for short strings.
+        /// It only does something where `force_backend` can change the backend
+        #[inline]
+        pub fn tune() -> $crate::Backend {
+            _tune()
+        }
+
+        /// Quote replacing `b`, or `None` when it isn't escaped
+        #[inline]
+        pub fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+            chars::escape_byte(b)
+        }
+
+        /// Quote replacing `c`, or `None` when it isn't escaped
+        #[inline]
+        pub fn escape_char_quote(c: char) -> ::core::option::Option<&'static str> {
+            if c.is_ascii() {
+                chars::escape_byte(c as u8)
+            } else {
+                ::core::option::Option::None
+            }
+        }
+
+        #[inline]
+        pub fn f_escape(s: &[u8], buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
+            _f_escape(s, buf)
+        }
+
+        #[inline]
+        pub fn f_escape_char(c: char, buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
+            chars::f_escape_char(c, buf)
+        }
+
+        $crate::cfg_buffer! {
+            /// Escape byte slice to `Buffer`
+            ///
+            /// # SIGILL
+            /// Can produce **SIGILL** if compile with `sse2` or `avx2` and execute without they
+            /// Because not exist way to build multiple static allocations by type
+            /// And it's very expensive check it in runtime
+            /// https://github.com/rust-lang/rust/issues/57775
+            #[inline]
+            pub fn b_escape<B: $crate::Buffer>(s: &[u8], buf: &mut B) {
+                _b_escape(s, buf)
+            }
+
+            /// Escape char to `buf-min::Buffer`
+            #[inline]
+            pub fn b_escape_char<B: $crate::Buffer>(s: char, buf: &mut B) {
+                chars::b_escape_char(s, buf)
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// `escape_fragments` escaping with `$escape`, the bytes counted by `$carry`
+/// are held in the buffer `$buf` until the next fragments
+///
+/// `$escape` is evaluated once per call, so the implementation is chosen
+/// before the loop over the fragments
+macro_rules! escape_fragments {
+    ($escape:expr, $carry:path, $buf:expr) => {
+        /// Write escaped `fragments` to `writer` one after the other, the output
+        /// is the same as escaping their concatenation
+        #[inline]
+        pub fn escape_fragments<'a, I, W>(fragments: I, writer: &mut W) -> ::core::fmt::Result
+        where
+            I: ::core::iter::IntoIterator<Item = &'a str>,
+            W: ::core::fmt::Write + ?::core::marker::Sized,
+        {
+            $crate::scan::escape_fragments(
+                ::core::iter::IntoIterator::into_iter(fragments),
+                writer,
+                $escape,
+                $carry,
+                &mut $buf,
+            )
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Chunked items of `escape_new!`
+///
+/// `$carry` is the length of the bytes at the end of a chunk that start an
+/// escape the next chunk may finish, they are held back until then
+macro_rules! escape_chunks {
+    ($carry:path) => {
+        $crate::cfg_alloc! {
+            /// Escapes input given in chunks, the output is the same as escaping
+            /// the concatenation of the chunks
+            #[derive(Debug, Default)]
+            pub struct StreamEscaper {
+                carry: $crate::writer::Vec<u8>,
+            }
+
+            impl StreamEscaper {
+                #[inline]
+                pub fn new() -> Self {
+                    StreamEscaper {
+                        carry: $crate::writer::Vec::new(),
+                    }
+                }
+
+                /// Append escaped `chunk` to `out`
+                ///
+                /// The bytes at its end starting a trigger that the next chunk
+                /// may finish are held back, they are escaped with the next
+                /// chunk or by `finish`
+                #[inline]
+                pub fn feed(&mut self, chunk: &[u8], out: &mut $crate::writer::Vec<u8>) {
+                    $crate::writer::feed_chunk(&mut self.carry, chunk, out, escape_bytes, $carry)
+                }
+
+                /// Append the rest of the output to `out`, ending the stream
+                #[inline]
+                pub fn finish(self, out: &mut $crate::writer::Vec<u8>) {
+                    escape_bytes(&self.carry, out)
+                }
+            }
+        }
+
+        $crate::cfg_std! {
+            /// Reader yielding the escaped bytes of `reader`
+            #[inline]
+            pub fn escaping_reader<R: $crate::writer::io::Read>(reader: R) -> $crate::io::EscapingReader<R> {
+                $crate::io::EscapingReader::with_carry(reader, escape_bytes, $carry)
+            }
+
+            /// Writer escaping everything written to it before forwarding it to `writer`
+            #[inline]
+            pub fn escaping_writer<W: $crate::writer::io::Write>(writer: W) -> $crate::io::EscapingWriter<W> {
+                $crate::io::EscapingWriter::with_carry(writer, escape_bytes, $carry)
+            }
+
+            $crate::cfg_tokio! {
+                /// Async writer escaping everything written to it before forwarding it to `writer`
+                #[inline]
+                pub fn async_escaping_writer<W: $crate::io::AsyncWrite + ::core::marker::Unpin>(
+                    writer: W,
+                ) -> $crate::io::AsyncEscapingWriter<W> {
+                    $crate::io::AsyncEscapingWriter::with_carry(writer, escape_bytes, $carry)
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// `unescape` of the `unescape` option
+///
+/// `find` is the search of the escaper of the first bytes of the quotes, in
+/// the module `quote_starts` generated without `escape_new!`, and the other
+/// arm takes the quotes sorted with their characters
+macro_rules! escape_unescape {
+    (find) => {
+        /// Position of the first quote start in `bytes`
+        #[inline]
+        pub fn find(bytes: &[u8]) -> ::core::option::Option<usize> {
+            $crate::scan::find_first(bytes, |fmt| _escape(bytes, fmt))
+        }
+    };
+    ([$($quote:expr),*]) => {
+        $crate::cfg_alloc! {
+            static V_UNESCAPE_QUOTES: &[(&str, u8)] = &[$($quote),*];
+
+            #[inline]
+            fn unescape_decode(rest: &[u8]) -> $crate::unescape::Decoded {
+                $crate::unescape::decode_quote(rest, V_UNESCAPE_QUOTES)
+            }
+
+            /// `s` with the quotes replaced by their characters, borrowing it
+            /// when there is none
+            ///
+            /// The quotes start with escaped characters, so an error is the
+            /// start of a quote that isn't followed by any
+            #[inline]
+            pub fn unescape(
+                s: &str,
+            ) -> ::core::result::Result<$crate::writer::Cow<'_, str>, $crate::UnescapeError> {
+                $crate::unescape::unescape_with(s, quote_starts::find, unescape_decode)
+            }
+
+            /// `s` with the quotes replaced by their characters and `U+FFFD` in
+            /// place of the starts of the quotes that aren't followed by any
+            #[inline]
+            pub fn unescape_lossy(s: &str) -> $crate::writer::Cow<'_, str> {
+                $crate::unescape::unescape_lossy_with(s, quote_starts::find, unescape_decode)
+            }
+
+            /// Stream unescaping input given in chunks, the quotes can be cut
+            /// between them
+            #[inline]
+            pub fn unescape_stream() -> $crate::UnescapeStream {
+                $crate::UnescapeStream::new(quote_starts::find, unescape_decode)
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Functions of `finder!`, the characters without a quote have an empty one
+macro_rules! escape_find {
+    () => {
+        /// Position of the first byte of `s` that is looked for
+        #[inline]
+        pub fn find_first_escape(s: &str) -> ::core::option::Option<usize> {
+            find_first_escape_bytes(s.as_bytes())
+        }
+
+        /// Position of the first byte of `bytes` that is looked for
+        ///
+        /// A byte may be inside a character, the scans go on from there with
+        /// the bytes
+        #[inline]
+        pub fn find_first_escape_bytes(bytes: &[u8]) -> ::core::option::Option<usize> {
+            $crate::scan::find_first(bytes, |fmt| _escape(bytes, fmt))
+        }
+
+        /// Quote of `b`, `None` when it isn't looked for or has no quote
+        #[inline]
+        pub fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+            chars::escape_byte(b).filter(|quote| !quote.is_empty())
+        }
+
+        /// Implementation used by `find_first_escape`
+        #[inline]
+        pub fn current_backend() -> $crate::Backend {
+            _backend()
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Escaping with the triggers of `new!`
+///
+/// `single` is called in the module `single` of the escaper of the single
+/// byte pairs, `none` stands for it when there are only triggers, and the
+/// other arm takes the triggers sorted with their quotes and the length of
+/// the longest quote. The first bytes of the triggers are looked for by
+/// `find` of the module `trigger_starts`
+macro_rules! escape_triggers {
+    (single) => {
+        pub(super) const QUOTES_MAX: usize = V_ESCAPE_QUOTES_MAX;
+
+        #[inline]
+        pub(super) fn escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            _escape(bytes, fmt)
+        }
+
+        #[inline]
+        pub(super) fn escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            _escape_fn()
+        }
+
+        #[inline]
+        pub(super) fn f_escape(
+            bytes: &[u8],
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> ::core::option::Option<usize> {
+            _f_escape(bytes, buf)
+        }
+
+        $crate::cfg_buffer! {
+            #[inline]
+            pub(super) fn b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+                _b_escape(bytes, buf)
+            }
+
+            #[inline]
+            pub(super) fn b_escape_char<B: $crate::Buffer>(c: char, buf: &mut B) {
+                chars::b_escape_char(c, buf)
+            }
+        }
+
+        #[inline]
+        pub(super) fn backend() -> $crate::Backend {
+            _backend()
+        }
+
+        #[inline]
+        pub(super) fn force_backend(backend: $crate::Backend) -> bool {
+            _force_backend(backend)
+        }
+
+        #[inline]
+        pub(super) fn tune() -> $crate::Backend {
+            _tune()
+        }
+
+        #[inline]
+        pub(super) fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+            chars::escape_byte(b)
+        }
+
+        #[inline]
+        pub(super) fn escape_char(c: char, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            chars::escape_char(c, fmt)
+        }
+
+        #[inline]
+        pub(super) fn f_escape_char(
+            c: char,
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> ::core::option::Option<usize> {
+            chars::f_escape_char(c, buf)
+        }
+    };
+    (none) => {
+        const V_ESCAPE_QUOTES_MAX: usize = 0;
+
+        mod chars {
+            #[inline]
+            pub fn escape_byte(_: u8) -> ::core::option::Option<&'static str> {
+                ::core::option::Option::None
+            }
+
+            #[inline]
+            pub fn escape_char(c: char, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::Write::write_char(fmt, c)
+            }
+
+            $crate::escape_char_safe!();
+        }
+
+        mod scalar {
+            $crate::escape_scalar_safe!();
+        }
+
+        use self::scalar::escape as _escape;
+        use self::scalar::f_escape as _f_escape;
+
+        #[inline]
+        fn _escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            _escape
+        }
+        $crate::cfg_buffer! {
+            use self::scalar::b_escape as _b_escape;
+        }
+        $crate::cfg_backend!(const $crate::Backend::Scalar);
+        $crate::escape_triggers!(single);
+    };
+    ([$(($trigger:expr, $quote:expr)),+], $max:expr) => {
+        static V_ESCAPE_TRIGGERS: &[$crate::triggers::Trigger] = &[$(($trigger, $quote)),+];
+
+        // The carry and a fragment start joined, the start has a character
+        // past the longest trigger
+        const V_ESCAPE_FRAGMENTS_BUF: usize =
+            2 * $crate::triggers::max_len(&[$($trigger as &[u8]),+]) + 4;
+
+        const V_ESCAPE_QUOTES_MAX: usize = if single::QUOTES_MAX < $max {
+            $max
+        } else {
+            single::QUOTES_MAX
+        };
+
+        #[allow(non_camel_case_types)]
+        struct V_ESCAPE;
+
+        #[inline]
+        fn _escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            $crate::triggers::escape(bytes, fmt, trigger_starts::find, V_ESCAPE_TRIGGERS, single::escape)
+        }
+
+        /// `_escape` with the escaper of the single bytes chosen once
+        #[inline]
+        fn _escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            let run = single::escape_fn();
+            move |bytes: &[u8], fmt: &mut ::core::fmt::Formatter| {
+                $crate::triggers::escape(bytes, fmt, trigger_starts::find, V_ESCAPE_TRIGGERS, &run)
+            }
+        }
+
+        #[allow(dead_code)]
+        #[inline]
+        fn _carry_len(bytes: &[u8]) -> usize {
+            $crate::triggers::carry_len(bytes, trigger_starts::find, V_ESCAPE_TRIGGERS)
+        }
+
+        #[inline]
+        fn _f_escape(
+            bytes: &[u8],
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> ::core::option::Option<usize> {
+            $crate::triggers::f_escape(bytes, buf, trigger_starts::find, V_ESCAPE_TRIGGERS, single::f_escape)
+        }
+
+        $crate::cfg_buffer! {
+            #[inline]
+            fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+                $crate::triggers::b_escape(bytes, buf, trigger_starts::find, V_ESCAPE_TRIGGERS, single::b_escape::<B>)
+            }
+        }
+
+        #[inline]
+        fn _backend() -> $crate::Backend {
+            single::backend()
+        }
+
+        #[inline]
+        fn _force_backend(backend: $crate::Backend) -> bool {
+            single::force_backend(backend)
+        }
+
+        #[inline]
+        fn _tune() -> $crate::Backend {
+            single::tune()
+        }
+
+        mod chars {
+            use super::{single, V_ESCAPE_TRIGGERS};
+
+            #[inline]
+            pub fn escape_byte(b: u8) -> ::core::option::Option<&'static str> {
+                single::escape_byte(b)
+            }
+
+            #[inline]
+            pub fn quote_at(rest: &[u8]) -> ::core::option::Option<(&'static str, usize)> {
+                $crate::triggers::quote_at(rest, V_ESCAPE_TRIGGERS, escape_byte)
+            }
+
+            #[inline]
+            pub fn escape_char(c: char, fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                single::escape_char(c, fmt)
+            }
+
+            #[inline]
+            pub fn f_escape_char(
+                c: char,
+                buf: &mut [::core::mem::MaybeUninit<u8>],
+            ) -> ::core::option::Option<usize> {
+                single::f_escape_char(c, buf)
+            }
+
+            $crate::cfg_buffer! {
+                #[inline]
+                pub fn b_escape_char<B: $crate::Buffer>(c: char, buf: &mut B) {
+                    single::b_escape_char(c, buf)
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Define `$name` calling the implementation `$select` returns for the
+/// current backend
+///
+/// The backend is resolved in the first call, and the result is cached in
+/// the atomic function pointer `$cache` so later calls are a load and an
+/// indirect call. `force_backend` overwrites it
+// https://github.com/BurntSushi/rust-memchr/blob/master/src/x86/mod.rs#L9-L29
+macro_rules! escape_dispatch {
+    ($vis:vis fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty, $cache:ident, $select:ident) => {
+        static $cache: ::core::sync::atomic::AtomicPtr<()> = ::core::sync::atomic::AtomicPtr::new({
+            unsafe fn detect($($arg: $ty),*) -> $ret {
+                let fun = $select(_backend()).expect("backend is available");
+                $cache.store(fun as *mut (), ::core::sync::atomic::Ordering::Relaxed);
+                fun($($arg),*)
+            }
+
+            detect as *mut ()
+        });
+
+        #[inline(always)]
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            type Fn = unsafe fn($($ty),*) -> $ret;
+
+            let fun = $cache.load(::core::sync::atomic::Ordering::Relaxed);
+            // SAFETY: only implementations the running cpu supports are cached
+            unsafe { ::core::mem::transmute::<*mut (), Fn>(fun)($($arg),*) }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Implementation `$fn` of `$backend`, if it was generated and the running
+/// cpu supports it
+///
+/// The tables are the ones of `cfg_escape`
+macro_rules! escape_select {
+    (
+        $fn:ident,
+        $backend:expr,
+        [$(($rcfg:meta, $rmodule:ident, $rbackend:ident, $detect:expr)),*],
+        [$(($scfg:meta, $smodule:ident, $sbackend:ident)),*]
+    ) => {
+        match $backend {
+            $crate::Backend::Scalar => ::core::option::Option::Some(scalar::$fn as _),
+            $crate::Backend::Swar => ::core::option::Option::Some(swar::$fn as _),
+            $(
+                #[cfg($rcfg)]
+                $crate::Backend::$rbackend if $detect => {
+                    ::core::option::Option::Some(ranges::$rmodule::$fn as _)
+                }
+            )*
+            $(
+                #[cfg($scfg)]
+                $crate::Backend::$sbackend => ::core::option::Option::Some(ranges::$smodule::$fn as _),
+            )*
+            _ => ::core::option::Option::None,
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// First `$value` whose cfg is enabled, or `$default`
+///
+/// Only the chosen one is compiled, so the others may name modules that
+/// don't exist in the target
+macro_rules! cfg_first {
+    ([($cfg:meta, $value:expr) $(, ($rcfg:meta, $rvalue:expr))*], $default:expr) => {{
+        #[cfg($cfg)] {
+            $value
+        }
+        #[cfg(not($cfg))] {
+            $crate::cfg_first!([$(($rcfg, $rvalue)),*], $default)
+        }
+    }};
+    ([], $default:expr) => {
+        $default
     };
-    (true, $($t:tt)+) => {
-        #[cfg(target_arch = "x86_64")]
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// cfg_if for escape function
+///
+/// Takes the implementations of the `ranges` modules in the order they are
+/// preferred, as built by the generator:
+///
+/// - `[(cfg, module, Backend, detect), ...]` those checked on the running cpu
+///   with `detect`, they are dispatched through a cached function pointer
+/// - `[(cfg, module, Backend), ...]` those the cfg is enough for, the first
+///   one enabled is called directly
+///
+/// Without any of them `swar` is called
+macro_rules! cfg_escape {
+    (scalar) => {
         #[inline(always)]
-        pub unsafe fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
-            $crate::cfg_escape_bytes!(if $($t)+, bytes, buf)
+        fn _escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            scalar::escape(bytes, fmt)
         }
 
-        #[cfg(not(all(target_arch = "x86_64", not(b_escape_nosimd))))]
-        $crate::cfg_escape_bytes!(fn);
+        #[allow(dead_code)]
+        #[inline(always)]
+        fn _escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            scalar::escape
+        }
     };
-    (fn) => {
+    (
+        [$(($rcfg:meta, $rmodule:ident, $rbackend:ident, $detect:expr)),*],
+        [$(($scfg:meta, $smodule:ident, $sbackend:ident)),*]
+    ) => {
+        #[cfg(any($($rcfg),*))]
+        $crate::escape_dispatch!(
+            fn _escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result,
+            V_ESCAPE_FN,
+            _escape_select
+        );
+
+        #[cfg(any($($rcfg),*))]
+        fn _escape_select(
+            backend: $crate::Backend,
+        ) -> ::core::option::Option<unsafe fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result> {
+            $crate::escape_select!(
+                escape,
+                backend,
+                [$(($rcfg, $rmodule, $rbackend, $detect)),*],
+                [$(($scfg, $smodule, $sbackend)),*]
+            )
+        }
+
+        /// `_escape` with the implementation chosen once, for loops over many
+        /// inputs
+        #[cfg(any($($rcfg),*))]
+        #[allow(dead_code)]
+        #[inline]
+        fn _escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            let fun = _escape_select(_backend()).expect("backend is available");
+            // SAFETY: only implementations the running cpu supports are selected
+            move |bytes: &[u8], fmt: &mut ::core::fmt::Formatter| unsafe { fun(bytes, fmt) }
+        }
+
+        #[cfg(not(any($($rcfg),*)))]
+        #[inline(always)]
+        fn _escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            unsafe {
+                $crate::cfg_first!([$(($scfg, ranges::$smodule::escape)),*], swar::escape)(bytes, fmt)
+            }
+        }
+
+        #[cfg(not(any($($rcfg),*)))]
+        #[allow(dead_code)]
         #[inline(always)]
-        pub unsafe fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+        fn _escape_fn() -> impl ::core::ops::Fn(&[u8], &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            _escape
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// cfg_if for escape function
+///
+/// The tables are the ones of `cfg_escape`
+macro_rules! cfg_escape_ptr {
+    (scalar) => {
+        #[inline(always)]
+        pub fn _f_escape(bytes: &[u8], buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
+            scalar::f_escape(bytes, buf)
+        }
+    };
+    (
+        [$(($rcfg:meta, $rmodule:ident, $rbackend:ident, $detect:expr)),*],
+        [$(($scfg:meta, $smodule:ident, $sbackend:ident)),*]
+    ) => {
+        #[cfg(any($($rcfg),*))]
+        $crate::escape_dispatch!(
+            pub fn _f_escape(bytes: &[u8], buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize>,
+            V_ESCAPE_F_FN,
+            _f_escape_select
+        );
+
+        #[cfg(any($($rcfg),*))]
+        fn _f_escape_select(
+            backend: $crate::Backend,
+        ) -> ::core::option::Option<unsafe fn(&[u8], &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize>> {
+            $crate::escape_select!(
+                f_escape,
+                backend,
+                [$(($rcfg, $rmodule, $rbackend, $detect)),*],
+                [$(($scfg, $smodule, $sbackend)),*]
+            )
+        }
+
+        #[cfg(not(any($($rcfg),*)))]
+        #[inline(always)]
+        pub fn _f_escape(bytes: &[u8], buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
+            unsafe {
+                $crate::cfg_first!([$(($scfg, ranges::$smodule::f_escape)),*], swar::f_escape)(bytes, buf)
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// cfg_if for escape function
+///
+/// The tables are the ones of `cfg_escape`. The function is generic over the
+/// buffer so it can't be cached, the backend is matched in each call
+macro_rules! cfg_escape_bytes {
+    (scalar) => {
+        #[inline(always)]
+        pub fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
             scalar::b_escape(bytes, buf)
         }
     };
-    (if true, $bytes:ident, $buf:ident) => {{
-        #[cfg(not(v_escape_avx))] {
-            #[cfg(not(v_escape_sse))] {
-                scalar::b_escape($bytes, $buf)
+    (
+        [$(($rcfg:meta, $rmodule:ident, $rbackend:ident, $detect:expr)),*],
+        [$(($scfg:meta, $smodule:ident, $sbackend:ident)),*]
+    ) => {
+        #[cfg(any($($rcfg),*))]
+        #[inline(always)]
+        pub fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+            // The backend is only set to one the running cpu supports
+            unsafe {
+                match _backend() {
+                    $crate::Backend::Scalar => scalar::b_escape(bytes, buf),
+                    $(
+                        #[cfg($rcfg)]
+                        $crate::Backend::$rbackend => ranges::$rmodule::b_escape(bytes, buf),
+                    )*
+                    $(
+                        #[cfg($scfg)]
+                        $crate::Backend::$sbackend => ranges::$smodule::b_escape(bytes, buf),
+                    )*
+                    _ => swar::b_escape(bytes, buf),
+                }
             }
-            #[cfg(v_escape_sse)] {
-                ranges::sse::b_escape($bytes, $buf)
+        }
+
+        #[cfg(not(any($($rcfg),*)))]
+        #[inline(always)]
+        pub fn _b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+            unsafe {
+                $crate::cfg_first!([$(($scfg, ranges::$smodule::b_escape)),*], swar::b_escape)(bytes, buf)
             }
         }
-        #[cfg(v_escape_avx)] {
-            ranges::avx::b_escape($bytes, $buf)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// cfg_if for backend functions
+///
+/// The tables are the ones of `cfg_escape`
+macro_rules! cfg_backend {
+    (scalar) => {
+        $crate::cfg_backend!(const $crate::Backend::Scalar);
+    };
+    (
+        [$(($rcfg:meta, $rmodule:ident, $rbackend:ident, $detect:expr)),*],
+        [$(($scfg:meta, $smodule:ident, $sbackend:ident)),*]
+    ) => {
+        #[cfg(any($($rcfg),*))]
+        $crate::cfg_backend!(dispatch);
+
+        #[cfg(any($($rcfg),*))]
+        fn _detect_backend() -> $crate::Backend {
+            $(
+                #[cfg($rcfg)]
+                {
+                    if $detect {
+                        return $crate::Backend::$rbackend;
+                    }
+                }
+            )*
+            $crate::cfg_first!([$(($scfg, $crate::Backend::$sbackend)),*], $crate::Backend::Swar)
+        }
+
+        #[cfg(not(any($($rcfg),*)))]
+        $crate::cfg_backend!(const $crate::cfg_first!(
+            [$(($scfg, $crate::Backend::$sbackend)),*],
+            $crate::Backend::Swar
+        ));
+    };
+    (const $backend:expr) => {
+        #[inline(always)]
+        fn _backend() -> $crate::Backend {
+            $backend
         }
-    }};
-    (if false, $bytes:ident, $buf:ident) => {{
-        #[cfg(not(v_escape_sse))] {
-            scalar::b_escape($bytes, $buf)
+
+        #[inline(always)]
+        fn _force_backend(backend: $crate::Backend) -> bool {
+            backend == _backend()
         }
-        #[cfg(v_escape_sse)] {
-            ranges::sse::b_escape($bytes, $buf)
+
+        #[inline(always)]
+        fn _tune() -> $crate::Backend {
+            _backend()
         }
-    }};
+    };
+    (dispatch) => {
+        // Index in `Backend::ALL` plus one, zero until it is resolved
+        static V_ESCAPE_BACKEND: ::core::sync::atomic::AtomicU8 = ::core::sync::atomic::AtomicU8::new(0);
+
+        fn _backend() -> $crate::Backend {
+            use ::core::sync::atomic::Ordering;
+
+            match V_ESCAPE_BACKEND.load(Ordering::Relaxed) {
+                0 => {
+                    let backend = _detect_backend();
+                    V_ESCAPE_BACKEND.store(backend as u8 + 1, Ordering::Relaxed);
+                    backend
+                }
+                i => $crate::Backend::ALL[i as usize - 1],
+            }
+        }
+
+        fn _force_backend(backend: $crate::Backend) -> bool {
+            use ::core::sync::atomic::Ordering;
+
+            match (_escape_select(backend), _f_escape_select(backend)) {
+                (::core::option::Option::Some(escape), ::core::option::Option::Some(f_escape)) => {
+                    V_ESCAPE_BACKEND.store(backend as u8 + 1, Ordering::Relaxed);
+                    V_ESCAPE_FN.store(escape as *mut (), Ordering::Relaxed);
+                    V_ESCAPE_F_FN.store(f_escape as *mut (), Ordering::Relaxed);
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        fn _tune() -> $crate::Backend {
+            use ::core::hint::black_box;
+            use ::core::mem::MaybeUninit;
+            use $crate::time::{Duration, Instant};
+
+            const ROUNDS: usize = 1000;
+            // Short text with some of the usual escapes
+            const INPUT: &[u8] = b"<p class=\"note\">Fish & chips, 'tea' for two</p>\n\
+                <a href=\"/?q=1&page=2\">next \\ last</a>";
+
+            let mut buf = [MaybeUninit::uninit(); 16 * INPUT.len()];
+            let mut best = (_backend(), Duration::MAX);
+            for &backend in &$crate::Backend::ALL {
+                if let ::core::option::Option::Some(f_escape) = _f_escape_select(backend) {
+                    let start = Instant::now();
+                    for _ in 0..ROUNDS {
+                        unsafe { black_box(f_escape(black_box(INPUT), &mut buf)) };
+                    }
+                    let elapsed = start.elapsed();
+
+                    if elapsed < best.1 {
+                        best = (backend, elapsed);
+                    }
+                }
+            }
+
+            _force_backend(best.0);
+            best.0
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Differential tests of a `new!` escaper, the output of every backend it can
+/// be forced to is checked against escaping the input byte by byte
+///
+/// The backend is forced for the whole escaper, so its other tests running
+/// meanwhile may use any of them
+macro_rules! escape_tests {
+    () => {
+        #[cfg(test)]
+        mod v_escape_tests {
+            extern crate std;
+
+            use self::std::{prelude::v1::*, vec};
+
+            use super::*;
+
+            /// Characters of 2, 3 and 4 bytes, at the edges of their lengths
+            const WIDE: [char; 7] = [
+                '\u{80}',
+                'é',
+                '\u{7FF}',
+                '\u{800}',
+                '✓',
+                '\u{FFFF}',
+                '\u{10FFFF}',
+            ];
+
+            /// `s` escaped byte by byte
+            fn reference(s: &str) -> Vec<u8> {
+                let mut out = Vec::with_capacity(s.len());
+                let mut rest = s.as_bytes();
+                while !rest.is_empty() {
+                    match chars::quote_at(rest) {
+                        ::core::option::Option::Some((quote, len)) => {
+                            out.extend_from_slice(quote.as_bytes());
+                            rest = &rest[len..];
+                        }
+                        ::core::option::Option::None => {
+                            out.push(rest[0]);
+                            rest = &rest[1..];
+                        }
+                    }
+                }
+                out
+            }
+
+            /// Check every input with every backend, the detected one is
+            /// restored at the end
+            fn check(inputs: &[String]) {
+                let detected = current_backend();
+                for &backend in $crate::Backend::ALL.iter() {
+                    if !force_backend(backend) {
+                        continue;
+                    }
+                    for s in inputs {
+                        let expected = reference(s);
+                        ::core::assert_eq!(
+                            escape(s).to_string().as_bytes(),
+                            &expected[..],
+                            "{:?} escaping {:?}",
+                            backend,
+                            s
+                        );
+
+                        let mut buf = vec![0; expected.len()];
+                        let len = f_escape(s.as_bytes(), $crate::as_uninit(&mut buf));
+                        ::core::assert_eq!(
+                            len,
+                            ::core::option::Option::Some(expected.len()),
+                            "{:?} escaping {:?}",
+                            backend,
+                            s
+                        );
+                        ::core::assert_eq!(buf, expected, "{:?} escaping {:?}", backend, s);
+                    }
+                }
+                force_backend(detected);
+            }
+
+            #[test]
+            fn test_single_bytes() {
+                let chars = (0..0x80u8).map(char::from).chain(WIDE.iter().copied());
+                let mut inputs = vec![];
+                for c in chars {
+                    // At every offset of the widest vector and past it
+                    for pad in &[0, 1, 15, 31, 63, 64] {
+                        let mut s = String::new();
+                        s.extend(::core::iter::repeat('a').take(*pad));
+                        s.push(c);
+                        s.extend(::core::iter::repeat('a').take(*pad));
+                        inputs.push(s);
+                    }
+                }
+                check(&inputs);
+            }
+
+            #[test]
+            fn test_lengths() {
+                let pattern: String = (0..0x80u8)
+                    .map(char::from)
+                    .chain(WIDE.iter().copied())
+                    .collect();
+                let pattern = pattern.repeat(8);
+                let mut inputs = vec![];
+                for &len in &[
+                    0, 1, 7, 8, 9, 15, 16, 17, 31, 32, 33, 63, 64, 65, 127, 128, 129,
+                ] {
+                    // Every cut of the pattern, ending at a char boundary
+                    for start in 0..8 {
+                        let s = &pattern[pattern.char_indices().nth(start).unwrap().0..];
+                        let end = (0..=len).rev().find(|&i| s.is_char_boundary(i)).unwrap();
+                        inputs.push(String::from(&s[..end]));
+                    }
+                }
+                check(&inputs);
+            }
+
+            #[test]
+            fn test_random() {
+                // xorshift64, with a fixed seed to be reproducible
+                let mut state = 0x9E37_79B9_7F4A_7C15u64;
+                let mut next = move || {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state
+                };
+                let inputs: Vec<String> = (0..256)
+                    .map(|_| {
+                        let len = next() % 300;
+                        (0..len)
+                            .map(|_| match next() % 16 {
+                                0 => WIDE[(next() % 7) as usize],
+                                n => char::from((next() >> n) as u8 & 0x7F),
+                            })
+                            .collect()
+                    })
+                    .collect();
+                check(&inputs);
+            }
+        }
+    };
 }
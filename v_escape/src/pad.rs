@@ -0,0 +1,104 @@
+use core::fmt::{self, Alignment, Display, Formatter, Write};
+
+/// `Display` calling `escape`
+pub(crate) struct Escaped<F>(pub(crate) F);
+
+impl<F: Fn(&mut Formatter) -> fmt::Result> Display for Escaped<F> {
+    #[inline]
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        (self.0)(fmt)
+    }
+}
+
+/// Counts chars, stopping when it reaches `max`
+struct Counter {
+    chars: usize,
+    max: usize,
+}
+
+impl Write for Counter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.chars += s.chars().count();
+        if self.max <= self.chars {
+            self.chars = self.max;
+            return Err(fmt::Error);
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes up to `remaining` chars to the inner formatter
+struct Truncate<'a, 'b> {
+    inner: &'a mut Formatter<'b>,
+    remaining: usize,
+}
+
+impl<'a, 'b> Write for Truncate<'a, 'b> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.char_indices().nth(self.remaining) {
+            Some((i, _)) => {
+                self.remaining = 0;
+                self.inner.write_str(&s[..i])
+            }
+            None => {
+                self.remaining -= s.chars().count();
+                self.inner.write_str(s)
+            }
+        }
+    }
+}
+
+/// Write the output of `escape` to `fmt` like `Formatter::pad` would write it,
+/// truncated to the precision and padded to the width
+///
+/// The escaped length is needed before writing, so it escapes twice. Without
+/// a width nor a precision it is written as it is, in a single pass
+pub fn pad<F>(fmt: &mut Formatter, escape: F) -> fmt::Result
+where
+    F: Fn(&mut Formatter) -> fmt::Result,
+{
+    if fmt.width().is_none() && fmt.precision().is_none() {
+        return escape(fmt);
+    }
+    let escaped = Escaped(escape);
+    let max = fmt.precision().unwrap_or(usize::MAX);
+    let width = fmt.width().unwrap_or(0);
+
+    let mut counter = Counter { chars: 0, max };
+    if width != 0 {
+        // An error means it reached the precision
+        let _ = write!(counter, "{}", escaped);
+    }
+
+    let padding = width.saturating_sub(counter.chars);
+    let (pre, post) = match fmt.align() {
+        Some(Alignment::Right) => (padding, 0),
+        Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+        Some(Alignment::Left) | None => (0, padding),
+    };
+    let fill = fmt.fill();
+
+    for _ in 0..pre {
+        fmt.write_char(fill)?;
+    }
+    if max == usize::MAX {
+        write!(fmt, "{}", escaped)?;
+    } else {
+        write!(
+            Truncate {
+                inner: fmt,
+                remaining: max,
+            },
+            "{}",
+            escaped
+        )?;
+    }
+    for _ in 0..post {
+        fmt.write_char(fill)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Ranges powerpc64 altivec implementation
+//!
+//! `vec_xl` has no alignment requirements so there is no aligning step
+//!
+//! There is no movemask in altivec. The comparison is weighted by the bit of each
+//! lane and the four lanes of every word are added with `vec_sum4s`. Every step
+//! works on lanes, never on the register bytes, so bit `n` of the mask is byte `n`
+//! of the slice in both little and big endian
+use core::{
+    arch::powerpc64::{
+        vec_add, vec_and, vec_cmpeq, vec_cmpgt, vec_extract, vec_or, vec_splats, vec_sum4s, vec_xl,
+        vector_bool_char, vector_signed_char, vector_unsigned_char, vector_unsigned_int,
+    },
+    mem::{size_of, transmute},
+};
+
+use super::{below, translation, Escaper, Escapes, Sink};
+
+const ALTIVEC_VECTOR_SIZE: usize = size_of::<vector_signed_char>();
+
+kernels!("None, `altivec` is enabled at compile time");
+
+#[inline(always)]
+unsafe fn bool_or(a: vector_bool_char, b: vector_bool_char) -> vector_bool_char {
+    transmute::<vector_unsigned_char, vector_bool_char>(vec_or(
+        a,
+        transmute::<vector_bool_char, vector_unsigned_char>(b),
+    ))
+}
+
+/// Lanes of `a` in the ranges of `escapes`
+#[inline(always)]
+unsafe fn masking<E: Escapes>(escapes: &E, a: vector_signed_char) -> vector_bool_char {
+    let ranges = escapes.ranges();
+    // No byte is greater than itself
+    let mut mask = vec_cmpgt(a, a);
+    for &[l, r] in ranges.intervals {
+        mask = bool_or(
+            mask,
+            vec_cmpgt(
+                vec_add(a, vec_splats(translation(r))),
+                vec_splats(below(l, r)),
+            ),
+        );
+    }
+    for &c in ranges.chars {
+        mask = bool_or(mask, vec_cmpeq(a, vec_splats(c as i8)));
+    }
+    mask
+}
+
+#[inline(always)]
+unsafe fn movemask(m: vector_bool_char) -> u64 {
+    let v_weights: vector_unsigned_char =
+        transmute([1u8, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128]);
+    let v_zero: vector_unsigned_int = vec_splats(0u32);
+    let s: vector_unsigned_int = vec_sum4s(vec_and(m, v_weights), v_zero);
+    let mask = vec_extract::<_, 0>(s)
+        | vec_extract::<_, 1>(s)
+        | ((vec_extract::<_, 2>(s) | vec_extract::<_, 3>(s)) << 8);
+    mask as u64
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < ALTIVEC_VECTOR_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
+
+    // Process all slices with at least one set of length `ALTIVEC_VECTOR_SIZE`
+    while ptr <= end_ptr.sub(ALTIVEC_VECTOR_SIZE) {
+        let a: vector_signed_char = vec_xl(0, ptr as *const i8);
+        s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)))?;
+        ptr = ptr.add(ALTIVEC_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(ALTIVEC_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less than `ALTIVEC_VECTOR_SIZE` elements
+    // so the last vector is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = ALTIVEC_VECTOR_SIZE - sub!(end_ptr, ptr);
+            let a: vector_signed_char = vec_xl(0, ptr.sub(d) as *const i8);
+            s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)) >> d)?;
+        });
+    }
+
+    s.finish()
+}
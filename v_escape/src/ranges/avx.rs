@@ -1,81 +1,134 @@
-/// Generate ranges avx2 implementation
-///
-/// ## Following macros must be defined
-/// - `fallback!()`
-///     when length is less than 32
-/// - `write_mask!(mut $mask: {integer}, $ptr: *const u8)`
-///     when bit mask is non equal 0
-/// - `write_forward(mut $mask: {integer}, $until: usize)`
-///     when bit mask is non equal 0  and valid bits until
-///
-#[macro_export]
-macro_rules! loop_range_switch_avx2  {
-    (($len:ident, $ptr:ident, $start_ptr:ident, $end_ptr:ident) $($t:tt, )+) => {
-        use std::arch::x86_64::{
-            __m256i, _mm256_load_si256, _mm256_loadu_si256, _mm256_movemask_epi8, _mm256_or_si256,
-        };
-
-        const M256_VECTOR_SIZE: usize = std::mem::size_of::<__m256i>();
-
-        if $len < M256_VECTOR_SIZE {
-            $crate::loop_range_switch_sse2!(($len, $ptr, $start_ptr, $end_ptr) $($t, )+);
-        } else {
-            $crate::translations_256!($($t, )+);
-
-            // Aligning pointer by using `_mm256_loadu_si256` on unaligned bytes.
-            {
-                const M256_VECTOR_ALIGN: usize = M256_VECTOR_SIZE - 1;
-                let align = M256_VECTOR_SIZE - ($start_ptr as usize & M256_VECTOR_ALIGN);
-                if align < M256_VECTOR_SIZE {
-                    let mut mask = {
-                        let a = _mm256_loadu_si256($ptr as *const __m256i);
-                        _mm256_movemask_epi8(masking!(a))
-                    };
-
-                    if mask != 0 {
-                        write_forward!(mask, align);
-                    }
-                    // Aligning pointer
-                    $ptr = $ptr.add(align);
-                }
-            }
+//! Ranges avx2 implementation
+use core::{
+    arch::x86_64::{
+        __m256i, _mm256_add_epi8, _mm256_cmpeq_epi8, _mm256_cmpgt_epi8, _mm256_load_si256,
+        _mm256_loadu_si256, _mm256_movemask_epi8, _mm256_or_si256, _mm256_set1_epi8,
+        _mm256_setzero_si256,
+    },
+    mem::size_of,
+};
 
-            $crate::avx_main_loop!(($len, $ptr, $end_ptr) $($t, )+);
+use super::{below, translation, Escaper, Escapes, Sink};
 
-            // When the rest of string has a $length greater then `M256_VECTOR_SIZE`
-            // but less than `LOOP_SIZE`, we process it `M256_VECTOR_SIZE` bits at
-            // a time until there are left less then `M256_VECTOR_SIZE` elements
-            while $ptr <= $end_ptr.sub(M256_VECTOR_SIZE) {
-                debug_assert_eq!(0, ($ptr as usize) % M256_VECTOR_SIZE);
+const M256_VECTOR_SIZE: usize = size_of::<__m256i>();
+const M256_VECTOR_ALIGN: usize = M256_VECTOR_SIZE - 1;
+const LOOP_SIZE: usize = 4 * M256_VECTOR_SIZE;
 
-                let mut mask = {
-                    let a = _mm256_load_si256($ptr as *const __m256i);
-                    _mm256_movemask_epi8(masking!(a))
-                };
+kernels!("The running cpu must support avx2");
 
-                if mask != 0 {
-                    write_mask!(mask, $ptr);
-                }
-                $ptr = $ptr.add(M256_VECTOR_SIZE);
-            }
+/// Lanes of `a` in the ranges of `escapes`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn masking<E: Escapes>(escapes: &E, a: __m256i) -> __m256i {
+    let ranges = escapes.ranges();
+    let mut mask = _mm256_setzero_si256();
+    for &[l, r] in ranges.intervals {
+        mask = _mm256_or_si256(
+            mask,
+            _mm256_cmpgt_epi8(
+                _mm256_add_epi8(a, _mm256_set1_epi8(translation(r))),
+                _mm256_set1_epi8(below(l, r)),
+            ),
+        );
+    }
+    for &c in ranges.chars {
+        mask = _mm256_or_si256(mask, _mm256_cmpeq_epi8(a, _mm256_set1_epi8(c as i8)));
+    }
+    mask
+}
 
-            debug_assert!($end_ptr.sub(M256_VECTOR_SIZE) < $ptr);
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn movemask(a: __m256i) -> u64 {
+    _mm256_movemask_epi8(a) as u32 as u64
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
 
-            // At this point at most there is less then `M256_VECTOR_SIZE` elements
-            // so the macro `write_forward` is used to finalize de process
-            if $ptr < $end_ptr {
-                let d = M256_VECTOR_SIZE - $crate::sub!($end_ptr, $ptr);
+    if len < M256_VECTOR_SIZE {
+        return super::sse::run(s);
+    }
+
+    // Aligning pointer by using `_mm256_loadu_si256` on unaligned bytes.
+    {
+        let align = M256_VECTOR_SIZE - (start_ptr as usize & M256_VECTOR_ALIGN);
+        if align < M256_VECTOR_SIZE {
+            let mask = movemask(masking(escapes, _mm256_loadu_si256(ptr as *const __m256i)));
+            s.mask(0, mask & ((1 << align) - 1))?;
+            // Aligning pointer
+            ptr = ptr.add(align);
+        }
+    }
 
-                let mut mask = ({
-                    debug_assert_eq!(M256_VECTOR_SIZE, $crate::sub!($end_ptr, $ptr.sub(d)), "Over runs");
-                    let a = _mm256_loadu_si256($ptr.sub(d) as *const __m256i);
-                    _mm256_movemask_epi8(masking!(a))
-                } as u32).wrapping_shr(d as u32);
+    // Using function `_mm256_load_si256` for faster behavior on aligned bytes.
+    // Getting 4 sets of length `M256_VECTOR_SIZE` each (`LOOP_SIZE=4*M256_VECTOR_SIZE`)
+    // and combining them with `or`, so the common case of no escapes
+    // costs a single test per `LOOP_SIZE` elements
+    if LOOP_SIZE <= len {
+        while ptr <= end_ptr.sub(LOOP_SIZE) {
+            debug_assert_eq!(0, (ptr as usize) % M256_VECTOR_SIZE);
 
-                if mask != 0 {
-                    write_mask!(mask, $ptr);
-                }
+            let cmp_a = masking(escapes, _mm256_load_si256(ptr as *const __m256i));
+            let cmp_b = masking(
+                escapes,
+                _mm256_load_si256(ptr.add(M256_VECTOR_SIZE) as *const __m256i),
+            );
+            let cmp_c = masking(
+                escapes,
+                _mm256_load_si256(ptr.add(M256_VECTOR_SIZE * 2) as *const __m256i),
+            );
+            let cmp_d = masking(
+                escapes,
+                _mm256_load_si256(ptr.add(M256_VECTOR_SIZE * 3) as *const __m256i),
+            );
+
+            if _mm256_movemask_epi8(_mm256_or_si256(
+                _mm256_or_si256(cmp_a, cmp_b),
+                _mm256_or_si256(cmp_c, cmp_d),
+            )) != 0
+            {
+                let at = sub!(ptr, start_ptr);
+                s.mask(at, movemask(cmp_a))?;
+                s.mask(at + M256_VECTOR_SIZE, movemask(cmp_b))?;
+                s.mask(at + M256_VECTOR_SIZE * 2, movemask(cmp_c))?;
+                s.mask(at + M256_VECTOR_SIZE * 3, movemask(cmp_d))?;
             }
+
+            ptr = ptr.add(LOOP_SIZE);
         }
-    };
+    }
+
+    // When the rest of string has a length greater then `M256_VECTOR_SIZE`
+    // but less than `LOOP_SIZE`, we process it `M256_VECTOR_SIZE` bits at
+    // a time until there are left less then `M256_VECTOR_SIZE` elements
+    while ptr <= end_ptr.sub(M256_VECTOR_SIZE) {
+        debug_assert_eq!(0, (ptr as usize) % M256_VECTOR_SIZE);
+        let mask = movemask(masking(escapes, _mm256_load_si256(ptr as *const __m256i)));
+        s.mask(sub!(ptr, start_ptr), mask)?;
+        ptr = ptr.add(M256_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(M256_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less then `M256_VECTOR_SIZE` elements
+    // so the last vector is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = M256_VECTOR_SIZE - sub!(end_ptr, ptr);
+            let mask = movemask(masking(
+                escapes,
+                _mm256_loadu_si256(ptr.sub(d) as *const __m256i),
+            ));
+            s.mask(sub!(ptr, start_ptr), mask >> d)?;
+        });
+    }
+
+    s.finish()
 }
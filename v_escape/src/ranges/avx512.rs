@@ -0,0 +1,117 @@
+//! Ranges avx512bw implementation
+//!
+//! Comparisons write straight to a `__mmask64`
+use core::{
+    arch::x86_64::{
+        __m512i, _mm512_add_epi8, _mm512_cmpeq_epi8_mask, _mm512_cmpgt_epi8_mask,
+        _mm512_load_si512, _mm512_loadu_si512, _mm512_maskz_loadu_epi8, _mm512_set1_epi8,
+    },
+    mem::size_of,
+};
+
+use super::{below, translation, Escaper, Escapes, Sink};
+
+const M512_VECTOR_SIZE: usize = size_of::<__m512i>();
+const M512_VECTOR_ALIGN: usize = M512_VECTOR_SIZE - 1;
+const LOOP_SIZE: usize = 4 * M512_VECTOR_SIZE;
+
+kernels!("The running cpu must support avx2 and avx512bw");
+
+/// Lanes of `a` in the ranges of `escapes`
+#[inline]
+#[target_feature(enable = "avx2,avx512bw")]
+unsafe fn masking<E: Escapes>(escapes: &E, a: __m512i) -> u64 {
+    let ranges = escapes.ranges();
+    let mut mask = 0;
+    for &[l, r] in ranges.intervals {
+        mask |= _mm512_cmpgt_epi8_mask(
+            _mm512_add_epi8(a, _mm512_set1_epi8(translation(r))),
+            _mm512_set1_epi8(below(l, r)),
+        );
+    }
+    for &c in ranges.chars {
+        mask |= _mm512_cmpeq_epi8_mask(a, _mm512_set1_epi8(c as i8));
+    }
+    mask
+}
+
+#[target_feature(enable = "avx2,avx512bw")]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < M512_VECTOR_SIZE {
+        return super::avx::run(s);
+    }
+
+    // Aligning pointer by using `_mm512_loadu_si512` on unaligned bytes.
+    {
+        let align = M512_VECTOR_SIZE - (start_ptr as usize & M512_VECTOR_ALIGN);
+        if align < M512_VECTOR_SIZE {
+            let mask = masking(escapes, _mm512_loadu_si512(ptr as *const __m512i));
+            s.mask(0, mask & ((1 << align) - 1))?;
+            // Aligning pointer
+            ptr = ptr.add(align);
+        }
+    }
+
+    // Using function `_mm512_load_si512` for faster behavior on aligned bytes.
+    // Getting 4 sets of length `M512_VECTOR_SIZE` each (`LOOP_SIZE=4*M512_VECTOR_SIZE`)
+    // and combining their masks with `or`, so the common case of no escapes
+    // costs a single test per `LOOP_SIZE` elements
+    if LOOP_SIZE <= len {
+        while ptr <= end_ptr.sub(LOOP_SIZE) {
+            debug_assert_eq!(0, (ptr as usize) % M512_VECTOR_SIZE);
+
+            let mask_a = masking(escapes, _mm512_load_si512(ptr as *const __m512i));
+            let mask_b = masking(
+                escapes,
+                _mm512_load_si512(ptr.add(M512_VECTOR_SIZE) as *const __m512i),
+            );
+            let mask_c = masking(
+                escapes,
+                _mm512_load_si512(ptr.add(M512_VECTOR_SIZE * 2) as *const __m512i),
+            );
+            let mask_d = masking(
+                escapes,
+                _mm512_load_si512(ptr.add(M512_VECTOR_SIZE * 3) as *const __m512i),
+            );
+
+            if (mask_a | mask_b | mask_c | mask_d) != 0 {
+                let at = sub!(ptr, start_ptr);
+                s.mask(at, mask_a)?;
+                s.mask(at + M512_VECTOR_SIZE, mask_b)?;
+                s.mask(at + M512_VECTOR_SIZE * 2, mask_c)?;
+                s.mask(at + M512_VECTOR_SIZE * 3, mask_d)?;
+            }
+
+            ptr = ptr.add(LOOP_SIZE);
+        }
+    }
+
+    // When the rest of string has a length greater then `M512_VECTOR_SIZE`
+    // but less than `LOOP_SIZE`, we process it `M512_VECTOR_SIZE` bits at
+    // a time until there are left less then `M512_VECTOR_SIZE` elements
+    while ptr <= end_ptr.sub(M512_VECTOR_SIZE) {
+        debug_assert_eq!(0, (ptr as usize) % M512_VECTOR_SIZE);
+        let mask = masking(escapes, _mm512_load_si512(ptr as *const __m512i));
+        s.mask(sub!(ptr, start_ptr), mask)?;
+        ptr = ptr.add(M512_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(M512_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less then `M512_VECTOR_SIZE` elements.
+    // A masked load never touches the bytes past `end_ptr`, but the masked out
+    // lanes are zeroed so the mask must be cleared for them after the comparison
+    if ptr < end_ptr {
+        let k = (1u64 << sub!(end_ptr, ptr)) - 1;
+        let mask = masking(escapes, _mm512_maskz_loadu_epi8(k, ptr as *const i8)) & k;
+        s.mask(sub!(ptr, start_ptr), mask)?;
+    }
+
+    s.finish()
+}
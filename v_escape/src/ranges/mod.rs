@@ -1,430 +1,268 @@
-#[macro_use]
-mod avx;
-#[macro_use]
-mod sse;
-#[macro_use]
-mod switch;
-
-#[macro_export]
-#[doc(hidden)]
-macro_rules! escape_ranges {
-    (avx2 $($t:tt)+) => {
-        #[inline]
-        #[target_feature(enable = "avx2")]
-        $crate::escape_ranges!(impl $crate::loop_range_switch_avx2 where $($t)+);
-    };
-    (sse2 $($t:tt)+) => {
+//! Loops looking for the bytes to escape a vector at a time
+//!
+//! They are generic over the escaper, an implementation of `Escapes` with the
+//! intervals and characters to look for and the quotes of every byte. The
+//! generated code has a zero sized type with constant ones, so each loop is
+//! built with the comparisons of that escaper and nothing more, as if written
+//! by hand. A `DynEscaper` has them in memory, built at runtime
+//!
+//! Every backend module has the same three functions, `escape` to a formatter,
+//! `f_escape` to a slice and `b_escape` to a `Buffer`
+use core::{convert::Infallible, fmt, mem::MaybeUninit, ptr, str};
+
+/// The three entry points of a backend module, calling its `run`
+macro_rules! kernels {
+    ($safety:literal) => {
+        /// Escape `bytes` to `fmt`
+        ///
+        /// # Safety
+        #[doc = $safety]
         #[inline]
-        #[target_feature(enable = "sse2")]
-        $crate::escape_ranges!(impl $crate::loop_range_switch_sse2 where $($t)+);
-    };
-    (impl $loops:path where ($T:ident, $Q:ident, $Q_LEN:ident) $($t:tt)+) => {
-        pub unsafe fn escape(bytes: &[u8], fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-
-            let len = bytes.len();
-            let start_ptr = bytes.as_ptr();
-            let end_ptr = bytes[len..].as_ptr();
-            let mut ptr = start_ptr;
-
-            let mut start = 0;
-
-            macro_rules! mask_bodies_callback {
-                ($callback:path) => {
-                    // Format bytes in the mask that starts in the current pointer
-                    macro_rules! mask_bodies {
-                        ($mask:ident, $at:ident, $cur:ident, $ptr:ident) => {
-                            // Calls macro `bodies!` at position `$at + $cur`
-                            // of byte `*$ptr` + `$curr` with macro `$crate::mask_body!`
-                            $callback!($T, $Q, $Q_LEN, $at + $cur, *$ptr.add($cur), start, fmt, bytes, $crate::mask_body);
-
-                            // Create binary vector of all zeros except
-                            // position `$curr` and xor operation with `$mask`
-                            $mask ^= 1 << $cur;
-                            // Test vs Check  if `$mask` is empty
-                            if $mask == 0 {
-                                break;
-                            }
-
-                            // Get to the next possible escape character avoiding zeros
-                            $cur = $mask.trailing_zeros() as usize;
-                        };
-                    }
-                };
-            }
-
-            $crate::mask_bodies_escaping!($($t)+);
-
-            // Macro to write with mask
-            macro_rules! write_mask {
-                ($mask:ident, $ptr:ident) => {{
-                    // Reference to the start of mask
-                    let at = $crate::sub!($ptr, start_ptr);
-                    // Get to the first possible escape character avoiding zeros
-                    let mut cur = $mask.trailing_zeros() as usize;
-
-                    loop {
-                        // Writing in `$fmt` with `$mask`
-                        // The main loop will break when mask == 0
-                        mask_bodies!($mask, at, cur, $ptr);
-                    }
-
-                    debug_assert_eq!(at, $crate::sub!($ptr, start_ptr))
-                }};
-            }
-
-            // Write a sliced mask
-            macro_rules! write_forward {
-                ($mask: ident, $align:ident) => {{
-                    let at = $crate::sub!(ptr, start_ptr);
-                    let mut cur = $mask.trailing_zeros() as usize;
-
-                    while cur < $align {
-                        mask_bodies!($mask, at, cur, ptr);
-                    }
-
-                    debug_assert_eq!(at, $crate::sub!(ptr, start_ptr))
-                }};
-            }
-
-            macro_rules! fallback_callback {
-                (default) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                $crate::bodies!(
-                                    $T,
-                                    $Q,
-                                    $Q_LEN,
-                                    $crate::sub!(ptr, start_ptr),
-                                    *ptr,
-                                    start,
-                                    fmt,
-                                    bytes,
-                                    $crate::mask_body
-                                );
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-                (one) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                if *ptr == $T {
-                                    $crate::bodies_exact_one!(
-                                        $T,
-                                        $Q,
-                                        $Q_LEN,
-                                        $crate::sub!(ptr, start_ptr),
-                                        *ptr,
-                                        start,
-                                        fmt,
-                                        bytes,
-                                        $crate::mask_body
-                                    );
-                                }
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-            }
-
-            $crate::fallback_escaping!($($t)+);
+        pub unsafe fn escape<E: super::Escapes>(
+            escapes: &E,
+            bytes: &[u8],
+            fmt: &mut ::core::fmt::Formatter,
+        ) -> ::core::fmt::Result {
+            run(super::Escaper::new(escapes, bytes, fmt))
+        }
 
-            $loops!((len, ptr, start_ptr, end_ptr) $($t)+);
+        /// Escape `bytes` to the start of `buf`, returns the length written or
+        /// `None` when it doesn't fit
+        ///
+        /// # Safety
+        #[doc = $safety]
+        #[inline]
+        pub unsafe fn f_escape<E: super::Escapes>(
+            escapes: &E,
+            bytes: &[u8],
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> Option<usize> {
+            let mut cursor = super::Cursor { buf, len: 0 };
+            run(super::Escaper::new(escapes, bytes, &mut cursor)).ok()?;
+            Some(cursor.len)
+        }
 
-            // Write since start to the end of the slice
-            debug_assert!(start <= len);
-            if start < len {
-                fmt.write_str(std::str::from_utf8_unchecked(&bytes[start..len]))?;
+        /// Escape `bytes` to the end of `buf`
+        ///
+        /// # Safety
+        #[doc = $safety]
+        #[cfg(feature = "buf-min")]
+        #[inline]
+        pub unsafe fn b_escape<E: super::Escapes, B: crate::Buffer>(
+            escapes: &E,
+            bytes: &[u8],
+            buf: &mut B,
+        ) {
+            match run(super::Escaper::new(escapes, bytes, super::Buf(buf))) {
+                Ok(()) => (),
+                Err(e) => match e {},
             }
-
-            Ok(())
         }
     };
 }
 
-#[macro_export]
-#[doc(hidden)]
-macro_rules! escape_ranges_ptr {
-    (avx2 $($t:tt)+) => {
-        #[inline]
-        #[target_feature(enable = "avx2")]
-        $crate::escape_ranges_ptr!(impl $crate::loop_range_switch_avx2 where $($t)+);
+/// Escape the last bytes of the input, less than a vector, from `$at` with
+/// `$overlap`, which loads the last vector overlapping the processed bytes
+///
+/// Miri and the sanitizers flag those loads, under Miri and with the feature
+/// `strict-provenance` the bytes are escaped one at a time instead
+macro_rules! tail {
+    ($s:ident, $at:expr, $overlap:block) => {
+        #[cfg(not(any(miri, feature = "strict-provenance")))]
+        $overlap
+        #[cfg(any(miri, feature = "strict-provenance"))]
+        $s.fallback($at)?;
     };
-    (sse2 $($t:tt)+) => {
-        #[inline]
-        #[target_feature(enable = "sse2")]
-        $crate::escape_ranges_ptr!(impl $crate::loop_range_switch_sse2 where $($t)+);
-    };
-    (impl $loops:path where ($T:ident, $Q:ident, $Q_LEN:ident) $($t:tt)+) => {
-        pub unsafe fn f_escape(bytes: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
-            let mut buf_cur = 0;
-
-            let len = bytes.len();
-            let start_ptr = bytes.as_ptr();
-            let end_ptr = bytes[len..].as_ptr();
-            let mut ptr = start_ptr;
-
-            let mut start = 0;
-
-            macro_rules! mask_bodies_callback {
-                ($callback:path) => {
-                    // Format bytes in the mask that starts in the current pointer
-                    macro_rules! mask_bodies {
-                        ($mask:ident, $at:ident, $cur:ident, $ptr:ident) => {
-                            // Calls macro `bodies!` at position `$at + $cur`
-                            // of byte `*$ptr` + `$curr` with macro `$crate::mask_body!`
-                            $callback!($T, $Q, $Q_LEN, $at + $cur, *$ptr.add($cur), start, buf_cur, buf, start_ptr, $crate::mask_body_ptr);
-
-                            // Create binary vector of all zeros except
-                            // position `$curr` and xor operation with `$mask`
-                            $mask ^= 1 << $cur;
-                            // Test vs Check  if `$mask` is empty
-                            if $mask == 0 {
-                                break;
-                            }
-
-                            // Get to the next possible escape character avoiding zeros
-                            $cur = $mask.trailing_zeros() as usize;
-                        };
-                    }
-                };
-            }
-
-            $crate::mask_bodies_escaping_ptr!($($t)+);
+}
 
-            // Macro to write with mask
-            macro_rules! write_mask {
-                ($mask:ident, $ptr:ident) => {{
-                    // Reference to the start of mask
-                    let at = $crate::sub!($ptr, start_ptr);
-                    // Get to the first possible escape character avoiding zeros
-                    let mut cur = $mask.trailing_zeros() as usize;
+#[cfg(all(
+    feature = "powerpc-simd",
+    target_arch = "powerpc64",
+    target_feature = "altivec"
+))]
+pub mod altivec;
+#[cfg(target_arch = "x86_64")]
+pub mod avx;
+#[cfg(target_arch = "x86_64")]
+pub mod avx512;
+#[cfg(all(feature = "arm-simd", target_arch = "arm", target_feature = "neon"))]
+pub mod neon;
+#[cfg(feature = "portable-simd")]
+pub mod portable;
+#[cfg(target_arch = "riscv64")]
+pub mod rvv;
+#[cfg(target_arch = "x86_64")]
+pub mod sse;
+pub mod swar;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub mod wasm;
+
+/// Bytes the loops look for
+///
+/// Intervals are checked adding the translation that moves their end to the top
+/// of the signed bytes, so any byte above the bound is in them
+#[derive(Clone, Copy, Debug)]
+pub struct Ranges<'a> {
+    /// Inclusive intervals `[l, r]`, any but `[0, 255]`
+    pub intervals: &'a [[u8; 2]],
+    pub chars: &'a [u8],
+    /// No other byte than the escaped ones is in them, so the quote of a
+    /// found byte is never checked to be there
+    pub exact: bool,
+}
 
-                    loop {
-                        // Writing in `$fmt` with `$mask`
-                        // The main loop will break when mask == 0
-                        mask_bodies!($mask, at, cur, $ptr);
-                    }
+/// Escaper the loops are built for
+///
+/// # Safety
+/// When the ranges are `exact` every byte in them must have a quote. The quotes
+/// must keep valid utf-8 the output of valid utf-8, the bytes in between them
+/// are written without checking
+pub unsafe trait Escapes {
+    fn ranges(&self) -> Ranges<'_>;
+
+    /// Quote of byte `b`, if it has to be escaped
+    fn quote(&self, b: u8) -> Option<&str>;
+}
 
-                    debug_assert_eq!(at, $crate::sub!($ptr, start_ptr))
-                }};
-            }
+/// Addend moving the interval ending in `r` to the top of the signed bytes
+///
+/// Wrapping, so any byte can end the interval
+#[allow(dead_code)]
+#[inline(always)]
+const fn translation(r: u8) -> i8 {
+    127u8.wrapping_sub(r) as i8
+}
 
-            // Write a sliced mask
-            macro_rules! write_forward {
-                ($mask: ident, $align:ident) => {{
-                    let at = $crate::sub!(ptr, start_ptr);
-                    let mut cur = $mask.trailing_zeros() as usize;
+/// Signed bound over which a translated byte is in the interval `[l, r]`
+#[allow(dead_code)]
+#[inline(always)]
+const fn below(l: u8, r: u8) -> i8 {
+    126u8.wrapping_sub(r - l) as i8
+}
 
-                    while cur < $align {
-                        mask_bodies!($mask, at, cur, ptr);
-                    }
+/// Destination of the escaped bytes
+trait Sink {
+    type Error;
 
-                    debug_assert_eq!(at, $crate::sub!(ptr, start_ptr))
-                }};
-            }
+    /// # Safety
+    /// The bytes are a whole quote, or a slice of the input between quotes
+    unsafe fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
 
-            macro_rules! fallback_callback {
-                (default) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                $crate::bodies_ptr!(
-                                    $T,
-                                    $Q,
-                                    $Q_LEN,
-                                    $crate::sub!(ptr, start_ptr),
-                                    *ptr,
-                                    start,
-                                    buf_cur,
-                                    buf,
-                                    start_ptr,
-                                    $crate::mask_body_ptr
-                                );
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-                (one) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                if *ptr == $T {
-                                    $crate::bodies_exact_one_ptr!(
-                                        $T,
-                                        $Q,
-                                        $Q_LEN,
-                                        $crate::sub!(ptr, start_ptr),
-                                        *ptr,
-                                        start,
-                                        buf_cur,
-                                        buf,
-                                        start_ptr,
-                                        $crate::mask_body_ptr
-                                    );
-                                }
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-            }
+impl Sink for &mut fmt::Formatter<'_> {
+    type Error = fmt::Error;
 
-            $crate::fallback_escaping!($($t)+);
+    #[inline(always)]
+    unsafe fn write(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.write_str(str::from_utf8_unchecked(bytes))
+    }
+}
 
-            $loops!((len, ptr, start_ptr, end_ptr) $($t)+);
+/// Slice written from the start, fails when it is full
+struct Cursor<'a> {
+    buf: &'a mut [MaybeUninit<u8>],
+    len: usize,
+}
 
-            // Write since start to the end of the slice
-            debug_assert!(start <= len);
-            if start < len {
-                let len = len - start;
-                $crate::write_ptr!(buf_cur, buf, start_ptr.add(start), len);
-            }
+impl Sink for &mut Cursor<'_> {
+    type Error = ();
 
-            Some(buf_cur)
+    #[inline(always)]
+    unsafe fn write(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        if self.buf.len() < self.len + bytes.len() {
+            return Err(());
         }
-    };
+        ptr::copy_nonoverlapping(
+            bytes.as_ptr(),
+            self.buf.as_mut_ptr().add(self.len) as *mut u8,
+            bytes.len(),
+        );
+        self.len += bytes.len();
+        Ok(())
+    }
 }
 
-#[macro_export]
-#[doc(hidden)]
-macro_rules! escape_ranges_bytes {
-    (avx2 $($t:tt)+) => {
-        #[inline]
-        #[target_feature(enable = "avx2")]
-        $crate::escape_ranges_bytes!(impl $crate::loop_range_switch_avx2 where $($t)+);
-    };
-    (sse2 $($t:tt)+) => {
-        #[inline]
-        #[target_feature(enable = "sse2")]
-        $crate::escape_ranges_bytes!(impl $crate::loop_range_switch_sse2 where $($t)+);
-    };
-    (impl $loops:path where ($T:ident, $Q:ident, $Q_LEN:ident) $($t:tt)+) => {
-        pub unsafe fn b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
-            let len = bytes.len();
-            let start_ptr = bytes.as_ptr();
-            let end_ptr = bytes[len..].as_ptr();
-            let mut ptr = start_ptr;
-
-            let mut start = 0;
-
-            macro_rules! mask_bodies_callback {
-                ($callback:path) => {
-                    // Format bytes in the mask that starts in the current pointer
-                    macro_rules! mask_bodies {
-                        ($mask:ident, $at:ident, $cur:ident, $ptr:ident) => {
-                            // Calls macro `bodies!` at position `$at + $cur`
-                            // of byte `*$ptr` + `$curr` with macro `$crate::mask_body!`
-                            $callback!($T, $Q, $Q_LEN, $at + $cur, *$ptr.add($cur), start, bytes, buf, $crate::mask_body_bytes);
-
-                            // Create binary vector of all zeros except
-                            // position `$curr` and xor operation with `$mask`
-                            $mask ^= 1 << $cur;
-                            // Test vs Check  if `$mask` is empty
-                            if $mask == 0 {
-                                break;
-                            }
+/// `Buffer` written to the end
+#[cfg(feature = "buf-min")]
+struct Buf<'a, B>(&'a mut B);
 
-                            // Get to the next possible escape character avoiding zeros
-                            $cur = $mask.trailing_zeros() as usize;
-                        };
-                    }
-                };
-            }
-
-            $crate::mask_bodies_escaping_bytes!($($t)+);
-
-            // Macro to write with mask
-            macro_rules! write_mask {
-                ($mask:ident, $ptr:ident) => {{
-                    // Reference to the start of mask
-                    let at = $crate::sub!($ptr, start_ptr);
-                    // Get to the first possible escape character avoiding zeros
-                    let mut cur = $mask.trailing_zeros() as usize;
-
-                    loop {
-                        // Writing in `$fmt` with `$mask`
-                        // The main loop will break when mask == 0
-                        mask_bodies!($mask, at, cur, $ptr);
-                    }
-
-                    debug_assert_eq!(at, $crate::sub!($ptr, start_ptr))
-                }};
-            }
-
-            // Write a sliced mask
-            macro_rules! write_forward {
-                ($mask: ident, $align:ident) => {{
-                    let at = $crate::sub!(ptr, start_ptr);
-                    let mut cur = $mask.trailing_zeros() as usize;
-
-                    while cur < $align {
-                        mask_bodies!($mask, at, cur, ptr);
-                    }
-
-                    debug_assert_eq!(at, $crate::sub!(ptr, start_ptr))
-                }};
-            }
+#[cfg(feature = "buf-min")]
+impl<B: crate::Buffer> Sink for Buf<'_, B> {
+    type Error = Infallible;
 
-            macro_rules! fallback_callback {
-                (default) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                $crate::bodies_bytes!(
-                                    $T,
-                                    $Q,
-                                    $Q_LEN,
-                                    $crate::sub!(ptr, start_ptr),
-                                    *ptr,
-                                    start,
-                                    bytes,
-                                    buf,
-                                    $crate::mask_body_bytes
-                                );
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-                (one) => {
-                    macro_rules! fallback {
-                        () => {
-                            while ptr < end_ptr {
-                                if *ptr == $T {
-                                    $crate::bodies_exact_one_bytes!(
-                                        $T,
-                                        $Q,
-                                        $Q_LEN,
-                                        $crate::sub!(ptr, start_ptr),
-                                        *ptr,
-                                        start,
-                                        bytes,
-                                        buf,
-                                        $crate::mask_body_bytes
-                                    );
-                                }
-                                ptr = ptr.offset(1);
-                            }
-                        };
-                    }
-                };
-            }
-
-            $crate::fallback_escaping!($($t)+);
+    #[inline(always)]
+    unsafe fn write(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+}
 
-            $loops!((len, ptr, start_ptr, end_ptr) $($t)+);
+/// State of a loop, the quotes are written as the bytes to escape are found
+struct Escaper<'a, E, W> {
+    escapes: &'a E,
+    bytes: &'a [u8],
+    /// Start of the bytes not written yet
+    start: usize,
+    sink: W,
+}
 
-            // Write since start to the end of the slice
-            debug_assert!(start <= len);
-            if start < len {
-                $crate::write_bytes!(&bytes[start..], buf);
+#[allow(dead_code)]
+impl<'a, E: Escapes, W: Sink> Escaper<'a, E, W> {
+    #[inline(always)]
+    fn new(escapes: &'a E, bytes: &'a [u8], sink: W) -> Self {
+        Escaper {
+            escapes,
+            bytes,
+            start: 0,
+            sink,
+        }
+    }
+
+    /// Escape byte `i` if it has a quote
+    #[inline(always)]
+    unsafe fn byte(&mut self, i: usize) -> Result<(), W::Error> {
+        debug_assert!(i < self.bytes.len());
+        if let Some(quote) = self.escapes.quote(*self.bytes.get_unchecked(i)) {
+            if self.start < i {
+                self.sink.write(self.bytes.get_unchecked(self.start..i))?;
             }
+            self.sink.write(quote.as_bytes())?;
+            self.start = i + 1;
         }
-    };
+        Ok(())
+    }
+
+    /// Escape the bytes found in `mask`, bit `n` of it is byte `at + n`
+    #[inline(always)]
+    unsafe fn mask(&mut self, at: usize, mut mask: u64) -> Result<(), W::Error> {
+        while mask != 0 {
+            let i = at + mask.trailing_zeros() as usize;
+            debug_assert!(
+                !self.escapes.ranges().exact || self.escapes.quote(self.bytes[i]).is_some()
+            );
+            self.byte(i)?;
+            // Clear the lowest set bit
+            mask &= mask - 1;
+        }
+        Ok(())
+    }
+
+    /// Escape a byte at a time from `from` to the end
+    #[inline(always)]
+    unsafe fn fallback(&mut self, from: usize) -> Result<(), W::Error> {
+        for i in from..self.bytes.len() {
+            self.byte(i)?;
+        }
+        Ok(())
+    }
+
+    /// Write since the last quote to the end of the slice
+    #[inline(always)]
+    unsafe fn finish(mut self) -> Result<(), W::Error> {
+        debug_assert!(self.start <= self.bytes.len());
+        if self.start < self.bytes.len() {
+            self.sink.write(self.bytes.get_unchecked(self.start..))?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,92 @@
+//! Ranges arm (32 bits) neon implementation
+//!
+//! Unaligned reads are used all over so there is no aligning step
+//!
+//! There is no movemask in neon and armv7 lacks the across vector additions of
+//! aarch64. The comparison is weighted by the bit of each lane and reduced with
+//! pairwise long additions until each half of the vector fits in one byte
+use core::{
+    arch::arm::{
+        int8x16_t, uint8x16_t, vaddq_s8, vandq_u8, vceqq_s8, vcgtq_s8, vdupq_n_s8, vdupq_n_u8,
+        vgetq_lane_u64, vorrq_u8, vpaddlq_u16, vpaddlq_u32, vpaddlq_u8,
+    },
+    mem::{size_of, transmute},
+    ptr::read_unaligned,
+};
+
+use super::{below, translation, Escaper, Escapes, Sink};
+
+const NEON_VECTOR_SIZE: usize = size_of::<int8x16_t>();
+
+kernels!("None, `neon` is enabled at compile time");
+
+/// Lanes of `a` in the ranges of `escapes`
+#[inline(always)]
+unsafe fn masking<E: Escapes>(escapes: &E, a: int8x16_t) -> uint8x16_t {
+    let ranges = escapes.ranges();
+    let mut mask = vdupq_n_u8(0);
+    for &[l, r] in ranges.intervals {
+        mask = vorrq_u8(
+            mask,
+            vcgtq_s8(
+                vaddq_s8(a, vdupq_n_s8(translation(r))),
+                vdupq_n_s8(below(l, r)),
+            ),
+        );
+    }
+    for &c in ranges.chars {
+        mask = vorrq_u8(mask, vceqq_s8(a, vdupq_n_s8(c as i8)));
+    }
+    mask
+}
+
+#[inline(always)]
+unsafe fn movemask(m: uint8x16_t) -> u64 {
+    let v_weights: uint8x16_t =
+        transmute([1u8, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128]);
+    let s = vpaddlq_u32(vpaddlq_u16(vpaddlq_u8(vandq_u8(m, v_weights))));
+    vgetq_lane_u64::<0>(s) | (vgetq_lane_u64::<1>(s) << 8)
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < NEON_VECTOR_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
+
+    // Process all slices with at least one set of length `NEON_VECTOR_SIZE`
+    while ptr <= end_ptr.sub(NEON_VECTOR_SIZE) {
+        let a = read_unaligned(ptr as *const int8x16_t);
+        s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)))?;
+        ptr = ptr.add(NEON_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(NEON_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less than `NEON_VECTOR_SIZE` elements.
+    // Building the mask takes a handful of instructions in armv7, so when only
+    // a few elements are left they are done with the fallback. Otherwise the
+    // last vector is loaded overlapping the already processed ones and the
+    // mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = NEON_VECTOR_SIZE - sub!(end_ptr, ptr);
+
+            if NEON_VECTOR_SIZE / 2 < d {
+                s.fallback(sub!(ptr, start_ptr))?;
+            } else {
+                let a = read_unaligned(ptr.sub(d) as *const int8x16_t);
+                s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)) >> d)?;
+            }
+        });
+    }
+
+    s.finish()
+}
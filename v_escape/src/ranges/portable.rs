@@ -0,0 +1,67 @@
+//! Ranges `std::simd` implementation
+//!
+//! Unaligned reads are used all over so there is no aligning step
+use core::{
+    mem::size_of,
+    ptr::read_unaligned,
+    simd::{
+        cmp::{SimdPartialEq, SimdPartialOrd},
+        i8x32, mask8x32,
+    },
+};
+
+use super::{below, translation, Escaper, Escapes, Sink};
+
+const PORTABLE_VECTOR_SIZE: usize = size_of::<i8x32>();
+
+kernels!("None, it is `unsafe` as the ones of the other backends");
+
+/// Lanes of `a` in the ranges of `escapes`
+#[inline(always)]
+fn masking<E: Escapes>(escapes: &E, a: i8x32) -> mask8x32 {
+    let ranges = escapes.ranges();
+    let mut mask = mask8x32::splat(false);
+    for &[l, r] in ranges.intervals {
+        mask |= (a + i8x32::splat(translation(r))).simd_gt(i8x32::splat(below(l, r)));
+    }
+    for &c in ranges.chars {
+        mask |= a.simd_eq(i8x32::splat(c as i8));
+    }
+    mask
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < PORTABLE_VECTOR_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
+
+    // Process all slices with at least one set of length `PORTABLE_VECTOR_SIZE`
+    while ptr <= end_ptr.sub(PORTABLE_VECTOR_SIZE) {
+        let a = read_unaligned(ptr as *const i8x32);
+        s.mask(sub!(ptr, start_ptr), masking(escapes, a).to_bitmask())?;
+        ptr = ptr.add(PORTABLE_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(PORTABLE_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less than `PORTABLE_VECTOR_SIZE` elements
+    // so the last vector is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = PORTABLE_VECTOR_SIZE - sub!(end_ptr, ptr);
+            let a = read_unaligned(ptr.sub(d) as *const i8x32);
+            s.mask(sub!(ptr, start_ptr), masking(escapes, a).to_bitmask() >> d)?;
+        });
+    }
+
+    s.finish()
+}
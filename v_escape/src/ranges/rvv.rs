@@ -0,0 +1,124 @@
+//! Ranges RISC-V Vector (RVV 1.0) implementation
+//!
+//! The loop is vector length agnostic: every iteration `vsetvli` picks how many
+//! bytes fit in a `m8` register group, so the same code runs on any `VLEN`
+//! and the tail needs no special handling. There is no aligning step either,
+//! `vle8.v` has no alignment requirements
+//!
+//! The first 64 bits of the comparison mask are read back to a scalar register,
+//! so when the first escape of a strip is past them the loop just moves to it
+use core::arch::asm;
+
+use super::{Escaper, Escapes, Sink};
+
+const RVV_MASK_BITS: usize = 64;
+
+kernels!("The running cpu must support the `V` extension");
+
+/// Run a strip of `avl` bytes at `ptr` in inline assembly
+///
+/// Returns its length, the index of the first escape (or `-1`) and the first
+/// 64 bits of the mask. The intervals and characters are looped over inside
+/// the assembly, every term is or-ed into `v0`. The scalar operands are
+/// truncated to the 8 bits of the elements, so the translation and the bound
+/// of an interval are computed wrapping in a register
+#[inline(always)]
+unsafe fn masking<E: Escapes>(escapes: &E, ptr: *const u8, avl: usize) -> (usize, isize, u64) {
+    let ranges = escapes.ranges();
+    let vl: usize;
+    let first: isize;
+    let mask: u64;
+
+    asm!(
+        ".option push",
+        ".option arch, +v",
+        "vsetvli {vl}, {avl}, e8, m8, ta, ma",
+        "vle8.v v8, ({ptr})",
+        "vmclr.m v0",
+        "beqz {n_intervals}, 3f",
+        "2:",
+        "lbu {l}, 0({intervals})",
+        "lbu {r}, 1({intervals})",
+        // Bound `126 - (r - l)`
+        "sub {l}, {r}, {l}",
+        "li {t}, 126",
+        "sub {l}, {t}, {l}",
+        // Translation `127 - r`
+        "li {t}, 127",
+        "sub {r}, {t}, {r}",
+        "vadd.vx v16, v8, {r}",
+        "vmsgt.vx v1, v16, {l}",
+        "vmor.mm v0, v0, v1",
+        "addi {intervals}, {intervals}, 2",
+        "addi {n_intervals}, {n_intervals}, -1",
+        "bnez {n_intervals}, 2b",
+        "3:",
+        "beqz {n_chars}, 5f",
+        "4:",
+        "lbu {l}, 0({chars})",
+        "vmseq.vx v1, v8, {l}",
+        "vmor.mm v0, v0, v1",
+        "addi {chars}, {chars}, 1",
+        "addi {n_chars}, {n_chars}, -1",
+        "bnez {n_chars}, 4b",
+        "5:",
+        "vfirst.m {first}, v0",
+        "vsetivli zero, 1, e64, m1, ta, ma",
+        "vmv.x.s {mask}, v0",
+        ".option pop",
+        ptr = in(reg) ptr,
+        avl = in(reg) avl,
+        intervals = inout(reg) ranges.intervals.as_ptr() => _,
+        n_intervals = inout(reg) ranges.intervals.len() => _,
+        chars = inout(reg) ranges.chars.as_ptr() => _,
+        n_chars = inout(reg) ranges.chars.len() => _,
+        l = out(reg) _,
+        r = out(reg) _,
+        t = out(reg) _,
+        vl = out(reg) vl,
+        first = lateout(reg) first,
+        mask = lateout(reg) mask,
+        out("v0") _, out("v1") _,
+        out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+        out("v12") _, out("v13") _, out("v14") _, out("v15") _,
+        out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+        out("v20") _, out("v21") _, out("v22") _, out("v23") _,
+        options(pure, readonly, nostack),
+    );
+
+    (vl, first, mask)
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(bytes.len());
+    let mut ptr = start_ptr;
+
+    while ptr < end_ptr {
+        let (vl, first, mut mask) = masking(escapes, ptr, sub!(end_ptr, ptr));
+
+        if first < 0 {
+            // No escapes in the whole strip
+            ptr = ptr.add(vl);
+        } else if RVV_MASK_BITS <= first as usize {
+            // Skip to the first escape so it falls in the scalar mask
+            ptr = ptr.add(first as usize);
+        } else {
+            // Bits past `vl` are agnostic and must be cleared
+            let n = if vl < RVV_MASK_BITS {
+                mask &= (1 << vl) - 1;
+                vl
+            } else {
+                RVV_MASK_BITS
+            };
+
+            debug_assert_ne!(mask, 0);
+            s.mask(sub!(ptr, start_ptr), mask)?;
+            ptr = ptr.add(n);
+        }
+    }
+
+    s.finish()
+}
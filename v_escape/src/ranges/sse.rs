@@ -1,75 +1,96 @@
-/// Generate ranges sse2 implementation
-///
-/// ## Following macros must be defined
-/// - `fallback!()`
-///     when length is less than 16
-/// - `write_mask!(mut $mask: {integer}, $ptr: *const u8)`
-///     when bit mask is non equal 0
-/// - `write_forward(mut $mask: {integer}, $until: usize)`
-///     when bit mask is non equal 0  and valid bits until
-///
-#[macro_export]
-macro_rules! loop_range_switch_sse2  {
-    (($len:ident, $ptr:ident, $start_ptr:ident, $end_ptr:ident) $($t:tt, )+) => {
-        use std::arch::x86_64::{__m128i, _mm_load_si128, _mm_loadu_si128, _mm_movemask_epi8};
+//! Ranges sse2 implementation
+use core::{
+    arch::x86_64::{
+        __m128i, _mm_add_epi8, _mm_cmpeq_epi8, _mm_cmpgt_epi8, _mm_load_si128, _mm_loadu_si128,
+        _mm_movemask_epi8, _mm_or_si128, _mm_set1_epi8, _mm_setzero_si128,
+    },
+    mem::size_of,
+};
 
-        const M128_VECTOR_SIZE: usize = std::mem::size_of::<__m128i>();
-        const M128_VECTOR_ALIGN: usize = M128_VECTOR_SIZE - 1;
+use super::{below, translation, Escaper, Escapes, Sink};
 
-        if $len < M128_VECTOR_SIZE {
-            fallback!();
-        } else {
-            $crate::translations_128!($($t, )+);
+const M128_VECTOR_SIZE: usize = size_of::<__m128i>();
+const M128_VECTOR_ALIGN: usize = M128_VECTOR_SIZE - 1;
 
-            // Write mask for unaligned elements from the start
-            // of the vector and aligning pointer
-            {
-                // Calculating index of aligned pointer
-                let align = M128_VECTOR_SIZE - ($start_ptr as usize & M128_VECTOR_ALIGN);
-                if align < M128_VECTOR_SIZE {
-                    let mut mask = {
-                        let a = _mm_loadu_si128($ptr as *const __m128i);
-                        _mm_movemask_epi8(masking!(a))
-                    };
-                    // Writing mask for unaligned elements
-                    if mask != 0 {
-                        write_forward!(mask, align);
-                    }
-                    // Aligning pointer
-                    $ptr = $ptr.add(align);
-                }
-            }
-            // Process all aligned slices with at least one set of length `M128_VECTOR_SIZE`
-            while $ptr <= $end_ptr.sub(M128_VECTOR_SIZE) {
-                debug_assert_eq!(0, ($ptr as usize) % M128_VECTOR_SIZE);
-                let mut mask = {
-                    let a = _mm_load_si128($ptr as *const __m128i);
-                    _mm_movemask_epi8(masking!(a))
-                };
+kernels!("The running cpu must support sse2");
 
-                if mask != 0 {
-                    write_mask!(mask, $ptr);
-                }
-                $ptr = $ptr.add(M128_VECTOR_SIZE);
-            }
+/// Lanes of `a` in the ranges of `escapes`
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn masking<E: Escapes>(escapes: &E, a: __m128i) -> __m128i {
+    let ranges = escapes.ranges();
+    let mut mask = _mm_setzero_si128();
+    for &[l, r] in ranges.intervals {
+        mask = _mm_or_si128(
+            mask,
+            _mm_cmpgt_epi8(
+                _mm_add_epi8(a, _mm_set1_epi8(translation(r))),
+                _mm_set1_epi8(below(l, r)),
+            ),
+        );
+    }
+    for &c in ranges.chars {
+        mask = _mm_or_si128(mask, _mm_cmpeq_epi8(a, _mm_set1_epi8(c as i8)));
+    }
+    mask
+}
 
-            debug_assert!($end_ptr.sub(M128_VECTOR_SIZE) < $ptr);
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn movemask(a: __m128i) -> u64 {
+    _mm_movemask_epi8(a) as u32 as u64
+}
 
-            // At this point at most there is less than `M128_VECTOR_SIZE` elements
-            // so the macro `write_mask` is used to the last elements
-            if $ptr < $end_ptr {
-                let d = M128_VECTOR_SIZE - $crate::sub!($end_ptr, $ptr);
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
 
-                let mut mask = ({
-                    debug_assert_eq!(M128_VECTOR_SIZE, $crate::sub!($end_ptr, $ptr.sub(d)));
-                    let a = _mm_loadu_si128($ptr.sub(d) as *const __m128i);
-                    _mm_movemask_epi8(masking!(a))
-                } as u16).wrapping_shr(d as u32);
+    if len < M128_VECTOR_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
 
-                if mask != 0 {
-                    write_mask!(mask, $ptr);
-                }
-            }
+    // Write mask for unaligned elements from the start
+    // of the vector and aligning pointer
+    {
+        // Calculating index of aligned pointer
+        let align = M128_VECTOR_SIZE - (start_ptr as usize & M128_VECTOR_ALIGN);
+        if align < M128_VECTOR_SIZE {
+            let mask = movemask(masking(escapes, _mm_loadu_si128(ptr as *const __m128i)));
+            // Writing mask for unaligned elements
+            s.mask(0, mask & ((1 << align) - 1))?;
+            // Aligning pointer
+            ptr = ptr.add(align);
         }
-    };
+    }
+
+    // Process all aligned slices with at least one set of length `M128_VECTOR_SIZE`
+    while ptr <= end_ptr.sub(M128_VECTOR_SIZE) {
+        debug_assert_eq!(0, (ptr as usize) % M128_VECTOR_SIZE);
+        let mask = movemask(masking(escapes, _mm_load_si128(ptr as *const __m128i)));
+        s.mask(sub!(ptr, start_ptr), mask)?;
+        ptr = ptr.add(M128_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(M128_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less than `M128_VECTOR_SIZE` elements
+    // so the last vector is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = M128_VECTOR_SIZE - sub!(end_ptr, ptr);
+            let mask = movemask(masking(
+                escapes,
+                _mm_loadu_si128(ptr.sub(d) as *const __m128i),
+            ));
+            s.mask(sub!(ptr, start_ptr), mask >> d)?;
+        });
+    }
+
+    s.finish()
 }
@@ -0,0 +1,107 @@
+//! Ranges SWAR (simd within a register) implementation
+//!
+//! Used where there is no vector unit. Processes a `u64` at a time, the bytes
+//! are read in little endian so bit `n` of the mask is byte `n` of the slice
+//! in every target
+use core::{mem::size_of, ptr::read_unaligned};
+
+use super::{Escaper, Escapes, Sink};
+
+const SWAR_WORD_SIZE: usize = size_of::<u64>();
+const LO: u64 = 0x0101_0101_0101_0101;
+const LO7: u64 = 0x7F7F_7F7F_7F7F_7F7F;
+const HI: u64 = 0x8080_8080_8080_8080;
+
+kernels!("None, it is `unsafe` as the ones of the other backends");
+
+/// Set the high bit of every byte of `w` equal to `c`
+///
+/// The high bit is cleared before adding so no carry crosses to the next byte
+#[inline(always)]
+fn eq(w: u64, c: u8) -> u64 {
+    let x = w ^ (LO * c as u64);
+    !(((x & LO7) + LO7) | x) & !LO7
+}
+
+/// Set the high bit of every byte of `w` in the interval `[l, r]`
+///
+/// The interval is split at 128, the part above is checked on `w` with the
+/// high bit of each byte flipped
+#[inline(always)]
+fn in_range(w: u64, l: u8, r: u8) -> u64 {
+    let low = if l < 0x80 {
+        in_ascii_range(w, l, if r < 0x7F { r } else { 0x7F })
+    } else {
+        0
+    };
+    let high = if 0x80 <= r {
+        in_ascii_range(w ^ HI, l.saturating_sub(0x80), r - 0x80)
+    } else {
+        0
+    };
+    low | high
+}
+
+/// Both bounds are lower than 128. Setting the high bit of each byte before
+/// subtracting keeps the borrows inside the byte
+#[inline(always)]
+fn in_ascii_range(w: u64, l: u8, r: u8) -> u64 {
+    let x = w | HI;
+    (x - LO * l as u64) & !(x - LO * (r as u64 + 1)) & !w & HI
+}
+
+/// Bytes of `w` in the ranges of `escapes`, as the high bit of each
+#[inline(always)]
+fn masking<E: Escapes>(escapes: &E, w: u64) -> u64 {
+    let ranges = escapes.ranges();
+    let mut mask = 0;
+    for &[l, r] in ranges.intervals {
+        mask |= in_range(w, l, r);
+    }
+    for &c in ranges.chars {
+        mask |= eq(w, c);
+    }
+    mask
+}
+
+/// Gathers the high bit of every byte in the lowest byte
+#[inline(always)]
+fn movemask(m: u64) -> u64 {
+    (m >> 7).wrapping_mul(0x0102_0408_1020_4080) >> 56
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < SWAR_WORD_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
+
+    // Process all slices with at least one set of length `SWAR_WORD_SIZE`
+    while ptr <= end_ptr.sub(SWAR_WORD_SIZE) {
+        let a = u64::from_le(read_unaligned(ptr as *const u64));
+        s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)))?;
+        ptr = ptr.add(SWAR_WORD_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(SWAR_WORD_SIZE) < ptr);
+
+    // At this point at most there is less than `SWAR_WORD_SIZE` elements
+    // so the last word is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = SWAR_WORD_SIZE - sub!(end_ptr, ptr);
+            let a = u64::from_le(read_unaligned(ptr.sub(d) as *const u64));
+            s.mask(sub!(ptr, start_ptr), movemask(masking(escapes, a)) >> d)?;
+        });
+    }
+
+    s.finish()
+}
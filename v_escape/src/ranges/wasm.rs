@@ -0,0 +1,72 @@
+//! Ranges wasm32 simd128 implementation
+//!
+//! `v128_load` has no alignment requirements, so unlike x86 there is
+//! no aligning step before the main loop
+use core::{
+    arch::wasm32::{
+        i8x16_add, i8x16_eq, i8x16_gt, i8x16_splat, u8x16_bitmask, v128, v128_load, v128_or,
+    },
+    mem::size_of,
+};
+
+use super::{below, translation, Escaper, Escapes, Sink};
+
+const V128_VECTOR_SIZE: usize = size_of::<v128>();
+
+kernels!("None, `simd128` is enabled at compile time");
+
+/// Lanes of `a` in the ranges of `escapes`
+#[inline(always)]
+fn masking<E: Escapes>(escapes: &E, a: v128) -> v128 {
+    let ranges = escapes.ranges();
+    let mut mask = i8x16_splat(0);
+    for &[l, r] in ranges.intervals {
+        mask = v128_or(
+            mask,
+            i8x16_gt(
+                i8x16_add(a, i8x16_splat(translation(r))),
+                i8x16_splat(below(l, r)),
+            ),
+        );
+    }
+    for &c in ranges.chars {
+        mask = v128_or(mask, i8x16_eq(a, i8x16_splat(c as i8)));
+    }
+    mask
+}
+
+#[inline]
+pub(super) unsafe fn run<E: Escapes, W: Sink>(mut s: Escaper<E, W>) -> Result<(), W::Error> {
+    let (escapes, bytes) = (s.escapes, s.bytes);
+    let len = bytes.len();
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = start_ptr.add(len);
+    let mut ptr = start_ptr;
+
+    if len < V128_VECTOR_SIZE {
+        s.fallback(0)?;
+        return s.finish();
+    }
+
+    // Process all slices with at least one set of length `V128_VECTOR_SIZE`
+    while ptr <= end_ptr.sub(V128_VECTOR_SIZE) {
+        let mask = u8x16_bitmask(masking(escapes, v128_load(ptr as *const v128)));
+        s.mask(sub!(ptr, start_ptr), mask as u64)?;
+        ptr = ptr.add(V128_VECTOR_SIZE);
+    }
+
+    debug_assert!(end_ptr.sub(V128_VECTOR_SIZE) < ptr);
+
+    // At this point at most there is less than `V128_VECTOR_SIZE` elements
+    // so the last vector is loaded overlapping the already processed ones
+    // and the mask is shifted to drop them
+    if ptr < end_ptr {
+        tail!(s, sub!(ptr, start_ptr), {
+            let d = V128_VECTOR_SIZE - sub!(end_ptr, ptr);
+            let mask = u8x16_bitmask(masking(escapes, v128_load(ptr.sub(d) as *const v128)));
+            s.mask(sub!(ptr, start_ptr), (mask as u64) >> d)?;
+        });
+    }
+
+    s.finish()
+}
@@ -0,0 +1,119 @@
+//! Escapers looked up by name, as `"html"` or `"json"`
+//!
+//! The preset crates register theirs with their `register` function, and any
+//! other escaper is added with a static `Entry`:
+//!
+//! ```
+//! use v_escape::registry::{registry, Entry};
+//!
+//! mod shell {
+//!     v_escape::new!(Shell, preset = "shell");
+//! }
+//!
+//! static SHELL: Entry = Entry::new("shell", shell::ESCAPER);
+//!
+//! # fn main() {
+//! registry().register(&SHELL);
+//!
+//! let escaper = registry().get("shell").unwrap();
+//! let mut out = String::new();
+//! escaper.escape_to("a b", &mut out).unwrap();
+//! assert_eq!(out, "a\\ b");
+//! # }
+//! ```
+//!
+//! The entries are a linked list that is only ever pushed to, so it needs no
+//! lock nor allocation and is there without `std` too
+use core::{
+    iter, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+use crate::Escaper;
+
+static REGISTRY: Registry = Registry {
+    head: AtomicPtr::new(ptr::null_mut()),
+};
+
+/// The registry shared by the whole program
+#[inline]
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+/// Escaper with its name, to be registered
+pub struct Entry {
+    name: &'static str,
+    escaper: &'static dyn Escaper,
+    next: AtomicPtr<Entry>,
+    registered: AtomicBool,
+}
+
+impl Entry {
+    #[inline]
+    pub const fn new(name: &'static str, escaper: &'static dyn Escaper) -> Self {
+        Entry {
+            name,
+            escaper,
+            next: AtomicPtr::new(ptr::null_mut()),
+            registered: AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[inline]
+    pub fn escaper(&self) -> &'static dyn Escaper {
+        self.escaper
+    }
+}
+
+/// Escapers by name, see `registry`
+pub struct Registry {
+    /// Last registered entry
+    head: AtomicPtr<Entry>,
+}
+
+impl Registry {
+    /// Add `entry`, it replaces the escaper of an earlier one with the same
+    /// name. Registering an entry again does nothing
+    pub fn register(&self, entry: &'static Entry) {
+        if entry.registered.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let new = entry as *const Entry as *mut Entry;
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            entry.next.store(head, Ordering::Relaxed);
+            match self
+                .head
+                .compare_exchange_weak(head, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Escaper registered as `name`
+    pub fn get(&self, name: &str) -> Option<&'static dyn Escaper> {
+        self.entries()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.escaper)
+    }
+
+    /// Registered entries, the last registered first
+    pub fn entries(&self) -> impl Iterator<Item = &'static Entry> {
+        let mut next = self.head.load(Ordering::Acquire);
+        iter::from_fn(move || {
+            // SAFETY: only `&'static Entry`s are pushed, and never removed
+            let entry: &'static Entry = unsafe { next.as_ref()? };
+            next = entry.next.load(Ordering::Relaxed);
+            Some(entry)
+        })
+    }
+}
@@ -0,0 +1,187 @@
+//! Byte at a time loops without `unsafe`, the scalar implementation of the
+//! `forbid-unsafe` feature
+//!
+//! Every byte is looked up with the `escape_byte` of the escaper. Runs of an
+//! input that isn't utf-8 are written to a `str` output with `U+FFFD` in place
+//! of the invalid sequences
+use core::{convert::Infallible, fmt, mem::MaybeUninit, str};
+
+/// Call `f` with every unescaped run of `bytes` followed by the quote ending
+/// it, the last run has none
+#[inline(always)]
+fn parts<E, F>(bytes: &[u8], escape_byte: fn(u8) -> Option<&'static str>, mut f: F) -> Result<(), E>
+where
+    F: FnMut(&[u8], Option<&'static str>) -> Result<(), E>,
+{
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if let Some(quote) = escape_byte(b) {
+            f(&bytes[start..i], Some(quote))?;
+            start = i + 1;
+        }
+    }
+    f(&bytes[start..], None)
+}
+
+/// Write `run` with `write`, lossily when it isn't utf-8
+#[inline]
+fn lossy<E, W>(mut run: &[u8], mut write: W) -> Result<(), E>
+where
+    W: FnMut(&str) -> Result<(), E>,
+{
+    loop {
+        match str::from_utf8(run) {
+            Ok(s) => return if s.is_empty() { Ok(()) } else { write(s) },
+            Err(e) => {
+                let (valid, rest) = run.split_at(e.valid_up_to());
+                if let Ok(valid) = str::from_utf8(valid) {
+                    write(valid)?;
+                }
+                write("\u{FFFD}")?;
+                match e.error_len() {
+                    Some(len) => run = &rest[len..],
+                    None => return Ok(()),
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+pub fn escape(
+    bytes: &[u8],
+    fmt: &mut fmt::Formatter,
+    escape_byte: fn(u8) -> Option<&'static str>,
+) -> fmt::Result {
+    parts(bytes, escape_byte, |run, quote| {
+        lossy(run, |s| fmt.write_str(s))?;
+        quote.map_or(Ok(()), |quote| fmt.write_str(quote))
+    })
+}
+
+/// Copy `src` at `*cur` in `buf`, `None` when it doesn't fit
+#[inline(always)]
+fn copy(src: &[u8], buf: &mut [MaybeUninit<u8>], cur: &mut usize) -> Option<()> {
+    let dst = buf.get_mut(*cur..*cur + src.len())?;
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d = MaybeUninit::new(s);
+    }
+    *cur += src.len();
+    Some(())
+}
+
+#[inline]
+pub fn f_escape(
+    bytes: &[u8],
+    buf: &mut [MaybeUninit<u8>],
+    escape_byte: fn(u8) -> Option<&'static str>,
+) -> Option<usize> {
+    let mut cur = 0;
+    parts(bytes, escape_byte, |run, quote| {
+        copy(run, buf, &mut cur).ok_or(())?;
+        quote.map_or(Ok(()), |quote| {
+            copy(quote.as_bytes(), buf, &mut cur).ok_or(())
+        })
+    })
+    .ok()?;
+    Some(cur)
+}
+
+#[cfg(feature = "buf-min")]
+#[inline]
+pub fn b_escape<B: crate::Buffer>(
+    bytes: &[u8],
+    buf: &mut B,
+    escape_byte: fn(u8) -> Option<&'static str>,
+) {
+    let _ = parts::<Infallible, _>(bytes, escape_byte, |run, quote| {
+        lossy::<Infallible, _>(run, |s| {
+            buf.extend(s);
+            Ok(())
+        })?;
+        if let Some(quote) = quote {
+            buf.extend(quote);
+        }
+        Ok(())
+    });
+}
+
+#[inline]
+pub fn f_escape_char(
+    c: char,
+    buf: &mut [MaybeUninit<u8>],
+    escape_byte: fn(u8) -> Option<&'static str>,
+) -> Option<usize> {
+    let mut utf8 = [0; 4];
+    let quote = match c.is_ascii().then(|| escape_byte(c as u8)).flatten() {
+        Some(quote) => quote,
+        None => c.encode_utf8(&mut utf8),
+    };
+    let mut cur = 0;
+    copy(quote.as_bytes(), buf, &mut cur)?;
+    Some(cur)
+}
+
+#[cfg(feature = "buf-min")]
+#[inline]
+pub fn b_escape_char<B: crate::Buffer>(
+    c: char,
+    buf: &mut B,
+    escape_byte: fn(u8) -> Option<&'static str>,
+) {
+    let mut utf8 = [0; 4];
+    match c.is_ascii().then(|| escape_byte(c as u8)).flatten() {
+        Some(quote) => buf.extend(quote),
+        None => buf.extend(c.encode_utf8(&mut utf8)),
+    }
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Scalar module of the `forbid-unsafe` feature, over the `escape_byte` of
+/// the `chars` module
+macro_rules! escape_scalar_safe {
+    () => {
+        #[inline]
+        pub fn escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            $crate::safe::escape(bytes, fmt, super::chars::escape_byte)
+        }
+
+        #[inline]
+        pub fn f_escape(
+            bytes: &[u8],
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> ::core::option::Option<usize> {
+            $crate::safe::f_escape(bytes, buf, super::chars::escape_byte)
+        }
+
+        $crate::cfg_buffer! {
+            #[inline]
+            pub fn b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+                $crate::safe::b_escape(bytes, buf, super::chars::escape_byte)
+            }
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+/// Char functions of the `forbid-unsafe` feature, over `escape_byte`
+macro_rules! escape_char_safe {
+    () => {
+        #[inline]
+        pub fn f_escape_char(
+            c: char,
+            buf: &mut [::core::mem::MaybeUninit<u8>],
+        ) -> ::core::option::Option<usize> {
+            $crate::safe::f_escape_char(c, buf, escape_byte)
+        }
+
+        $crate::cfg_buffer! {
+            #[inline]
+            pub fn b_escape_char<B: $crate::Buffer>(c: char, buf: &mut B) {
+                $crate::safe::b_escape_char(c, buf, escape_byte)
+            }
+        }
+    };
+}
@@ -3,8 +3,8 @@
 macro_rules! escape_scalar {
     ($($t:tt)+) => {
         #[inline]
-        pub fn escape(bytes: &[u8], fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-            use std::str::from_utf8_unchecked;
+        pub fn escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+            use ::core::str::from_utf8_unchecked;
 
             let len = bytes.len();
             let start_ptr = bytes.as_ptr();
@@ -57,7 +57,7 @@ macro_rules! escape_scalar {
             }
             fmt.write_str(unsafe { from_utf8_unchecked(&bytes[start..]) })?;
 
-            Ok(())
+            ::core::result::Result::Ok(())
         }
     };
 }
@@ -67,7 +67,7 @@ macro_rules! escape_scalar {
 macro_rules! escape_scalar_ptr {
     ($($t:tt)+) => {
         #[inline]
-        pub unsafe fn f_escape(bytes: &[u8], buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<usize> {
+        pub fn f_escape(bytes: &[u8], buf: &mut [::core::mem::MaybeUninit<u8>]) -> ::core::option::Option<usize> {
             let len = bytes.len();
             let start_ptr = bytes.as_ptr();
             let end_ptr = bytes[len..].as_ptr();
@@ -77,6 +77,8 @@ macro_rules! escape_scalar_ptr {
             let mut buf_cur = 0;
             let mut start = 0;
 
+            unsafe {
+
             while ptr < end_ptr {
                 macro_rules! _inside {
                     (impl one $byte:ident, $quote:ident) => {
@@ -117,13 +119,15 @@ macro_rules! escape_scalar_ptr {
             }
 
             // Write since start to the end of the slice
-            debug_assert!(start <= len);
+            ::core::debug_assert!(start <= len);
             if start < len {
                 let len = len - start;
                 $crate::write_ptr!(buf_cur, buf, start_ptr.add(start), len);
             }
 
-            Some(buf_cur)
+            }
+
+            ::core::option::Option::Some(buf_cur)
         }
     };
 }
@@ -133,7 +137,7 @@ macro_rules! escape_scalar_ptr {
 macro_rules! escape_scalar_bytes {
     ($($t:tt)+) => {
         #[inline]
-        pub unsafe fn b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
+        pub fn b_escape<B: $crate::Buffer>(bytes: &[u8], buf: &mut B) {
             let len = bytes.len();
             let start_ptr = bytes.as_ptr();
             let end_ptr = bytes[len..].as_ptr();
@@ -142,6 +146,8 @@ macro_rules! escape_scalar_bytes {
 
             let mut start = 0;
 
+            unsafe {
+
             while ptr < end_ptr {
                 macro_rules! _inside {
                     (impl one $byte:ident, $quote:ident) => {
@@ -180,10 +186,12 @@ macro_rules! escape_scalar_bytes {
             }
 
             // Write since start to the end of the slice
-            debug_assert!(start <= len);
+            ::core::debug_assert!(start <= len);
             if start < len {
                 $crate::write_bytes!(&bytes[start..], buf);
             }
+
+            }
         }
     };
 }
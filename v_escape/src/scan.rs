@@ -0,0 +1,320 @@
+use core::{
+    cell::Cell,
+    fmt::{self, Formatter, Write},
+};
+
+use crate::pad::Escaped;
+
+/// Follows the unescaped runs, stopping the escaping loop at the first
+/// write that doesn't continue the input
+struct Finder<'a> {
+    input: &'a [u8],
+    consumed: usize,
+}
+
+impl<'a> Write for Finder<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            Ok(())
+        } else if s.as_ptr() == self.input.as_ptr().wrapping_add(self.consumed) {
+            self.consumed += s.len();
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+/// Position of the first byte of `input` that `escape` replaces
+///
+/// The loop is stopped at the first escape, so it doesn't do a full escape pass
+pub fn find_first<F>(input: &[u8], escape: F) -> Option<usize>
+where
+    F: Fn(&mut Formatter) -> fmt::Result,
+{
+    let mut finder = Finder { input, consumed: 0 };
+    // An error means it found an escape
+    let _ = write!(finder, "{}", Escaped(escape));
+
+    if finder.consumed == input.len() {
+        None
+    } else {
+        Some(finder.consumed)
+    }
+}
+
+/// Sums the length of every write
+struct Len(usize);
+
+impl Write for Len {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Length in bytes of the output of `escape`, without writing it
+pub fn escaped_len<F>(escape: F) -> usize
+where
+    F: Fn(&mut Formatter) -> fmt::Result,
+{
+    let mut len = Len(0);
+    // Never fails, `Len` doesn't return errors
+    let _ = write!(len, "{}", Escaped(escape));
+
+    len.0
+}
+
+/// Write `fragments` escaped by `escape` to `writer`, the output is the same
+/// as escaping their concatenation
+///
+/// The bytes at the end of a fragment counted by `carry_len` may start a
+/// trigger the next fragments finish, they are held in `buf` and joined with
+/// the start of the next one. `buf` has room for twice the longest trigger and
+/// a character, it is empty when there are no triggers
+pub fn escape_fragments<'a, I, W, E>(
+    fragments: I,
+    writer: &mut W,
+    escape: E,
+    carry_len: fn(&[u8]) -> usize,
+    buf: &mut [u8],
+) -> fmt::Result
+where
+    I: Iterator<Item = &'a str>,
+    W: Write + ?Sized,
+    E: FnMut(&[u8], &mut Formatter) -> fmt::Result,
+{
+    // Taken by the only call of the formatting closure
+    let state = Cell::new(Some((fragments, escape, buf)));
+    write!(
+        writer,
+        "{}",
+        Escaped(|fmt: &mut Formatter| {
+            let (fragments, mut escape, buf) = state.take().ok_or(fmt::Error)?;
+            let mut carried = 0;
+            for fragment in fragments {
+                let mut rest = fragment;
+                // The carry joined with the start of `rest`, a char at a time
+                while carried != 0 && !rest.is_empty() {
+                    let mut head = rest.len().min(buf.len() - carried);
+                    while !rest.is_char_boundary(head) {
+                        head -= 1;
+                    }
+                    buf[carried..carried + head].copy_from_slice(&rest.as_bytes()[..head]);
+                    let joined = carried + head;
+                    let end = joined - carry_len(&buf[..joined]);
+                    escape(&buf[..end], fmt)?;
+                    if carried <= end {
+                        rest = &rest[end - carried..];
+                        carried = 0;
+                    } else {
+                        buf.copy_within(end..joined, 0);
+                        carried = joined - end;
+                        rest = &rest[head..];
+                    }
+                }
+                if carried == 0 {
+                    let bytes = rest.as_bytes();
+                    let end = bytes.len() - carry_len(bytes);
+                    escape(&bytes[..end], fmt)?;
+                    carried = bytes.len() - end;
+                    buf[..carried].copy_from_slice(&bytes[end..]);
+                }
+            }
+            escape(&buf[..carried], fmt)
+        })
+    )
+}
+
+/// Write `s` to `out`, the runs between the characters `find` stops at as
+/// they are and every found character with `escape`
+///
+/// `escape` gets `s` and the position of the character, it writes what takes
+/// its place and returns the position where the next run starts. The finders
+/// look for the first byte of a character, so it is at its start
+#[inline]
+pub fn escape_found<W, F>(
+    s: &str,
+    out: &mut W,
+    find: fn(&[u8]) -> Option<usize>,
+    mut escape: F,
+) -> fmt::Result
+where
+    W: Write + ?Sized,
+    F: FnMut(&str, usize, &mut W) -> Result<usize, fmt::Error>,
+{
+    let mut start = 0;
+    while let Some(i) = find(&s.as_bytes()[start..]) {
+        let i = start + i;
+        out.write_str(&s[start..i])?;
+        start = escape(s, i, out)?;
+    }
+    out.write_str(&s[start..])
+}
+
+/// `escape_found` writing a found character as its quote of `escape_byte`,
+/// or with `escape_char` when it has none, which returns `None` to write the
+/// character as it is
+#[inline]
+pub fn escape_found_chars<W, C>(
+    s: &str,
+    out: &mut W,
+    find: fn(&[u8]) -> Option<usize>,
+    escape_byte: fn(u8) -> Option<&'static str>,
+    mut escape_char: C,
+) -> fmt::Result
+where
+    W: Write + ?Sized,
+    C: FnMut(char, &mut W) -> Option<fmt::Result>,
+{
+    escape_found(s, out, find, |s, i, out| {
+        let c = char_at(s, i);
+        let end = i + c.len_utf8();
+        match escape_byte(s.as_bytes()[i]) {
+            // Only the ascii characters have a quote of their own
+            Some(quote) if c.is_ascii() => out.write_str(quote)?,
+            _ => match escape_char(c, out) {
+                Some(result) => result?,
+                None => out.write_str(&s[i..end])?,
+            },
+        }
+        Ok(end)
+    })
+}
+
+/// Character of `s` starting at `i`
+#[inline]
+pub fn char_at(s: &str, i: usize) -> char {
+    s[i..].chars().next().expect("character at a boundary")
+}
+
+/// Length in bytes of `bytes` escaped one quote at a time, without the
+/// formatting machinery, for input that may not be utf-8
+#[inline]
+pub fn quoted_len(bytes: &[u8], quote_at: fn(&[u8]) -> Option<(&'static str, usize)>) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match quote_at(&bytes[i..]) {
+            Some((quote, read)) => {
+                len += quote.len();
+                i += read;
+            }
+            None => {
+                len += 1;
+                i += 1;
+            }
+        }
+    }
+    len
+}
+
+/// Iterator over the escaped output, alternating unescaped runs borrowed
+/// from the input and quotes
+///
+/// Every step looks for the next escape with `find`, the input is scanned
+/// only as far as it is consumed
+pub struct EscapeIter<'a> {
+    rest: &'a [u8],
+    quote: Option<&'static str>,
+    find: fn(&[u8]) -> Option<usize>,
+    quote_at: fn(&[u8]) -> Option<(&'static str, usize)>,
+}
+
+impl<'a> EscapeIter<'a> {
+    #[inline]
+    pub fn new(
+        input: &'a str,
+        find: fn(&[u8]) -> Option<usize>,
+        quote_at: fn(&[u8]) -> Option<(&'static str, usize)>,
+    ) -> Self {
+        Self {
+            rest: input.as_bytes(),
+            quote: None,
+            find,
+            quote_at,
+        }
+    }
+}
+
+impl<'a> Iterator for EscapeIter<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        if let Some(quote) = self.quote.take() {
+            return Some(quote);
+        }
+        while !self.rest.is_empty() {
+            match (self.find)(self.rest) {
+                Some(i) => {
+                    let (quote, len) = (self.quote_at)(&self.rest[i..]).expect("escaped byte");
+                    // SAFETY: the escaped bytes are ascii or every byte of the
+                    // multi-byte characters, so a run has whole characters
+                    let run = unsafe { core::str::from_utf8_unchecked(&self.rest[..i]) };
+                    self.rest = &self.rest[i + len..];
+                    // Empty quotes are skipped, chunks are never empty
+                    match (run.is_empty(), quote.is_empty()) {
+                        (true, true) => (),
+                        (true, false) => return Some(quote),
+                        (false, true) => return Some(run),
+                        (false, false) => {
+                            self.quote = Some(quote);
+                            return Some(run);
+                        }
+                    }
+                }
+                // SAFETY: the rest is a run too
+                None => {
+                    let rest = core::mem::take(&mut self.rest);
+                    return Some(unsafe { core::str::from_utf8_unchecked(rest) });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> core::iter::FusedIterator for EscapeIter<'a> {}
+
+/// Iterator over the byte offsets of the escapes of the input, with their quotes
+pub struct EscapePositions<'a> {
+    input: &'a [u8],
+    pos: usize,
+    find: fn(&[u8]) -> Option<usize>,
+    quote_at: fn(&[u8]) -> Option<(&'static str, usize)>,
+}
+
+impl<'a> EscapePositions<'a> {
+    #[inline]
+    pub fn new(
+        input: &'a str,
+        find: fn(&[u8]) -> Option<usize>,
+        quote_at: fn(&[u8]) -> Option<(&'static str, usize)>,
+    ) -> Self {
+        Self {
+            input: input.as_bytes(),
+            pos: 0,
+            find,
+            quote_at,
+        }
+    }
+}
+
+impl<'a> Iterator for EscapePositions<'a> {
+    type Item = (usize, &'static str);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, &'static str)> {
+        let i = self.pos + (self.find)(&self.input[self.pos..])?;
+        let (quote, len) = (self.quote_at)(&self.input[i..]).expect("escaped byte");
+        self.pos = i + len;
+
+        Some((i, quote))
+    }
+}
+
+impl<'a> core::iter::FusedIterator for EscapePositions<'a> {}
@@ -0,0 +1,176 @@
+//! Escaping of the triggers of `new!`, sequences of bytes escaped as a whole
+//!
+//! The first bytes of the triggers are looked for with an escaper of their
+//! own, so the input is skipped with the vector loops and only the bytes found
+//! are checked against the triggers. The runs between the triggers are escaped
+//! with the single byte pairs
+use core::{fmt, mem::MaybeUninit, str};
+
+/// Bytes of a trigger and its quote
+pub type Trigger = (&'static [u8], &'static str);
+
+/// Quote and length of the trigger starting `rest`
+#[inline]
+pub fn trigger_at(rest: &[u8], triggers: &[Trigger]) -> Option<(&'static str, usize)> {
+    triggers
+        .iter()
+        .find(|(bytes, _)| rest.starts_with(bytes))
+        .map(|&(bytes, quote)| (quote, bytes.len()))
+}
+
+/// Quote and length of the trigger or the escaped byte starting `rest`
+#[inline]
+pub fn quote_at(
+    rest: &[u8],
+    triggers: &[Trigger],
+    escape_byte: fn(u8) -> Option<&'static str>,
+) -> Option<(&'static str, usize)> {
+    trigger_at(rest, triggers).or_else(|| {
+        rest.first()
+            .and_then(|&b| escape_byte(b))
+            .map(|quote| (quote, 1))
+    })
+}
+
+/// Call `f` with every run of `bytes` between the triggers followed by the
+/// quote of the trigger ending it, the last run has none
+///
+/// A first byte without the rest of its trigger stays in the run, the scan
+/// goes on after it. A trigger escaped as itself is given from `bytes`, so
+/// the scans following the writes see it as left unescaped
+#[inline(always)]
+fn parts<'a, E, F>(
+    bytes: &'a [u8],
+    find: fn(&[u8]) -> Option<usize>,
+    triggers: &[Trigger],
+    mut f: F,
+) -> Result<(), E>
+where
+    F: FnMut(&'a [u8], Option<&'a str>) -> Result<(), E>,
+{
+    let mut start = 0;
+    let mut from = 0;
+    while let Some(i) = find(&bytes[from..]) {
+        let i = from + i;
+        match trigger_at(&bytes[i..], triggers) {
+            Some((quote, len)) => {
+                let trigger = &bytes[i..i + len];
+                let quote = match str::from_utf8(trigger) {
+                    Ok(trigger) if trigger == quote => trigger,
+                    _ => quote,
+                };
+                f(&bytes[start..i], Some(quote))?;
+                start = i + len;
+                from = start;
+            }
+            None => from = i + 1,
+        }
+    }
+    f(&bytes[start..], None)
+}
+
+/// Length of the carry of `bytes`, the bytes at its end starting a trigger
+/// that the next chunk of the input may finish
+///
+/// The scan of `parts` is followed, a byte inside an escaped trigger doesn't
+/// start another one, up to the first byte with only a part of a trigger left
+/// after it
+#[inline]
+pub fn carry_len(bytes: &[u8], find: fn(&[u8]) -> Option<usize>, triggers: &[Trigger]) -> usize {
+    let mut from = 0;
+    while let Some(i) = find(&bytes[from..]) {
+        let i = from + i;
+        let rest = &bytes[i..];
+        match trigger_at(rest, triggers) {
+            Some((_, len)) => from = i + len,
+            None if triggers
+                .iter()
+                .any(|(trigger, _)| trigger.len() > rest.len() && trigger.starts_with(rest)) =>
+            {
+                return rest.len()
+            }
+            None => from = i + 1,
+        }
+    }
+    0
+}
+
+/// Length of the longest of `triggers`, the carry is shorter
+#[inline]
+pub const fn max_len(triggers: &[&[u8]]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < triggers.len() {
+        if max < triggers[i].len() {
+            max = triggers[i].len();
+        }
+        i += 1;
+    }
+    max
+}
+
+/// Length of the carry of an escaper without triggers, its escapes are single
+/// bytes so it has none
+#[inline]
+pub fn no_carry(_: &[u8]) -> usize {
+    0
+}
+
+#[inline]
+pub fn escape<R>(
+    bytes: &[u8],
+    fmt: &mut fmt::Formatter,
+    find: fn(&[u8]) -> Option<usize>,
+    triggers: &[Trigger],
+    run: R,
+) -> fmt::Result
+where
+    R: Fn(&[u8], &mut fmt::Formatter) -> fmt::Result,
+{
+    parts(bytes, find, triggers, |bytes, quote| {
+        run(bytes, fmt)?;
+        quote.map_or(Ok(()), |quote| fmt.write_str(quote))
+    })
+}
+
+#[inline]
+pub fn f_escape(
+    bytes: &[u8],
+    buf: &mut [MaybeUninit<u8>],
+    find: fn(&[u8]) -> Option<usize>,
+    triggers: &[Trigger],
+    run: fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+) -> Option<usize> {
+    let mut cur = 0;
+    parts::<(), _>(bytes, find, triggers, |bytes, quote| {
+        cur += run(bytes, &mut buf[cur..]).ok_or(())?;
+        if let Some(quote) = quote {
+            let dst = buf.get_mut(cur..cur + quote.len()).ok_or(())?;
+            for (d, &s) in dst.iter_mut().zip(quote.as_bytes()) {
+                *d = MaybeUninit::new(s);
+            }
+            cur += quote.len();
+        }
+        Ok(())
+    })
+    .ok()?;
+    Some(cur)
+}
+
+#[cfg(feature = "buf-min")]
+#[inline]
+pub fn b_escape<B: crate::Buffer>(
+    bytes: &[u8],
+    buf: &mut B,
+    find: fn(&[u8]) -> Option<usize>,
+    triggers: &[Trigger],
+    run: fn(&[u8], &mut B),
+) {
+    let _ = parts::<core::convert::Infallible, _>(bytes, find, triggers, |bytes, quote| {
+        run(bytes, buf);
+        if let Some(quote) = quote {
+            buf.extend(quote);
+        }
+        Ok(())
+    });
+}
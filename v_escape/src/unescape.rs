@@ -0,0 +1,297 @@
+//! Quotes mapped back to their characters, the `unescape` of the escapers
+//! generated with `unescape = true`
+//!
+//! The first bytes of the quotes are escaped, so each of them in the escaped
+//! text starts a quote. They are looked for with an escaper of their own,
+//! which has the vector loops of the escaper
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+/// Kind of an [`UnescapeError`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnescapeErrorKind {
+    /// A quote starts at the position but no quote is there
+    Unknown,
+    /// The input ends inside a quote
+    Incomplete,
+    /// The quote is well formed but isn't a character, like a reference to a
+    /// surrogate
+    Invalid,
+}
+
+/// Error of `unescape`, the quote at `position` can't be unescaped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnescapeError {
+    position: usize,
+    kind: UnescapeErrorKind,
+}
+
+impl UnescapeError {
+    /// Byte offset in the input of the quote
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Why the quote can't be unescaped
+    #[inline]
+    pub fn kind(&self) -> UnescapeErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for UnescapeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self.kind {
+            UnescapeErrorKind::Unknown => "unknown quote",
+            UnescapeErrorKind::Incomplete => "incomplete quote",
+            UnescapeErrorKind::Invalid => "invalid character",
+        };
+        write!(fmt, "{} at byte {}", what, self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnescapeError {}
+
+/// Read of the quote starting an input by a decoder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// The quote is the character, with the length of the quote
+    Char(char, usize),
+    /// The input is the start of a quote, which needs more bytes
+    Partial,
+    /// No quote starts the input
+    Unknown,
+    /// The quote, of this length, isn't a character
+    Invalid(usize),
+}
+
+/// Character replacing the quote at `at` of `bytes` and the end of the quote,
+/// `None` when it needs more bytes than the `last` ones. `position` is the
+/// position of `bytes` in the input
+///
+/// Lossy, the invalid quotes are `U+FFFD`, an unknown one replaces its first
+/// byte and an incomplete one the rest of `bytes`
+#[inline]
+fn read(
+    decode: fn(&[u8]) -> Decoded,
+    bytes: &[u8],
+    at: usize,
+    last: bool,
+    lossy: bool,
+    position: usize,
+) -> Result<Option<(char, usize)>, UnescapeError> {
+    let (kind, end) = match decode(&bytes[at..]) {
+        Decoded::Char(c, len) => return Ok(Some((c, at + len))),
+        Decoded::Partial if !last => return Ok(None),
+        Decoded::Partial => (UnescapeErrorKind::Incomplete, bytes.len()),
+        Decoded::Unknown => (UnescapeErrorKind::Unknown, at + 1),
+        Decoded::Invalid(len) => (UnescapeErrorKind::Invalid, at + len),
+    };
+    if lossy {
+        Ok(Some((char::REPLACEMENT_CHARACTER, end)))
+    } else {
+        Err(UnescapeError {
+            position: position + at,
+            kind,
+        })
+    }
+}
+
+/// Quote of `quotes`, sorted and none starting another, that starts `rest`
+#[inline]
+pub fn decode_quote(rest: &[u8], quotes: &[(&str, u8)]) -> Decoded {
+    // Sorted, the quotes before the one starting `rest` are less than it and
+    // the ones after greater. A shorter `rest` is equal to the quote it starts
+    let len = rest.len();
+    match quotes.binary_search_by(|(quote, _)| {
+        quote.as_bytes()[..quote.len().min(len)].cmp(&rest[..quote.len().min(len)])
+    }) {
+        Ok(i) if quotes[i].0.len() <= len => {
+            Decoded::Char(char::from(quotes[i].1), quotes[i].0.len())
+        }
+        Ok(_) => Decoded::Partial,
+        Err(_) => Decoded::Unknown,
+    }
+}
+
+/// `s` with every quote `find` points at replaced by the character `decode`
+/// reads from it
+///
+/// `find` gets the rest of `s` after the last quote and `decode` the rest of
+/// it from the quote start. The quotes have to end at a character boundary
+#[inline]
+pub fn unescape_with(
+    s: &str,
+    find: fn(&[u8]) -> Option<usize>,
+    decode: fn(&[u8]) -> Decoded,
+) -> Result<Cow<'_, str>, UnescapeError> {
+    unescape_str(s, find, decode, false)
+}
+
+/// Like [`unescape_with`], with `U+FFFD` in place of the quotes that can't be
+/// unescaped
+#[inline]
+pub fn unescape_lossy_with(
+    s: &str,
+    find: fn(&[u8]) -> Option<usize>,
+    decode: fn(&[u8]) -> Decoded,
+) -> Cow<'_, str> {
+    match unescape_str(s, find, decode, true) {
+        Ok(s) => s,
+        Err(_) => unreachable!("lossy unescaping doesn't fail"),
+    }
+}
+
+fn unescape_str(
+    s: &str,
+    find: fn(&[u8]) -> Option<usize>,
+    decode: fn(&[u8]) -> Decoded,
+    lossy: bool,
+) -> Result<Cow<'_, str>, UnescapeError> {
+    let bytes = s.as_bytes();
+    let mut next = match find(bytes) {
+        Some(first) => first,
+        None => return Ok(Cow::Borrowed(s)),
+    };
+
+    let mut out = String::with_capacity(s.len());
+    let mut start = 0;
+    loop {
+        let at = start + next;
+        out.push_str(&s[start..at]);
+
+        // The input is whole, it doesn't need more bytes
+        if let Some((c, end)) = read(decode, bytes, at, true, lossy, 0)? {
+            out.push(c);
+            start = end;
+        }
+
+        match find(&bytes[start..]) {
+            Some(i) => next = i,
+            None => break,
+        }
+    }
+    out.push_str(&s[start..]);
+
+    Ok(Cow::Owned(out))
+}
+
+/// Length of the character cut at the end of `bytes`
+#[inline]
+fn cut_char(bytes: &[u8]) -> usize {
+    for (i, &b) in bytes.iter().rev().take(3).enumerate() {
+        // Continuation bytes are `0b10xx_xxxx`
+        if b & 0xC0 != 0x80 {
+            let width = match b {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if width > i + 1 { i + 1 } else { 0 };
+        }
+    }
+    0
+}
+
+/// Unescapes utf-8 input given in chunks, the output is the same as
+/// unescaping the concatenation of the chunks
+///
+/// The quotes and characters cut at the end of a chunk are kept until the
+/// next one completes them. After an error the stream should be dropped, its
+/// output is unspecified. [`lossy`](UnescapeStream::lossy) streams write
+/// `U+FFFD` in place of the errors
+#[derive(Debug, Clone)]
+pub struct UnescapeStream {
+    find: fn(&[u8]) -> Option<usize>,
+    decode: fn(&[u8]) -> Decoded,
+    /// Cut end of the last chunk
+    pending: Vec<u8>,
+    /// Position in the stream of the start of `pending`
+    position: usize,
+    lossy: bool,
+}
+
+impl UnescapeStream {
+    /// Stream unescaping the quotes `find` points at with `decode`, as in
+    /// [`unescape_with`]
+    #[inline]
+    pub fn new(find: fn(&[u8]) -> Option<usize>, decode: fn(&[u8]) -> Decoded) -> Self {
+        UnescapeStream {
+            find,
+            decode,
+            pending: Vec::new(),
+            position: 0,
+            lossy: false,
+        }
+    }
+
+    /// This stream writing `U+FFFD` in place of the quotes that can't be
+    /// unescaped, as in [`unescape_lossy_with`], so it doesn't fail
+    #[inline]
+    pub fn lossy(mut self) -> Self {
+        self.lossy = true;
+        self
+    }
+
+    /// Append unescaped `chunk` to `out`, but for a cut quote or character at
+    /// its end
+    pub fn feed(&mut self, chunk: &[u8], out: &mut Vec<u8>) -> Result<(), UnescapeError> {
+        if self.pending.is_empty() {
+            let done = self.unescape(chunk, false, out)?;
+            self.pending.extend_from_slice(&chunk[done..]);
+            self.position += done;
+        } else {
+            let mut bytes = core::mem::take(&mut self.pending);
+            bytes.extend_from_slice(chunk);
+            let done = self.unescape(&bytes, false, out)?;
+            bytes.drain(..done);
+            self.pending = bytes;
+            self.position += done;
+        }
+        Ok(())
+    }
+
+    /// Append the rest of the output to `out`, ending the stream. A quote cut
+    /// at the end is an error
+    pub fn finish(mut self, out: &mut Vec<u8>) -> Result<(), UnescapeError> {
+        let bytes = core::mem::take(&mut self.pending);
+        self.unescape(&bytes, true, out).map(|_| ())
+    }
+
+    /// Unescape `bytes` to `out`, returns the length of the unescaped prefix
+    fn unescape(
+        &self,
+        bytes: &[u8],
+        last: bool,
+        out: &mut Vec<u8>,
+    ) -> Result<usize, UnescapeError> {
+        let end = if last {
+            bytes.len()
+        } else {
+            bytes.len() - cut_char(bytes)
+        };
+        let mut start = 0;
+        while start < end {
+            let at = match (self.find)(&bytes[start..end]) {
+                Some(i) => start + i,
+                None => break,
+            };
+            out.extend_from_slice(&bytes[start..at]);
+
+            match read(self.decode, bytes, at, last, self.lossy, self.position)? {
+                Some((c, end)) => {
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                    start = end;
+                }
+                None => return Ok(at),
+            }
+        }
+        let end = end.max(start);
+        out.extend_from_slice(&bytes[start..end]);
+
+        Ok(end)
+    }
+}
@@ -0,0 +1,344 @@
+use core::{fmt, mem::MaybeUninit, ptr, slice};
+
+pub use alloc::{borrow::Cow, string::String, vec::Vec};
+#[cfg(feature = "std")]
+pub use std::io;
+
+/// Collects the escaped output, only allocating when something is escaped
+///
+/// The escaping loops write unescaped runs as slices of the input, and the
+/// quotes kept as they are in idempotent mode too. A write that doesn't
+/// continue the input is a replacement, until then nothing is copied
+pub struct CowWriter<'a> {
+    input: &'a str,
+    consumed: usize,
+    out: Option<String>,
+}
+
+impl<'a> CowWriter<'a> {
+    #[inline]
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            consumed: 0,
+            out: None,
+        }
+    }
+
+    #[inline]
+    pub fn into_cow(self) -> Cow<'a, str> {
+        match self.out {
+            Some(out) => Cow::Owned(out),
+            None => Cow::Borrowed(&self.input[..self.consumed]),
+        }
+    }
+}
+
+impl<'a> fmt::Write for CowWriter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match &mut self.out {
+            Some(out) => out.push_str(s),
+            None if continues(self.input, self.consumed, s) => self.consumed += s.len(),
+            None => {
+                let mut out = String::with_capacity(self.input.len() + s.len());
+                out.push_str(&self.input[..self.consumed]);
+                out.push_str(s);
+                self.out = Some(out);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `s` is the slice of `input` starting at `consumed`, an empty write
+/// changes nothing
+#[inline]
+fn continues(input: &str, consumed: usize, s: &str) -> bool {
+    s.is_empty() || ptr::eq(s.as_ptr(), input.as_ptr().wrapping_add(consumed))
+}
+
+/// Appends the escaped output to a `String`, remembering if anything was
+/// escaped, a write that doesn't continue the input as `CowWriter` tells
+pub struct StringWriter<'a> {
+    input: &'a str,
+    consumed: usize,
+    escaped: bool,
+    out: &'a mut String,
+}
+
+impl<'a> StringWriter<'a> {
+    #[inline]
+    pub fn new(input: &'a str, out: &'a mut String) -> Self {
+        Self {
+            input,
+            consumed: 0,
+            escaped: false,
+            out,
+        }
+    }
+
+    /// A quote was written, or the input wasn't written to its end as
+    /// happens when its last bytes are removed
+    #[inline]
+    pub fn escaped(&self) -> bool {
+        self.escaped || self.consumed != self.input.len()
+    }
+}
+
+impl<'a> fmt::Write for StringWriter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if !self.escaped && continues(self.input, self.consumed, s) {
+            self.consumed += s.len();
+        } else {
+            self.escaped = true;
+        }
+        self.out.push_str(s);
+
+        Ok(())
+    }
+}
+
+/// Appends the escaped output to a `String` until `left` bytes are used
+///
+/// Quotes are appended whole or not at all, unescaped runs are cut at the
+/// last char boundary that fits. The first write that doesn't fit stops the
+/// escaping loop
+pub struct BudgetWriter<'a> {
+    input: &'a str,
+    left: usize,
+    truncated: bool,
+    out: &'a mut String,
+}
+
+impl<'a> BudgetWriter<'a> {
+    #[inline]
+    pub fn new(input: &'a str, max_len: usize, out: &'a mut String) -> Self {
+        Self {
+            input,
+            left: max_len,
+            truncated: false,
+            out,
+        }
+    }
+
+    #[inline]
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<'a> fmt::Write for BudgetWriter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() <= self.left {
+            self.left -= s.len();
+            self.out.push_str(s);
+            return Ok(());
+        }
+
+        if self.input.as_bytes().as_ptr_range().contains(&s.as_ptr()) {
+            let mut end = self.left;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.out.push_str(&s[..end]);
+        }
+        self.truncated = true;
+
+        Err(fmt::Error)
+    }
+}
+
+/// Append the output of `f_escape` to `buf`
+///
+/// The first try only reserves the length of `bytes`, when it doesn't fit it
+/// escapes again with the worst case, every byte replaced by the longest quote
+#[inline]
+pub fn extend_vec<F>(bytes: &[u8], buf: &mut Vec<u8>, quotes_max: usize, f_escape: F)
+where
+    F: Fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+{
+    let len = buf.len();
+    buf.reserve(bytes.len());
+    let written = match f_escape(bytes, buf.spare_capacity_mut()) {
+        Some(written) => written,
+        None => {
+            buf.reserve(bytes.len() * quotes_max.max(1));
+            f_escape(bytes, buf.spare_capacity_mut()).expect("worst case capacity")
+        }
+    };
+
+    // SAFETY: `f_escape` initialized `written` bytes past `len`
+    unsafe { buf.set_len(len + written) }
+}
+
+#[cfg(feature = "bytes")]
+pub use bytes::{Bytes, BytesMut};
+
+/// Append the output of `f_escape` to `buf`, as `extend_vec` does
+#[cfg(feature = "bytes")]
+#[inline]
+pub fn extend_bytes_mut<F>(bytes: &[u8], buf: &mut BytesMut, quotes_max: usize, f_escape: F)
+where
+    F: Fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+{
+    let len = buf.len();
+    buf.reserve(bytes.len());
+    let written = match f_escape(bytes, buf.spare_capacity_mut()) {
+        Some(written) => written,
+        None => {
+            buf.reserve(bytes.len() * quotes_max.max(1));
+            f_escape(bytes, buf.spare_capacity_mut()).expect("worst case capacity")
+        }
+    };
+
+    // SAFETY: `f_escape` initialized `written` bytes past `len`
+    unsafe { buf.set_len(len + written) }
+}
+
+/// Forwards every write to an `io::Write`, counting the written bytes
+///
+/// `fmt::Error` carries no information, so the first io error is kept
+#[cfg(feature = "std")]
+pub struct IoWriter<'a, W: io::Write + ?Sized> {
+    inner: &'a mut W,
+    written: usize,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: io::Write + ?Sized> IoWriter<'a, W> {
+    #[inline]
+    pub fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            written: 0,
+            error: None,
+        }
+    }
+
+    /// Written bytes or the io error of a failed `fmt::Result`
+    #[inline]
+    pub fn finish(self, result: fmt::Result) -> io::Result<usize> {
+        match (result, self.error) {
+            (Ok(()), _) => Ok(self.written),
+            (Err(_), Some(e)) => Err(e),
+            (Err(_), None) => Err(io::Error::other("formatter error")),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: io::Write + ?Sized> fmt::Write for IoWriter<'a, W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.inner.write_all(s.as_bytes()) {
+            Ok(()) => {
+                self.written += s.len();
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(e);
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+/// Escape `buf` from `first`, the position of its first escape, keeping
+/// the allocation and the unescaped prefix in place
+///
+/// The tail is moved to the end of the spare capacity and escaped from there
+/// back over its old place. The first try only reserves the length of the
+/// tail, when the output doesn't fit the tail is put back and it escapes
+/// again with the worst case, as `extend_vec` does
+#[inline]
+pub fn escape_from<F>(buf: &mut Vec<u8>, first: usize, quotes_max: usize, f_escape: F)
+where
+    F: Fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+{
+    let tail = buf.len() - first;
+    buf.reserve(tail);
+    if let Some(written) = escape_tail(buf, first, &f_escape) {
+        // SAFETY: `f_escape` initialized `written` bytes past `first`
+        unsafe { buf.set_len(first + written) };
+        return;
+    }
+
+    // SAFETY: the tail is still whole at the end of the capacity, it is moved
+    // back to `first` and was initialized there
+    unsafe {
+        let ptr = buf.as_mut_ptr();
+        ptr::copy(ptr.add(buf.capacity() - tail), ptr.add(first), tail);
+        buf.set_len(first + tail);
+    }
+    buf.reserve(tail * quotes_max.max(1));
+    let written = escape_tail(buf, first, &f_escape).expect("worst case capacity");
+    // SAFETY: `f_escape` initialized `written` bytes past `first`
+    unsafe { buf.set_len(first + written) };
+}
+
+/// Move the tail of `buf` from `first` to the end of its capacity and escape
+/// it into the room left between `first` and its copy
+///
+/// `buf` keeps its length, the caller sets it from the bytes written
+#[inline]
+fn escape_tail<F>(buf: &mut Vec<u8>, first: usize, f_escape: &F) -> Option<usize>
+where
+    F: Fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+{
+    let tail = buf.len() - first;
+    let at = buf.capacity() - tail;
+    let ptr = buf.as_mut_ptr();
+    // SAFETY: `at` is past the length, so the tail and its copy are both in
+    // the allocation, and the output `first..at` doesn't overlap the copy
+    unsafe {
+        ptr::copy(ptr.add(first), ptr.add(at), tail);
+        let src = slice::from_raw_parts(ptr.add(at), tail);
+        let dst = slice::from_raw_parts_mut(ptr.add(first).cast::<MaybeUninit<u8>>(), at - first);
+        f_escape(src, dst)
+    }
+}
+
+/// `s` escaped from `first` as `escape_from` does, reusing its allocation
+#[inline]
+pub fn escape_string_from<F>(s: String, first: usize, quotes_max: usize, f_escape: F) -> String
+where
+    F: Fn(&[u8], &mut [MaybeUninit<u8>]) -> Option<usize>,
+{
+    let mut bytes = s.into_bytes();
+    escape_from(&mut bytes, first, quotes_max, f_escape);
+    // SAFETY: only ascii bytes or every byte of the multi-byte characters of
+    // valid utf-8 were replaced by `&str`s
+    unsafe { String::from_utf8_unchecked(bytes) }
+}
+
+/// Append escaped `chunk` to `out`, following the bytes held back in `carry`
+///
+/// The bytes at the end counted by `carry_len` may start an escape the next
+/// chunk finishes, they are left in `carry`
+#[inline]
+pub fn feed_chunk<F, C>(
+    carry: &mut Vec<u8>,
+    chunk: &[u8],
+    out: &mut Vec<u8>,
+    escape_bytes: F,
+    carry_len: C,
+) where
+    F: Fn(&[u8], &mut Vec<u8>),
+    C: Fn(&[u8]) -> usize,
+{
+    if carry.is_empty() {
+        let end = chunk.len() - carry_len(chunk);
+        escape_bytes(&chunk[..end], out);
+        carry.extend_from_slice(&chunk[end..]);
+    } else {
+        carry.extend_from_slice(chunk);
+        let end = carry.len() - carry_len(carry);
+        escape_bytes(&carry[..end], out);
+        carry.drain(..end);
+    }
+}
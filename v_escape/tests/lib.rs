@@ -49,12 +49,236 @@ macro_rules! test {
             assert_eq!(escape_char(c).to_string(), c.to_string());
         }
 
+        let mut buf = String::with_capacity(escaped.len());
+        for c in escapes.chars() {
+            match escape_char_quote(c) {
+                Some(quote) => buf.push_str(quote),
+                None => buf.push(c),
+            }
+            assert_eq!(escape_byte(c as u8), escape_char_quote(c));
+        }
+        assert_eq!(buf, escaped);
+        for c in utf8.chars() {
+            assert_eq!(escape_char_quote(c), None);
+        }
+        for b in 0x80..=0xFF {
+            assert_eq!(escape_byte(b), None);
+        }
+
         assert_eq!($name::from(empty).to_string(), empty);
         assert_eq!($name::from(escapes).to_string(), escaped);
         assert_eq!(escape(&empty_heap).to_string(), empty);
         assert_eq!(escape(&cow).to_string(), escaped);
         assert_eq!(escape(&string).to_string(), escaped);
         assert_eq!(escape(&utf8).to_string(), utf8);
+        assert!(!needs_escaping(empty));
+        assert!(!needs_escaping(utf8));
+        assert!(!needs_escaping(string_long));
+        assert!(needs_escaping(escapes));
+        assert_eq!(find_first_escape(utf8), None);
+        assert_eq!(find_first_escape(escapes), Some(0));
+        assert_eq!(
+            find_first_escape(&[string_long, escapes, short].join("")),
+            Some(string_long.len())
+        );
+        assert_eq!(
+            find_first_escape(&[utf8, escapes, utf8, escapes].join("")),
+            Some(utf8.len())
+        );
+        assert_eq!(
+            find_first_escape_bytes([utf8, escapes].concat().as_bytes()),
+            Some(utf8.len())
+        );
+        let mut buf = String::new();
+        escape_fragments(vec![short, escapes, empty, utf8, escapes], &mut buf).unwrap();
+        escape_fragments(None, &mut buf).unwrap();
+        assert_eq!(buf, [short, escaped, utf8, escaped].join(""));
+        assert_eq!(escape_iter(empty).next(), None);
+        assert_eq!(escape_iter(utf8).collect::<Vec<_>>(), [utf8]);
+        let mix = [utf8, escapes, string_long, escapes].join("");
+        let chunks: Vec<_> = escape_iter(&mix).collect();
+        assert!(chunks.iter().all(|chunk| !chunk.is_empty()));
+        assert_eq!(
+            chunks.concat(),
+            [utf8, escaped, string_long, escaped].join("")
+        );
+        assert_eq!(chunks[0], utf8);
+        assert_eq!(escape_positions(utf8).next(), None);
+        let positions: Vec<_> = escape_positions(&mix).collect();
+        let expected: Vec<_> = mix
+            .bytes()
+            .enumerate()
+            .filter_map(|(i, b)| escape_byte(b).map(|quote| (i, quote)))
+            .collect();
+        assert_eq!(positions, expected);
+        assert_eq!(positions[0].0, utf8.len());
+        assert_eq!(escaped_len(empty), 0);
+        assert_eq!(escaped_len(utf8), utf8.len());
+        assert_eq!(escaped_len(escapes), escaped.len());
+        assert_eq!(
+            escaped_len(&[string_long, escapes, utf8, escapes].join("")),
+            [string_long, escaped, utf8, escaped].join("").len()
+        );
+        let mix = [short, escapes, utf8].join("");
+        let mix_escaped = [short, escaped, utf8].join("");
+        let mut buf = vec![0; mix_escaped.len()];
+        assert_eq!(escape_to_slice(&mix, &mut buf), Ok(mix_escaped.len()));
+        assert_eq!(buf, mix_escaped.as_bytes());
+        assert_eq!(
+            escape_to_slice(&mix, &mut buf[..short.len()]),
+            Err(v_escape::BufferTooSmall)
+        );
+        assert_eq!(escape_to_slice(empty, &mut []), Ok(0));
+        #[cfg(not(feature = "forbid-unsafe"))]
+        {
+            let mut buf = vec![0; mix_escaped.len()];
+            unsafe {
+                assert_eq!(
+                    escape_raw(mix.as_ptr(), mix.len(), buf.as_mut_ptr(), buf.len()),
+                    mix_escaped.len() as isize
+                );
+                assert_eq!(buf, mix_escaped.as_bytes());
+                assert_eq!(
+                    escape_raw(mix.as_ptr(), mix.len(), buf.as_mut_ptr(), short.len()),
+                    -(mix_escaped.len() as isize)
+                );
+                assert_eq!(escape_raw(std::ptr::null(), 0, std::ptr::null_mut(), 0), 0);
+            }
+        }
+
+        assert!(matches!(escape_cow(empty), Cow::Borrowed("")));
+        assert!(matches!(escape_cow(utf8), Cow::Borrowed(_)));
+        assert!(matches!(escape_cow(short), Cow::Borrowed(_)));
+        assert!(matches!(escape_cow(string_long), Cow::Borrowed(_)));
+        assert_eq!(escape_cow(escapes), escaped);
+        assert_eq!(
+            escape_cow(&[short, escapes, short].join("")),
+            [short, escaped, short].join("")
+        );
+        assert_eq!(
+            escape_cow(&[utf8, escapes].join("")),
+            [utf8, escaped].join("")
+        );
+
+        let mut buf = String::from(short);
+        assert!(!escape_into(empty, &mut buf));
+        assert!(!escape_into(utf8, &mut buf));
+        assert!(escape_into(escapes, &mut buf));
+        assert!(escape_into(&[short, escapes].join(""), &mut buf));
+        assert_eq!(buf, [short, utf8, escaped, short, escaped].join(""));
+
+        let mix = [short, escapes, utf8].join("");
+        let mix_escaped = [short, escaped, utf8].join("");
+        let mut buf = String::new();
+        assert!(!escape_truncated(&mix, mix_escaped.len(), &mut buf));
+        assert_eq!(buf, mix_escaped);
+        for max_len in 0..short.len() + escaped.len() + 8 {
+            let mut buf = String::from(short);
+            assert!(escape_truncated(&mix, max_len, &mut buf));
+            let out = &buf[short.len()..];
+            assert!(out.len() <= max_len);
+            assert!(mix_escaped.starts_with(out));
+            // Stops at a quote boundary, the next chunk doesn't fit
+            let next = escape_iter(&mix)
+                .scan(0, |len, chunk| {
+                    *len += chunk.len();
+                    Some((*len, chunk))
+                })
+                .find(|&(len, _)| out.len() < len)
+                .unwrap();
+            assert!(next.0 > max_len);
+        }
+
+        let mut buf = short.as_bytes().to_vec();
+        escape_bytes(escapes.as_bytes(), &mut buf);
+        escape_bytes(string_long.as_bytes(), &mut buf);
+        escape_bytes(&escapes.repeat(1024).into_bytes(), &mut buf);
+        escape_bytes(&[0xFF, 0xFE], &mut buf);
+        let mut expected = [short, escaped, string_long, &escaped.repeat(1024)]
+            .join("")
+            .into_bytes();
+        expected.extend_from_slice(&[0xFF, 0xFE]);
+        assert_eq!(buf, expected);
+
+        let clean = string_long.to_string();
+        let ptr = clean.as_ptr();
+        let clean = escape_owned(clean);
+        assert_eq!(clean, string_long);
+        assert_eq!(clean.as_ptr(), ptr);
+        assert_eq!(
+            escape_owned([utf8, escapes, short, escapes].join("")),
+            [utf8, escaped, short, escaped].join("")
+        );
+        assert!(matches!(
+            escape_cow_in(Cow::Borrowed(string_long)),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(escape_cow_in(Cow::Borrowed(escapes)), escaped);
+        let clean = string_long.to_string();
+        let ptr = clean.as_ptr();
+        match escape_cow_in(Cow::Owned(clean)) {
+            Cow::Owned(clean) => assert_eq!(clean.as_ptr(), ptr),
+            Cow::Borrowed(_) => panic!("owned input"),
+        }
+        assert_eq!(
+            escape_cow_in(Cow::Owned([short, escapes].join(""))),
+            [short, escaped].join("")
+        );
+        let clean = escape_vec(string_long.as_bytes().to_vec());
+        assert_eq!(clean, string_long.as_bytes());
+        let mut dirty = [short, escapes, short].join("").into_bytes();
+        dirty.push(0xFF);
+        let mut expected = [short, escaped, short].join("").into_bytes();
+        expected.push(0xFF);
+        assert_eq!(escape_vec(dirty), expected);
+        // Escaped in place when the spare capacity holds the tail and its output
+        let mut dirty = String::with_capacity(
+            short.len() + escapes.len() + 2 * escapes.len().max(escaped.len()),
+        );
+        dirty.push_str(short);
+        dirty.push_str(escapes);
+        let ptr = dirty.as_ptr();
+        let dirty = escape_owned(dirty);
+        assert_eq!(dirty, [short, escaped].join(""));
+        assert_eq!(dirty.as_ptr(), ptr);
+        let long = [string_long, &escapes.repeat(300)].join("");
+        assert_eq!(
+            escape_vec(long.into_bytes()),
+            [string_long, &escaped.repeat(300)].join("").into_bytes()
+        );
+
+        let input = [short, escapes, utf8, escapes, string_long].join("");
+        let mut expected = vec![];
+        escape_bytes(input.as_bytes(), &mut expected);
+        for size in &[1, 3, 7, 64, 1000] {
+            let mut stream = StreamEscaper::new();
+            let mut out = vec![];
+            for chunk in input.as_bytes().chunks(*size) {
+                stream.feed(chunk, &mut out);
+            }
+            stream.finish(&mut out);
+            assert_eq!(out, expected);
+        }
+
+        let mut out = vec![];
+        let mut reader = escaping_reader(input.as_bytes());
+        std::io::copy(&mut reader, &mut out).unwrap();
+        assert_eq!(out, expected);
+
+        let mut writer = escaping_writer(vec![]);
+        for chunk in input.as_bytes().chunks(7) {
+            std::io::Write::write_all(&mut writer, chunk).unwrap();
+        }
+        assert_eq!(writer.into_inner(), expected);
+
+        let mut buf = vec![];
+        let mix = [short, escapes, string_long, escapes].join("");
+        let mix_escaped = [short, escaped, string_long, escaped].join("");
+        assert_eq!(escape_to_writer(&mix, &mut buf).unwrap(), mix_escaped.len());
+        assert_eq!(escape_to_writer(empty, &mut buf).unwrap(), 0);
+        assert_eq!(buf, mix_escaped.as_bytes());
+        let mut buf = [0u8; 3];
+        assert!(escape_to_writer(string_long, &mut &mut buf[..]).is_err());
         assert_eq!($name::from(string_long).to_string(), string_long);
         assert_eq!(
             $name::from(escapes.repeat(1024).as_ref()).to_string(),
@@ -235,6 +459,20 @@ fn test_escape() {
     test_ptr!("<", "foo")
 }
 
+#[test]
+fn test_dyn_escaper() {
+    use v_escape::Escaper;
+
+    let escapers: [&dyn Escaper; 3] = [ESCAPER, bytes_buff::ESCAPER, scalar_only::ESCAPER];
+    let expected = ["fooAQ&", "baf&", "&lt;AQ&amp;"];
+    for (escaper, expected) in escapers.iter().zip(expected.iter()) {
+        let mut out = String::new();
+        escaper.escape_to("<AQ&", &mut out).unwrap();
+        assert_eq!(out, *expected);
+        assert_eq!(escaper.escaped_len("<AQ&"), expected.len());
+    }
+}
+
 mod bytes_buff {
     v_escape::new!(MyE, "65->a || 60->b || 61->c || 66->d || 80->e || 81->f");
     #[test]
@@ -537,14 +775,1383 @@ mod test_avx {
     }
 }
 
-mod char_syntax {
+mod test_swar {
+    mod numbers {
+        v_escape::new!(
+            MyE,
+            "#0->zero || #1->one || #2->two || #3->three || #4->four || #5->five || \
+             #6->six || #7->seven || #8->eight || #9->nine",
+            simd = false
+        );
+
+        #[test]
+        fn test_escape_a() {
+            test!(
+                MyE,
+                "0123456789",
+                "zeroonetwothreefourfivesixseveneightnine"
+            );
+            test_ptr!("0123456789", "zeroonetwothreefourfivesixseveneightnine");
+        }
+
+        #[test]
+        fn test_escape_b() {
+            test!(
+                MyE,
+                "0 1-2 3-4 56789",
+                "zero one-two three-four fivesixseveneightnine"
+            );
+            test_ptr!(
+                "0 1-2 3-4 56789",
+                "zero one-two three-four fivesixseveneightnine"
+            );
+        }
+    }
+
     mod a {
-        v_escape::new!(MyE, " ->f");
+        // 3 ranges
+        v_escape::new!(
+            MyE,
+            "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+            simd = false
+        );
 
         #[test]
         fn test_escape() {
-            test!(MyE, " ", "f");
-            test_ptr!(" ", "f");
+            test!(MyE, "<=ABPQ", "bcadef");
+            test_ptr!("<=ABPQ", "bcadef");
+        }
+    }
+
+    mod b {
+        // 2 ranges and 1 escape
+        v_escape::new!(
+            MyE,
+            "60->a || 61->b || 65->c || 80->d || 81->e",
+            simd = false
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=APQ", "abcde");
+            test_ptr!("<=APQ", "abcde");
+        }
+    }
+
+    mod c {
+        // 1 range and 2 escapes
+        v_escape::new!(MyE, "60->a || 65->c || 80->d || 62->e", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>AP", "aecd");
+            test_ptr!("<>AP", "aecd");
+        }
+    }
+
+    mod d {
+        // 3 escapes
+        v_escape::new!(MyE, "60->a || 80->b || 65->c", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<AP", "acb");
+            test_ptr!("<AP", "acb");
+        }
+    }
+
+    mod e {
+        // 2 ranges
+        v_escape::new!(
+            MyE,
+            "60->a || 61->b || 81->c || 80->d || 62->e",
+            simd = false
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=>PQ", "abedc");
+            test_ptr!("<=>PQ", "abedc");
+        }
+    }
+
+    mod f {
+        // 1 range and 1 escape
+        v_escape::new!(MyE, "60->a || 61->b || 80->c || 62->d", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=>P", "abdc");
+            test_ptr!("<=>P", "abdc");
+        }
+    }
+
+    mod g {
+        // 2 escapes
+        v_escape::new!(MyE, "60->a || 80->b", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<P", "ab");
+            test_ptr!("<P", "ab");
+        }
+    }
+
+    mod h {
+        // 1 range
+        v_escape::new!(MyE, "60->a || 61->b", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=", "ab");
+            test_ptr!("<=", "ab");
+        }
+    }
+
+    mod i {
+        // 1 escapes
+        v_escape::new!(MyE, "60->f", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<", "f");
+            test_ptr!("<", "f");
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod test_avx512 {
+    mod a {
+        // 3 ranges
+        v_escape::new!(
+            MyE,
+            "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+            avx512 = true
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=ABPQ", "bcadef");
+            test_ptr!("<=ABPQ", "bcadef");
+        }
+    }
+
+    mod b {
+        // 1 range and 2 escapes
+        v_escape::new!(MyE, "60->a || 65->c || 80->d || 62->e", avx512 = true);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>AP", "aecd");
+            test_ptr!("<>AP", "aecd");
+        }
+    }
+
+    mod c {
+        // 1 escapes
+        v_escape::new!(MyE, "60->f", avx512 = true);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<", "f");
+            test_ptr!("<", "f");
+        }
+    }
+
+    mod no_avx {
+        v_escape::new!(
+            MyE,
+            "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+            avx = false,
+            avx512 = true
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=ABPQ", "bcadef");
+            test_ptr!("<=ABPQ", "bcadef");
+        }
+    }
+}
+
+mod test_static {
+    mod a {
+        // 3 ranges
+        v_escape::new!(
+            MyE,
+            "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+            detect = "static"
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<=ABPQ", "bcadef");
+            test_ptr!("<=ABPQ", "bcadef");
         }
     }
+
+    mod b {
+        // 1 range and 2 escapes
+        v_escape::new!(
+            MyE,
+            "60->a || 65->c || 80->d || 62->e",
+            avx512 = true,
+            detect = "static"
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>AP", "aecd");
+            test_ptr!("<>AP", "aecd");
+        }
+    }
+
+    mod c {
+        // 1 escapes
+        v_escape::new!(MyE, "60->f", avx = false, detect = "static");
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<", "f");
+            test_ptr!("<", "f");
+        }
+    }
+}
+
+// Only the scalar loop is generated with `forbid-unsafe`
+#[cfg(all(target_arch = "x86_64", not(feature = "forbid-unsafe")))]
+mod backend {
+    use v_escape::Backend;
+
+    v_escape::new!(
+        MyE,
+        "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+        avx512 = true
+    );
+
+    #[test]
+    fn test_force_backend() {
+        let detected = current_backend();
+        assert_ne!(detected, Backend::Scalar);
+        assert!(!force_backend(Backend::Neon));
+        assert_eq!(current_backend(), detected);
+
+        for &backend in &[
+            Backend::Scalar,
+            Backend::Swar,
+            Backend::Sse,
+            Backend::Avx,
+            Backend::Avx512,
+        ] {
+            if force_backend(backend) {
+                assert_eq!(current_backend(), backend);
+                test!(MyE, "<=ABPQ", "bcadef");
+                test_ptr!("<=ABPQ", "bcadef");
+            }
+        }
+    }
+
+    mod tune {
+        // Own escaper, the tests share the selected backend
+        v_escape::new!(
+            MyE,
+            "65->a || 60->b || 61->c || 66->d || 80->e || 81->f",
+            avx512 = true
+        );
+
+        #[test]
+        fn test_tune() {
+            let backend = tune();
+            assert_eq!(current_backend(), backend);
+            test!(MyE, "<=ABPQ", "bcadef");
+            test_ptr!("<=ABPQ", "bcadef");
+        }
+    }
+}
+
+mod pad {
+    v_escape::new!(MyE, "60->&lt; || 62->&gt;");
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(format!("{:8}|", escape("<a>")), "&lt;a&gt;|");
+        assert_eq!(format!("{:12}|", escape("<a>")), "&lt;a&gt;   |");
+        assert_eq!(format!("{:>12}|", escape("<a>")), "   &lt;a&gt;|");
+        assert_eq!(format!("{:*^13}|", escape("<a>")), "**&lt;a&gt;**|");
+        assert_eq!(format!("{:.6}|", escape("<a>")), "&lt;a&|");
+        assert_eq!(format!("{:>8.6}|", escape("<a>")), "  &lt;a&|");
+        assert_eq!(format!("{:.0}|", escape("<a>")), "|");
+        assert_eq!(format!("{:>4}|", escape("é<")), "é&lt;|");
+        assert_eq!(format!("{:>7}|", escape("é<")), "  é&lt;|");
+        assert_eq!(format!("{:>6}|", escape_char('<')), "  &lt;|");
+        assert_eq!(format!("{:-<4.2}|", escape_char('>')), "&g--|");
+    }
+}
+
+mod char_syntax {
+    mod a {
+        v_escape::new!(MyE, " ->f");
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, " ", "f");
+            test_ptr!(" ", "f");
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+mod async_writer {
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use tokio::io::AsyncWrite;
+
+    v_escape::new!(MyE, "60->&lt; || 62->&gt;");
+
+    /// Takes at most 3 bytes per write, and every other write is pending
+    #[derive(Default)]
+    struct Slow {
+        out: Vec<u8>,
+        ready: bool,
+    }
+
+    impl AsyncWrite for Slow {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Poll::Pending;
+            }
+            let n = buf.len().min(3);
+            self.out.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_async_escaping_writer() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut writer = async_escaping_writer(Slow::default());
+        let input = "<a>foo</a> bar <<>>";
+
+        for chunk in input.as_bytes().chunks(4) {
+            let mut chunk = chunk;
+            while !chunk.is_empty() {
+                if let Poll::Ready(n) = Pin::new(&mut writer).poll_write(&mut cx, chunk) {
+                    chunk = &chunk[n.unwrap()..];
+                }
+            }
+        }
+        while Pin::new(&mut writer).poll_flush(&mut cx).is_pending() {}
+
+        assert_eq!(
+            writer.into_inner().out,
+            escape(input).to_string().as_bytes()
+        );
+    }
+
+    mod triggers {
+        use std::{
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        use tokio::io::AsyncWrite;
+
+        use super::Slow;
+
+        v_escape::new!(MyE, "60->&lt; || \"]]>\" -> \"]]]]><![CDATA[>\"");
+
+        #[test]
+        fn test_async_escaping_writer_triggers() {
+            let mut cx = Context::from_waker(Waker::noop());
+            let input = "a]]>b]]]><]]";
+            for size in 1..input.len() {
+                let mut writer = async_escaping_writer(Slow::default());
+                for chunk in input.as_bytes().chunks(size) {
+                    let mut chunk = chunk;
+                    while !chunk.is_empty() {
+                        if let Poll::Ready(n) = Pin::new(&mut writer).poll_write(&mut cx, chunk) {
+                            chunk = &chunk[n.unwrap()..];
+                        }
+                    }
+                }
+                while Pin::new(&mut writer).poll_shutdown(&mut cx).is_pending() {}
+
+                assert_eq!(
+                    writer.into_inner().out,
+                    escape(input).to_string().as_bytes(),
+                    "{}",
+                    size
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+mod bytes_mut {
+    use bytes::BytesMut;
+
+    v_escape::new!(MyE, "60->&lt; || 62->&gt;");
+
+    #[test]
+    fn test_bytes_mut() {
+        let input = "<a>foo</a> bar <<>>";
+        let expected = escape(input).to_string();
+
+        let mut buf = BytesMut::from("x");
+        escape_bytes_mut(input, &mut buf);
+        escape_bytes_mut("", &mut buf);
+        escape_bytes_mut(&input.repeat(64), &mut buf);
+        assert_eq!(
+            buf,
+            format!("x{}{}", expected, expected.repeat(64)).as_bytes()
+        );
+
+        assert_eq!(escape(input).to_bytes(), expected.as_bytes());
+        assert_eq!(escape("foo").to_bytes(), "foo".as_bytes());
+        assert!(escape("").to_bytes().is_empty());
+    }
+}
+
+mod preset {
+    mod html {
+        v_escape::new!(MyE, preset = "html");
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+            test_ptr!("<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+        }
+    }
+
+    mod html_extended {
+        // Adds a pair and replaces the quote of `'`
+        v_escape::new!(MyE, "96->&#96; || 39->&#39;", preset = "html", simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "\"&'/<>`", "&quot;&amp;&#39;&#x2f;&lt;&gt;&#96;");
+            test_ptr!("\"&'/<>`", "&quot;&amp;&#39;&#x2f;&lt;&gt;&#96;");
+        }
+    }
+
+    mod shell {
+        v_escape::new!(MyE, preset = "shell", avx = false);
+
+        #[test]
+        fn test_escape() {
+            assert_eq!(
+                escape("rm -rf $HOME; ls").to_string(),
+                r"rm\ -rf\ \$HOME\;\ ls"
+            );
+            assert_eq!(escape("a\nb\tc").to_string(), "a'\n'b\\\tc");
+        }
+    }
+
+    mod json {
+        v_escape::new!(MyE, preset = "json");
+
+        #[test]
+        fn test_escape() {
+            assert_eq!(escape("\"a\\\u{1}\n").to_string(), "\\\"a\\\\\\u0001\\n");
+        }
+    }
+
+    mod latex {
+        v_escape::new!(MyE, preset = "latex");
+
+        #[test]
+        fn test_escape() {
+            assert_eq!(escape("50% & $x_1$").to_string(), "50\\% \\& \\$x\\_1\\$");
+        }
+    }
+}
+
+mod range_syntax {
+    v_escape::new!(MyE, "0x00-0x08->? || 0x0B-0x1F->? || 0x7F->?");
+
+    #[test]
+    fn test_escape() {
+        let controls: String = (0u8..0x20)
+            .chain(Some(0x7F))
+            .filter(|b| !matches!(b, 0x09 | 0x0A))
+            .map(char::from)
+            .collect();
+        let escaped = "?".repeat(controls.len());
+        test!(MyE, controls.as_str(), escaped.as_str());
+        test_ptr!(controls.as_str(), escaped.as_str());
+        assert_eq!(escape("a\tb\nc\x07").to_string(), "a\tb\nc?");
+    }
+}
+
+mod template {
+    v_escape::new!(MyE, "0x00-0x1F->\\u00{:02x} || <->&#x{:02X}; || >->&#{:};");
+
+    #[test]
+    fn test_escape() {
+        let controls: String = (0u8..0x20).map(char::from).chain("<>".chars()).collect();
+        let escaped: String = (0u8..0x20)
+            .map(|b| format!("\\u00{:02x}", b))
+            .chain(vec!["&#x3C;".to_string(), "&#62;".to_string()])
+            .collect();
+        test!(MyE, controls.as_str(), escaped.as_str());
+        test_ptr!(controls.as_str(), escaped.as_str());
+    }
+}
+
+mod literal_syntax {
+    v_escape::new!(
+        MyE,
+        r#"'<' -> "&lt;" || 0x3E -> "&gt;" || '\n' -> "\\n" || '\t' -> "\\t" || '"' -> "\\\"""#
+    );
+
+    #[test]
+    fn test_escape() {
+        test!(MyE, "<>\n\t\"", "&lt;&gt;\\n\\t\\\"");
+        test_ptr!("<>\n\t\"", "&lt;&gt;\\n\\t\\\"");
+    }
+}
+
+mod file {
+    mod a {
+        v_escape::new!(MyE, file = "tests/escapes.txt");
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "&<>", "&amp;&lt;&gt;");
+            test_ptr!("&<>", "&amp;&lt;&gt;");
+        }
+    }
+
+    mod b {
+        // Pairs of the file and inline
+        v_escape::new!(
+            MyE,
+            "'\"' -> \"&quot;\"",
+            file = "tests/escapes.txt",
+            simd = false
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "\"&<>", "&quot;&amp;&lt;&gt;");
+            test_ptr!("\"&<>", "&quot;&amp;&lt;&gt;");
+        }
+    }
+}
+
+mod new_escape {
+    mod a {
+        v_escape::new_escape!(MyE, "<->&lt; || >->&gt;");
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>", "&lt;&gt;");
+            test_ptr!("<>", "&lt;&gt;");
+        }
+    }
+
+    mod b {
+        v_escape::new_escape!(MyE, preset = html, simd = false);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+            test_ptr!("<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+        }
+    }
+}
+
+mod module {
+    // Two escapers side by side
+    v_escape::new!(Text, "<->&lt; || >->&gt; || &->&amp;", module = text);
+    v_escape::new!(
+        Attr,
+        "'\"' -> \"&quot;\" || &->&amp;",
+        module = attr,
+        simd = false
+    );
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(text::escape("<\"&>").to_string(), "&lt;\"&amp;&gt;");
+        assert_eq!(attr::escape("<\"&>").to_string(), "<&quot;&amp;>");
+        assert_eq!(Text::from("<").to_string(), "&lt;");
+        assert_eq!(Attr::from("\"").to_string(), "&quot;");
+        assert!(!attr::needs_escaping("<>"));
+    }
+}
+
+mod chain {
+    mod exact {
+        // More intervals than the 3 ranges, all of them compared
+        v_escape::new!(
+            MyE,
+            "0x01-0x03->? || 0x0B->! || 0x0E-0x10->? || 0x22->q || 0x5C->b"
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "\x01\x02\x03\x0B\x0E\x0F\x10\"\\", "???!???qb");
+            test_ptr!("\x01\x02\x03\x0B\x0E\x0F\x10\"\\", "???!???qb");
+        }
+    }
+
+    mod lossy {
+        // Too many to compare, the closest are merged
+        v_escape::new!(
+            MyE,
+            "C->1 || E->2 || G->3 || I->4 || K->5 || M->6 || c->7 || \
+             e->8 || g->9 || i->0 || k->+ || m->- || q->="
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "CEGIKMcegikmq", "1234567890+-=");
+            test_ptr!("CEGIKMcegikmq", "1234567890+-=");
+        }
+    }
+}
+
+mod strategy {
+    mod eq {
+        v_escape::new!(MyE, preset = "html", strategy = eq);
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+            test_ptr!("<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+        }
+    }
+
+    mod ranges {
+        v_escape::new!(
+            MyE,
+            "0x01-0x03->? || 0x0B->! || 0x0E-0x10->? || 0x22->q || 0x5C->b",
+            strategy = ranges
+        );
+
+        #[test]
+        fn test_escape() {
+            test!(MyE, "\x01\x02\x03\x0B\x0E\x0F\x10\"\\", "???!???qb");
+            test_ptr!("\x01\x02\x03\x0B\x0E\x0F\x10\"\\", "???!???qb");
+        }
+    }
+}
+
+mod high_bytes {
+    use v_escape::Backend;
+
+    const BACKENDS: &[Backend] = &[
+        Backend::Scalar,
+        Backend::Swar,
+        Backend::Sse,
+        Backend::Avx,
+        Backend::Avx512,
+    ];
+
+    mod framing {
+        // Bytes that are never in utf-8
+        v_escape::new!(MyE, "0xC0->[C0] || 0xFE->[FE] || 0xFF->[FF]", avx512 = true);
+
+        #[test]
+        fn test_escape() {
+            let input = b"a\xC0b\xFE\xFFc".repeat(100);
+            let expected = "a[C0]b[FE][FF]c".repeat(100);
+            for &backend in super::BACKENDS {
+                if force_backend(backend) {
+                    let mut buf = vec![];
+                    escape_bytes(&input, &mut buf);
+                    assert_eq!(buf, expected.as_bytes());
+                    assert_eq!(escape_vec(input.clone()), expected.as_bytes());
+                }
+            }
+            assert_eq!(escape_byte(0xFF), Some("[FF]"));
+            assert_eq!(escape_byte(0x80), None);
+            assert!(!needs_escaping(&"aé\u{10FFFF}".repeat(20)));
+        }
+    }
+
+    mod lossy {
+        // Too many to compare, the closest are merged across 128
+        v_escape::new!(
+            MyE,
+            "0x01->a || 0x03->b || 0x05->c || 0x07->d || 0x09->e || 0xC0-0xC1->f || \
+             0xF5->g || 0xF7->h || 0xF9->i || 0xFB->j || 0xFD->k || 0xFF->l",
+            avx512 = true
+        );
+
+        #[test]
+        fn test_escape() {
+            let input = b"\x01\x02\x03\x05\x07\x09\x7F\x80\xC0\xC1\xF5\xF6\xF7\xF9\xFB\xFD\xFE\xFF"
+                .repeat(40);
+            let expected = b"a\x02bcde\x7F\x80ffg\xF6hijk\xFEl".repeat(40);
+            for &backend in super::BACKENDS {
+                if force_backend(backend) {
+                    let mut buf = vec![];
+                    escape_bytes(&input, &mut buf);
+                    assert_eq!(buf, expected);
+                }
+            }
+        }
+    }
+
+    mod percent {
+        // Every byte of the multi-byte characters
+        v_escape::new!(MyE, "'%'->%25 || 0x80-0xFF->%{:02X}", avx512 = true);
+
+        #[test]
+        fn test_escape() {
+            let input = "100% é ✓ 🦀 ".repeat(30);
+            let expected = "100%25 %C3%A9 %E2%9C%93 %F0%9F%A6%80 ".repeat(30);
+            for &backend in super::BACKENDS {
+                if force_backend(backend) {
+                    assert_eq!(escape(&input).to_string(), expected);
+                    assert_eq!(escaped_len(&input), expected.len());
+                    assert_eq!(escape_iter(&input).collect::<String>(), expected);
+                    assert_eq!(escape_owned(input.clone()), expected);
+                }
+            }
+            assert_eq!(find_first_escape("ab é"), Some(3));
+            assert_eq!(
+                escape_positions("é%").collect::<Vec<_>>(),
+                vec![(0, "%C3"), (1, "%A9"), (2, "%25")]
+            );
+            // Characters are only looked up when they are ascii
+            assert_eq!(escape_char('é').to_string(), "é");
+            assert_eq!(escape_char('%').to_string(), "%25");
+        }
+    }
+}
+
+mod differential {
+    // The generated tests are run with the ones of this file
+    mod html {
+        v_escape::new!(MyE, preset = "html", avx512 = true, tests = true);
+    }
+
+    mod lossy {
+        v_escape::new!(
+            MyE,
+            "C->1 || E->2 || G->3 || I->4 || K->5 || M->6 || c->7 || \
+             e->8 || g->9 || i->0 || k->+ || m->- || q->=",
+            avx512 = true,
+            tests = true
+        );
+    }
+
+    mod percent {
+        v_escape::new!(
+            MyE,
+            "'%'->%25 || 0x80-0xFF->%{:02X}",
+            avx512 = true,
+            tests = true
+        );
+    }
+
+    mod swar {
+        v_escape::new!(MyE, "0x00-0x1F->? || '\"'->q", simd = false, tests = true);
+    }
+
+    v_escape::new!(MyE, "<->&lt; || >->&gt;", module = module, tests = true);
+
+    #[test]
+    fn test_module() {
+        assert_eq!(MyE::from("<a>").to_string(), "&lt;a&gt;");
+    }
+}
+
+mod scalar_only {
+    use v_escape::Backend;
+
+    v_escape::new!(MyE, preset = "html", simd = scalar, tests = true);
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(current_backend(), Backend::Scalar);
+        assert!(!force_backend(Backend::Swar));
+        test!(MyE, "<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+        test_ptr!("<>&\"'/", "&lt;&gt;&amp;&quot;&#x27;&#x2f;");
+    }
+}
+
+mod no_prelude {
+    #[no_implicit_prelude]
+    mod html {
+        ::v_escape::new!(MyE, preset = "html", avx512 = true, tests = true);
+    }
+
+    #[no_implicit_prelude]
+    mod one {
+        ::v_escape::new!(MyE, "<->&lt;", simd = false, tests = true);
+    }
+
+    #[no_implicit_prelude]
+    mod nested {
+        ::v_escape::new!(MyE, "<->&lt; || >->&gt;", module = escaper, tests = true);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(html::escape("<a>").to_string(), "&lt;a&gt;");
+        assert_eq!(one::escape("<a>").to_string(), "&lt;a>");
+        assert_eq!(nested::MyE::from("<a>").to_string(), "&lt;a&gt;");
+    }
+}
+
+mod dynamic {
+    use v_escape::{
+        dynamic::{BuildError, DynEscaper, EscaperBuilder},
+        Backend, Escaper,
+    };
+
+    /// `s` escaped byte by byte with the quotes of `escaper`
+    fn reference(escaper: &DynEscaper, s: &str) -> String {
+        let mut out = vec![];
+        for &b in s.as_bytes() {
+            match escaper.escape_byte(b) {
+                Some(quote) => out.extend_from_slice(quote.as_bytes()),
+                None => out.push(b),
+            }
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Check `escaper` with every backend it can be forced to
+    fn check(mut escaper: DynEscaper) {
+        let inputs: Vec<String> = (0..0x80u8)
+            .map(char::from)
+            .chain("é✓\u{10FFFF}".chars())
+            .flat_map(|c| {
+                [0, 1, 15, 31, 63, 64, 100].iter().map(move |&pad| {
+                    let mut s = "a".repeat(pad);
+                    s.push(c);
+                    s.push_str(&"b".repeat(pad));
+                    s
+                })
+            })
+            .collect();
+
+        for &backend in Backend::ALL.iter() {
+            if !escaper.force_backend(backend) {
+                continue;
+            }
+            assert_eq!(escaper.backend(), backend);
+            for s in &inputs {
+                let expected = reference(&escaper, s);
+                assert_eq!(escaper.escape(s).to_string(), expected, "{:?}", backend);
+                assert_eq!(escaper.escaped_len(s), expected.len());
+                let mut buf = vec![0; expected.len()];
+                assert_eq!(escaper.escape_to_slice(s, &mut buf), Ok(expected.len()));
+                assert_eq!(buf, expected.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_html() {
+        let escaper = EscaperBuilder::new()
+            .pair(b'<', "&lt;")
+            .pair(b'>', "&gt;")
+            .pair(b'&', "&amp;")
+            .pair(b'"', "&quot;")
+            .pair(b'\'', "&#x27;")
+            .pair(b'/', "&#x2f;")
+            .build()
+            .unwrap();
+        assert_eq!(
+            escaper.escape("<a href='/'>").to_string(),
+            "&lt;a href=&#x27;&#x2f;&#x27;&gt;"
+        );
+        assert_eq!(format!("{:>8}", escaper.escape("<")), "    &lt;");
+        check(escaper);
+    }
+
+    #[test]
+    fn test_ranges() {
+        // Consecutive characters and, past 12 compares, merged ones
+        let escapers = [
+            DynEscaper::new((0..0x20).map(|c| (c, "?"))).unwrap(),
+            DynEscaper::new(b"aeiou0123456789".iter().map(|&c| (c, "_"))).unwrap(),
+            DynEscaper::new(b"!#%'),/;>A[_}".iter().map(|&c| (c, "__"))).unwrap(),
+            DynEscaper::new((0..=255).map(|c| (c, "x"))).unwrap(),
+            DynEscaper::new(None).unwrap(),
+        ];
+        for escaper in escapers.iter() {
+            check(escaper.clone());
+        }
+    }
+
+    #[test]
+    fn test_dyn_escaper() {
+        let escaper = DynEscaper::new(vec![(b'<', "foo")]).unwrap();
+        let escapers: [&dyn Escaper; 2] = [&escaper, super::ESCAPER];
+        for escaper in escapers.iter() {
+            let mut out = String::new();
+            escaper.escape_to("a<b", &mut out).unwrap();
+            assert_eq!(out, "afoob");
+            assert_eq!(escaper.escaped_len("a<b"), 5);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            DynEscaper::new(vec![(b'<', "a"), (b'>', "b"), (b'<', "c")]).unwrap_err(),
+            BuildError::Repeated(b'<')
+        );
+        assert_eq!(
+            DynEscaper::new(vec![(0xC3, "?")]).unwrap_err(),
+            BuildError::PartialUtf8(0xC3)
+        );
+        assert!(DynEscaper::new((0x80..=0xFF).map(|c| (c, "?"))).is_ok());
+    }
+}
+
+mod registry {
+    use v_escape::registry::{registry, Entry};
+
+    static FIRST: Entry = Entry::new("test", super::ESCAPER);
+    static SECOND: Entry = Entry::new("test", super::bytes_buff::ESCAPER);
+
+    #[test]
+    fn test_registry() {
+        assert!(registry().get("test").is_none());
+
+        registry().register(&FIRST);
+        let mut out = String::new();
+        registry()
+            .get("test")
+            .unwrap()
+            .escape_to("<", &mut out)
+            .unwrap();
+        assert_eq!(out, "foo");
+
+        // The last one registered is found, and registering again does nothing
+        registry().register(&SECOND);
+        registry().register(&FIRST);
+        let mut out = String::new();
+        registry()
+            .get("test")
+            .unwrap()
+            .escape_to("<", &mut out)
+            .unwrap();
+        assert_eq!(out, "b");
+        assert_eq!(
+            registry().entries().filter(|e| e.name() == "test").count(),
+            2
+        );
+        assert!(registry().get("missing").is_none());
+    }
+}
+
+mod then {
+    v_escape::new!(
+        MyE,
+        "'<' -> \"&lt;\" || '&' -> \"&amp;\"",
+        then = "'&' -> \"+\" || '\\r' -> \"\" || '\\n' -> \" \"",
+        tests = true
+    );
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a<b&c\r\nd").to_string(), "a+lt;b+amp;c d");
+        assert_eq!(escape_byte(b'<'), Some("+lt;"));
+        assert_eq!(escape_byte(b'\n'), Some(" "));
+    }
+}
+
+mod shared_quotes {
+    v_escape::new!(
+        MyE,
+        "0->? || 1->? || '<' -> \"&lt;\" || 127->? || '>' -> \"&gt;\"",
+        tests = true
+    );
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("\0<\x01>\x7F").to_string(), "?&lt;?&gt;?");
+        assert_eq!(escape_byte(1), Some("?"));
+        assert_eq!(escape_byte(b'>'), Some("&gt;"));
+        assert_eq!(escape_byte(2), None);
+    }
+}
+
+mod unescape {
+    use std::borrow::Cow;
+    use v_escape::UnescapeErrorKind;
+
+    v_escape::new!(
+        MyE,
+        "'<' -> \"&lt;\" || '>' -> \"&gt;\" || '&' -> \"&amp;\" || '\"' -> \"&quot;\"",
+        unescape = true
+    );
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(unescape(""), Ok(Cow::Borrowed(""))));
+        assert!(matches!(unescape("foo ✓"), Ok(Cow::Borrowed("foo ✓"))));
+        assert_eq!(unescape("&lt;a&gt;&amp;&quot;").unwrap(), "<a>&\"");
+        assert_eq!(unescape("é&amp;lt;✓").unwrap(), "é&lt;✓");
+
+        let err = unescape("a &lt; b &foo; c").unwrap_err();
+        assert_eq!(err.position(), 9);
+        assert_eq!(err.kind(), UnescapeErrorKind::Unknown);
+        assert_eq!(err.to_string(), "unknown quote at byte 9");
+        let err = unescape("&l").unwrap_err();
+        assert_eq!(
+            (err.position(), err.kind()),
+            (0, UnescapeErrorKind::Incomplete)
+        );
+        assert_eq!(err.to_string(), "incomplete quote at byte 0");
+        assert_eq!(unescape("&lt;&").unwrap_err().position(), 4);
+
+        // Past the widest vector
+        let s = "<p class=\"a&b\">✓</p>".repeat(20);
+        assert_eq!(unescape(&escape(&s).to_string()).unwrap(), s);
+        for len in 0..100 {
+            let s: String = "a<&é\"".chars().cycle().take(len).collect();
+            assert_eq!(unescape(&escape(&s).to_string()).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_unescape_lossy() {
+        assert!(matches!(unescape_lossy("foo ✓"), Cow::Borrowed("foo ✓")));
+        assert_eq!(unescape_lossy("&lt;a&gt;"), "<a>");
+        assert_eq!(unescape_lossy("a &foo; é&am"), "a \u{FFFD}foo; é\u{FFFD}");
+    }
+
+    #[test]
+    fn test_unescape_stream() {
+        let s = escape(&"<p class=\"a&b\">é✓</p>".repeat(3)).to_string();
+        for size in 1..s.len() {
+            let mut stream = unescape_stream();
+            let mut out = Vec::new();
+            for chunk in s.as_bytes().chunks(size) {
+                stream.feed(chunk, &mut out).unwrap();
+            }
+            stream.finish(&mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), unescape(&s).unwrap());
+        }
+
+        let mut stream = unescape_stream();
+        let mut out = Vec::new();
+        stream.feed(b"a&q", &mut out).unwrap();
+        stream.feed(b"uot", &mut out).unwrap();
+        assert_eq!(out, b"a");
+        assert_eq!(stream.finish(&mut out).unwrap_err().position(), 1);
+
+        let mut stream = unescape_stream();
+        stream.feed(b"&lt", &mut out).unwrap();
+        assert_eq!(stream.feed(b"&", &mut out).unwrap_err().position(), 0);
+    }
+
+    mod scalar_only {
+        v_escape::new!(
+            MyE,
+            "'<' -> \"<<\" || '>' -> \">>\"",
+            simd = scalar,
+            unescape = true
+        );
+
+        #[test]
+        fn test_unescape() {
+            assert_eq!(unescape("a<<b>>").unwrap(), "a<b>");
+            assert_eq!(unescape("<<<").unwrap_err().position(), 2);
+        }
+    }
+}
+
+mod triggers {
+    use std::borrow::Cow;
+
+    v_escape::new!(
+        MyE,
+        "'<' -> \"&lt;\" || '&' -> \"&amp;\" || \"]]>\" -> \"]]]]><![CDATA[>\" || \"--\" -> \"- -\"",
+        tests = true
+    );
+
+    #[test]
+    fn test_triggers() {
+        assert_eq!(escape("a]]>b").to_string(), "a]]]]><![CDATA[>b");
+        assert_eq!(escape("]]]>").to_string(), "]]]]]><![CDATA[>");
+        assert_eq!(
+            escape("]] > <!-- & -->").to_string(),
+            "]] > &lt;!- - &amp; - ->"
+        );
+        assert_eq!(escape("---").to_string(), "- --");
+        assert_eq!(escape("é]]>✓]").to_string(), "é]]]]><![CDATA[>✓]");
+        assert!(matches!(escape_cow("a]] -"), Cow::Borrowed("a]] -")));
+        assert!(!needs_escaping("]]-]>"));
+        assert_eq!(find_first_escape("a]]]>"), Some(2));
+        assert_eq!(
+            escape_positions("<-->").collect::<Vec<_>>(),
+            [(0, "&lt;"), (1, "- -")]
+        );
+        assert_eq!(escape_iter("a--b").collect::<Vec<_>>(), ["a", "- -", "b"]);
+        assert_eq!(escape_vec(b"--]]>".to_vec()), b"- -]]]]><![CDATA[>");
+
+        // Past the widest vector
+        for len in 0..100 {
+            let s: String = "]]>-é<-".chars().cycle().take(len).collect();
+            let escaped = escape(&s).to_string();
+            assert_eq!(escaped_len(&s), escaped.len());
+            assert_eq!(escape_cow(&s), escaped);
+            let mut buf = vec![0; escaped.len()];
+            assert_eq!(escape_to_slice(&s, &mut buf), Ok(escaped.len()));
+            assert_eq!(buf, escaped.as_bytes());
+            assert_eq!(escape_iter(&s).collect::<String>(), escaped);
+        }
+    }
+
+    #[test]
+    fn test_triggers_chunks() {
+        let input = "a]]>b]]]>c--->d<]]-]>é--".repeat(3);
+        let expected = escape(&input).to_string();
+        for size in 1..input.len() {
+            let mut stream = StreamEscaper::new();
+            let mut out = vec![];
+            for chunk in input.as_bytes().chunks(size) {
+                stream.feed(chunk, &mut out);
+            }
+            stream.finish(&mut out);
+            assert_eq!(out, expected.as_bytes(), "{}", size);
+
+            let mut writer = escaping_writer(vec![]);
+            for chunk in input.as_bytes().chunks(size) {
+                std::io::Write::write_all(&mut writer, chunk).unwrap();
+            }
+            std::io::Write::flush(&mut writer).unwrap();
+            assert_eq!(writer.into_inner(), expected.as_bytes(), "{}", size);
+        }
+
+        let mut out = vec![];
+        let chained = std::io::Read::chain(input.as_bytes(), &b"]"[..]);
+        let mut reader = escaping_reader(std::io::Read::chain(chained, &b"]>]]"[..]));
+        std::io::copy(&mut reader, &mut out).unwrap();
+        assert_eq!(out, format!("{}]]]]><![CDATA[>]]", expected).as_bytes());
+
+        // Every split in two and three fragments
+        let bounds: Vec<usize> = (0..=input.len())
+            .filter(|&i| input.is_char_boundary(i))
+            .collect();
+        for &i in &bounds {
+            let mut buf = String::new();
+            escape_fragments([&input[..i], &input[i..]], &mut buf).unwrap();
+            assert_eq!(buf, expected, "{}", i);
+            for &j in bounds.iter().filter(|&&j| i <= j && j < i + 6) {
+                let mut buf = String::new();
+                let fragments = [&input[..i], "", &input[i..j], &input[j..]];
+                escape_fragments(fragments, &mut buf).unwrap();
+                assert_eq!(buf, expected, "{} {}", i, j);
+            }
+        }
+
+        // A flush ends the trigger started before it
+        let mut writer = escaping_writer(vec![]);
+        std::io::Write::write_all(&mut writer, b"a]]").unwrap();
+        std::io::Write::flush(&mut writer).unwrap();
+        std::io::Write::write_all(&mut writer, b">-").unwrap();
+        assert_eq!(writer.get_ref(), b"a]]>");
+        std::io::Write::flush(&mut writer).unwrap();
+        assert_eq!(writer.into_inner(), b"a]]>-");
+    }
+
+    mod only {
+        v_escape::new!(Script, "\"</\" -> \"<\\\\/\" || \"<!--\" -> \"<\\\\!--\"");
+
+        #[test]
+        fn test_only_triggers() {
+            assert_eq!(escape("<p></p>").to_string(), "<p><\\/p>");
+            assert_eq!(
+                escape("\"<!--</script>\"").to_string(),
+                "\"<\\!--<\\/script>\""
+            );
+            assert_eq!(escape_byte(b'<'), None);
+            assert_eq!(escape_char('<').to_string(), "<");
+            assert_eq!(current_backend(), v_escape::Backend::Scalar);
+        }
+
+        #[test]
+        fn test_only_triggers_chunks() {
+            let mut stream = StreamEscaper::new();
+            let mut out = vec![];
+            stream.feed(b"<", &mut out);
+            assert!(out.is_empty());
+            stream.feed(b"/", &mut out);
+            stream.feed(b"p><!-", &mut out);
+            stream.feed(b"-<", &mut out);
+            stream.finish(&mut out);
+            assert_eq!(out, b"<\\/p><\\!--<");
+
+            let mut writer = escaping_writer(vec![]);
+            std::io::Write::write_all(&mut writer, b"<").unwrap();
+            std::io::Write::write_all(&mut writer, b"/").unwrap();
+            std::io::Write::flush(&mut writer).unwrap();
+            assert_eq!(writer.into_inner(), b"<\\/");
+        }
+
+        #[test]
+        fn test_only_triggers_fragments() {
+            let mut buf = String::new();
+            escape_fragments(["a<", "/b"], &mut buf).unwrap();
+            assert_eq!(buf, "a<\\/b");
+
+            let mut buf = String::new();
+            escape_fragments(["<", "!", "-", "", "-<", "<", "/"], &mut buf).unwrap();
+            assert_eq!(buf, "<\\!--<<\\/");
+
+            let mut buf = String::new();
+            escape_fragments(["<!-", "<", "/é"], &mut buf).unwrap();
+            assert_eq!(buf, "<!-<\\/é");
+        }
+    }
+}
+
+mod idempotent {
+    use std::borrow::Cow;
+
+    v_escape::new!(
+        MyE,
+        "'<' -> \"&lt;\" || '>' -> \"&gt;\" || '&' -> \"&amp;\" || '\"' -> \"&quot;\" || '\\'' -> \"&quot;\"",
+        idempotent = true,
+        tests = true
+    );
+
+    #[test]
+    fn test_idempotent() {
+        assert_eq!(escape("a & b").to_string(), "a &amp; b");
+        assert_eq!(escape("a &amp; b").to_string(), "a &amp; b");
+        assert_eq!(
+            escape("&lt;a&gt; &am &quot;").to_string(),
+            "&lt;a&gt; &amp;am &quot;"
+        );
+        assert_eq!(escape("<'&amp;'>").to_string(), "&lt;&quot;&amp;&quot;&gt;");
+        assert_eq!(escape("&&amp;&").to_string(), "&amp;&amp;&amp;");
+        assert!(!needs_escaping("&lt;é&amp;"));
+        // Already escaped input is left as it is
+        assert!(matches!(escape_cow("a&amp;b"), Cow::Borrowed("a&amp;b")));
+        assert!(matches!(
+            escape_cow("&lt;&quot;"),
+            Cow::Borrowed("&lt;&quot;")
+        ));
+        assert_eq!(escape_cow("&amp;&"), "&amp;&amp;");
+        let mut buf = String::new();
+        assert!(!escape_into("a&amp;b", &mut buf));
+        assert!(escape_into("&amp;<", &mut buf));
+        assert_eq!(buf, "a&amp;b&amp;&lt;");
+
+        for len in 0..100 {
+            let s: String = "&<amp;&quot;é>\"".chars().cycle().take(len).collect();
+            let escaped = escape(&s).to_string();
+            assert_eq!(escape(&escaped).to_string(), escaped);
+            assert_eq!(escaped_len(&s), escaped.len());
+        }
+    }
+
+    #[test]
+    fn test_idempotent_chunks() {
+        let input = "a &amp; b &am &quot;<&&amp;>";
+        let expected = escape(input).to_string();
+        for size in 1..input.len() {
+            let mut stream = StreamEscaper::new();
+            let mut out = vec![];
+            for chunk in input.as_bytes().chunks(size) {
+                stream.feed(chunk, &mut out);
+            }
+            stream.finish(&mut out);
+            assert_eq!(out, expected.as_bytes(), "{}", size);
+        }
+
+        let mut writer = escaping_writer(vec![]);
+        std::io::Write::write_all(&mut writer, b"&am").unwrap();
+        std::io::Write::write_all(&mut writer, b"p; &").unwrap();
+        std::io::Write::flush(&mut writer).unwrap();
+        assert_eq!(writer.into_inner(), b"&amp; &amp;");
+
+        let mut buf = String::new();
+        escape_fragments(["a &am", "p; &qu", "ot", ";"], &mut buf).unwrap();
+        assert_eq!(buf, "a &amp; &quot;");
+    }
+
+    mod json {
+        v_escape::new!(MyJson, preset = "json", idempotent = true);
+
+        #[test]
+        fn test_idempotent_json() {
+            assert_eq!(escape("a\n\\n").to_string(), "a\\n\\n");
+            assert_eq!(escape("\\\\ \\u001f \\x").to_string(), "\\\\ \\u001f \\\\x");
+        }
+    }
+}
+
+mod finder {
+    use std::fmt::Write;
+
+    v_escape::finder!(quotes, "0x00-0x1F || '\"'->\\\" || 0x80-0xBF || 0xC2-0xF4");
+    v_escape::finder!(scalar, "0x00-0x1F || '\"'->\\\"", simd = scalar);
+
+    #[test]
+    fn test_finder() {
+        let long = "a".repeat(70);
+        for find in [quotes::find_first_escape, scalar::find_first_escape] {
+            assert_eq!(find(""), None);
+            assert_eq!(find(&long), None);
+            assert_eq!(find("ab\"c"), Some(2));
+            assert_eq!(find(&format!("{}\n", long)), Some(70));
+        }
+        assert_eq!(quotes::find_first_escape("abé"), Some(2));
+        assert_eq!(quotes::find_first_escape(&format!("{}😀", long)), Some(70));
+        assert_eq!(scalar::find_first_escape("abé"), None);
+        assert_eq!(quotes::find_first_escape_bytes(b"a\x01"), Some(1));
+
+        assert_eq!(quotes::escape_byte(b'"'), Some("\\\""));
+        assert_eq!(quotes::escape_byte(0x01), None);
+        assert_eq!(quotes::escape_byte(0xC3), None);
+        assert_eq!(quotes::escape_byte(b'a'), None);
+    }
+
+    #[test]
+    fn test_escape_found() {
+        let escape = |s: &str| {
+            let mut out = String::new();
+            v_escape::scan::escape_found_chars(
+                s,
+                &mut out,
+                quotes::find_first_escape_bytes,
+                quotes::escape_byte,
+                |c, out| match c {
+                    'é' => Some(out.write_str("e")),
+                    '\u{1}' => Some(out.write_str("^A")),
+                    _ => None,
+                },
+            )
+            .unwrap();
+            out
+        };
+        assert_eq!(escape(""), "");
+        assert_eq!(escape("abc"), "abc");
+        assert_eq!(escape("a\"é\u{1}\n😀b"), "a\\\"e^A\n😀b");
+        let s = "ö\"é".repeat(40);
+        assert_eq!(escape(&s), "ö\\\"e".repeat(40));
+    }
 }
@@ -1,196 +1,450 @@
-use std::{
-    fmt::{Display, Write},
-    str,
-};
+use std::{cmp::Reverse, str};
 
-use quote::quote;
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote};
 
-use crate::parser::Pair;
+use crate::{parser::Pair, Detect, Strategy};
 
-type Ranges = Vec<u8>;
+type Ranges = Vec<u16>;
+
+/// Implementation of a target architecture in the `ranges` module
+struct Target {
+    /// Condition of the target the module is built for
+    cfg: TokenStream,
+    module: &'static str,
+    /// Variant of `v_escape::Backend`
+    backend: &'static str,
+    /// Check of the running cpu and the equivalent cfg of `detect = static`,
+    /// none when `cfg` already requires the target feature
+    detect: Option<(TokenStream, TokenStream)>,
+}
 
 struct Generator<'a> {
     pairs: &'a [Pair<'a>],
     simd: bool,
+    /// Only the byte at a time loop, without swar
+    scalar: bool,
+    /// Only safe code, the scalar loop of `v_escape::safe`
+    safe: bool,
     avx: bool,
+    avx512: bool,
+    detect: Detect,
+    strategy: Strategy,
 }
 
-pub fn generate(pairs: &[Pair], simd: bool, avx: bool) -> String {
-    Generator::new(pairs, simd, avx).build()
+#[allow(clippy::too_many_arguments)]
+pub fn generate(
+    pairs: &[Pair],
+    simd: bool,
+    scalar: bool,
+    safe: bool,
+    avx: bool,
+    avx512: bool,
+    detect: Detect,
+    strategy: Strategy,
+) -> TokenStream {
+    Generator::new(pairs, simd, scalar, safe, avx, avx512, detect, strategy).build()
 }
 
 impl<'a> Generator<'a> {
-    pub fn new<'n>(pairs: &'n [Pair<'n>], simd: bool, avx: bool) -> Generator<'n> {
-        Generator { pairs, simd, avx }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<'n>(
+        pairs: &'n [Pair<'n>],
+        simd: bool,
+        scalar: bool,
+        safe: bool,
+        avx: bool,
+        avx512: bool,
+        detect: Detect,
+        strategy: Strategy,
+    ) -> Generator<'n> {
+        // The tables are looked up with binary searches
+        debug_assert!(pairs.windows(2).all(|p| p[0].char < p[1].char));
+        Generator {
+            pairs,
+            simd,
+            scalar: scalar || safe,
+            safe,
+            avx,
+            avx512,
+            detect,
+            strategy,
+        }
     }
 
-    pub fn build(&self) -> String {
-        let mut buf = Buffer::new(0);
+    pub fn build(&self) -> TokenStream {
+        let table = self.static_table();
+        let functions = self.functions();
+        let cfg_if = self.cfg_if();
 
-        self.write_static_table(&mut buf);
-        self.write_functions(&mut buf);
-        self.write_cfg_if(&mut buf);
-
-        buf.buf
+        quote!(#table #functions #cfg_if)
     }
 
-    fn write_static_table(&self, buf: &mut Buffer) {
-        let len = self.pairs.len();
-        let quote = str::from_utf8(self.pairs[0].quote).unwrap();
-
-        if len == 1 {
-            buf.writeln(&format!(
-                "const V_ESCAPE_CHAR: u8 = {};",
-                self.pairs[0].char
-            ));
-            buf.writeln(&format!("static V_ESCAPE_QUOTES: &str = {:#?};", quote));
+    fn static_table(&self) -> TokenStream {
+        let max = self.pairs.iter().map(|s| s.quote.len()).max().unwrap_or(0);
+        // Quotes are checked to be utf-8 by the parser
+        let mut quotes = self.pairs.iter().map(|s| str::from_utf8(&s.quote).unwrap());
+
+        let (table, len) = if self.pairs.len() == 1 {
+            let char = Literal::u8_unsuffixed(self.pairs[0].char);
+            let quote = quotes.next();
+            let table = quote!(
+                const V_ESCAPE_CHAR: u8 = #char;
+                static V_ESCAPE_QUOTES: &str = #quote;
+            );
+            (table, 1)
         } else {
-            buf.write("static V_ESCAPE_TABLE: [u8; 256] = [");
-            for i in 0..=255 as u8 {
+            // Characters with the same quote share its entry
+            let mut unique: Vec<&str> = vec![];
+            let index: Vec<usize> = quotes
+                .map(|quote| match unique.iter().position(|u| *u == quote) {
+                    Some(i) => i,
+                    None => {
+                        unique.push(quote);
+                        unique.len() - 1
+                    }
+                })
+                .collect();
+            let len = unique.len();
+            let table = (0..=255u8).map(|i| {
                 let n = self
                     .pairs
                     .binary_search_by(|s| s.char.cmp(&i))
-                    .unwrap_or(len);
-                buf.write(&format!("{}, ", n))
-            }
-            buf.writeln("];");
+                    .map_or(len, |n| index[n]);
+                Literal::usize_unsuffixed(n)
+            });
+            let size = Literal::usize_unsuffixed(len);
+            let table = quote!(
+                static V_ESCAPE_TABLE: [u8; 256] = [#(#table),*];
+                static V_ESCAPE_QUOTES: [&str; #size] = [#(#unique),*];
+            );
+            (table, len)
+        };
 
-            let quotes: Vec<&str> = self
-                .pairs
-                .iter()
-                .map(|s| str::from_utf8(s.quote).unwrap())
-                .collect();
-            buf.writeln(&format!(
-                "static V_ESCAPE_QUOTES: [&str; {}] = {:#?};",
-                len, quotes
-            ));
+        let (len, max) = (
+            Literal::usize_unsuffixed(len),
+            Literal::usize_unsuffixed(max),
+        );
+        quote!(
+            #table
+            const V_ESCAPE_LEN: usize = #len;
+            const V_ESCAPE_QUOTES_MAX: usize = #max;
+        )
+    }
+
+    fn functions(&self) -> TokenStream {
+        // The escaper, `escape_new!` implements `v_escape::Escaper` for it
+        let escaper = quote!(
+            #[allow(non_camel_case_types)]
+            struct V_ESCAPE;
+        );
+        if self.safe {
+            let safe = self.safe();
+            return quote!(#escaper #safe);
+        }
+        let scalar = self.scalar();
+        let chars = self.chars();
+        if self.scalar {
+            return quote!(#escaper #scalar #chars);
         }
+        let escapes = self.escapes();
+        let swar = self.swar();
+        let ranges = if self.simd {
+            self.ranges()
+        } else {
+            TokenStream::new()
+        };
 
-        buf.writeln(&format!("const V_ESCAPE_LEN: usize = {};", len));
+        quote!(#escaper #escapes #scalar #swar #chars #ranges)
     }
 
-    fn write_functions(&self, buf: &mut Buffer) {
-        self.write_scalar(buf);
-        self.write_char(buf);
-        if self.simd {
-            self.write_ranges(buf);
+    /// Arguments of the support macros naming the tables
+    fn tables(&self) -> TokenStream {
+        if self.pairs.len() == 1 {
+            quote!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES)
+        } else {
+            quote!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN)
         }
     }
 
-    fn write_char(&self, buf: &mut Buffer) {
-        let code = if self.pairs.len() == 1 {
-            quote!(
-                mod chars {
-                    use super::*;
-                    v_escape::escape_char!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                    v_escape::escape_char_ptr!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                    v_escape::escape_char_bytes!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                }
-            )
+    /// Tables the generated modules import by name, instead of a glob of their parent
+    fn imports(&self) -> TokenStream {
+        if self.pairs.len() == 1 {
+            quote!(V_ESCAPE_CHAR, V_ESCAPE_QUOTES)
         } else {
-            quote!(
-                mod chars {
-                    use super::*;
-                    v_escape::escape_char!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
-                    v_escape::escape_char_ptr!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
-                    v_escape::escape_char_bytes!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
-                }
-            )
+            quote!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN)
+        }
+    }
+
+    fn chars(&self) -> TokenStream {
+        let tables = self.tables();
+        let imports = self.imports();
+        quote!(
+            mod chars {
+                use super::{#imports};
+                ::v_escape::escape_char!(#tables);
+                ::v_escape::escape_byte!(#tables);
+                ::v_escape::escape_char_ptr!(#tables);
+                ::v_escape::cfg_buffer!(::v_escape::escape_char_bytes!(#tables););
+            }
+        )
+    }
+
+    /// Scalar and chars modules without unsafe code
+    fn safe(&self) -> TokenStream {
+        let tables = self.tables();
+        let imports = self.imports();
+        quote!(
+            mod scalar {
+                ::v_escape::escape_scalar_safe!();
+            }
+            mod chars {
+                use super::{#imports};
+                ::v_escape::escape_char!(#tables);
+                ::v_escape::escape_byte!(#tables);
+                ::v_escape::escape_char_safe!();
+            }
+        )
+    }
+
+    fn scalar(&self) -> TokenStream {
+        let tables = self.tables();
+        let imports = self.imports();
+        quote!(
+            mod scalar {
+                use super::{#imports};
+                ::v_escape::escape_scalar!(#tables);
+                ::v_escape::escape_scalar_ptr!(#tables);
+                ::v_escape::cfg_buffer!(::v_escape::escape_scalar_bytes!(#tables););
+            }
+        )
+    }
+
+    /// Implementations in the `ranges` module, in the order they are preferred
+    fn targets(&self) -> Vec<Target> {
+        if !self.simd {
+            return vec![];
+        }
+        let x86_64 = quote!(target_arch = "x86_64");
+        let wasm32 = quote!(all(target_arch = "wasm32", target_feature = "simd128"));
+        let riscv64 = quote!(target_arch = "riscv64");
+        let altivec = quote!(all(target_arch = "powerpc64", target_feature = "altivec"));
+        let neon = quote!(all(target_arch = "arm", target_feature = "neon"));
+        let x86 = |module, backend, feature| Target {
+            cfg: x86_64.clone(),
+            module,
+            backend,
+            detect: Some((
+                quote!(::v_escape::is_x86_feature_detected!(#feature)),
+                quote!(target_feature = #feature),
+            )),
         };
-        buf.writeln(&code.to_string());
+        let fixed = |cfg: &TokenStream, module, backend| Target {
+            cfg: cfg.clone(),
+            module,
+            backend,
+            detect: None,
+        };
+
+        let mut t = vec![];
+        if self.avx512 {
+            t.push(x86("avx512", "Avx512", "avx512bw"));
+        }
+        if self.avx {
+            t.push(x86("avx", "Avx", "avx2"));
+        }
+        t.push(x86("sse", "Sse", "sse2"));
+        t.push(fixed(&wasm32, "wasm", "Simd128"));
+        t.push(Target {
+            cfg: riscv64.clone(),
+            module: "rvv",
+            backend: "Rvv",
+            detect: Some((
+                quote!(::v_escape::is_rvv_detected()),
+                quote!(target_feature = "v"),
+            )),
+        });
+
+        // Only where there is no architecture specific implementation
+        let mut specific = vec![x86_64.clone(), riscv64, wasm32];
+        if cfg!(feature = "powerpc-simd") {
+            t.push(fixed(&altivec, "altivec", "Altivec"));
+            specific.push(altivec);
+        }
+        if cfg!(feature = "arm-simd") {
+            t.push(fixed(&neon, "neon", "Neon"));
+            specific.push(neon);
+        }
+        if cfg!(feature = "portable-simd") {
+            let portable = quote!(not(any(#(#specific),*)));
+            t.push(fixed(&portable, "portable", "Portable"));
+        }
+        t
     }
 
-    fn write_scalar(&self, buf: &mut Buffer) {
-        let code = if self.pairs.len() == 1 {
-            quote!(
-                mod scalar {
-                    use super::*;
-                    v_escape::escape_scalar!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                    v_escape::escape_scalar_ptr!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                    v_escape::escape_scalar_bytes!(one V_ESCAPE_CHAR, V_ESCAPE_QUOTES);
-                }
-            )
-        } else {
+    fn ranges(&self) -> TokenStream {
+        let modules = self.targets().into_iter().map(|t| {
+            let (cfg, name) = (t.cfg, format_ident!("{}", t.module));
+            let kernels = self.kernels(&name);
             quote!(
-                mod scalar {
-                    use super::*;
-                    v_escape::escape_scalar!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
-                    v_escape::escape_scalar_ptr!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
-                    v_escape::escape_scalar_bytes!(V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN);
+                #[cfg(#cfg)]
+                pub mod #name {
+                    use super::super::V_ESCAPE;
+                    #kernels
                 }
             )
-        };
-        buf.writeln(&code.to_string());
+        });
+
+        quote!(
+            #[cfg(not(v_escape_nosimd))]
+            mod ranges {
+                #(#modules)*
+            }
+        )
+    }
+
+    fn swar(&self) -> TokenStream {
+        let kernels = self.kernels(&format_ident!("swar"));
+        quote!(
+            mod swar {
+                use super::V_ESCAPE;
+                #kernels
+            }
+        )
     }
 
-    fn write_ranges(&self, buf: &mut Buffer) {
-        buf.writeln(r#"#[cfg(all(target_arch = "x86_64", not(v_escape_nosimd)))]"#);
-        buf.writeln("mod ranges {");
+    /// Functions of a generated module, calling the loops of the module
+    /// `name` of `v_escape::ranges` with the ranges of `V_ESCAPE`
+    fn kernels(&self, name: &Ident) -> TokenStream {
+        quote!(
+            #[inline]
+            pub unsafe fn escape(bytes: &[u8], fmt: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::v_escape::ranges::#name::escape(&V_ESCAPE, bytes, fmt)
+            }
 
-        let ranges: &[u8] = &self.calculate_ranges();
+            #[inline]
+            pub unsafe fn f_escape(
+                bytes: &[u8],
+                buf: &mut [::core::mem::MaybeUninit<u8>],
+            ) -> ::core::option::Option<usize> {
+                ::v_escape::ranges::#name::f_escape(&V_ESCAPE, bytes, buf)
+            }
 
-        let t: &[&str] = if self.avx { &["avx", "sse"] } else { &["sse"] };
+            ::v_escape::cfg_buffer! {
+                #[inline]
+                pub unsafe fn b_escape<B: ::v_escape::Buffer>(bytes: &[u8], buf: &mut B) {
+                    ::v_escape::ranges::#name::b_escape(&V_ESCAPE, bytes, buf)
+                }
+            }
+        )
+    }
+
+    /// Ranges of `V_ESCAPE` the loops of `v_escape::ranges` are built for, its
+    /// quotes are the ones of `chars`
+    fn escapes(&self) -> TokenStream {
+        let (intervals, chars, exact) = self.range_args();
+        let intervals = intervals.into_iter().map(|(l, r)| {
+            let (l, r) = (Literal::u8_unsuffixed(l), Literal::u8_unsuffixed(r));
+            quote!([#l, #r])
+        });
+        let chars = chars.into_iter().map(Literal::u8_unsuffixed);
+
+        quote!(
+            // Quotes are checked to be utf-8, and only the bytes with one
+            // are in the ranges when they are exact
+            unsafe impl ::v_escape::ranges::Escapes for V_ESCAPE {
+                #[inline(always)]
+                fn ranges(&self) -> ::v_escape::ranges::Ranges<'_> {
+                    ::v_escape::ranges::Ranges {
+                        intervals: &[#(#intervals),*],
+                        chars: &[#(#chars),*],
+                        exact: #exact,
+                    }
+                }
 
-        for i in t {
-            buf.write("pub mod ");
-            buf.write(i);
-            buf.writeln(" {");
-            buf.writeln("use super::super::*;");
-            buf.write("v_escape::escape_ranges!(");
-            buf.write(i);
-            if self.pairs.len() == 1 {
-                buf.write("2 (V_ESCAPE_CHAR, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
-            } else {
-                buf.write("2 (V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
+                #[inline(always)]
+                fn quote(&self, b: u8) -> ::core::option::Option<&str> {
+                    chars::escape_byte(b)
+                }
             }
-            self.write_macro_tt(buf, ranges);
-            buf.writeln(");");
-            buf.write("v_escape::escape_ranges_ptr!(");
-            buf.write(i);
-            if self.pairs.len() == 1 {
-                buf.write("2 (V_ESCAPE_CHAR, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
-            } else {
-                buf.write("2 (V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
+        )
+    }
+
+    /// Intervals and characters the loops look for, and whether no other
+    /// byte is in them
+    ///
+    /// By default the at most 3 ranges of `calculate_ranges` are only taken
+    /// when they match exactly, the ones of up to 2 intervals or 3 characters,
+    /// otherwise a false positive is checked against the table and the chain
+    /// is cheaper
+    fn range_args(&self) -> (Vec<(u8, u8)>, Vec<u8>, bool) {
+        let (chain, exact) = match self.strategy {
+            // A single character has its own implementation
+            Strategy::Eq if 1 < self.pairs.len() => {
+                (self.pairs.iter().map(|p| (p.char, p.char)).collect(), true)
             }
-            self.write_macro_tt(buf, ranges);
-            buf.writeln(");");
-            buf.write("v_escape::escape_ranges_bytes!(");
-            buf.write(i);
-            if self.pairs.len() == 1 {
-                buf.write("2 (V_ESCAPE_CHAR, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
-            } else {
-                buf.write("2 (V_ESCAPE_TABLE, V_ESCAPE_QUOTES, V_ESCAPE_LEN) ");
+            Strategy::Auto => {
+                let (chain, exact) = self.calculate_chain();
+                let ranges_exact =
+                    chain.len() <= 2 || (chain.len() == 3 && chain.iter().all(|(l, r)| l == r));
+                if exact && ranges_exact {
+                    (vec![], true)
+                } else {
+                    (chain, exact)
+                }
             }
-            self.write_macro_tt(buf, ranges);
-            buf.writeln(");");
-            buf.writeln("}");
+            _ => (vec![], true),
+        };
+        if chain.is_empty() {
+            return split_ranges(&self.calculate_ranges());
         }
-        buf.writeln("}");
-    }
 
-    fn write_cfg_if(&self, buf: &mut Buffer) {
-        buf.writeln(&format!(
-            "v_escape::cfg_escape!({}, {});",
-            self.simd, self.avx
-        ));
-        buf.writeln(&format!(
-            "v_escape::cfg_escape_ptr!({}, {});",
-            self.simd, self.avx
-        ));
-        buf.writeln(&format!(
-            "v_escape::cfg_escape_bytes!({}, {});",
-            self.simd, self.avx
-        ));
+        let (chars, intervals): (Vec<_>, Vec<_>) = chain.into_iter().partition(|(l, r)| l == r);
+        (
+            intervals,
+            chars.into_iter().map(|(c, _)| c).collect(),
+            exact,
+        )
     }
 
-    fn write_macro_tt<T, I>(&self, buf: &mut Buffer, i: I)
-    where
-        T: Display,
-        I: IntoIterator<Item = T>,
-    {
-        for c in i.into_iter() {
-            buf.buf.write_fmt(format_args!("{}, ", c)).unwrap();
+    fn cfg_if(&self) -> TokenStream {
+        if self.scalar {
+            // Direct calls, without any cfg
+            return quote!(
+                ::v_escape::cfg_escape!(scalar);
+                ::v_escape::cfg_escape_ptr!(scalar);
+                ::v_escape::cfg_buffer!(::v_escape::cfg_escape_bytes!(scalar););
+                ::v_escape::cfg_backend!(scalar);
+            );
+        }
+
+        // Table of the implementations checked on the running cpu, and of
+        // those chosen by their cfg
+        let (mut runtime, mut fixed) = (vec![], vec![]);
+        for t in self.targets() {
+            let cfg = t.cfg;
+            let module = format_ident!("{}", t.module);
+            let backend = format_ident!("{}", t.backend);
+            match (t.detect, self.detect) {
+                (Some((detect, _)), Detect::Runtime) => runtime.push(quote!(
+                    (all(not(v_escape_nosimd), #cfg), #module, #backend, #detect)
+                )),
+                (Some((_, feature)), Detect::Static) => fixed.push(quote!(
+                    (all(not(v_escape_nosimd), #cfg, #feature), #module, #backend)
+                )),
+                (None, _) => {
+                    fixed.push(quote!((all(not(v_escape_nosimd), #cfg), #module, #backend)))
+                }
+            }
         }
+        let table = quote!([#(#runtime),*], [#(#fixed),*]);
+
+        quote!(
+            ::v_escape::cfg_escape!(#table);
+            ::v_escape::cfg_escape_ptr!(#table);
+            ::v_escape::cfg_buffer!(::v_escape::cfg_escape_bytes!(#table););
+            ::v_escape::cfg_backend!(#table);
+        )
     }
 
     fn calculate_ranges(&self) -> Ranges {
@@ -198,7 +452,7 @@ impl<'a> Generator<'a> {
         let mut ranges: Ranges = vec![];
 
         if self.pairs.len() == 1 {
-            ranges.push(self.pairs[0].char);
+            ranges.push(self.pairs[0].char.into());
             ranges.push(FLAG);
 
             return ranges;
@@ -212,35 +466,37 @@ impl<'a> Generator<'a> {
                 d.push((i, diff));
             }
         }
-        d.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        d.sort_unstable_by_key(|d| Reverse(d.1));
 
         match d.len() {
+            // Every byte doesn't fit in one range, it is split in 2
+            0 if e == 255 => ranges.extend_from_slice(&[0, 127, 128, 255]),
             0 => {
                 // 1 range
-                ranges.push(self.pairs[0].char);
-                ranges.push(self.pairs[e].char);
+                ranges.push(self.pairs[0].char.into());
+                ranges.push(self.pairs[e].char.into());
             }
             1 => {
                 if e == 1 {
                     // 2 equals
-                    ranges.push(self.pairs[0].char);
-                    ranges.push(self.pairs[e].char);
+                    ranges.push(self.pairs[0].char.into());
+                    ranges.push(self.pairs[e].char.into());
                     ranges.push(FLAG);
                 } else {
                     let i = d[0].0;
                     if i == 0 {
                         // 1 equal and 1 range
-                        ranges.push(self.pairs[i + 1].char);
-                        ranges.push(self.pairs[e].char);
-                        ranges.push(self.pairs[0].char);
+                        ranges.push(self.pairs[i + 1].char.into());
+                        ranges.push(self.pairs[e].char.into());
+                        ranges.push(self.pairs[0].char.into());
                     } else {
                         // 1 equal and 1 range
-                        ranges.push(self.pairs[0].char);
-                        ranges.push(self.pairs[i].char);
-                        ranges.push(self.pairs[i + 1].char);
+                        ranges.push(self.pairs[0].char.into());
+                        ranges.push(self.pairs[i].char.into());
+                        ranges.push(self.pairs[i + 1].char.into());
                         if i + 1 != e {
                             // 2 ranges
-                            ranges.push(self.pairs[e].char);
+                            ranges.push(self.pairs[e].char.into());
                         }
                     }
                 }
@@ -250,7 +506,7 @@ impl<'a> Generator<'a> {
                     assert_eq!(e, 2);
                     // 3 escapes
                     for Pair { char, .. } in self.pairs {
-                        ranges.push(*char);
+                        ranges.push((*char).into());
                     }
                     ranges.push(FLAG);
 
@@ -288,168 +544,552 @@ impl<'a> Generator<'a> {
         ranges
     }
 
+    /// Intervals of consecutive characters, the neighbours with the smallest
+    /// gap merged until they fit in `MAX_COMPARES`, and whether no other
+    /// character was merged in
+    fn calculate_chain(&self) -> (Vec<(u8, u8)>, bool) {
+        let mut chain: Vec<(u8, u8)> = vec![];
+        for Pair { char, .. } in self.pairs {
+            match chain.last_mut() {
+                Some((_, r)) if *r + 1 == *char => *r = *char,
+                _ => chain.push((*char, *char)),
+            }
+        }
+        // Every byte doesn't fit in one interval
+        if chain == [(0, 255)] {
+            chain = vec![(0, 127), (128, 255)];
+        }
+
+        let mut exact = true;
+        while compares(&chain) > MAX_COMPARES {
+            let i = (0..chain.len() - 1)
+                .min_by_key(|&i| chain[i + 1].0 - chain[i].1)
+                .unwrap();
+            chain[i].1 = chain[i + 1].1;
+            chain.remove(i + 1);
+            exact = false;
+        }
+
+        (chain, exact)
+    }
+
     #[inline]
     fn push_1_ranges_2_equals_at_first(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[l + 1].char);
-        r.push(self.pairs[e].char);
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[f + 1].char);
+        r.push(self.pairs[l + 1].char.into());
+        r.push(self.pairs[e].char.into());
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[f + 1].char.into());
         r.push(FLAG);
     }
 
     #[inline]
     fn push_1_ranges_2_equals_at_last(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[f].char);
-        r.push(self.pairs[l].char);
-        r.push(self.pairs[e].char);
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[f].char.into());
+        r.push(self.pairs[l].char.into());
+        r.push(self.pairs[e].char.into());
         r.push(FLAG);
     }
 
     #[inline]
     fn push_1_ranges_2_equals_at_first_last(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[f + 1].char);
-        r.push(self.pairs[l].char);
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[e].char);
+        r.push(self.pairs[f + 1].char.into());
+        r.push(self.pairs[l].char.into());
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[e].char.into());
         r.push(FLAG);
     }
 
     #[inline]
     fn push_2_ranges_1_equals(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[f].char);
-        r.push(self.pairs[l + 1].char);
-        r.push(self.pairs[e].char);
-        r.push(self.pairs[f + 1].char);
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[f].char.into());
+        r.push(self.pairs[l + 1].char.into());
+        r.push(self.pairs[e].char.into());
+        r.push(self.pairs[f + 1].char.into());
     }
 
     #[inline]
     fn push_2_ranges_1_equals_at_first(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[f + 1].char);
-        r.push(self.pairs[l].char);
-        r.push(self.pairs[l + 1].char);
-        r.push(self.pairs[e].char);
-        r.push(self.pairs[0].char);
+        r.push(self.pairs[f + 1].char.into());
+        r.push(self.pairs[l].char.into());
+        r.push(self.pairs[l + 1].char.into());
+        r.push(self.pairs[e].char.into());
+        r.push(self.pairs[0].char.into());
     }
 
     #[inline]
     fn push_2_ranges_1_equals_at_last(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[f].char);
-        r.push(self.pairs[f + 1].char);
-        r.push(self.pairs[l].char);
-        r.push(self.pairs[e].char);
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[f].char.into());
+        r.push(self.pairs[f + 1].char.into());
+        r.push(self.pairs[l].char.into());
+        r.push(self.pairs[e].char.into());
     }
 
     #[inline]
     fn push_3_ranges(&self, r: &mut Ranges, f: usize, l: usize, e: usize) {
-        r.push(self.pairs[0].char);
-        r.push(self.pairs[f].char);
-        r.push(self.pairs[f + 1].char);
-        r.push(self.pairs[l].char);
-        r.push(self.pairs[l + 1].char);
-        r.push(self.pairs[e].char);
+        r.push(self.pairs[0].char.into());
+        r.push(self.pairs[f].char.into());
+        r.push(self.pairs[f + 1].char.into());
+        r.push(self.pairs[l].char.into());
+        r.push(self.pairs[l + 1].char.into());
+        r.push(self.pairs[e].char.into());
     }
 }
 
-// TODO: remove in favor of rust code logger
-struct Buffer {
-    // The buffer to generate the code into
-    buf: String,
-    // The current level of indentation (in spaces)
-    indent: u8,
-    // Whether the output buffer is currently at the start of a line
-    start: bool,
+/// Characters compared one by one with `Strategy::Eq`, more of them are
+/// compared as with `Strategy::Auto`
+pub const MAX_EQ: usize = 16;
+
+/// Compares of a vector past which checking the false positives of two
+/// merged intervals against the table is cheaper than comparing both
+const MAX_COMPARES: usize = 12;
+
+/// Compares of a chain, an interval needs an addition and a compare and a
+/// character only a compare
+fn compares(chain: &[(u8, u8)]) -> usize {
+    chain.iter().map(|(l, r)| if l == r { 1 } else { 2 }).sum()
 }
 
-impl Buffer {
-    fn new(indent: u8) -> Self {
-        Self {
-            buf: String::new(),
-            indent,
-            start: true,
-        }
+// End flag for indicate more escapes than ranges, out of the bytes
+const FLAG: u16 = 256;
+
+/// Intervals, characters and exactness of the ranges of `calculate_ranges`
+fn split_ranges(ranges: &[u16]) -> (Vec<(u8, u8)>, Vec<u8>, bool) {
+    let b: Vec<u8> = ranges.iter().map(|&r| r as u8).collect();
+    match (ranges.len(), ranges.last() == Some(&FLAG)) {
+        // 1 range and 2 equals
+        (5, true) => (vec![(b[0], b[1])], vec![b[2], b[3]], false),
+        // Up to 3 equals
+        (_, true) => (vec![], b[..b.len() - 1].to_vec(), true),
+        (2, _) => (vec![(b[0], b[1])], vec![], true),
+        (3, _) => (vec![(b[0], b[1])], vec![b[2]], true),
+        (4, _) => (vec![(b[0], b[1]), (b[2], b[3])], vec![], true),
+        (5, _) => (vec![(b[0], b[1]), (b[2], b[3])], vec![b[4]], false),
+        _ => (
+            vec![(b[0], b[1]), (b[2], b[3]), (b[4], b[5])],
+            vec![],
+            false,
+        ),
     }
+}
 
-    fn writeln(&mut self, s: &str) {
-        if s == "}" {
-            self.dedent();
-        }
-        if !s.is_empty() {
-            self.write(s);
-        }
-        self.buf.push('\n');
-        if s.ends_with('{') {
-            self.indent();
-        }
-        self.start = true;
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parser::Pair, Detect};
+    use proc_macro2::TokenTree;
+
+    static E: &[u8] = b"f";
 
-    fn write(&mut self, s: &str) {
-        if self.start {
-            for _ in 0..(self.indent * 4) {
-                self.buf.push(' ');
+    /// Identifiers of the modules defined at the top level of `code`, with
+    /// those of the nested modules of `ranges`
+    fn modules(code: TokenStream) -> Vec<String> {
+        let mut names = vec![];
+        let mut tokens = code.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            if let TokenTree::Ident(ident) = &token {
+                if ident == "mod" {
+                    if let Some(TokenTree::Ident(name)) = tokens.next() {
+                        names.push(name.to_string());
+                        if name == "ranges" {
+                            if let Some(TokenTree::Group(body)) = tokens.peek() {
+                                names.extend(
+                                    modules(body.stream())
+                                        .into_iter()
+                                        .map(|m| format!("ranges::{}", m)),
+                                );
+                            }
+                        }
+                    }
+                }
             }
-            self.start = false;
         }
-        self.buf.push_str(s);
+        names
     }
 
-    fn indent(&mut self) {
-        self.indent += 1;
+    #[test]
+    fn test_build() {
+        let pairs = &[Pair::new(b'<', b"&lt;"), Pair::new(b'>', b"&gt;")];
+        let code = Generator::new(
+            pairs,
+            true,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        )
+        .build();
+        let names = modules(code);
+        assert_eq!(names[..4], ["scalar", "swar", "chars", "ranges"]);
+        assert!(names.contains(&"ranges::sse".to_string()));
+        assert!(!names.contains(&"ranges::avx".to_string()));
+        assert!(!names.contains(&"ranges::avx512".to_string()));
+
+        let code = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            true,
+            true,
+            Detect::Static,
+            Strategy::Auto,
+        )
+        .build();
+        assert_eq!(modules(code), ["scalar", "swar", "chars"]);
+
+        let g = Generator::new(
+            pairs,
+            false,
+            true,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        let code = g.build();
+        assert!(!code.to_string().contains("# [cfg"));
+        assert_eq!(modules(code), ["scalar", "chars"]);
+
+        // `simd` is ignored, only the safe scalar loop is left
+        let code = Generator::new(
+            pairs,
+            true,
+            false,
+            true,
+            true,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        )
+        .build();
+        assert!(!code.to_string().contains("unsafe"));
+        assert!(code.to_string().contains("escape_scalar_safe"));
+        assert_eq!(modules(code), ["scalar", "chars"]);
     }
 
-    fn dedent(&mut self) {
-        if self.indent == 0 {
-            panic!("dedent() called while indentation == 0");
-        }
-        self.indent -= 1;
-    }
-}
+    #[test]
+    fn test_dispatch_table() {
+        let pairs = &[Pair::new(b'<', b"&lt;"), Pair::new(b'>', b"&gt;")];
+        let g = |simd, detect| {
+            Generator::new(
+                pairs,
+                simd,
+                false,
+                false,
+                true,
+                false,
+                detect,
+                Strategy::Auto,
+            )
+            .cfg_if()
+            .to_string()
+        };
 
-// End flag for indicate more escapes than ranges
-const FLAG: u8 = 128;
+        let runtime = g(true, Detect::Runtime);
+        assert!(runtime
+            .contains(&quote!(avx, Avx, ::v_escape::is_x86_feature_detected!("avx2")).to_string()));
+        assert!(runtime.contains(&quote!(rvv, Rvv, ::v_escape::is_rvv_detected()).to_string()));
+        assert!(!runtime.contains("avx512"));
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::parser::Pair;
+        let fixed = g(true, Detect::Static);
+        assert!(!fixed.contains("is_x86_feature_detected"));
+        assert!(fixed.contains(r#"target_feature = "avx2""#));
 
-    static E: &[u8] = b"f";
+        let swar = quote!(::v_escape::cfg_escape!([], []););
+        assert!(g(false, Detect::Runtime).starts_with(&swar.to_string()));
+    }
+
+    #[test]
+    fn test_static_table() {
+        let pairs = &[Pair::new(b'<', b"&lt;")];
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        let expected = quote!(
+            const V_ESCAPE_CHAR: u8 = 60;
+            static V_ESCAPE_QUOTES: &str = "&lt;";
+            const V_ESCAPE_LEN: usize = 1;
+            const V_ESCAPE_QUOTES_MAX: usize = 4;
+        );
+        assert_eq!(g.static_table().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_static_table_dedupe() {
+        let pairs = &[
+            Pair::new(0, b"&#xfffd;"),
+            Pair::new(1, b"&#xfffd;"),
+            Pair::new(b'<', b"&lt;"),
+            Pair::new(127, b"&#xfffd;"),
+        ];
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        let code = g.static_table().to_string();
+        assert!(code.contains(
+            &quote!(
+                static V_ESCAPE_QUOTES: [&str; 2] = ["&#xfffd;", "&lt;"];
+            )
+            .to_string()
+        ));
+        assert!(code.contains(
+            &quote!(
+                const V_ESCAPE_LEN: usize = 2;
+            )
+            .to_string()
+        ));
+        let table: Vec<String> = (0..=255u8)
+            .map(|i| match i {
+                0 | 1 | 127 => "0",
+                b'<' => "1",
+                _ => "2",
+            })
+            .map(String::from)
+            .collect();
+        let table = table.join(" , ");
+        assert!(code.contains(&format!("[u8 ; 256] = [{}]", table)));
+    }
 
     #[test]
     fn test_1_escape() {
         let pairs = &[Pair::new(0, E)];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![0, 128])
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 256])
     }
 
     #[test]
     fn test_2_escape() {
         let pairs = &[Pair::new(0, E), Pair::new(2, E)];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![0, 2, 128])
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 2, 256])
     }
 
     #[test]
     fn test_3_escape() {
         let pairs = &[Pair::new(0, E), Pair::new(2, E), Pair::new(4, E)];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![0, 2, 4, 128])
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 2, 4, 256])
     }
 
     #[test]
     fn test_1_range() {
         let pairs = &[Pair::new(0, E), Pair::new(1, E)];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 1])
     }
 
+    #[test]
+    fn test_parsed_range() {
+        let pairs = &crate::parser::parse("0x00-0x1F->? || 0x7F->!").unwrap();
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 31, 127])
+    }
+
+    #[test]
+    fn test_chain() {
+        let pairs =
+            &crate::parser::parse("0x00-0x08->? || 0x0B->! || 0x0E-0x1F->? || '\"'->q").unwrap();
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        assert_eq!(
+            g.calculate_chain(),
+            (vec![(0, 8), (11, 11), (14, 31), (34, 34)], true)
+        );
+        assert_eq!(g.range_args(), (vec![(0, 8), (14, 31)], vec![11, 34], true));
+
+        // 8 intervals, 15 compares
+        let pairs = &crate::parser::parse(crate::preset::pairs("shell").unwrap()).unwrap();
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        let (chain, exact) = g.calculate_chain();
+        assert!(!exact);
+        assert!(compares(&chain) <= MAX_COMPARES);
+        assert_eq!(chain[..3], [(9, 10), (32, 42), (59, 63)]);
+
+        // Forced
+        let pairs = &crate::parser::parse("a-d->? || x->!").unwrap();
+        let g = Generator::new(
+            pairs,
+            true,
+            false,
+            false,
+            true,
+            false,
+            Detect::Runtime,
+            Strategy::Eq,
+        );
+        assert_eq!(g.range_args(), (vec![], vec![97, 98, 99, 100, 120], true));
+        let pairs = &crate::parser::parse("a->? || b->!").unwrap();
+        let g = Generator::new(
+            pairs,
+            true,
+            false,
+            false,
+            true,
+            false,
+            Detect::Runtime,
+            Strategy::Eq,
+        );
+        assert_eq!(g.range_args(), (vec![], vec![97, 98], true));
+        let pairs = &crate::parser::parse("a-d->? || x->!").unwrap();
+        let g = Generator::new(
+            pairs,
+            true,
+            false,
+            false,
+            true,
+            false,
+            Detect::Runtime,
+            Strategy::Ranges,
+        );
+        assert_eq!(g.calculate_ranges(), vec![97, 100, 120]);
+        assert_eq!(g.range_args(), (vec![(97, 100)], vec![120], true));
+
+        // Up to 3 intervals keep the ranges
+        let pairs = &[Pair::new(0, E), Pair::new(2, E)];
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        assert_eq!(g.range_args(), (vec![], vec![0, 2], true));
+    }
+
+    #[test]
+    fn test_high_bytes() {
+        let pairs = &[Pair::new(128, E)];
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        assert_eq!(g.calculate_ranges(), vec![128, 256]);
+
+        let pairs = &crate::parser::parse("0xC0-0xC1->? || 0xFF->!").unwrap();
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        assert_eq!(g.calculate_ranges(), vec![192, 193, 255]);
+
+        // Every byte is split at 128
+        let pairs = &crate::parser::parse("0x00-0xFF->?").unwrap();
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+        assert_eq!(g.calculate_ranges(), vec![0, 127, 128, 255]);
+        assert_eq!(g.calculate_chain(), (vec![(0, 127), (128, 255)], true));
+    }
+
     #[test]
     fn test_2_range() {
         let pairs = &[
@@ -458,7 +1098,16 @@ mod test {
             Pair::new(3, E),
             Pair::new(4, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 1, 3, 4])
     }
@@ -473,7 +1122,16 @@ mod test {
             Pair::new(6, E),
             Pair::new(7, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 1, 3, 4, 6, 7]);
         let pairs = &[
@@ -494,7 +1152,16 @@ mod test {
             Pair::new(126, E),
             Pair::new(127, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 9, 50, 64, 126, 127])
     }
@@ -502,12 +1169,30 @@ mod test {
     #[test]
     fn test_1_range_1_escape() {
         let pairs = &[Pair::new(0, E), Pair::new(1, E), Pair::new(3, E)];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 1, 3]);
 
         let pairs = &[Pair::new(0, E), Pair::new(2, E), Pair::new(3, E)];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![2, 3, 0]);
 
@@ -517,7 +1202,16 @@ mod test {
             Pair::new(2, E),
             Pair::new(4, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 2, 4]);
 
@@ -530,7 +1224,16 @@ mod test {
             Pair::new(55, E),
             Pair::new(67, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![50, 55, 67]);
     }
@@ -544,7 +1247,16 @@ mod test {
             Pair::new(4, E),
             Pair::new(6, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![0, 1, 3, 4, 6]);
     }
@@ -558,7 +1270,16 @@ mod test {
             Pair::new(7, E),
             Pair::new(8, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![4, 5, 7, 8, 0]);
     }
@@ -574,7 +1295,16 @@ mod test {
             Pair::new(52, E),
             Pair::new(98, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![14, 16, 50, 52, 98]);
     }
@@ -591,7 +1321,16 @@ mod test {
             Pair::new(98, E),
         ];
 
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![14, 16, 50, 52, 98]);
         let pairs = &[
@@ -612,7 +1351,16 @@ mod test {
             Pair::new(58, E),
             Pair::new(98, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![14, 19, 50, 58, 98]);
     }
@@ -628,7 +1376,16 @@ mod test {
             Pair::new(98, E),
         ];
 
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![14, 16, 50, 52, 98]);
         let pairs = &[
@@ -645,7 +1402,16 @@ mod test {
             Pair::new(58, E),
             Pair::new(98, E),
         ];
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![14, 19, 50, 58, 98]);
     }
@@ -660,7 +1426,16 @@ mod test {
             Pair::new(81, E),
         ];
 
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![60, 61, 80, 81, 65]);
 
@@ -683,7 +1458,16 @@ mod test {
             Pair::new(120, E),
         ];
 
-        let g = Generator::new(pairs, false, false);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
 
         assert_eq!(g.calculate_ranges(), vec![52, 62, 101, 120, 80]);
     }
@@ -696,9 +1480,18 @@ mod test {
             Pair::new(4, E),
             Pair::new(6, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![0, 1, 4, 6, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 1, 4, 6, 256]);
 
         let pairs = &[
             Pair::new(0, E),
@@ -719,9 +1512,18 @@ mod test {
             Pair::new(73, E),
             Pair::new(127, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![0, 14, 73, 127, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![0, 14, 73, 127, 256]);
     }
 
     #[test]
@@ -732,9 +1534,18 @@ mod test {
             Pair::new(5, E),
             Pair::new(6, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![5, 6, 0, 2, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![5, 6, 0, 2, 256]);
 
         let pairs = &[
             Pair::new(0, E),
@@ -754,9 +1565,18 @@ mod test {
             Pair::new(17, E),
             Pair::new(18, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![5, 18, 0, 2, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![5, 18, 0, 2, 256]);
     }
 
     #[test]
@@ -767,9 +1587,18 @@ mod test {
             Pair::new(3, E),
             Pair::new(8, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![2, 3, 0, 8, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![2, 3, 0, 8, 256]);
 
         let pairs = &[
             Pair::new(0, E),
@@ -791,8 +1620,17 @@ mod test {
             Pair::new(17, E),
             Pair::new(127, E),
         ];
-        let g = Generator::new(pairs, false, false);
-
-        assert_eq!(g.calculate_ranges(), vec![2, 17, 0, 127, 128]);
+        let g = Generator::new(
+            pairs,
+            false,
+            false,
+            false,
+            false,
+            false,
+            Detect::Runtime,
+            Strategy::Auto,
+        );
+
+        assert_eq!(g.calculate_ranges(), vec![2, 17, 0, 127, 256]);
     }
 }
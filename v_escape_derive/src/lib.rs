@@ -2,6 +2,7 @@
 extern crate nom;
 
 use proc_macro::TokenStream;
+use quote::quote;
 use syn::punctuated::Punctuated;
 use syn::{
     parse::{Parse, ParseBuffer},
@@ -11,40 +12,442 @@ use syn::{
 
 mod generator;
 mod parser;
+mod preset;
+
+/// Generate the escaper `$name`, its static tables and functions, from the
+/// name followed by the same arguments as `derive!`
+#[proc_macro]
+pub fn new_escape(input: TokenStream) -> TokenStream {
+    let NewEscape { name, builder, .. } = match syn::parse::<NewEscape>(input) {
+        Ok(s) => s,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    match expand(builder, Some(&name)) {
+        Ok(code) => code.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Generate the finder module `$name`, its static tables and functions, from
+/// the name followed by the arguments of `new_escape!` that look for the
+/// characters
+#[proc_macro]
+pub fn new_finder(input: TokenStream) -> TokenStream {
+    let NewEscape { name, builder, .. } = match syn::parse::<NewEscape>(input) {
+        Ok(s) => s,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    match expand_finder(builder, &name) {
+        Ok(code) => code.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
 
 /// Generate static tables and call macros
+#[deprecated(note = "use `new_escape!`, which also generates the escaper type")]
 #[proc_macro]
 pub fn derive(input: TokenStream) -> TokenStream {
+    match syn::parse::<Builder>(input).and_then(|builder| expand(builder, None)) {
+        Ok(code) => code.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Generated code of the arguments, with the escaper type `name` when given
+fn expand(builder: Builder, name: Option<&syn::Ident>) -> syn::Result<proc_macro2::TokenStream> {
     let Args {
         avx,
+        avx512,
+        detect,
+        file,
+        forbid_unsafe,
+        idempotent,
+        module,
         pairs,
+        preset,
         print,
+        scalar,
         simd,
-    } = match syn::parse::<Builder>(input).and_then(Builder::build) {
-        Ok(s) => s,
-        Err(e) => return e.to_compile_error().into(),
+        span,
+        strategy,
+        tests,
+        then,
+        unescape,
+    } = builder.build()?;
+    let (pairs, triggers) = match (&pairs, preset) {
+        (Some(pairs), Some(preset)) => parser::parse_all(pairs).and_then(|(pairs, triggers)| {
+            Ok((parser::merge(pairs, parser::parse(preset)?), triggers))
+        }),
+        (Some(pairs), None) => parser::parse_all(pairs),
+        (None, Some(preset)) => parser::parse(preset).map(|pairs| (pairs, vec![])),
+        (None, None) => unreachable!("checked by the builder"),
+    }
+    .map_err(|e| syn::Error::new(span, e))?;
+    let then_pairs = then.as_ref().map(syn::LitStr::value);
+    let pairs = match &then_pairs {
+        Some(then_pairs) => parser::parse(then_pairs)
+            .map(|then_pairs| parser::then(pairs, then_pairs))
+            .map_err(|e| syn::Error::new(then.as_ref().unwrap().span(), e))?,
+        None => pairs,
     };
-    let code = generator::generate(&parser::parse(&pairs), simd, avx);
+    let mut triggers = triggers;
+    if idempotent {
+        if unescape {
+            return Err(syn::Error::new(
+                span,
+                "options `idempotent` and `unescape` can't be combined, a quote in the input \
+                 is left as it is",
+            ));
+        }
+        triggers.extend(parser::idempotent(&pairs));
+        parser::check_triggers(&mut triggers)
+            .map_err(|e| syn::Error::new(span, format!("option `idempotent`: {}", e)))?;
+    }
+    let (strategy, note) = eq_fallback(strategy, pairs.len(), span);
+    let generate = |pairs: &[parser::Pair], strategy| {
+        generator::generate(
+            pairs,
+            simd,
+            scalar,
+            forbid_unsafe,
+            avx,
+            avx512,
+            detect,
+            strategy,
+        )
+    };
+    let mut code = if triggers.is_empty() {
+        generate(&pairs, strategy)
+    } else {
+        triggers_code(&pairs, &triggers, strategy, generate)
+    };
+    code.extend(note);
+    if unescape {
+        if !triggers.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                "option `unescape` can't map triggers back, only single characters",
+            ));
+        }
+        code.extend(unescape_code(&pairs, span, generate)?);
+    }
+    if let Some(file) = file {
+        // Rebuild when the file changes
+        code.extend(quote!(
+            const _: &[u8] = include_bytes!(#file);
+        ));
+    }
+    if tests {
+        // The tests go through the functions of the escaper type
+        if name.is_none() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "option `tests` needs `new_escape!`",
+            ));
+        }
+        code.extend(quote!(::v_escape::escape_tests!();));
+    }
+    match (name, module) {
+        (Some(name), Some(module)) => {
+            let new = escape_new(name, &triggers);
+            // Every item lives in the module, so the fixed names of the
+            // tables and functions don't collide with other escapers
+            code = quote!(
+                pub mod #module {
+                    #code
+                    #new
+                }
+                pub use #module::#name;
+            );
+        }
+        (Some(name), None) => code.extend(escape_new(name, &triggers)),
+        (None, Some(module)) => {
+            return Err(syn::Error::new(
+                module.span(),
+                "option `module` needs `new_escape!`",
+            ))
+        }
+        (None, None) => (),
+    }
 
     if print {
         eprintln!("{}", code);
     }
 
-    code.parse().unwrap()
+    Ok(code)
+}
+
+/// Generated module `module` of a finder, the escaper of its characters
+/// with only the functions looking for them
+fn expand_finder(builder: Builder, module: &syn::Ident) -> syn::Result<proc_macro2::TokenStream> {
+    let Args {
+        avx,
+        avx512,
+        detect,
+        file,
+        forbid_unsafe,
+        idempotent,
+        module: in_module,
+        pairs,
+        preset,
+        print,
+        scalar,
+        simd,
+        span,
+        strategy,
+        tests,
+        then,
+        unescape,
+    } = builder.build()?;
+    let escaping = [
+        ("idempotent", idempotent),
+        ("module", in_module.is_some()),
+        ("tests", tests),
+        ("then", then.is_some()),
+        ("unescape", unescape),
+    ];
+    if let Some((option, _)) = escaping.iter().find(|(_, given)| *given) {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "option `{}` can't be given to `finder!`, it only looks for the characters",
+                option
+            ),
+        ));
+    }
+    let pairs = match (&pairs, preset) {
+        (Some(pairs), Some(preset)) => parser::parse_finder(pairs)
+            .and_then(|pairs| Ok(parser::merge(pairs, parser::parse(preset)?))),
+        (Some(pairs), None) => parser::parse_finder(pairs),
+        (None, Some(preset)) => parser::parse(preset),
+        (None, None) => unreachable!("checked by the builder"),
+    }
+    .map_err(|e| syn::Error::new(span, e))?;
+    let (strategy, note) = eq_fallback(strategy, pairs.len(), span);
+
+    let mut code = generator::generate(
+        &pairs,
+        simd,
+        scalar,
+        forbid_unsafe,
+        avx,
+        avx512,
+        detect,
+        strategy,
+    );
+    code.extend(note);
+    if let Some(file) = file {
+        // Rebuild when the file changes
+        code.extend(quote!(
+            const _: &[u8] = include_bytes!(#file);
+        ));
+    }
+    let code = quote!(
+        #[allow(dead_code)]
+        mod #module {
+            #code
+            ::v_escape::escape_find!();
+        }
+    );
+
+    if print {
+        eprintln!("{}", code);
+    }
+
+    Ok(code)
+}
+
+/// `strategy` and the warning of `strategy = eq` falling back to `auto`
+/// when there are more than `generator::MAX_EQ` characters
+fn eq_fallback(
+    strategy: Strategy,
+    len: usize,
+    span: proc_macro2::Span,
+) -> (Strategy, Option<proc_macro2::TokenStream>) {
+    match strategy {
+        Strategy::Eq if generator::MAX_EQ < len => (
+            Strategy::Auto,
+            Some(note(
+                span,
+                &format!(
+                    "`strategy = eq` compares at most {} characters one by one, the {} given \
+                     are compared as with `strategy = auto`",
+                    generator::MAX_EQ,
+                    len
+                ),
+            )),
+        ),
+        strategy => (strategy, None),
+    }
+}
+
+/// `escape_new!` call of the escaper `name`
+fn escape_new(name: &syn::Ident, triggers: &[parser::Trigger]) -> proc_macro2::TokenStream {
+    if triggers.is_empty() {
+        quote!(::v_escape::escape_new!(#name);)
+    } else {
+        quote!(::v_escape::escape_new!(#name, triggers);)
+    }
+}
+
+/// Code escaping the triggers, the single byte pairs are escaped between them
+/// by an escaper in the module `single` and the first bytes of the triggers
+/// are looked for by another one in `trigger_starts`
+fn triggers_code<G>(
+    pairs: &[parser::Pair],
+    triggers: &[parser::Trigger],
+    strategy: Strategy,
+    generate: G,
+) -> proc_macro2::TokenStream
+where
+    G: Fn(&[parser::Pair], Strategy) -> proc_macro2::TokenStream,
+{
+    let single = if pairs.is_empty() {
+        quote!(::v_escape::escape_triggers!(none);)
+    } else {
+        let single = generate(pairs, strategy);
+        quote!(
+            #single
+            ::v_escape::escape_triggers!(single);
+        )
+    };
+    // Sorted by their bytes, the first bytes are sorted too
+    let mut starts: Vec<u8> = triggers.iter().map(|t| t.bytes[0]).collect();
+    starts.dedup();
+    let starts: Vec<parser::Pair> = starts
+        .into_iter()
+        .map(|c| parser::Pair {
+            char: c,
+            quote: std::borrow::Cow::Owned(vec![c]),
+        })
+        .collect();
+    let scan = generate(&starts, Strategy::Auto);
+
+    let max = triggers.iter().map(|t| t.quote.len()).max().unwrap_or(0);
+    let triggers = triggers.iter().map(|t| {
+        let bytes = proc_macro2::Literal::byte_string(&t.bytes);
+        let quote = std::str::from_utf8(&t.quote).unwrap();
+        quote!((#bytes, #quote))
+    });
+
+    quote!(
+        #[allow(dead_code)]
+        mod single {
+            #single
+        }
+        #[allow(dead_code)]
+        mod trigger_starts {
+            #scan
+            ::v_escape::escape_unescape!(find);
+        }
+        ::v_escape::escape_triggers!([#(#triggers),*], #max);
+    )
+}
+
+/// `unescape` and the escaper of the first bytes of the quotes it looks for,
+/// built by `generate` as the escaper is
+fn unescape_code<G>(
+    pairs: &[parser::Pair],
+    span: proc_macro2::Span,
+    generate: G,
+) -> syn::Result<proc_macro2::TokenStream>
+where
+    G: Fn(&[parser::Pair], Strategy) -> proc_macro2::TokenStream,
+{
+    let quotes = parser::unescape(pairs).map_err(|e| syn::Error::new(span, e))?;
+    let mut starts: Vec<u8> = quotes.iter().map(|(quote, _)| quote[0]).collect();
+    starts.sort_unstable();
+    starts.dedup();
+    let starts: Vec<parser::Pair> = starts
+        .into_iter()
+        .map(|c| parser::Pair {
+            char: c,
+            quote: std::borrow::Cow::Owned(vec![c]),
+        })
+        .collect();
+    let scan = generate(&starts, Strategy::Auto);
+    // Checked to be utf-8 by the parser
+    let quotes = quotes.iter().map(|(quote, c)| {
+        let quote = std::str::from_utf8(quote).unwrap();
+        let c = proc_macro2::Literal::u8_unsuffixed(*c);
+        quote!((#quote, #c))
+    });
+
+    Ok(quote!(
+        #[allow(dead_code)]
+        mod quote_starts {
+            #scan
+            ::v_escape::escape_unescape!(find);
+        }
+        ::v_escape::escape_unescape!([#(#quotes),*]);
+    ))
+}
+
+/// Warning with `message` at `span`, stable proc macros can't emit one so
+/// it is the deprecation note of an item used there
+fn note(span: proc_macro2::Span, message: &str) -> proc_macro2::TokenStream {
+    quote::quote_spanned!(span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct Note;
+            let _ = Note;
+        };
+    )
 }
 
 /// Proc macro arguments data
 struct Args {
-    pairs: String,
+    pairs: Option<String>,
+    file: Option<String>,
+    /// Only safe code, with the `forbid-unsafe` feature
+    forbid_unsafe: bool,
+    /// Pass through the quotes already in the input
+    idempotent: bool,
+    /// Module of the generated items
+    module: Option<syn::Ident>,
+    preset: Option<&'static str>,
     avx: bool,
+    avx512: bool,
+    detect: Detect,
     print: bool,
+    /// Only the byte at a time loop, `simd = scalar`
+    scalar: bool,
     simd: bool,
+    /// Where the pairs are given, for the errors
+    span: proc_macro2::Span,
+    strategy: Strategy,
+    /// Generate differential tests of the backends
+    tests: bool,
+    /// Pairs escaping the output of the pairs, in the same pass
+    then: Option<syn::LitStr>,
+    /// Generate `unescape`, mapping the quotes back
+    unescape: bool,
+}
+
+/// How the simd implementation is chosen
+#[derive(Clone, Copy)]
+pub(crate) enum Detect {
+    /// Checking the cpu the first time it is called
+    Runtime,
+    /// Only with the target features enabled at compile time
+    Static,
+}
+
+/// How the characters are compared by the simd and swar implementations
+#[derive(Clone, Copy)]
+pub(crate) enum Strategy {
+    /// Chosen by how many characters there are and how close they are
+    Auto,
+    /// Every character on its own
+    Eq,
+    /// At most 3 ranges, with false positives checked against the table
+    Ranges,
 }
 
 /// Key-value argument
 struct MetaOpt<Lit: Parse> {
     pub path: syn::Path,
-    pub eq_token: Token![=],
+    pub _eq_token: Token![=],
     pub lit: Lit,
 }
 
@@ -52,57 +455,464 @@ impl<Lit: Parse> Parse for MetaOpt<Lit> {
     fn parse<'a>(input: &'a ParseBuffer<'a>) -> syn::Result<Self> {
         Ok(Self {
             path: input.parse()?,
-            eq_token: input.parse()?,
+            _eq_token: input.parse()?,
             lit: input.parse()?,
         })
     }
 }
 
+/// Value of an option, a literal or a bare word as in `ranges = auto`
+enum OptValue {
+    Lit(syn::Lit),
+    Word(syn::Ident),
+}
+
+impl Parse for OptValue {
+    fn parse<'a>(input: &'a ParseBuffer<'a>) -> syn::Result<Self> {
+        if input.peek(syn::Lit) {
+            input.parse().map(OptValue::Lit)
+        } else {
+            // Keywords are words too, as in `detect = static`
+            syn::ext::IdentExt::parse_any(input).map(OptValue::Word)
+        }
+    }
+}
+
+impl OptValue {
+    fn span(&self) -> proc_macro2::Span {
+        match self {
+            OptValue::Lit(lit) => lit.span(),
+            OptValue::Word(word) => word.span(),
+        }
+    }
+
+    /// Value of a string option, given quoted or as a bare word
+    fn str_value(&self) -> Option<String> {
+        match self {
+            OptValue::Lit(syn::Lit::Str(s)) => Some(s.value()),
+            OptValue::Word(word) => Some(word.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Options accepted after the pairs
+const OPTIONS: &[&str] = &[
+    "avx",
+    "avx512",
+    "detect",
+    "file",
+    "idempotent",
+    "module",
+    "preset",
+    "print",
+    "ranges",
+    "simd",
+    "strategy",
+    "tests",
+    "then",
+    "unescape",
+];
+
 /// Proc macro arguments parser
 struct Builder {
-    pub pairs: syn::LitStr,
-    pub comma: Option<Token![,]>,
-    pub opts: Punctuated<MetaOpt<syn::LitBool>, Token![,]>,
+    pub pairs: Option<syn::LitStr>,
+    pub _comma: Option<Token![,]>,
+    pub opts: Punctuated<MetaOpt<OptValue>, Token![,]>,
 }
 
 impl Parse for Builder {
     fn parse<'a>(input: &'a ParseBuffer<'a>) -> syn::Result<Self> {
+        let pairs = if input.peek(syn::LitStr) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
         Ok(Self {
-            pairs: input.parse()?,
-            comma: input.parse()?,
+            pairs,
+            _comma: input.parse()?,
             opts: Punctuated::parse_terminated(input)?,
         })
     }
 }
 
+/// `new_escape!` arguments, the escaper name and the `derive!` ones
+struct NewEscape {
+    pub name: syn::Ident,
+    pub _comma: Token![,],
+    pub builder: Builder,
+}
+
+impl Parse for NewEscape {
+    fn parse<'a>(input: &'a ParseBuffer<'a>) -> syn::Result<Self> {
+        Ok(Self {
+            name: input.parse()?,
+            _comma: input.parse()?,
+            builder: input.parse()?,
+        })
+    }
+}
+
 impl Builder {
     /// Consume and return arguments data
     fn build(self) -> syn::Result<Args> {
         let Builder { pairs, opts, .. } = self;
         let mut avx = true;
+        let mut avx512 = false;
+        // Runtime detection needs `std`
+        let mut detect = if cfg!(feature = "std") {
+            Detect::Runtime
+        } else {
+            Detect::Static
+        };
+        let mut file = None;
+        let mut idempotent = false;
+        let mut module = None;
+        let mut span = proc_macro2::Span::call_site();
+        let mut preset = None;
         let mut print = false;
+        let mut tests = false;
+        let mut then = None;
+        let mut unescape = false;
+        let mut ranges = None;
         let mut simd = true;
+        let mut scalar = false;
+        let mut strategy = Strategy::Auto;
+        let mut seen = Vec::new();
 
         for MetaOpt { path, lit, .. } in opts {
-            if path.is_ident("avx") {
-                avx = lit.value
-            } else if path.is_ident("print") {
-                print = lit.value;
-            } else if path.is_ident("simd") {
-                simd = lit.value;
-            } else {
+            let name = match path.get_ident() {
+                Some(ident) if OPTIONS.contains(&ident.to_string().as_str()) => ident.to_string(),
+                _ => {
+                    return Err(syn::Error::new(
+                        path.span(),
+                        format!(
+                            "unknown option `{}`, expected one of: {}",
+                            quote::ToTokens::to_token_stream(&path),
+                            OPTIONS.join(", ")
+                        ),
+                    ))
+                }
+            };
+            if seen.contains(&name) {
                 return Err(syn::Error::new(
                     path.span(),
-                    format!("invalid attribute '{:?}'", path.get_ident()),
+                    format!("option `{}` is given more than once", name),
                 ));
             }
+
+            match name.as_str() {
+                "avx" => avx = bool_value(&name, &lit)?,
+                "avx512" => avx512 = bool_value(&name, &lit)?,
+                "detect" => {
+                    detect = match lit.str_value().as_deref() {
+                        Some("runtime") if cfg!(feature = "std") => Detect::Runtime,
+                        Some("runtime") => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                "`detect = \"runtime\"` needs the `std` feature of v_escape",
+                            ))
+                        }
+                        Some("static") => Detect::Static,
+                        _ => return Err(value_error(&name, &lit, "\"runtime\" or \"static\"")),
+                    }
+                }
+                "file" => {
+                    file = Some(read_pairs(&name, &lit)?);
+                    span = lit.span();
+                }
+                "idempotent" => idempotent = bool_value(&name, &lit)?,
+                "module" => {
+                    module = match lit.str_value().map(|s| syn::parse_str::<syn::Ident>(&s)) {
+                        Some(Ok(ident)) => Some(syn::Ident::new(&ident.to_string(), lit.span())),
+                        _ => return Err(value_error(&name, &lit, "a module name")),
+                    }
+                }
+                "preset" => {
+                    preset = lit.str_value().and_then(|s| preset::pairs(&s));
+                    if preset.is_none() {
+                        return Err(value_error(
+                            &name,
+                            &lit,
+                            "\"html\", \"json\", \"latex\" or \"shell\"",
+                        ));
+                    }
+                }
+                "print" => print = bool_value(&name, &lit)?,
+                "ranges" => {
+                    ranges = match (&lit, lit.str_value().as_deref()) {
+                        (OptValue::Lit(syn::Lit::Bool(b)), _) => Some(b.value),
+                        (_, Some("auto")) => None,
+                        _ => return Err(value_error(&name, &lit, "`auto`, `true` or `false`")),
+                    }
+                }
+                "simd" => match (&lit, lit.str_value().as_deref()) {
+                    (OptValue::Lit(syn::Lit::Bool(b)), _) => simd = b.value,
+                    (_, Some("scalar")) => {
+                        simd = false;
+                        scalar = true;
+                    }
+                    _ => return Err(value_error(&name, &lit, "`true`, `false` or `scalar`")),
+                },
+                "strategy" => {
+                    strategy = match lit.str_value().as_deref() {
+                        Some("auto") => Strategy::Auto,
+                        Some("eq") => Strategy::Eq,
+                        Some("ranges") => Strategy::Ranges,
+                        _ => return Err(value_error(&name, &lit, "`auto`, `eq` or `ranges`")),
+                    }
+                }
+                "tests" => tests = bool_value(&name, &lit)?,
+                "then" => match &lit {
+                    OptValue::Lit(syn::Lit::Str(s)) => then = Some(s.clone()),
+                    lit => return Err(value_error(&name, lit, "a string of pairs")),
+                },
+                "unescape" => unescape = bool_value(&name, &lit)?,
+                _ => unreachable!("checked against OPTIONS"),
+            }
+            seen.push(name);
+        }
+
+        if let Some(pairs) = &pairs {
+            span = pairs.span();
+        }
+        let (file, pairs) = match (file, pairs) {
+            (Some((path, content)), Some(pairs)) => (
+                Some(path),
+                Some(format!("{} || {}", pairs.value(), content)),
+            ),
+            (Some((path, content)), None) => (Some(path), Some(content)),
+            (None, pairs) => (None, pairs.map(|pairs| pairs.value())),
+        };
+        if pairs.is_none() && preset.is_none() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "expected pairs, a file or a preset",
+            ));
+        }
+        if ranges == Some(true) && !simd {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`ranges = true` needs `simd = true`",
+            ));
         }
 
         Ok(Args {
-            pairs: pairs.value(),
+            pairs,
+            file,
+            // It replaces `simd` and the options of the simd code
+            forbid_unsafe: cfg!(feature = "forbid-unsafe"),
+            idempotent,
+            module,
+            preset,
             avx,
+            avx512,
+            detect,
             print,
-            simd,
+            scalar,
+            // Ranges are the simd implementation, without them only swar is left
+            simd: simd && ranges != Some(false),
+            span,
+            strategy,
+            tests,
+            then,
+            unescape,
         })
     }
 }
+
+/// Value of a boolean option
+fn bool_value(name: &str, lit: &OptValue) -> syn::Result<bool> {
+    match lit {
+        OptValue::Lit(syn::Lit::Bool(b)) => Ok(b.value),
+        lit => Err(value_error(name, lit, "`true` or `false`")),
+    }
+}
+
+/// Path and pairs of the `file` option, a path relative to the crate root
+/// of a file with a pair per line, empty lines are skipped
+fn read_pairs(name: &str, lit: &OptValue) -> syn::Result<(String, String)> {
+    let path = match lit {
+        OptValue::Lit(syn::Lit::Str(s)) => s.value(),
+        lit => return Err(value_error(name, lit, "a path string")),
+    };
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&root).join(path);
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+        syn::Error::new(
+            lit.span(),
+            format!("unable to read `{}`: {}", path.display(), e),
+        )
+    })?;
+    let pairs: Vec<&str> = content.lines().filter(|line| !line.is_empty()).collect();
+    if pairs.is_empty() {
+        return Err(syn::Error::new(
+            lit.span(),
+            format!("`{}` has no pairs", path.display()),
+        ));
+    }
+
+    Ok((path.display().to_string(), pairs.join(" || ")))
+}
+
+/// Error of an invalid option value
+fn value_error(name: &str, lit: &OptValue, expected: &str) -> syn::Error {
+    syn::Error::new(
+        lit.span(),
+        format!("invalid value for option `{}`, expected {}", name, expected),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(input: &str) -> syn::Result<Args> {
+        syn::parse_str::<Builder>(input).and_then(Builder::build)
+    }
+
+    fn error(input: &str) -> String {
+        match build(input) {
+            Ok(_) => panic!("{} is valid", input),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_new_escape() {
+        let input = syn::parse_str::<NewEscape>(r#"MyE, "a->b", simd = false"#).unwrap();
+        assert_eq!(input.name, "MyE");
+        assert!(!input.builder.build().unwrap().simd);
+        assert!(syn::parse_str::<NewEscape>(r#""a->b""#).is_err());
+    }
+
+    #[test]
+    fn test_options() {
+        let args = build(r#""a->b", simd = false, avx512 = true, detect = static"#).unwrap();
+        assert!(!args.simd && args.avx512);
+        assert!(matches!(args.detect, Detect::Static));
+        assert!(build(r#""a->b", ranges = auto"#).unwrap().simd);
+        assert!(build(r#""a->b", ranges = "auto""#).unwrap().simd);
+        assert!(build(r#""a->b", ranges = true"#).unwrap().simd);
+        assert!(!build(r#""a->b", ranges = false"#).unwrap().simd);
+        let args = build(r#""a->b", simd = scalar"#).unwrap();
+        assert!(!args.simd && args.scalar);
+        assert!(build(r#"preset = html"#).unwrap().preset.is_some());
+        assert!(matches!(
+            build(r#""a->b", strategy = eq"#).unwrap().strategy,
+            Strategy::Eq
+        ));
+        assert_eq!(
+            build(r#""a->b", module = attr"#).unwrap().module.unwrap(),
+            "attr"
+        );
+        assert!(build(r#""a->b", tests = true"#).unwrap().tests);
+        assert_eq!(
+            matches!(build(r#""a->b""#).unwrap().detect, Detect::Runtime),
+            cfg!(feature = "std")
+        );
+        assert_eq!(
+            build(r#""a->b", then = "b->c""#)
+                .unwrap()
+                .then
+                .unwrap()
+                .value(),
+            "b->c"
+        );
+    }
+
+    #[test]
+    fn test_tests() {
+        let expand = |input, name: Option<&syn::Ident>| {
+            let builder = syn::parse_str::<Builder>(input).unwrap();
+            expand(builder, name).map(|code| code.to_string())
+        };
+        let name = syn::parse_str("MyE").unwrap();
+        assert!(expand(r#""a->b", tests = true"#, Some(&name))
+            .unwrap()
+            .contains("escape_tests"));
+        assert!(!expand(r#""a->b""#, Some(&name))
+            .unwrap()
+            .contains("escape_tests"));
+        assert_eq!(
+            expand(r#""a->b", tests = true"#, None)
+                .unwrap_err()
+                .to_string(),
+            "option `tests` needs `new_escape!`"
+        );
+    }
+
+    #[test]
+    fn test_finder() {
+        let expand = |input| {
+            let builder = syn::parse_str::<Builder>(input).unwrap();
+            let module = syn::parse_str("special").unwrap();
+            expand_finder(builder, &module).map(|code| code.to_string())
+        };
+        assert!(expand(r#""0x0A || 0x22->q""#)
+            .unwrap()
+            .contains("escape_find"));
+        assert_eq!(
+            expand(r#""0x0A", tests = true"#).unwrap_err().to_string(),
+            "option `tests` can't be given to `finder!`, it only looks for the characters"
+        );
+        assert_eq!(
+            expand(r#""0x0A", preset = html, unescape = true"#)
+                .unwrap_err()
+                .to_string(),
+            "option `unescape` can't be given to `finder!`, it only looks for the characters"
+        );
+    }
+
+    #[test]
+    fn test_eq_fallback() {
+        let expand = |input| {
+            let builder = syn::parse_str::<Builder>(input).unwrap();
+            expand(builder, None).unwrap().to_string()
+        };
+        assert!(!expand(r#""a-p->x", strategy = eq"#).contains("deprecated"));
+        assert!(expand(r#""a-q->x", strategy = eq"#).contains(
+            "`strategy = eq` compares at most 16 characters one by one, the 17 given are \
+             compared as with `strategy = auto`"
+        ));
+    }
+
+    #[test]
+    fn test_option_errors() {
+        assert_eq!(
+            error(r#""a->b", sse = true"#),
+            "unknown option `sse`, expected one of: avx, avx512, detect, file, idempotent, module, preset, print, ranges, simd, strategy, tests, then, unescape"
+        );
+        assert_eq!(
+            error(r#""a->b", avx = false, avx = true"#),
+            "option `avx` is given more than once"
+        );
+        assert_eq!(
+            error(r#""a->b", simd = "no""#),
+            "invalid value for option `simd`, expected `true`, `false` or `scalar`"
+        );
+        assert_eq!(
+            error(r#""a->b", ranges = maybe"#),
+            "invalid value for option `ranges`, expected `auto`, `true` or `false`"
+        );
+        assert_eq!(
+            error(r#""a->b", strategy = cmpestri"#),
+            "invalid value for option `strategy`, expected `auto`, `eq` or `ranges`"
+        );
+        assert_eq!(
+            error(r#""a->b", simd = false, ranges = true"#),
+            "`ranges = true` needs `simd = true`"
+        );
+        assert_eq!(error("simd = false"), "expected pairs, a file or a preset");
+        assert_eq!(
+            error(r#""a->b", module = "html attr""#),
+            "invalid value for option `module`, expected a module name"
+        );
+        assert!(error(r#"file = "missing.txt""#).starts_with("unable to read `"));
+        assert_eq!(
+            error(r#""a->b", then = true"#),
+            "invalid value for option `then`, expected a string of pairs"
+        );
+    }
+}
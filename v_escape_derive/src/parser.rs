@@ -1,46 +1,283 @@
-use std::{convert::TryInto, i8, str};
+use std::{borrow::Cow, str};
 
 use nom::{AsBytes, Needed};
 
 type Input<'a> = nom::types::CompleteByteSlice<'a>;
 
 #[allow(non_snake_case)]
-fn Input(input: &[u8]) -> Input {
+fn Input(input: &[u8]) -> Input<'_> {
     nom::types::CompleteByteSlice(input)
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Pair<'a> {
     pub char: u8,
-    pub quote: &'a [u8],
+    pub quote: Cow<'a, [u8]>,
 }
 
 impl<'a> Pair<'a> {
-    pub fn new(char: u8, quote: &[u8]) -> Pair {
-        Pair { char, quote }
+    #[cfg(test)]
+    pub fn new(char: u8, quote: &[u8]) -> Pair<'_> {
+        Pair {
+            char,
+            quote: Cow::Borrowed(quote),
+        }
+    }
+
+    /// Replace the placeholders of the quote by the character
+    fn fill(mut self) -> Self {
+        if let Some(quote) = fill_template(&self.quote, self.char) {
+            self.quote = Cow::Owned(quote);
+        }
+
+        self
+    }
+}
+
+/// Sequence of two or more ascii bytes escaped as a whole, `"[bytes]" -> "[quote]"`
+#[derive(Debug, PartialEq)]
+pub struct Trigger<'a> {
+    pub bytes: Vec<u8>,
+    pub quote: Cow<'a, [u8]>,
+}
+
+/// Pair, trigger or characters without a quote, in the order they are given
+enum Item<'a> {
+    Pair(Pair<'a>),
+    Trigger(Trigger<'a>),
+    Found(Vec<u8>),
+}
+
+/// Quote with every `{:[0][width][x|X|o|b]}` placeholder replaced by `char`
+/// formatted in that radix, decimal without a type. Returns `None` when there
+/// is no placeholder, other braces are kept as they are
+fn fill_template(quote: &[u8], char: u8) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(quote.len());
+    let mut filled = false;
+    let mut i = 0;
+
+    while i < quote.len() {
+        if quote[i..].starts_with(b"{:") {
+            let placeholder = quote[i..]
+                .iter()
+                .position(|&b| b == b'}')
+                .and_then(|end| Some((end, format_spec(&quote[i + 2..i + end], char)?)));
+            if let Some((end, formatted)) = placeholder {
+                out.extend_from_slice(formatted.as_bytes());
+                filled = true;
+                i += end + 1;
+                continue;
+            }
+        }
+        out.push(quote[i]);
+        i += 1;
     }
+
+    if filled {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+/// `char` formatted by the spec of a placeholder, `[0][width][x|X|o|b]`
+fn format_spec(spec: &[u8], char: u8) -> Option<String> {
+    let spec = str::from_utf8(spec).ok()?;
+    let (spec, ty) = match spec.as_bytes().last() {
+        Some(&ty) if b"xXob".contains(&ty) => (&spec[..spec.len() - 1], Some(ty)),
+        _ => (spec, None),
+    };
+    let zero = spec.starts_with('0');
+    let width = if spec.is_empty() {
+        0
+    } else if spec.bytes().all(|b| b.is_ascii_digit()) {
+        spec.parse().ok()?
+    } else {
+        return None;
+    };
+
+    Some(match (zero, ty) {
+        (true, Some(b'x')) => format!("{:01$x}", char, width),
+        (true, Some(b'X')) => format!("{:01$X}", char, width),
+        (true, Some(b'o')) => format!("{:01$o}", char, width),
+        (true, Some(_)) => format!("{:01$b}", char, width),
+        (true, None) => format!("{:01$}", char, width),
+        (false, Some(b'x')) => format!("{:1$x}", char, width),
+        (false, Some(b'X')) => format!("{:1$X}", char, width),
+        (false, Some(b'o')) => format!("{:1$o}", char, width),
+        (false, Some(_)) => format!("{:1$b}", char, width),
+        (false, None) => format!("{:1$}", char, width),
+    })
 }
 
-named!(parse_syntax<Input, Vec<Pair>>, many1!(parse_pair));
+// `many1!` would hide the failure of the first pair behind its own error
+named!(parse_syntax<Input, Vec<Item>>, map!(
+    pair!(parse_item, many0!(parse_item)),
+    |(first, rest)| first.into_iter().chain(rest.into_iter().flatten()).collect()
+));
+
+named!(parse_item<Input, Vec<Item>>, alt!(
+    map!(parse_pair, |p| vec![Item::Pair(p)]) |
+    map!(parse_range, |pairs| pairs.into_iter().map(Item::Pair).collect()) |
+    map!(parse_trigger, |t| vec![t]) |
+    map!(parse_found, |chars| vec![Item::Found(chars)])
+));
+
+// Arrow and quote, `-> "[quote]"` with escape sequences or `->[quote]` as is
+named!(parse_quote<Input, Cow<[u8]>>, alt!(
+    map!(
+        delimited!(
+            tuple!(opt!(tag!(" ")), tag!("->"), opt!(tag!(" "))),
+            str_literal,
+            alt!(tag!(" || ") | eof!())
+        ),
+        Cow::Owned
+    ) |
+    map!(
+        preceded!(tag!("->"), alt!(take_until_and_consume!(" || ") | nom::rest)),
+        |s| Cow::Borrowed(s.0)
+    )
+));
 
 named!(parse_pair<Input, Pair>, map!(
-    separated_pair!(is_char, tag!("->"), alt!(take_until_and_consume!(" || ") | nom::rest)),
-    |s| Pair::new(s.0, &s.1)
+    pair!(is_char, parse_quote),
+    |(char, quote)| Pair { char, quote }
 ));
 
+/// Range of characters with the same quote, `[first]-[last]->[quote]`
+fn parse_range(input: Input) -> nom::IResult<Input, Vec<Pair>> {
+    let (rest, ((first, last), quote)) = pair!(
+        input,
+        separated_pair!(is_char, tag!("-"), is_char),
+        parse_quote
+    )?;
+    if last <= first {
+        return Err(nom::Err::Failure(error_position!(input, ERR_RANGE)));
+    }
+
+    Ok((
+        rest,
+        (first..=last)
+            .map(|char| Pair {
+                char,
+                quote: quote.clone(),
+            })
+            .collect(),
+    ))
+}
+
+/// Character or range without a quote, `[character]` or `[first]-[last]`,
+/// only looked for by a finder
+fn parse_found(input: Input) -> nom::IResult<Input, Vec<u8>> {
+    let (rest, (first, last)) = terminated!(
+        input,
+        pair!(is_char, opt!(preceded!(tag!("-"), is_char))),
+        alt!(tag!(" || ") | eof!())
+    )?;
+    match last {
+        Some(last) if last <= first => Err(nom::Err::Failure(error_position!(input, ERR_RANGE))),
+        Some(last) => Ok((rest, (first..=last).collect())),
+        None => Ok((rest, vec![first])),
+    }
+}
+
+/// Trigger, `"[bytes]"` and its quote. A single byte between the quotes is a
+/// pair of that character
+fn parse_trigger(input: Input) -> nom::IResult<Input, Item> {
+    let (rest, (bytes, quote)) = pair!(input, str_literal, parse_quote)?;
+    match bytes.len() {
+        0 => Err(nom::Err::Failure(error_position!(input, ERR_TRIGGER))),
+        1 => Ok((
+            rest,
+            Item::Pair(Pair {
+                char: bytes[0],
+                quote,
+            }),
+        )),
+        _ if !bytes.is_ascii() => Err(nom::Err::Failure(error_position!(input, ERR_TRIGGER))),
+        _ => Ok((rest, Item::Trigger(Trigger { bytes, quote }))),
+    }
+}
+
+/// Escape sequence after a backslash, `n`, `t`, `r`, `0`, `\`, `'`, `"` or `x[hex][hex]`
+fn escape_sequence(input: Input) -> nom::IResult<Input, u8> {
+    let b = input.0;
+    let (len, byte) = match b.first() {
+        Some(b'n') => (1, b'\n'),
+        Some(b't') => (1, b'\t'),
+        Some(b'r') => (1, b'\r'),
+        Some(b'0') => (1, b'\0'),
+        Some(b'\\') => (1, b'\\'),
+        Some(b'\'') => (1, b'\''),
+        Some(b'"') => (1, b'"'),
+        Some(b'x') => match b.get(1..3).and_then(|h| str::from_utf8(h).ok()) {
+            Some(h) => match u8::from_str_radix(h, 16) {
+                Ok(n) => (3, n),
+                Err(_) => return Err(nom::Err::Failure(error_position!(input, ERR_ESCAPE))),
+            },
+            None => return Err(nom::Err::Failure(error_position!(input, ERR_ESCAPE))),
+        },
+        _ => return Err(nom::Err::Failure(error_position!(input, ERR_ESCAPE))),
+    };
+
+    Ok((Input(&b[len..]), byte))
+}
+
+/// Char literal, `'[character]'` or `'\[escape]'`
+fn char_literal(input: Input) -> nom::IResult<Input, u8> {
+    let error = || nom::Err::Error(error_position!(input, ERR_CHAR));
+    let b = input.0;
+    if b.first() != Some(&b'\'') {
+        return Err(error());
+    }
+    let (rest, char) = match b.get(1) {
+        Some(b'\\') => escape_sequence(Input(&b[2..]))?,
+        Some(&c) if c.is_ascii() => (Input(&b[2..]), c),
+        _ => return Err(error()),
+    };
+    match rest.0.first() {
+        Some(b'\'') => Ok((Input(&rest.0[1..]), char)),
+        _ => Err(error()),
+    }
+}
+
+/// String literal, `"[quote]"` with escape sequences
+fn str_literal(input: Input) -> nom::IResult<Input, Vec<u8>> {
+    let b = input.0;
+    if b.first() != Some(&b'"') {
+        return Err(nom::Err::Error(error_position!(input, ERR_CHAR)));
+    }
+    let mut out = Vec::new();
+    let mut i = 1;
+    while let Some(&c) = b.get(i) {
+        match c {
+            b'"' => return Ok((Input(&b[i + 1..]), out)),
+            b'\\' => {
+                let (rest, byte) = escape_sequence(Input(&b[i + 1..]))?;
+                out.push(byte);
+                i = b.len() - rest.len();
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    // Unterminated, it is a quote as is
+    Err(nom::Err::Error(error_position!(input, ERR_CHAR)))
+}
+
 macro_rules! is_digit {
     ($name:ident, $base:expr) => {
+        #[allow(clippy::from_str_radix_10)]
         fn $name(s: Input) -> Result<u8, nom::Err<Input>> {
-            if let Ok(n) = i8::from_str_radix(
+            u8::from_str_radix(
                 str::from_utf8(&s.as_bytes())
                     .map_err(|_| nom::Err::Failure(error_position!(s, ERR_UTF8)))?,
                 $base,
-            ) {
-                n.try_into()
-                    .map_err(|_| nom::Err::Failure(error_position!(s, ERR_OVERFLOW)))
-            } else {
-                Err(nom::Err::Failure(error_position!(s, ERR_OVERFLOW)))
-            }
+            )
+            .map_err(|_| nom::Err::Failure(error_position!(s, ERR_OVERFLOW)))
         }
     };
 }
@@ -49,75 +286,378 @@ is_digit!(is_digit_8, 8);
 is_digit!(is_digit_10, 10);
 is_digit!(is_digit_16, 16);
 
-fn try_into_i8(s: Input) -> Result<u8, nom::Err<Input>> {
-    let b = s.as_bytes();
-    if b.len() == 1 {
-        b[0].try_into()
-            // It is panic-free since previously it was an u8
-            .map(|n: i8| n as u8)
-            .map_err(|_| nom::Err::Failure(error_position!(s, ERR_OVERFLOW)))
-    } else {
-        Err(nom::Err::Incomplete(Needed::Size(1)))
+/// Single digit after `#`, as the character
+fn single_digit(s: Input) -> Result<u8, nom::Err<Input>> {
+    match s.as_bytes() {
+        [digit] => Ok(*digit),
+        _ => Err(nom::Err::Incomplete(Needed::Size(1))),
     }
 }
 
 named!(is_char<Input, u8>, alt!(
-    map_res!(preceded!(tag!("0x"), take_while1!(nom::is_hex_digit)), is_digit_16) |
-    map_res!(preceded!(tag!("0o"), take_while1!(nom::is_oct_digit)), is_digit_8) |
-    map_res!(preceded!(tag!("#"), take_while1!(nom::is_digit)), try_into_i8) |
-    map_res!(take_while1!(nom::is_digit), is_digit_10) |
-    map_res!(take!(1), try_into_i8)
+    char_literal |
+    call!(number, "0x", nom::is_hex_digit, is_digit_16) |
+    call!(number, "0o", nom::is_oct_digit, is_digit_8) |
+    call!(number, "#", nom::is_digit, single_digit) |
+    call!(number, "", nom::is_digit, is_digit_10) |
+    ascii_char
 ));
 
-pub fn parse(src: &str) -> Vec<Pair> {
-    let mut pairs = match parse_syntax(Input(src.as_bytes())) {
+/// Character as is, a byte over 127 is a piece of a multi-byte character
+/// and has to be given as a number
+fn ascii_char(input: Input) -> nom::IResult<Input, u8> {
+    let (rest, char) = take!(input, 1)?;
+    if !char.0[0].is_ascii() {
+        return Err(nom::Err::Failure(error_position!(input, ERR_ASCII)));
+    }
+
+    Ok((rest, char.0[0]))
+}
+
+/// Digits after `prefix` converted to a character, unlike `map_res!` the
+/// conversion keeps its failure so an overflow isn't reported as bad syntax
+fn number<'a>(
+    input: Input<'a>,
+    prefix: &str,
+    is_digit: fn(u8) -> bool,
+    convert: fn(Input<'a>) -> Result<u8, nom::Err<Input<'a>>>,
+) -> nom::IResult<Input<'a>, u8> {
+    let (rest, digits) = preceded!(input, tag!(prefix), take_while1!(is_digit))?;
+
+    convert(digits).map(|char| (rest, char))
+}
+
+/// Parse the pairs syntax, sorted by character, without triggers
+pub fn parse(src: &str) -> Result<Vec<Pair<'_>>, String> {
+    let (pairs, triggers) = parse_all(src)?;
+    match triggers.first() {
+        Some(t) => Err(format!(
+            "trigger {:?} can't be given here, only single characters",
+            String::from_utf8_lossy(&t.bytes)
+        )),
+        None => Ok(pairs),
+    }
+}
+
+/// Parse the pairs syntax, the pairs sorted by character and the triggers
+/// by their bytes
+///
+/// A syntax error or a character given twice is an error, with a message
+/// pointing at the pair that caused it
+pub fn parse_all(src: &str) -> Result<(Vec<Pair<'_>>, Vec<Trigger<'_>>), String> {
+    parse_items(src, false)
+}
+
+/// Parse the pairs of a finder, the quotes can be left out and a character
+/// without one has an empty quote
+pub fn parse_finder(src: &str) -> Result<Vec<Pair<'_>>, String> {
+    let (pairs, triggers) = parse_items(src, true)?;
+    match triggers.first() {
+        Some(t) => Err(format!(
+            "trigger {:?} can't be given to a finder, it looks for single characters",
+            String::from_utf8_lossy(&t.bytes)
+        )),
+        None => Ok(pairs),
+    }
+}
+
+/// `parse_all`, with the characters without a quote of a finder when `find`
+fn parse_items(src: &str, find: bool) -> Result<(Vec<Pair<'_>>, Vec<Trigger<'_>>), String> {
+    let (mut pairs, mut triggers) = match parse_syntax(Input(src.as_bytes())) {
         Ok((left, res)) => {
             if !left.is_empty() {
-                let s = str::from_utf8(left.0).unwrap();
-                panic!("Unable to parse syntax:\n\n{:?}", s);
-            } else {
-                res
+                return Err(syntax_error(left));
             }
+            let mut pairs = Vec::with_capacity(res.len());
+            let mut triggers = Vec::new();
+            for item in res {
+                match item {
+                    Item::Pair(pair) => pairs.push(pair.fill()),
+                    Item::Trigger(trigger) => triggers.push(trigger),
+                    Item::Found(chars) if find => {
+                        pairs.extend(chars.into_iter().map(|char| Pair {
+                            char,
+                            quote: Cow::Borrowed(&[]),
+                        }))
+                    }
+                    Item::Found(chars) => {
+                        return Err(format!(
+                            "character {:?} ({:#04x}) has no quote, only the characters of \
+                             `finder!` are given without one",
+                            chars[0] as char, chars[0]
+                        ))
+                    }
+                }
+            }
+            (pairs, triggers)
         }
-        Err(nom::Err::Error(err)) => panic!("Unable to parse pairs parameter:\n\n{:?}", err),
-        Err(nom::Err::Failure(err)) => match err.clone().into_error_kind() {
-            ERR_OVERFLOW => panic!(
-                "Number has to be between 0 and 127.\nOverflow at character:\n\n{:?}",
-                err
-            ),
-            ERR_UTF8 => panic!("Need valid utf-8 characters.\n\n{:?}", err),
-            _ => panic!("Unable to parse pairs parameter:\n\n{:?}", err),
-        },
-        Err(nom::Err::Incomplete(err)) => panic!("Parsing incomplete: {:?}", err),
+        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => {
+            return Err(match err {
+                nom::Context::Code(at, ERR_OVERFLOW) => format!(
+                    "character `{}` is out of range, it has to be between 0 and 255",
+                    token(at)
+                ),
+                nom::Context::Code(at, ERR_ASCII) => format!(
+                    "character `{}` is not ascii, a byte over 127 is given as a number like `0x80`",
+                    excerpt(at).chars().next().unwrap_or_default()
+                ),
+                nom::Context::Code(at, ERR_UTF8) => {
+                    format!("invalid utf-8 at `{}`", excerpt(at))
+                }
+                nom::Context::Code(at, ERR_ESCAPE) => format!(
+                    "unknown escape sequence `\\{}`, expected one of \\n \\t \\r \\0 \\\\ \\' \\\" \\x[hex][hex]",
+                    token(at)
+                ),
+                nom::Context::Code(at, ERR_RANGE) => format!(
+                    "range `{}` has to go from a lower to a higher character",
+                    excerpt(at)
+                ),
+                nom::Context::Code(at, ERR_TRIGGER) => format!(
+                    "trigger `{}` has to be ascii and not empty",
+                    excerpt(at)
+                ),
+                nom::Context::Code(at, _) => syntax_error(at),
+                #[allow(unreachable_patterns)]
+                _ => syntax_error(Input(src.as_bytes())),
+            });
+        }
+        Err(nom::Err::Incomplete(_)) => return Err(syntax_error(Input(src.as_bytes()))),
     };
 
-    let len = pairs.len();
-
-    // check minimum length
-    assert_ne!(len, 0);
+    if pairs.is_empty() && triggers.is_empty() {
+        return Err("expected at least one pair".into());
+    }
+    if let Some(p) = pairs.iter().find(|p| str::from_utf8(&p.quote).is_err()) {
+        return Err(format!(
+            "quote of character {:?} ({:#04x}) is not valid utf-8",
+            p.char as char, p.char
+        ));
+    }
 
     // need order for calculate ranges
     pairs.sort_unstable_by_key(|p| p.char);
 
     // check repeated
-    for i in 0..len - 1 {
-        let p1 = &pairs[i];
-        let p2 = &pairs[i + 1];
+    for w in pairs.windows(2) {
+        let (p1, p2) = (&w[0], &w[1]);
         if p1.char == p2.char {
-            panic!("{:?} and {:?} are repeated", p1, p2);
+            return Err(format!(
+                "character {:?} ({:#04x}) is repeated, with quotes {:?} and {:?}",
+                p1.char as char,
+                p1.char,
+                String::from_utf8_lossy(&p1.quote),
+                String::from_utf8_lossy(&p2.quote),
+            ));
+        }
+    }
+
+    // Escaping a byte of a multi-byte character would leave the rest of it
+    // in the escaped `str`, so these are escaped all together or not at all
+    let in_utf8 = |c: u8| matches!(c, 0x80..=0xBF | 0xC2..=0xF4);
+    let escaped = pairs.iter().filter(|p| in_utf8(p.char)).count();
+    if escaped != 0 && escaped != UTF8_BYTES {
+        let p = pairs.iter().find(|p| in_utf8(p.char)).unwrap();
+        return Err(format!(
+            "character {:#04x} is a byte of multi-byte characters, either every one of \
+             0x80-0xBF and 0xC2-0xF4 is escaped or none",
+            p.char
+        ));
+    }
+
+    if let Some(t) = triggers.iter().find(|t| str::from_utf8(&t.quote).is_err()) {
+        return Err(format!(
+            "quote of trigger {:?} is not valid utf-8",
+            String::from_utf8_lossy(&t.bytes)
+        ));
+    }
+
+    check_triggers(&mut triggers)?;
+
+    Ok((pairs, triggers))
+}
+
+/// Sort `triggers` by their bytes, a trigger given twice or starting another
+/// is an error
+pub fn check_triggers(triggers: &mut [Trigger]) -> Result<(), String> {
+    // Sorted, a trigger starting others is right before one of them
+    triggers.sort_unstable_by(|t1, t2| t1.bytes.cmp(&t2.bytes));
+    for w in triggers.windows(2) {
+        let (t1, t2) = (&w[0], &w[1]);
+        if t1.bytes == t2.bytes {
+            return Err(format!(
+                "trigger {:?} is repeated",
+                String::from_utf8_lossy(&t1.bytes)
+            ));
+        }
+        if t2.bytes.starts_with(&t1.bytes) {
+            return Err(format!(
+                "trigger {:?} starts trigger {:?}, only one of them can be escaped",
+                String::from_utf8_lossy(&t1.bytes),
+                String::from_utf8_lossy(&t2.bytes),
+            ));
         }
     }
 
+    Ok(())
+}
+
+/// Bytes of the multi-byte characters of utf-8, 0x80-0xBF and 0xC2-0xF4
+const UTF8_BYTES: usize = 0x40 + 0x33;
+
+/// Error of input that isn't a pair
+fn syntax_error(at: Input) -> String {
+    format!(
+        "unable to parse `{}`, expected `[character]->[quote]` pairs separated by ` || `",
+        excerpt(at)
+    )
+}
+
+/// Input up to the end of its character or escape sequence
+fn token(at: Input) -> String {
+    let b = at.0;
+    let end = b
+        .iter()
+        .skip(1)
+        .position(|c| b"-'\" \\".contains(c))
+        .map_or(b.len(), |i| i + 1);
+    String::from_utf8_lossy(&b[..end]).into_owned()
+}
+
+/// Input up to the end of its pair
+fn excerpt(at: Input) -> String {
+    let b = at.0;
+    let end = b.windows(4).position(|w| w == b" || ").unwrap_or(b.len());
+    String::from_utf8_lossy(&b[..end]).into_owned()
+}
+
+/// Add the `preset` pairs whose character isn't in `pairs`, so the given
+/// pairs override the preset ones
+pub fn merge<'a>(mut pairs: Vec<Pair<'a>>, preset: Vec<Pair<'a>>) -> Vec<Pair<'a>> {
+    for pair in preset {
+        if !pairs.iter().any(|p| p.char == pair.char) {
+            pairs.push(pair);
+        }
+    }
+    pairs.sort_unstable_by_key(|p| p.char);
+
     pairs
 }
 
+/// Pairs escaping with `pairs` and then with `then` in a single pass, the
+/// quotes of `pairs` are escaped with `then` and the characters only in
+/// `then` keep their quote
+pub fn then<'a>(pairs: Vec<Pair<'a>>, then: Vec<Pair<'a>>) -> Vec<Pair<'a>> {
+    let quote = |c: u8| then.iter().find(|p| p.char == c).map(|p| &p.quote[..]);
+    let pairs = pairs
+        .into_iter()
+        .map(|pair| {
+            if !pair.quote.iter().any(|&c| quote(c).is_some()) {
+                return pair;
+            }
+            let mut escaped = Vec::with_capacity(pair.quote.len());
+            for &c in pair.quote.iter() {
+                match quote(c) {
+                    Some(quote) => escaped.extend_from_slice(quote),
+                    None => escaped.push(c),
+                }
+            }
+            Pair {
+                char: pair.char,
+                quote: Cow::Owned(escaped),
+            }
+        })
+        .collect();
+
+    merge(pairs, then)
+}
+
+/// Triggers of the `idempotent` option, the quotes starting with an escaped
+/// character are passed through as they are instead of escaping that
+/// character again
+///
+/// Quotes that aren't ascii or are a single byte are left out, and the same
+/// quote of several characters is taken once
+pub fn idempotent<'a>(pairs: &'a [Pair<'a>]) -> Vec<Trigger<'a>> {
+    let mut triggers: Vec<Trigger> = pairs
+        .iter()
+        .filter(|p| {
+            p.quote.len() > 1
+                && p.quote.is_ascii()
+                && pairs.iter().any(|escaped| escaped.char == p.quote[0])
+        })
+        .map(|p| Trigger {
+            bytes: p.quote.to_vec(),
+            quote: Cow::Borrowed(&p.quote),
+        })
+        .collect();
+    triggers.sort_unstable_by(|t1, t2| t1.bytes.cmp(&t2.bytes));
+    triggers.dedup_by(|t1, t2| t1.bytes == t2.bytes);
+
+    triggers
+}
+
+/// Quotes with their character, sorted by quote, to map them back
+///
+/// Every quote has to be found on its own in the escaped text, so its first
+/// character is escaped and never left in the text as it is, and no quote
+/// starts another one
+pub fn unescape<'a>(pairs: &'a [Pair<'a>]) -> Result<Vec<(&'a [u8], u8)>, String> {
+    let mut quotes: Vec<(&[u8], u8)> = pairs.iter().map(|p| (&p.quote[..], p.char)).collect();
+    quotes.sort_unstable();
+
+    for &(quote, c) in &quotes {
+        if !c.is_ascii() {
+            return Err(format!(
+                "`unescape` maps quotes back to ascii characters, not to {:#04x}",
+                c
+            ));
+        }
+        match quote.first() {
+            None => {
+                return Err(format!(
+                    "the quote of {:?} is empty, `unescape` can't find it",
+                    c as char
+                ))
+            }
+            Some(&first) if !pairs.iter().any(|p| p.char == first) => {
+                return Err(format!(
+                    "the quote of {:?} starts with {:?}, which isn't escaped, so \
+                     `unescape` can't tell the quote from the same text",
+                    c as char, first as char
+                ))
+            }
+            Some(_) => (),
+        }
+    }
+    // Sorted, a quote starting others is right before one of them
+    for w in quotes.windows(2) {
+        if w[1].0.starts_with(w[0].0) {
+            return Err(format!(
+                "the quote of {:?} starts the one of {:?}, `unescape` can't tell them apart",
+                w[0].1 as char, w[1].1 as char
+            ));
+        }
+    }
+
+    Ok(quotes)
+}
+
 const ERR_OVERFLOW: nom::ErrorKind = nom::ErrorKind::Custom(0);
 const ERR_UTF8: nom::ErrorKind = nom::ErrorKind::Custom(1);
+const ERR_RANGE: nom::ErrorKind = nom::ErrorKind::Custom(2);
+const ERR_ESCAPE: nom::ErrorKind = nom::ErrorKind::Custom(3);
+const ERR_CHAR: nom::ErrorKind = nom::ErrorKind::Custom(4);
+const ERR_ASCII: nom::ErrorKind = nom::ErrorKind::Custom(5);
+const ERR_TRIGGER: nom::ErrorKind = nom::ErrorKind::Custom(6);
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn parse(src: &str) -> Vec<Pair<'_>> {
+        super::parse(src).unwrap()
+    }
+
     #[test]
     fn test_parser() {
         assert_eq!(parse("123->&lt; || "), vec![Pair::new(123, b"&lt;")]);
@@ -127,6 +667,223 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_merge() {
+        assert_eq!(
+            merge(parse("a->1 || c->2"), parse("c->3 || b->4")),
+            vec![
+                Pair::new(b'a', b"1"),
+                Pair::new(b'b', b"4"),
+                Pair::new(b'c', b"2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_then() {
+        assert_eq!(
+            then(parse("a->1b || c->2"), parse("b->x || #2->y || d->z")),
+            vec![
+                Pair::new(b'2', b"y"),
+                Pair::new(b'a', b"1x"),
+                Pair::new(b'b', b"x"),
+                Pair::new(b'c', b"y"),
+                Pair::new(b'd', b"z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        let pairs = parse("'<' -> \"&lt;\" || '&' -> \"&amp;\" || '>' -> \"&gt;\"");
+        assert_eq!(
+            unescape(&pairs),
+            Ok(vec![
+                (&b"&amp;"[..], b'&'),
+                (&b"&gt;"[..], b'>'),
+                (&b"&lt;"[..], b'<'),
+            ])
+        );
+
+        let err = |src| unescape(&parse(src)).unwrap_err();
+        assert_eq!(
+            err("a->a || 0x80-0xBF->a || 0xC2-0xF4->a"),
+            "`unescape` maps quotes back to ascii characters, not to 0x80"
+        );
+        assert_eq!(
+            err("a->x || b->"),
+            "the quote of 'b' is empty, `unescape` can't find it"
+        );
+        assert_eq!(
+            err("a->bar"),
+            "the quote of 'a' starts with 'b', which isn't escaped, so `unescape` can't \
+             tell the quote from the same text"
+        );
+        assert_eq!(
+            err("a->aa || b->aab"),
+            "the quote of 'a' starts the one of 'b', `unescape` can't tell them apart"
+        );
+        assert_eq!(
+            err("a->a || b->a"),
+            "the quote of 'a' starts the one of 'b', `unescape` can't tell them apart"
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            parse("0x00-0x02->? || 0x7F->!"),
+            vec![
+                Pair::new(0, b"?"),
+                Pair::new(1, b"?"),
+                Pair::new(2, b"?"),
+                Pair::new(0x7F, b"!"),
+            ]
+        );
+        assert_eq!(
+            parse("a-c->- || -->x"),
+            vec![
+                Pair::new(b'-', b"x"),
+                Pair::new(b'a', b"-"),
+                Pair::new(b'b', b"-"),
+                Pair::new(b'c', b"-"),
+            ]
+        );
+        // A pair has precedence over a range ending in `>`
+        assert_eq!(parse("a->->x"), vec![Pair::new(b'a', b"->x")]);
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            parse(r#"'<' -> "&lt;" || 0x3E -> "&gt;" || '\n' -> "\\n" || '\'' -> "\x27\"""#),
+            vec![
+                Pair::new(b'\n', b"\\n"),
+                Pair::new(b'\'', b"'\""),
+                Pair::new(b'<', b"&lt;"),
+                Pair::new(b'>', b"&gt;"),
+            ]
+        );
+        assert_eq!(
+            parse(r#"'\x00'-'\x02' -> "?" || '"' -> " || " || |->'""#),
+            vec![
+                Pair::new(0, b"?"),
+                Pair::new(1, b"?"),
+                Pair::new(2, b"?"),
+                Pair::new(b'"', b" || "),
+                Pair::new(b'|', b"'\""),
+            ]
+        );
+        // Old syntax, not literals
+        assert_eq!(
+            parse(r#"'->a || "->b"#),
+            vec![Pair::new(b'"', b"b"), Pair::new(b'\'', b"a")]
+        );
+    }
+
+    #[test]
+    fn test_triggers() {
+        let (pairs, triggers) = parse_all(
+            r#""]]>" -> "]]]]><![CDATA[>" || '&' -> "&amp;" || "--" -> "-\x2D" || "<" -> "&lt;""#,
+        )
+        .unwrap();
+        assert_eq!(
+            pairs,
+            vec![Pair::new(b'&', b"&amp;"), Pair::new(b'<', b"&lt;")]
+        );
+        assert_eq!(
+            triggers,
+            vec![
+                Trigger {
+                    bytes: b"--".to_vec(),
+                    quote: Cow::Borrowed(b"--"),
+                },
+                Trigger {
+                    bytes: b"]]>".to_vec(),
+                    quote: Cow::Borrowed(b"]]]]><![CDATA[>"),
+                },
+            ]
+        );
+        assert_eq!(parse_all(r#""</" -> "<\\/""#).unwrap().0, vec![]);
+
+        let error = |src| parse_all(src).unwrap_err();
+        assert_eq!(
+            error(r#""--" -> "a" || "--" -> "b""#),
+            "trigger \"--\" is repeated"
+        );
+        assert_eq!(
+            error(r#""</script" -> "a" || "</" -> "b""#),
+            "trigger \"</\" starts trigger \"</script\", only one of them can be escaped"
+        );
+        assert_eq!(
+            error(r#""é" -> "e""#),
+            "trigger `\"é\" -> \"e\"` has to be ascii and not empty"
+        );
+        assert_eq!(
+            error(r#""" -> "e""#),
+            "trigger `\"\" -> \"e\"` has to be ascii and not empty"
+        );
+        assert_eq!(
+            super::parse(r#"a->b || "--" -> "-""#).unwrap_err(),
+            "trigger \"--\" can't be given here, only single characters"
+        );
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let pairs = parse(
+            r#"'&' -> "&amp;" || '<' -> "&lt;" || '"' -> "&quot;" || '\'' -> "&quot;" || a->b || c->"é&""#,
+        );
+        let triggers = idempotent(&pairs);
+        let bytes: Vec<&[u8]> = triggers.iter().map(|t| &t.bytes[..]).collect();
+        assert_eq!(bytes, [&b"&amp;"[..], b"&lt;", b"&quot;"]);
+        assert!(triggers.iter().all(|t| t.bytes == t.quote[..]));
+
+        let pairs = parse(r#"'&' -> "&a" || '<' -> "&ab""#);
+        let mut triggers = idempotent(&pairs);
+        assert_eq!(
+            check_triggers(&mut triggers).unwrap_err(),
+            "trigger \"&a\" starts trigger \"&ab\", only one of them can be escaped"
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_panic_unknown_escape() {
+        parse(r#"'\q' -> "a""#);
+    }
+
+    #[test]
+    fn test_template() {
+        assert_eq!(
+            parse("<->&#x{:02X}; || 0x00-0x01->\\u00{:02x}"),
+            vec![
+                Pair::new(0, b"\\u0000"),
+                Pair::new(1, b"\\u0001"),
+                Pair::new(b'<', b"&#x3C;"),
+            ]
+        );
+        assert_eq!(
+            parse("a->{:}-{:o}-{:4b}-{:03} || b->{} {:q} {:0x"),
+            vec![
+                Pair::new(b'a', b"97-141-1100001-097"),
+                Pair::new(b'b', b"{} {:q} {:0x"),
+            ]
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_panic_range_reversed() {
+        parse("0x1F-0x00->?");
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_panic_range_repeated() {
+        parse("a-c->? || b->!");
+    }
+
     #[test]
     fn test_syntax() {
         assert_eq!(parse("b->& || "), vec![Pair::new(b'b', b"&")]);
@@ -166,6 +923,36 @@ mod test {
         parse("a->f || a->");
     }
 
+    #[test]
+    fn test_repeated_error() {
+        assert_eq!(
+            super::parse("b->x || a->f || a->").unwrap_err(),
+            "character 'a' (0x61) is repeated, with quotes \"f\" and \"\""
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |src| super::parse(src).unwrap_err();
+        assert_eq!(
+            error("a->b || é->f"),
+            "character `é` is not ascii, a byte over 127 is given as a number like `0x80`"
+        );
+        assert_eq!(
+            error("a->b || 0x1F-0x00->?"),
+            "range `0x1F-0x00->?` has to go from a lower to a higher character"
+        );
+        assert!(error(r#"'\q' -> "a" || a->b"#).starts_with("unknown escape sequence `\\q`"));
+        assert_eq!(
+            error("256->f || a->b"),
+            "character `256` is out of range, it has to be between 0 and 255"
+        );
+        assert_eq!(
+            error("a->b || ->f"),
+            "unable to parse `->f`, expected `[character]->[quote]` pairs separated by ` || `"
+        );
+    }
+
     #[should_panic]
     #[test]
     fn test_panic_bad_syntax_b() {
@@ -196,10 +983,56 @@ mod test {
         parse("256->f || ");
     }
 
-    #[should_panic]
     #[test]
-    fn test_panic_overflow_i8() {
-        parse("128->f || ");
+    fn test_high_bytes() {
+        assert_eq!(
+            parse(r"192->f || 0xFF->g || '\xFE'->h || 0o365-0o366->i"),
+            vec![
+                Pair::new(0xC0, b"f"),
+                Pair::new(0xF5, b"i"),
+                Pair::new(0xF6, b"i"),
+                Pair::new(0xFE, b"h"),
+                Pair::new(0xFF, b"g"),
+            ]
+        );
+        assert_eq!(parse("0x80-0xFF->?").len(), 128);
+        assert_eq!(
+            super::parse("a->b || 0xE9->e").unwrap_err(),
+            "character 0xe9 is a byte of multi-byte characters, either every one of \
+             0x80-0xBF and 0xC2-0xF4 is escaped or none"
+        );
+    }
+
+    #[test]
+    fn test_finder() {
+        assert_eq!(
+            super::parse_finder("0x00-0x02 || '\"'->q || a").unwrap(),
+            vec![
+                Pair::new(0x00, b""),
+                Pair::new(0x01, b""),
+                Pair::new(0x02, b""),
+                Pair::new(b'"', b"q"),
+                Pair::new(b'a', b""),
+            ]
+        );
+        assert_eq!(
+            super::parse_finder("0x80-0xBF || 0xC2-0xF4").unwrap().len(),
+            115
+        );
+        assert_eq!(
+            super::parse("a->b || c").unwrap_err(),
+            "character 'c' (0x63) has no quote, only the characters of `finder!` are given \
+             without one"
+        );
+        assert_eq!(
+            super::parse_finder("a || 0x1F-0x00").unwrap_err(),
+            "range `0x1F-0x00` has to go from a lower to a higher character"
+        );
+        assert_eq!(
+            super::parse_finder(r#""</" -> "<\\/""#).unwrap_err(),
+            "trigger \"</\" can't be given to a finder, it looks for single characters"
+        );
+        assert!(super::parse_finder("a || 0xC3").is_err());
     }
 
     #[should_panic]
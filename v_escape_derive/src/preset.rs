@@ -0,0 +1,39 @@
+//! Canonical pair sets selected with the `preset` option
+
+/// Same pairs as `v_htmlescape`
+const HTML: &str = "60->&lt; || 62->&gt; || 38->&amp; || 34->&quot; || 39->&#x27; || 47->&#x2f;";
+
+/// Same pairs as `v_jsonescape`
+const JSON: &str = "0x00->\\u0000 || 0x01->\\u0001 || 0x02->\\u0002 || 0x03->\\u0003 || \
+                    0x04->\\u0004 || 0x05->\\u0005 || 0x06->\\u0006 || 0x07->\\u0007 || \
+                    0x08->\\b || 0x09->\\t || 0x0A->\\n || 0x0B->\\u000b || \
+                    0x0C->\\f || 0x0D->\\r || 0x0E->\\u000e || 0x0F->\\u000f || \
+                    0x10->\\u0010 || 0x11->\\u0011 || 0x12->\\u0012 || 0x13->\\u0013 || \
+                    0x14->\\u0014 || 0x15->\\u0015 || 0x16->\\u0016 || 0x17->\\u0017 || \
+                    0x18->\\u0018 || 0x19->\\u0019 || 0x1A->\\u001a || 0x1B->\\u001b || \
+                    0x1C->\\u001c || 0x1D->\\u001d || 0x1E->\\u001e || 0x1F->\\u001f || \
+                    0x22->\\\" || 0x5C->\\\\";
+
+/// Same pairs as `v_latexescape`
+const LATEX: &str = "35->\\# || 36->\\$ || 37->\\% || 38->\\& || 92->\\textbackslash{} || \
+                     94->\\textasciicircum{} || 95->\\_ || 123->\\{ || 125->\\} || \
+                     126->\\textasciitilde{}";
+
+/// POSIX shell metacharacters escaped with a backslash, a newline can't be
+/// escaped that way so it is single quoted
+const SHELL: &str = "0x09->\\\t || 0x0A->'\n' || 0x20->\\  || 0x21->\\! || 0x22->\\\" || \
+                     0x23->\\# || 0x24->\\$ || 0x26->\\& || 0x27->\\' || 0x28->\\( || \
+                     0x29->\\) || 0x2A->\\* || 0x3B->\\; || 0x3C->\\< || 0x3E->\\> || \
+                     0x3F->\\? || 0x5B->\\[ || 0x5C->\\\\ || 0x5D->\\] || 0x5E->\\^ || \
+                     0x60->\\` || 0x7B->\\{ || 0x7C->\\| || 0x7D->\\} || 0x7E->\\~";
+
+/// Pairs syntax of the preset `name`
+pub fn pairs(name: &str) -> Option<&'static str> {
+    match name {
+        "html" => Some(HTML),
+        "json" => Some(JSON),
+        "latex" => Some(LATEX),
+        "shell" => Some(SHELL),
+        _ => None,
+    }
+}
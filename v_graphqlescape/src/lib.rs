@@ -0,0 +1,165 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_graphqlescape::escape;
+//!
+//! assert_eq!(escape("say \"hi\"\n").to_string(), "say \\\"hi\\\"\\n");
+//! ```
+//!
+//! `escape` is for the text between the `"` of a string value. `"` and `\`
+//! are escaped with a `\`, and the control characters and `DEL` as `\b`, `\t`,
+//! `\n`, `\f`, `\r` or `\uXXXX`.
+//!
+//! A block string, `"""..."""`, has a single escape, `\"""`, which
+//! `escape_block` writes for every `"""` of the text. Its value isn't the text
+//! between the quotes though, the common indentation of its lines is removed,
+//! and its blank lines at the start and the end. `can_be_block` checks if the
+//! value of the escaped text is the text itself, else a string value is used:
+//!
+//! ```rust
+//! use v_graphqlescape::{can_be_block, escape_block};
+//!
+//! let s = "Say \"\"\"hi\"\"\"\nto\n  everyone";
+//! assert!(can_be_block(s));
+//! assert_eq!(escape_block(s).to_string(), "Say \\\"\"\"hi\\\"\"\"\nto\n  everyone");
+//! assert!(!can_be_block("  indented\n  text"));
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            GraphQLEscape,
+            "0x00-0x07->\\u{:04X} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+             0x0B->\\u000B || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\u{:04X} || \
+             0x22->\\\" || 0x5C->\\\\ || 0x7F->\\u007F",
+            $($t)*
+        );
+        v_escape::new!(
+            GraphQLBlockEscape,
+            r#""\"\"\"" -> "\\\"\"\"""#,
+            module = block,
+            $($t)*
+        );
+
+        /// Escape `s` for a block string, `"""` as `\"""`
+        #[inline]
+        pub fn escape_block(s: &str) -> GraphQLBlockEscape<'_> {
+            block::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_graphqlescape_simd, v_graphqlescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_graphqlescape_simd, v_graphqlescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+#[inline]
+fn is_blank(line: &str) -> bool {
+    line.bytes().all(|b| b == b' ' || b == b'\t')
+}
+
+/// Whether the block string of `escape_block(s)` has `s` as its value
+///
+/// It has no control character but tab and newline, it doesn't end with a `"`,
+/// which would end the block string with its quotes, its first and last lines
+/// aren't blank and, when its other lines aren't all blank, one of them isn't
+/// indented
+pub fn can_be_block(s: &str) -> bool {
+    if s.is_empty() {
+        return true;
+    }
+    if s.ends_with('"')
+        || s.bytes()
+            .any(|b| (b.is_ascii_control() && b != b'\t' && b != b'\n') || b == 0x7F)
+    {
+        return false;
+    }
+
+    let mut lines = s.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let last = s.rsplit('\n').next().unwrap_or_default();
+    if is_blank(first) || is_blank(last) {
+        return false;
+    }
+    let mut indented = lines.filter(|line| !is_blank(line)).map(|line| {
+        let b = line.as_bytes()[0];
+        b == b' ' || b == b'\t'
+    });
+    // The lines after the first are dedented by their common indentation
+    !(indented.clone().next().is_some() && indented.all(|indented| indented))
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("graphql", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"graphql"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{can_be_block, escape, escape_block};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a'b/c é").to_string(), "a'b/c é");
+        assert_eq!(
+            escape("\0\u{8}\t\n\u{b}\u{c}\r\u{1f}\u{7f}").to_string(),
+            "\\u0000\\b\\t\\n\\u000B\\f\\r\\u001F\\u007F"
+        );
+        assert_eq!(escape("\"\\").to_string(), "\\\"\\\\");
+        assert_eq!(format!("{:>4}", escape("\n")), "  \\n");
+    }
+
+    #[test]
+    fn test_escape_block() {
+        assert_eq!(escape_block("").to_string(), "");
+        assert_eq!(escape_block("a\"b\"\"c\\d").to_string(), "a\"b\"\"c\\d");
+        assert_eq!(escape_block("\"\"\"").to_string(), "\\\"\"\"");
+        assert_eq!(escape_block("\"\"\"\"").to_string(), "\\\"\"\"\"");
+        assert_eq!(escape_block("\\\"\"\"").to_string(), "\\\\\"\"\"");
+    }
+
+    #[test]
+    fn test_can_be_block() {
+        assert!(can_be_block(""));
+        assert!(can_be_block("a"));
+        assert!(can_be_block("  a\n\tb\nc"));
+        assert!(can_be_block("a\n\n  b\nc"));
+        assert!(can_be_block("a \"\"\" b"));
+        assert!(!can_be_block("a\""));
+        assert!(!can_be_block("a\r\nb"));
+        assert!(!can_be_block("a\u{0}"));
+        assert!(!can_be_block(" "));
+        assert!(!can_be_block("\na"));
+        assert!(!can_be_block("a\n"));
+        assert!(!can_be_block("a\n  b"));
+        assert!(!can_be_block("a\n  b\n\n\tc"));
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("graphql").unwrap();
+        escaper.escape_to("a\"", &mut out).unwrap();
+        assert_eq!(out, "a\\\"");
+    }
+}
@@ -0,0 +1,37 @@
+use v_graphqlescape::{escape, escape_block, GraphQLEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(GraphQLEscape::from("a\nb").to_string(), "a\\nb");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\u{8}' => "\\b".into(),
+                '\t' => "\\t".into(),
+                '\n' => "\\n".into(),
+                '\u{c}' => "\\f".into(),
+                '\r' => "\\r".into(),
+                '"' | '\\' => format!("\\{}", c),
+                c if c.is_ascii_control() => format!("\\u{:04X}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+    }
+}
+
+#[test]
+fn test_escape_block() {
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = "ab\"\"\"\"c".chars().cycle().take(len).collect();
+        let escaped = escape_block(&s).to_string();
+        // Every `"""` left is escaped
+        let unescaped = escaped.replace("\\\"\"\"", "\u{0}");
+        assert!(!unescaped.contains("\"\"\""), "{}", len);
+        assert_eq!(unescaped.replace('\u{0}', "\"\"\""), s, "{}", len);
+    }
+}
@@ -0,0 +1,146 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_headerescape::escape;
+//!
+//! assert_eq!(
+//!     escape("eve\r\nSet-Cookie: admin=1").to_string(),
+//!     "eve  Set-Cookie: admin=1"
+//! );
+//! ```
+//!
+//! A field value of HTTP, RFC 9110, has no control octet but tab, a `CR` or a
+//! `LF` from user data would end the header and start another one. `escape`
+//! replaces every control octet and `DEL` with a space, as a recipient of
+//! `CR`, `LF` or `NUL` may do, `escape_strip` removes them and
+//! `escape_percent` writes them as `%XX`, and `%` as `%25`, for a value which
+//! is decoded again:
+//!
+//! ```rust
+//! use v_headerescape::{escape_percent, escape_strip, is_valid};
+//!
+//! assert_eq!(escape_strip("a\r\nb").to_string(), "ab");
+//! assert_eq!(escape_percent("100%\n").to_string(), "100%25%0A");
+//! assert!(is_valid("text/html; charset=utf-8"));
+//! assert!(!is_valid("a\nb"));
+//! ```
+//!
+//! Tab and the octets from `0x80`, `obs-text`, are kept. The spaces and tabs
+//! at the start and the end of a value are removed by its parsers, a value
+//! keeps them when they are part of it.
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            HeaderEscape,
+            "0x00-0x08->\" \" || 0x0A-0x1F->\" \" || 0x7F->\" \"",
+            $($t)*
+        );
+        v_escape::new!(
+            HeaderStripEscape,
+            "0x00-0x08->\"\" || 0x0A-0x1F->\"\" || 0x7F->\"\"",
+            module = strip,
+            $($t)*
+        );
+
+        /// Escape `s` removing its control octets
+        #[inline]
+        pub fn escape_strip(s: &str) -> HeaderStripEscape<'_> {
+            strip::escape(s)
+        }
+
+        v_escape::new!(
+            HeaderPercentEscape,
+            "0x00-0x08->%{:02X} || 0x0A-0x1F->%{:02X} || 0x25->%25 || 0x7F->%7F",
+            module = percent,
+            $($t)*
+        );
+
+        /// Escape `s` writing its control octets and `%` as `%XX`
+        #[inline]
+        pub fn escape_percent(s: &str) -> HeaderPercentEscape<'_> {
+            percent::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_headerescape_simd, v_headerescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_headerescape_simd, v_headerescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+/// Whether `s` is a valid field value as it is, without a control octet but
+/// tab
+#[inline]
+pub fn is_valid(s: &str) -> bool {
+    !needs_escaping(s)
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("header", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"header"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_percent, escape_strip, is_valid};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a\tb; q=0.5 \"é\"").to_string(), "a\tb; q=0.5 \"é\"");
+        assert_eq!(escape("\0a\r\n\u{1f}\u{7f}").to_string(), " a    ");
+        assert_eq!(format!("{:>3}", escape("\n")), "   ");
+    }
+
+    #[test]
+    fn test_escape_strip() {
+        assert_eq!(escape_strip("a\tb%").to_string(), "a\tb%");
+        assert_eq!(escape_strip("\0a\r\n\u{1f}b\u{7f}").to_string(), "ab");
+    }
+
+    #[test]
+    fn test_escape_percent() {
+        assert_eq!(escape_percent("a\tb é").to_string(), "a\tb é");
+        assert_eq!(
+            escape_percent("\0%\r\n\u{1f}\u{7f}").to_string(),
+            "%00%25%0D%0A%1F%7F"
+        );
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid(""));
+        assert!(is_valid("a\tb é"));
+        for b in (0u8..0x20).chain([0x7F]).filter(|&b| b != b'\t') {
+            let s = format!("{}{}", "a".repeat(40), char::from(b));
+            assert!(!is_valid(&s));
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("header").unwrap();
+        escaper.escape_to("a\r\n", &mut out).unwrap();
+        assert_eq!(out, "a  ");
+    }
+}
@@ -0,0 +1,35 @@
+use v_headerescape::{escape, escape_percent, escape_strip, is_valid, HeaderEscape};
+
+fn is_control(c: char) -> bool {
+    c.is_ascii_control() && c != '\t'
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(HeaderEscape::from("a\nb").to_string(), "a b");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+
+        let expected: String = s
+            .chars()
+            .map(|c| if is_control(c) { ' ' } else { c })
+            .collect();
+        let escaped = escape(&s).to_string();
+        assert_eq!(escaped, expected, "{}", len);
+        assert!(is_valid(&escaped));
+
+        let expected: String = s.chars().filter(|&c| !is_control(c)).collect();
+        assert_eq!(escape_strip(&s).to_string(), expected, "{}", len);
+
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                c if is_control(c) || c == '%' => format!("%{:02X}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape_percent(&s).to_string(), expected, "{}", len);
+    }
+}
@@ -0,0 +1,127 @@
+//! Escaping for the context of the text in the page
+//!
+//! Every context has an escaper of its own, text escaped for the content of
+//! an element isn't safe in a script or in a url
+use core::fmt::{self, Display};
+
+use crate::{
+    attr, css_string, js_string, url, CssStringEscape, HTMLAttrEscape, HTMLEscape, JsStringEscape,
+    UrlEscape,
+};
+
+/// Where the escaped text is written
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Context {
+    /// Content of an element, as `escape`
+    Element,
+    /// Attribute value, quoted or not, as `escape_attr`
+    Attr,
+    /// Inside a quoted string of a script, `\xHH` escapes and `\u2028` and
+    /// `\u2029` for the line and paragraph separators
+    JsString,
+    /// Inside a quoted string of a style, `\HH ` escapes
+    CssString,
+    /// Component of a url, every byte but the unreserved ones is
+    /// percent-encoded. The url itself has to be checked, as for a
+    /// `javascript:` scheme
+    Url,
+}
+
+impl Context {
+    /// Escaper of this context, to choose it at runtime
+    #[inline]
+    pub fn escaper(self) -> &'static dyn v_escape::Escaper {
+        match self {
+            Context::Element => crate::ESCAPER,
+            Context::Attr => attr::ESCAPER,
+            Context::JsString => js_string::ESCAPER,
+            Context::CssString => css_string::ESCAPER,
+            Context::Url => url::ESCAPER,
+        }
+    }
+}
+
+/// `s` escaped for `context`, the escaping happens when it is formatted
+pub enum ContextEscape<'a> {
+    Element(HTMLEscape<'a>),
+    Attr(HTMLAttrEscape<'a>),
+    JsString(JsStringEscape<'a>),
+    CssString(CssStringEscape<'a>),
+    Url(UrlEscape<'a>),
+}
+
+impl Display for ContextEscape<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextEscape::Element(e) => e.fmt(fmt),
+            ContextEscape::Attr(e) => e.fmt(fmt),
+            ContextEscape::JsString(e) => e.fmt(fmt),
+            ContextEscape::CssString(e) => e.fmt(fmt),
+            ContextEscape::Url(e) => e.fmt(fmt),
+        }
+    }
+}
+
+/// Escape `s` for `context`
+#[inline]
+pub fn escape(s: &str, context: Context) -> ContextEscape<'_> {
+    match context {
+        Context::Element => ContextEscape::Element(crate::escape(s)),
+        Context::Attr => ContextEscape::Attr(attr::escape(s)),
+        Context::JsString => ContextEscape::JsString(js_string::escape(s)),
+        Context::CssString => ContextEscape::CssString(css_string::escape(s)),
+        Context::Url => ContextEscape::Url(url::escape(s)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+    use super::{escape, Context};
+
+    #[test]
+    fn test_escape() {
+        let s = "</script><a href=\"x\" title='y'>\\`é&\n";
+        for (context, e) in [
+            (
+                Context::Element,
+                "&lt;&#x2f;script&gt;&lt;a href=&quot;x&quot; title=&#x27;y&#x27;&gt;\\`é&amp;\n",
+            ),
+            (
+                Context::Attr,
+                "&lt;&#x2f;script&gt;&lt;a&#x20;href&#x3d;&quot;x&quot;&#x20;\
+                 title&#x3d;&#x27;y&#x27;&gt;\\&#x60;é&amp;&#xa;",
+            ),
+            (
+                Context::JsString,
+                "\\x3c\\x2fscript\\x3e\\x3ca href=\\x22x\\x22 title=\\x27y\\x27\\x3e\\\\\\x60é\\x26\\x0a",
+            ),
+            (
+                Context::CssString,
+                "\\3c \\2f script\\3e \\3c a href=\\22 x\\22  title=\\27 y\\27 \\3e \\5c \\60 é\\26 \\a ",
+            ),
+            (
+                Context::Url,
+                "%3C%2Fscript%3E%3Ca%20href%3D%22x%22%20title%3D%27y%27%3E%5C%60%C3%A9%26%0A",
+            ),
+        ] {
+            assert_eq!(escape(s, context).to_string(), e, "{:?}", context);
+            let mut out = String::new();
+            context.escaper().escape_to(s, &mut out).unwrap();
+            assert_eq!(out, e, "{:?}", context);
+        }
+
+        // The line and paragraph separators end a line of a script
+        let s = "é\u{2028}\u{2029}😀".repeat(20);
+        let e = "é\\u2028\\u2029😀".repeat(20);
+        assert_eq!(escape(&s, Context::JsString).to_string(), e);
+        assert_eq!(Context::JsString.escaper().escaped_len(&s), e.len());
+
+        let s = "a-Z_0.9~".repeat(16);
+        for context in [Context::JsString, Context::CssString, Context::Url] {
+            assert_eq!(escape(&s, context).to_string(), s);
+        }
+    }
+}
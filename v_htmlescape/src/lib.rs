@@ -6,6 +6,47 @@
 //! print!("{}", escape("foo<bar"));
 //! ```
 //!
+//! Attribute values, quoted or not, are escaped with `escape_attr`:
+//!
+//! ```
+//! use v_htmlescape::escape_attr;
+//!
+//! assert_eq!(escape_attr("a b=`c`").to_string(), "a&#x20;b&#x3d;&#x60;c&#x60;");
+//! ```
+//!
+//! `escape_numeric` and `escape_numeric_upper` write only numeric references,
+//! for the parsers that don't know the named entities:
+//!
+//! ```
+//! use v_htmlescape::{escape_numeric, escape_numeric_upper};
+//!
+//! assert_eq!(escape_numeric("<a/>").to_string(), "&#x3c;a&#x2f;&#x3e;");
+//! assert_eq!(escape_numeric_upper("<a/>").to_string(), "&#x3C;a&#x2F;&#x3E;");
+//! ```
+//!
+//! `context::escape` escapes for where the text is written in the page, as in
+//! a string of a script or a url:
+//!
+//! ```
+//! use v_htmlescape::context::{escape, Context};
+//!
+//! assert_eq!(escape("</script>", Context::JsString).to_string(), "\\x3c\\x2fscript\\x3e");
+//! assert_eq!(escape("a b/é", Context::Url).to_string(), "a%20b%2F%C3%A9");
+//! ```
+//!
+//! With the `owasp` feature, `escape_owasp` escapes a backtick too, as OWASP
+//! recommends for untrusted data.
+//!
+//! With the `alloc` feature, `unescape` decodes the entities back:
+//!
+//! ```
+//! use v_htmlescape::unescape;
+//!
+//! assert_eq!(unescape("foo&lt;bar").unwrap(), "foo<bar");
+//! ```
+//!
+#![no_std]
+
 macro_rules! build {
     ($($t:tt)*) => {
         v_escape::new!(
@@ -13,6 +54,110 @@ macro_rules! build {
             "60->&lt; || 62->&gt; || 38->&amp; || 34->&quot; || 39->&#x27; || 47->&#x2f;",
             $($t)*
         );
+
+        // Unquoted values end at whitespace, `=` and a backtick start other
+        // attributes in old browsers
+        v_escape::new!(
+            HTMLAttrEscape,
+            "60->&lt; || 62->&gt; || 38->&amp; || 34->&quot; || 39->&#x27; || 47->&#x2f; || \
+            9->&#x9; || 10->&#xa; || 12->&#xc; || 13->&#xd; || 32->&#x20; || \
+            61->&#x3d; || 96->&#x60;",
+            module = attr,
+            $($t)*
+        );
+
+        /// Escape `s` for an attribute value, quoted with `"` or `'` or unquoted
+        ///
+        /// The whitespace, `=` and a backtick are escaped too, `attr` has
+        /// the rest of the functions of this escaper
+        #[inline]
+        pub fn escape_attr(s: &str) -> HTMLAttrEscape<'_> {
+            attr::escape(s)
+        }
+
+        v_escape::new!(
+            HTMLNumericEscape,
+            "60->&#x3c; || 62->&#x3e; || 38->&#x26; || 34->&#x22; || 39->&#x27; || 47->&#x2f;",
+            module = numeric,
+            $($t)*
+        );
+
+        v_escape::new!(
+            HTMLNumericUpperEscape,
+            "60->&#x3C; || 62->&#x3E; || 38->&#x26; || 34->&#x22; || 39->&#x27; || 47->&#x2F;",
+            module = numeric_upper,
+            $($t)*
+        );
+
+        /// Escape `s` like `escape` but with hexadecimal references in place of
+        /// the named entities, in lowercase as in `&#x3c;`
+        #[inline]
+        pub fn escape_numeric(s: &str) -> HTMLNumericEscape<'_> {
+            numeric::escape(s)
+        }
+
+        /// Escape `s` like `escape_numeric` but in uppercase, as in `&#x3C;`
+        #[inline]
+        pub fn escape_numeric_upper(s: &str) -> HTMLNumericUpperEscape<'_> {
+            numeric_upper::escape(s)
+        }
+
+        // Contexts of `context::escape`, inside a string literal of a style
+        // and a url component, the one of a script is `js_string`
+        v_escape::new!(
+            CssStringEscape,
+            "0x00-0x1F->\"\\\\{:x} \" || 0x22->\"\\\\22 \" || 0x26->\"\\\\26 \" || \
+            0x27->\"\\\\27 \" || 0x28->\"\\\\28 \" || 0x29->\"\\\\29 \" || \
+            0x2F->\"\\\\2f \" || 0x3C->\"\\\\3c \" || 0x3E->\"\\\\3e \" || \
+            0x5C->\"\\\\5c \" || 0x60->\"\\\\60 \" || 0x7F->\"\\\\7f \"",
+            module = css_string,
+            $($t)*
+        );
+
+        // Everything but the unreserved characters of RFC 3986
+        v_escape::new!(
+            UrlEscape,
+            "0x00-0x2C->%{:02X} || 0x2F->%2F || 0x3A-0x40->%{:02X} || 0x5B-0x5E->%{:02X} || \
+            0x60->%60 || 0x7B-0x7D->%{:02X} || 0x7F-0xFF->%{:02X}",
+            module = url,
+            $($t)*
+        );
+
+        // https://cheatsheetseries.owasp.org/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.html
+        #[cfg(feature = "owasp")]
+        v_escape::new!(
+            HTMLOwaspEscape,
+            "60->&lt; || 62->&gt; || 38->&amp; || 34->&quot; || 39->&#x27; || 47->&#x2f; || \
+            96->&#x60;",
+            module = owasp,
+            $($t)*
+        );
+
+        /// Escape `s` with the set OWASP recommends for untrusted data in html,
+        /// the one of `escape` and a backtick
+        #[cfg(feature = "owasp")]
+        #[inline]
+        pub fn escape_owasp(s: &str) -> HTMLOwaspEscape<'_> {
+            owasp::escape(s)
+        }
+    };
+}
+
+// The characters looked for by `unescape` and `js_string`
+macro_rules! build_finders {
+    ($($t:tt)*) => {
+        // The `&` of the entities
+        v_escape::finder!(amp, "'&'", $($t)*);
+
+        // The multi-byte characters are only looked for, `js_string` writes
+        // the line and paragraph separators
+        v_escape::finder!(
+            js_chars,
+            "0x00-0x1F->\\x{:02x} || 0x22->\\x22 || 0x26->\\x26 || 0x27->\\x27 || \
+            0x2F->\\x2f || 0x3C->\\x3c || 0x3E->\\x3e || 0x5C->\\\\ || 0x60->\\x60 || \
+            0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
     };
 }
 
@@ -24,15 +169,88 @@ pub mod fallback {
 cfg_if::cfg_if! {
     if #[cfg(all(v_htmlescape_simd, v_htmlescape_avx))] {
         build!(simd = true, avx = true);
+        build_finders!(simd = true, avx = true);
     } else if #[cfg(all(v_htmlescape_simd, v_htmlescape_sse))] {
         build!(simd = true, avx = false);
+        build_finders!(simd = true, avx = false);
     } else {
         pub use self::fallback::*;
+        build_finders!(simd = false);
+    }
+}
+
+/// Escaping inside a string literal of a script, the context
+/// `Context::JsString` of `context::escape`
+pub mod js_string {
+    use core::fmt::{self, Display, Formatter, Write};
+
+    fn write_escaped(s: &str, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+        v_escape::scan::escape_found_chars(
+            s,
+            out,
+            crate::js_chars::find_first_escape_bytes,
+            crate::js_chars::escape_byte,
+            |c, out| match c {
+                // They end the line in the engines before ES2019
+                '\u{2028}' => Some(out.write_str("\\u2028")),
+                '\u{2029}' => Some(out.write_str("\\u2029")),
+                _ => None,
+            },
+        )
+    }
+
+    /// Escaped `s` of [`escape`]
+    pub struct JsStringEscape<'a> {
+        s: &'a str,
+    }
+
+    impl<'a> From<&'a str> for JsStringEscape<'a> {
+        #[inline]
+        fn from(s: &'a str) -> Self {
+            JsStringEscape { s }
+        }
     }
+
+    impl<'a> Display for JsStringEscape<'a> {
+        fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+            v_escape::pad::pad(fmt, |fmt| write_escaped(self.s, fmt))
+        }
+    }
+
+    /// Escape `s` to be written inside a string literal of a script, as
+    /// `\xHH` and the line and paragraph separators as `\u2028` and `\u2029`
+    #[inline]
+    pub fn escape(s: &str) -> JsStringEscape<'_> {
+        JsStringEscape { s }
+    }
+
+    /// Escaper of `escape`, to choose it at runtime
+    pub static ESCAPER: &dyn v_escape::Escaper =
+        &v_escape::FnEscaper(|s, out| write_escaped(s, out));
+}
+
+pub use self::js_string::JsStringEscape;
+
+pub mod context;
+#[cfg(feature = "alloc")]
+mod unescape;
+#[cfg(feature = "alloc")]
+pub use self::unescape::{unescape, unescape_lossy, unescape_stream};
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("html", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"html"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
 }
 
 #[cfg(test)]
 mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+
     #[test]
     fn test_escape() {
         use super::HTMLEscape;
@@ -62,4 +280,74 @@ mod test {
                 .repeat(10_000)
         );
     }
+
+    #[test]
+    fn test_escape_attr() {
+        use super::{attr, escape_attr};
+
+        assert_eq!(escape_attr("").to_string(), "");
+        assert_eq!(escape_attr("foo").to_string(), "foo");
+        assert_eq!(
+            escape_attr("<a\"'/&>").to_string(),
+            "&lt;a&quot;&#x27;&#x2f;&amp;&gt;"
+        );
+        assert_eq!(
+            escape_attr(" \t\n\x0C\r=`").to_string(),
+            "&#x20;&#x9;&#xa;&#xc;&#xd;&#x3d;&#x60;"
+        );
+        let s = "x onclick=`alert(1)` y='z'".repeat(64);
+        let escaped = escape_attr(&s).to_string();
+        assert!(!escaped.contains(|c: char| " \t\n=`'\"<>".contains(c)));
+        assert_eq!(crate::unescape(&escaped).unwrap(), s);
+        assert_eq!(attr::escaped_len(&s), escaped.len());
+    }
+
+    #[test]
+    fn test_escape_numeric() {
+        use super::{escape_numeric, escape_numeric_upper, numeric};
+
+        assert_eq!(escape_numeric("foo").to_string(), "foo");
+        assert_eq!(
+            escape_numeric("<>&\"'/").to_string(),
+            "&#x3c;&#x3e;&#x26;&#x22;&#x27;&#x2f;"
+        );
+        assert_eq!(
+            escape_numeric_upper("<>&\"'/").to_string(),
+            "&#x3C;&#x3E;&#x26;&#x22;&#x27;&#x2F;"
+        );
+        let s = "// my <html> is \"unsafe\" & should be 'escaped'".repeat(64);
+        let escaped = escape_numeric(&s).to_string();
+        assert_eq!(crate::unescape(&escaped).unwrap(), s);
+        assert_eq!(
+            crate::unescape(&escape_numeric_upper(&s).to_string()).unwrap(),
+            s
+        );
+        assert_eq!(numeric::escaped_len(&s), escaped.len());
+    }
+
+    #[cfg(feature = "owasp")]
+    #[test]
+    fn test_escape_owasp() {
+        use super::{escape_owasp, owasp};
+
+        assert_eq!(escape_owasp("foo").to_string(), "foo");
+        assert_eq!(
+            escape_owasp("<>&\"'/`").to_string(),
+            "&lt;&gt;&amp;&quot;&#x27;&#x2f;&#x60;"
+        );
+        let s = "<img src=`x` onerror='alert(1)'>".repeat(64);
+        let escaped = escape_owasp(&s).to_string();
+        assert!(!escaped.contains('`'));
+        assert_eq!(crate::unescape(&escaped).unwrap(), s);
+        assert_eq!(owasp::escaped_len(&s), escaped.len());
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("html").unwrap();
+        escaper.escape_to("<a>", &mut out).unwrap();
+        assert_eq!(out, "&lt;a&gt;");
+    }
 }
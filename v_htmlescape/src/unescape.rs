@@ -0,0 +1,206 @@
+//! Html entities mapped back to their characters
+//!
+//! The `&` of the entities are looked for with a finder of their own, so the
+//! runs between them are skipped with the same vector loops as `escape`
+use v_escape::{
+    unescape::{unescape_lossy_with, unescape_with, Decoded},
+    writer::Cow,
+    UnescapeError, UnescapeStream,
+};
+
+/// Longest entity, with `&` and `;`
+const MAX_LEN: usize = 32;
+
+/// Named entities, sorted by name
+static ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("apos", '\''),
+    ("gt", '>'),
+    ("lt", '<'),
+    ("nbsp", '\u{a0}'),
+    ("quot", '"'),
+];
+
+/// Character of the numeric reference `digits` in `radix`, of length `len`
+#[inline]
+fn numeric(digits: &[u8], radix: u32, len: usize) -> Decoded {
+    if digits.is_empty() {
+        return Decoded::Unknown;
+    }
+    let mut n: Option<u32> = Some(0);
+    for &d in digits {
+        let d = match char::from(d).to_digit(radix) {
+            Some(d) => d,
+            None => return Decoded::Unknown,
+        };
+        n = n.and_then(|n| n.checked_mul(radix)?.checked_add(d));
+    }
+    match n.and_then(char::from_u32).filter(|&c| c != '\0') {
+        Some(c) => Decoded::Char(c, len),
+        None => Decoded::Invalid(len),
+    }
+}
+
+/// Character of the entity starting `rest` and its length with `&` and `;`
+#[inline]
+fn decode(rest: &[u8]) -> Decoded {
+    let rest = &rest[..rest.len().min(MAX_LEN)];
+    let end = match rest.iter().position(|&b| b == b';') {
+        Some(end) => end,
+        None if rest.len() < MAX_LEN
+            && rest[1..]
+                .iter()
+                .all(|&b| b == b'#' || b.is_ascii_alphanumeric()) =>
+        {
+            return Decoded::Partial
+        }
+        None => return Decoded::Unknown,
+    };
+    match &rest[1..end] {
+        [b'#', b'x' | b'X', hex @ ..] => numeric(hex, 16, end + 1),
+        [b'#', dec @ ..] => numeric(dec, 10, end + 1),
+        name => ENTITIES
+            .binary_search_by(|(entity, _)| entity.as_bytes().cmp(name))
+            .map_or(Decoded::Unknown, |i| Decoded::Char(ENTITIES[i].1, end + 1)),
+    }
+}
+
+/// `s` with the html entities replaced by their characters, borrowing it when
+/// there is none
+///
+/// Decodes the entities `escape` writes, `&apos;`, `&nbsp;` and the decimal
+/// and hexadecimal references, like `&#60;` and `&#x3c;`, of at most 32 bytes.
+/// Any other `&`, or a reference to `U+0000` or a surrogate, is an error at its
+/// position, of [`kind`](UnescapeError::kind) `Unknown` or `Invalid`
+///
+/// ```
+/// use v_htmlescape::{escape, unescape};
+///
+/// let s = "<a href='/'>&</a>";
+/// assert_eq!(unescape(&escape(s).to_string()).unwrap(), s);
+/// assert_eq!(unescape("&#169; &#x2014;").unwrap(), "© —");
+/// assert_eq!(unescape("a & b").unwrap_err().position(), 2);
+/// ```
+#[inline]
+pub fn unescape(s: &str) -> Result<Cow<'_, str>, UnescapeError> {
+    unescape_with(s, crate::amp::find_first_escape_bytes, decode)
+}
+
+/// `s` with the html entities replaced by their characters and `U+FFFD` in
+/// place of the errors of [`unescape`]
+///
+/// An invalid reference is replaced whole, of any other `&` only the `&` is
+///
+/// ```
+/// use v_htmlescape::unescape_lossy;
+///
+/// assert_eq!(unescape_lossy("a &amp; b & c &#xD800;"), "a & b \u{FFFD} c \u{FFFD}");
+/// ```
+#[inline]
+pub fn unescape_lossy(s: &str) -> Cow<'_, str> {
+    unescape_lossy_with(s, crate::amp::find_first_escape_bytes, decode)
+}
+
+/// Stream unescaping html given in chunks, the entities can be cut between
+/// them. With [`lossy`](UnescapeStream::lossy) it writes `U+FFFD` in place
+/// of the errors like [`unescape_lossy`]
+///
+/// ```
+/// let mut stream = v_htmlescape::unescape_stream();
+/// let mut out = Vec::new();
+/// stream.feed(b"&lt;a&am", &mut out).unwrap();
+/// assert_eq!(out, b"<a");
+/// stream.feed(b"p;", &mut out).unwrap();
+/// stream.finish(&mut out).unwrap();
+/// assert_eq!(out, b"<a&");
+/// ```
+#[inline]
+pub fn unescape_stream() -> UnescapeStream {
+    UnescapeStream::new(crate::amp::find_first_escape_bytes, decode)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{borrow::Cow, prelude::v1::*};
+    use super::{unescape, unescape_lossy, unescape_stream};
+    use v_escape::UnescapeErrorKind;
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(unescape(""), Ok(Cow::Borrowed(""))));
+        assert!(matches!(unescape("foo ✓"), Ok(Cow::Borrowed("foo ✓"))));
+        assert_eq!(
+            unescape("&lt;&gt;&amp;&quot;&#x27;&#x2f;&apos;&nbsp;").unwrap(),
+            "<>&\"'/'\u{a0}"
+        );
+        assert_eq!(unescape("&#60;&#x3C;&#X3c;&#128512;").unwrap(), "<<<😀");
+        assert_eq!(unescape("é&amp;lt;✓").unwrap(), "é&lt;✓");
+
+        use UnescapeErrorKind::*;
+        for (s, position, kind) in [
+            ("&", 0, Incomplete),
+            ("a&lt", 1, Incomplete),
+            ("&lt;&foo;", 4, Unknown),
+            ("&#;", 0, Unknown),
+            ("&#x;", 0, Unknown),
+            ("&#0;", 0, Invalid),
+            ("&#xD800;", 0, Invalid),
+            ("&#x110000;", 0, Invalid),
+            ("&#99999999999;", 0, Invalid),
+            ("&#12a;", 0, Unknown),
+            ("&LT;", 0, Unknown),
+            ("& ", 0, Unknown),
+            ("&#0000000000000000000000000000060;", 0, Unknown),
+        ] {
+            let err = unescape(s).unwrap_err();
+            assert_eq!((err.position(), err.kind()), (position, kind), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_unescape_lossy() {
+        assert!(matches!(unescape_lossy("foo ✓"), Cow::Borrowed("foo ✓")));
+        assert_eq!(unescape_lossy("&lt;a&gt;"), "<a>");
+        assert_eq!(unescape_lossy("a & b"), "a \u{FFFD} b");
+        assert_eq!(
+            unescape_lossy("&foo;&#0;&#xD800;"),
+            "\u{FFFD}foo;\u{FFFD}\u{FFFD}"
+        );
+        assert_eq!(unescape_lossy("é&am"), "é\u{FFFD}");
+    }
+
+    #[test]
+    fn test_unescape_stream() {
+        let s = "&lt;p class=&quot;&#x2014;&#128512;&quot;&gt;é&amp;✓&#60;";
+        let expected = unescape(s).unwrap();
+        for size in 1..s.len() {
+            let mut stream = unescape_stream();
+            let mut out = Vec::new();
+            for chunk in s.as_bytes().chunks(size) {
+                stream.feed(chunk, &mut out).unwrap();
+            }
+            stream.finish(&mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected, "{}", size);
+        }
+
+        let mut stream = unescape_stream();
+        let mut out = Vec::new();
+        stream.feed(b"a&lt;&a", &mut out).unwrap();
+        stream.feed(b"m", &mut out).unwrap();
+        assert_eq!(out, b"a<");
+        assert_eq!(stream.finish(&mut out).unwrap_err().position(), 5);
+
+        let mut stream = unescape_stream();
+        stream.feed(b"ab", &mut out).unwrap();
+        assert_eq!(stream.feed(b"c&a b", &mut out).unwrap_err().position(), 3);
+
+        let mut stream = unescape_stream().lossy();
+        let mut out = Vec::new();
+        stream.feed(b"a&b &#xD8", &mut out).unwrap();
+        stream.feed(b"00;&l", &mut out).unwrap();
+        stream.finish(&mut out).unwrap();
+        assert_eq!(out, "a\u{FFFD}b \u{FFFD}\u{FFFD}".as_bytes());
+    }
+}
@@ -166,3 +166,36 @@ quis lacus at, gravida maximus elit. Duis tristique, nisl nullam.
         string_long_escaped
     );
 }
+
+#[test]
+fn test_unescape() {
+    use v_htmlescape::unescape;
+
+    let escapes = "<>&\"'/";
+    let escaped = "&lt;&gt;&amp;&quot;&#x27;&#x2f;";
+    let string_long: &str = &"foobar".repeat(1024);
+
+    assert_eq!(unescape(escaped).unwrap(), escapes);
+    assert_eq!(
+        unescape(&escaped.repeat(1024)).unwrap(),
+        escapes.repeat(1024)
+    );
+    assert_eq!(unescape(string_long).unwrap(), string_long);
+    assert_eq!(
+        unescape(&[string_long, "&lt;", string_long].join("")).unwrap(),
+        [string_long, "<", string_long].join("")
+    );
+    assert_eq!(
+        unescape(&[string_long, "&"].join(""))
+            .unwrap_err()
+            .position(),
+        string_long.len()
+    );
+    for len in 0..100 {
+        let s: String = "a<&é\"'/".chars().cycle().take(len).collect();
+        assert_eq!(
+            unescape(&HTMLEscape::from(s.as_str()).to_string()).unwrap(),
+            s
+        );
+    }
+}
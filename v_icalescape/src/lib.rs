@@ -0,0 +1,90 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_icalescape::escape;
+//!
+//! assert_eq!(
+//!     escape("Lunch; bring salad, drinks\r\nRoom 2").to_string(),
+//!     "Lunch\\; bring salad\\, drinks\\nRoom 2"
+//! );
+//! ```
+//!
+//! `escape` is for a value of type TEXT of iCalendar, RFC 5545, and of vCard,
+//! RFC 6350, as a `SUMMARY`, a `DESCRIPTION` or a `NOTE`. `\`, `;` and `,` are
+//! escaped with a `\`, and a line end as `\n`, `CRLF` as a single one.
+//!
+//! TEXT can't have the control characters but the tab, the other ones and
+//! `DEL` are removed:
+//!
+//! ```rust
+//! use v_icalescape::escape;
+//!
+//! assert_eq!(escape("a\u{0}\tb\u{1b}\u{7f}").to_string(), "a\tb");
+//! ```
+//!
+//! The content line the value is written in is folded after 75 octets by its
+//! writer, the escaping doesn't know where the value starts in it.
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            ICalEscape,
+            "0x00-0x08->\"\" || 0x0A->\\n || 0x0B-0x0C->\"\" || 0x0D->\\n || \
+             0x0E-0x1F->\"\" || 0x2C->\\, || 0x3B->\\; || 0x5C->\\\\ || 0x7F->\"\" || \
+             \"\\r\\n\" -> \"\\\\n\"",
+            $($t)*
+        );
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_icalescape_simd, v_icalescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_icalescape_simd, v_icalescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("ical", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"ical"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::escape;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a:b \"c\"\té").to_string(), "a:b \"c\"\té");
+        assert_eq!(escape("\\;,").to_string(), "\\\\\\;\\,");
+        assert_eq!(escape("a\nb\r\nc\rd").to_string(), "a\\nb\\nc\\nd");
+        assert_eq!(escape("\r\r\n\n").to_string(), "\\n\\n\\n");
+        assert_eq!(escape("\0a\u{b}\u{c}b\u{1f}\u{7f}").to_string(), "ab");
+        assert_eq!(format!("{:>4}", escape(";")), "  \\;");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("ical").unwrap();
+        escaper.escape_to("a,b", &mut out).unwrap();
+        assert_eq!(out, "a\\,b");
+    }
+}
@@ -0,0 +1,26 @@
+use v_icalescape::{escape, ICalEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(ICalEscape::from("a\nb").to_string(), "a\\nb");
+
+    let all: String = (0..0x80u8)
+        .map(char::from)
+        .chain("é😀\r\n".chars())
+        .collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s
+            .replace("\r\n", "\n")
+            .chars()
+            .map(|c| match c {
+                '\n' | '\r' => "\\n".into(),
+                '\t' => c.to_string(),
+                c if c.is_ascii_control() => String::new(),
+                '\\' | ';' | ',' => format!("\\{}", c),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+    }
+}
@@ -0,0 +1,188 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_jsescape::escape;
+//!
+//! assert_eq!(escape("it's \"</script>\"").to_string(), "it\\'s \\\"\\x3C/script>\\\"");
+//! ```
+//!
+//! `escape` is for the text between the quotes of a string literal, `'` or
+//! `"`, and `escape_template` for the text of a template literal, between
+//! backticks:
+//!
+//! * `\`, `'` and `"` are escaped with a `\`, and the backtick and a `$`
+//!   before `{` too in a template literal
+//! * the control characters as `\b`, `\t`, `\n`, `\v`, `\f`, `\r` or `\xXX`,
+//!   and the line and paragraph separators, `U+2028` and `U+2029`, as
+//!   `\u2028` and `\u2029`, they end the line in older engines
+//! * `<` as `\x3C`, so the text of an inline `<script>` can't have a
+//!   `</script` that ends it nor a `<!--` that changes how it is read
+//!
+//! ```rust
+//! use v_jsescape::escape_template;
+//!
+//! assert_eq!(escape_template("`${a}` $b").to_string(), "\\`\\${a}\\` $b");
+//! ```
+//!
+//! The first bytes of the non-ascii characters are looked for with the ascii
+//! escaped characters, so the ascii runs between them are skipped with the
+//! simd loops of the escapers.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter, Write};
+
+// The multi-byte characters are only looked for, `write_escaped` writes them,
+// and `$` of `template` too as it depends on the byte after it
+macro_rules! build_finders {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            string,
+            "0x00-0x07->\\x{:02X} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || 0x0B->\\v || \
+             0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\x{:02X} || 0x22->\\\" || 0x27->\\' || \
+             0x3C->\\x3C || 0x5C->\\\\ || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+        v_escape::finder!(
+            template,
+            "0x00-0x07->\\x{:02X} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || 0x0B->\\v || \
+             0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\x{:02X} || 0x22->\\\" || 0x24 || \
+             0x27->\\' || 0x3C->\\x3C || 0x5C->\\\\ || 0x60->\\` || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_jsescape_simd, v_jsescape_avx))] {
+        build_finders!(simd = true, avx = true);
+    } else if #[cfg(all(v_jsescape_simd, v_jsescape_sse))] {
+        build_finders!(simd = true, avx = false);
+    } else {
+        build_finders!(simd = false);
+    }
+}
+
+fn write_escaped(s: &str, template: bool, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+    let find: fn(&[u8]) -> Option<usize> = if template {
+        template::find_first_escape_bytes
+    } else {
+        string::find_first_escape_bytes
+    };
+    v_escape::scan::escape_found(s, out, find, |s, i, out| {
+        let c = v_escape::scan::char_at(s, i);
+        let end = i + c.len_utf8();
+        out.write_str(match c {
+            // Only a substitution when it is before `{`
+            '$' if s.as_bytes().get(end) == Some(&b'{') => "\\$",
+            '\u{2028}' => "\\u2028",
+            '\u{2029}' => "\\u2029",
+            // The quotes of `string` are the same ones, the other characters
+            // are written as they are
+            _ => template::escape_byte(s.as_bytes()[i]).unwrap_or(&s[i..end]),
+        })?;
+        Ok(end)
+    })
+}
+
+macro_rules! escape_struct {
+    ($name:ident, $template:expr) => {
+        impl<'a> From<&'a str> for $name<'a> {
+            #[inline]
+            fn from(s: &'a str) -> Self {
+                $name { s }
+            }
+        }
+
+        impl<'a> Display for $name<'a> {
+            fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+                v_escape::pad::pad(fmt, |fmt| write_escaped(self.s, $template, fmt))
+            }
+        }
+    };
+}
+
+/// Escaped `s` of [`escape`]
+pub struct JSEscape<'a> {
+    s: &'a str,
+}
+
+escape_struct!(JSEscape, false);
+
+/// Escaped `s` of [`escape_template`]
+pub struct JSTemplateEscape<'a> {
+    s: &'a str,
+}
+
+escape_struct!(JSTemplateEscape, true);
+
+/// Escape `s` to be written between the quotes of a string literal
+#[inline]
+pub fn escape(s: &str) -> JSEscape<'_> {
+    JSEscape { s }
+}
+
+/// Escape `s` to be written between the backticks of a template literal
+#[inline]
+pub fn escape_template(s: &str) -> JSTemplateEscape<'_> {
+    JSTemplateEscape { s }
+}
+
+/// Escaper of `escape`, to choose it at runtime
+pub static ESCAPER: &dyn v_escape::Escaper =
+    &v_escape::FnEscaper(|s, out| write_escaped(s, false, out));
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("js", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"js"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_template, ESCAPER};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("foo `${bar}`").to_string(), "foo `${bar}`");
+        assert_eq!(escape("'\"\\").to_string(), "\\'\\\"\\\\");
+        assert_eq!(
+            escape("\0\u{8}\t\n\u{b}\u{c}\r\u{1b}\u{7f}").to_string(),
+            "\\x00\\b\\t\\n\\v\\f\\r\\x1B\u{7f}"
+        );
+        assert_eq!(
+            escape("<!--</SCRIPT>é\u{2028}\u{2029}😀").to_string(),
+            "\\x3C!--\\x3C/SCRIPT>é\\u2028\\u2029😀"
+        );
+        assert_eq!(format!("{:>4}", escape("\n")), "  \\n");
+    }
+
+    #[test]
+    fn test_escape_template() {
+        assert_eq!(escape_template("").to_string(), "");
+        assert_eq!(escape_template("$").to_string(), "$");
+        assert_eq!(escape_template("${").to_string(), "\\${");
+        assert_eq!(escape_template("$${x}$").to_string(), "$\\${x}$");
+        assert_eq!(
+            escape_template("`'\"\n</script>\u{2028}").to_string(),
+            "\\`\\'\\\"\\n\\x3C/script>\\u2028"
+        );
+    }
+
+    #[test]
+    fn test_escaper() {
+        let s: String = "a'\u{2028}\u{1}é<".repeat(20);
+        let mut out = String::new();
+        ESCAPER.escape_to(&s, &mut out).unwrap();
+        assert_eq!(out, escape(&s).to_string());
+        assert_eq!(ESCAPER.escaped_len(&s), out.len());
+
+        super::register();
+        assert!(v_escape::registry().get("js").is_some());
+    }
+}
@@ -0,0 +1,107 @@
+//! Json escaping of the non-ascii characters too, as `\uXXXX`
+//!
+//! The first bytes of the non-ascii characters are looked for with the other
+//! escaped characters, by an escaper of their own, so the runs between them are
+//! skipped with the same vector loops as `escape`
+use core::fmt::{self, Display, Formatter};
+
+// The multi-byte characters are only looked for, `write_escaped` writes them
+macro_rules! build_escaped {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            escaped,
+            "0x00-0x07->\\u00{:02x} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+            0x0B->\\u000b || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\u00{:02x} || \
+            0x22->\\\" || 0x5C->\\\\ || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_jsonescape_simd, v_jsonescape_avx))] {
+        build_escaped!(simd = true, avx = true);
+    } else if #[cfg(all(v_jsonescape_simd, v_jsonescape_sse))] {
+        build_escaped!(simd = true, avx = false);
+    } else {
+        build_escaped!(simd = false);
+    }
+}
+
+/// Escaped `s` of [`escape_ascii`]
+pub struct JSONAsciiEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> Display for JSONAsciiEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        v_escape::pad::pad(fmt, |fmt| {
+            v_escape::scan::escape_found_chars(
+                self.s,
+                fmt,
+                escaped::find_first_escape_bytes,
+                escaped::escape_byte,
+                |c, fmt| {
+                    let mut units = [0; 2];
+                    Some(
+                        c.encode_utf16(&mut units)
+                            .iter()
+                            .try_for_each(|unit| write!(fmt, "\\u{:04x}", unit)),
+                    )
+                },
+            )
+        })
+    }
+}
+
+/// Escape `s` like `escape` and every non-ascii character as `\uXXXX`, a
+/// surrogate pair past `U+FFFF`, so the output is ascii only
+///
+/// It is the output of `ensure_ascii=True` of Python and of the ascii
+/// formatter of serde_json
+///
+/// ```
+/// use v_jsonescape::escape_ascii;
+///
+/// assert_eq!(escape_ascii("é\n😀").to_string(), "\\u00e9\\n\\ud83d\\ude00");
+/// ```
+#[inline]
+pub fn escape_ascii(s: &str) -> JSONAsciiEscape<'_> {
+    JSONAsciiEscape { s }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::escape_ascii;
+
+    #[test]
+    fn test_escape_ascii() {
+        assert_eq!(escape_ascii("").to_string(), "");
+        assert_eq!(escape_ascii("foo").to_string(), "foo");
+        assert_eq!(
+            escape_ascii("\"a\\\u{8}\u{1f}/\u{7f}").to_string(),
+            "\\\"a\\\\\\b\\u001f/\u{7f}"
+        );
+        assert_eq!(
+            escape_ascii("\u{80}\u{7FF}\u{800}\u{FFFF}\u{10000}\u{10FFFF}").to_string(),
+            "\\u0080\\u07ff\\u0800\\uffff\\ud800\\udc00\\udbff\\udfff"
+        );
+        assert_eq!(format!("{:>8}", escape_ascii("é")), "  \\u00e9");
+
+        // Past the widest vector, every output is ascii and the same as
+        // escaping character by character
+        let s: String = "aé\"😀\n✓".chars().cycle().take(300).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| escape_ascii(&c.to_string()).to_string())
+            .collect();
+        let escaped = escape_ascii(&s).to_string();
+        assert!(escaped.is_ascii());
+        assert_eq!(escaped, expected);
+        #[cfg(feature = "alloc")]
+        assert_eq!(crate::unescape(&escaped).unwrap(), s);
+    }
+}
@@ -6,6 +6,56 @@
 //! print!("{}", escape("foo<bar"));
 //! ```
 //!
+//! `escape_unicode` writes every control character as `\u00XX`, without the
+//! short escapes like `\n`:
+//!
+//! ```
+//! use v_jsonescape::escape_unicode;
+//!
+//! assert_eq!(escape_unicode("a\n\"b\"").to_string(), "a\\u000a\\\"b\\\"");
+//! ```
+//!
+//! `escape_ascii` writes every non-ascii character as `\uXXXX` too, like
+//! `ensure_ascii=True` of Python:
+//!
+//! ```
+//! use v_jsonescape::escape_ascii;
+//!
+//! assert_eq!(escape_ascii("é😀").to_string(), "\\u00e9\\ud83d\\ude00");
+//! ```
+//!
+//! `escape_script_safe` escapes `/` as `\/` too, so a string can't end the
+//! `<script>` of html it is written in:
+//!
+//! ```
+//! use v_jsonescape::escape_script_safe;
+//!
+//! assert_eq!(escape_script_safe("</script>").to_string(), "<\\/script>");
+//! ```
+//!
+//! With the `std` feature, `write_json_string` writes a string with its
+//! quotes to `io::Write`, and `NdjsonWriter` and `JsonArrayWriter` write
+//! strings a line at a time or in an array:
+//!
+//! ```
+//! let mut out = Vec::new();
+//! v_jsonescape::write_json_string(&mut out, "a\nb").unwrap();
+//! assert_eq!(out, b"\"a\\nb\"");
+//! ```
+//!
+//! With the `serde_json` feature, `ser::format_escaped_str` writes a string
+//! through a formatter of `serde_json`.
+//!
+//! With the `alloc` feature, `unescape` decodes the escape sequences back:
+//!
+//! ```
+//! use v_jsonescape::unescape;
+//!
+//! assert_eq!(unescape("\\\"foo\\\"").unwrap(), "\"foo\"");
+//! ```
+//!
+#![no_std]
+
 // https://tools.ietf.org/id/draft-ietf-json-rfc4627bis-09.html#rfc.section.7
 // https://github.com/serde-rs/json/blob/master/src/ser.rs#L2113-L2143
 macro_rules! build {
@@ -48,6 +98,39 @@ macro_rules! build {
             0x5C->\\\\",
             $($t)*
         );
+
+        // Every control character as `\u00XX`, for the parsers that only
+        // know those
+        v_escape::new!(
+            JSONUnicodeEscape,
+            "0x00-0x1F->\\u00{:02x} || 0x22->\\\" || 0x5C->\\\\",
+            module = unicode,
+            $($t)*
+        );
+
+        /// Escape `s` like `escape` but with every control character as
+        /// `\u00XX`, as in `\u000a` for a newline
+        #[inline]
+        pub fn escape_unicode(s: &str) -> JSONUnicodeEscape<'_> {
+            unicode::escape(s)
+        }
+
+        // A `</script>` in a string would end the script it is written in
+        v_escape::new!(
+            JSONScriptSafeEscape,
+            "0x00-0x07->\\u00{:02x} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+            0x0B->\\u000b || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\u00{:02x} || \
+            0x22->\\\" || 0x2F->\\/ || 0x5C->\\\\",
+            module = script_safe,
+            $($t)*
+        );
+
+        /// Escape `s` like `escape` and `/` as `\/` too, to write it in a
+        /// `<script>` of html
+        #[inline]
+        pub fn escape_script_safe(s: &str) -> JSONScriptSafeEscape<'_> {
+            script_safe::escape(s)
+        }
     };
 }
 
@@ -66,8 +149,36 @@ cfg_if::cfg_if! {
     }
 }
 
+mod ascii;
+pub use self::ascii::{escape_ascii, JSONAsciiEscape};
+
+#[cfg(feature = "serde_json")]
+pub mod ser;
+
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub use self::writer::{write_json_string, JsonArrayWriter, NdjsonWriter};
+
+#[cfg(feature = "alloc")]
+mod unescape;
+#[cfg(feature = "alloc")]
+pub use self::unescape::{unescape, unescape_lossy, unescape_stream};
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("json", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"json"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
 #[cfg(test)]
 mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+
     #[test]
     fn test_escape() {
         use super::*;
@@ -92,7 +203,7 @@ mod test {
             assert_eq!(escape(&c.to_string()).to_string(), *e);
         }
         let tests = tests
-            .into_iter()
+            .iter()
             .fold((String::new(), String::new()), |mut acc, (c, e)| {
                 acc.0.push(*c);
                 acc.1.push_str(e);
@@ -113,4 +224,50 @@ mod test {
             assert_eq!(escape(c).to_string(), *e);
         }
     }
+
+    #[test]
+    fn test_escape_unicode() {
+        use super::*;
+
+        for b in 0..0x20u8 {
+            let c = char::from(b);
+            let e = format!("\\u{:04x}", b);
+            assert_eq!(escape_unicode(&c.to_string()).to_string(), e);
+            assert_eq!(unicode::escape_char(c).to_string(), e);
+        }
+        assert_eq!(
+            escape_unicode("\"a\\b\"/\u{3A3}\x7F").to_string(),
+            "\\\"a\\\\b\\\"/\u{3A3}\x7F"
+        );
+        let s = "\t\"é\n".repeat(40);
+        assert_eq!(
+            escape_unicode(&s).to_string(),
+            "\\u0009\\\"é\\u000a".repeat(40)
+        );
+    }
+
+    #[test]
+    fn test_escape_script_safe() {
+        use super::*;
+
+        assert_eq!(escape_script_safe("a/b").to_string(), "a\\/b");
+        assert_eq!(script_safe::escape_char('/').to_string(), "\\/");
+        assert_eq!(escape("a/b").to_string(), "a/b");
+
+        let s = "<p>\"é\n</p>".repeat(20);
+        let escaped = escape_script_safe(&s).to_string();
+        assert_eq!(escaped, "<p>\\\"é\\n<\\/p>".repeat(20));
+        assert!(!escaped.contains("</"));
+        #[cfg(feature = "alloc")]
+        assert_eq!(unescape(&escaped).unwrap(), s);
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("json").unwrap();
+        escaper.escape_to("\"a\"", &mut out).unwrap();
+        assert_eq!(out, "\\\"a\\\"");
+    }
 }
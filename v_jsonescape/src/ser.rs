@@ -0,0 +1,93 @@
+//! Json strings written through a `serde_json` formatter, looking for the
+//! escapes with the vector loops of `escape`
+//!
+//! The `Serializer` of `serde_json` looks for the escapes of a string itself
+//! and gives its formatter the runs and the escapes one by one, so a formatter
+//! can't change how a string is scanned. [`format_escaped_str`] writes a string
+//! to any formatter with the calls the `Serializer` makes, for the
+//! `Serialize` implementations and formatters that write their strings
+//! themselves
+extern crate std;
+
+use self::std::io;
+use serde_json::ser::{CharEscape, Formatter};
+
+/// Escape of `b`, one of the bytes `escape` escapes
+#[inline]
+fn char_escape(b: u8) -> CharEscape {
+    match b {
+        b'"' => CharEscape::Quote,
+        b'\\' => CharEscape::ReverseSolidus,
+        0x08 => CharEscape::Backspace,
+        0x0C => CharEscape::FormFeed,
+        b'\n' => CharEscape::LineFeed,
+        b'\r' => CharEscape::CarriageReturn,
+        b'\t' => CharEscape::Tab,
+        b => CharEscape::AsciiControl(b),
+    }
+}
+
+/// Write `s` as a json string to `writer` through `formatter`
+///
+/// It calls `begin_string`, `write_string_fragment` with the runs that aren't
+/// escaped, `write_char_escape` with every escape and `end_string`, like
+/// `serde_json` does, so the output is the same as serializing `s`
+///
+/// ```
+/// use serde_json::ser::{CompactFormatter, PrettyFormatter};
+/// use v_jsonescape::ser::format_escaped_str;
+///
+/// let mut out = Vec::new();
+/// format_escaped_str(&mut out, &mut CompactFormatter, "a\n\"b\"").unwrap();
+/// assert_eq!(out, br#""a\n\"b\"""#);
+/// assert_eq!(out, serde_json::to_vec("a\n\"b\"").unwrap());
+/// ```
+pub fn format_escaped_str<W, F>(writer: &mut W, formatter: &mut F, s: &str) -> io::Result<()>
+where
+    W: ?Sized + io::Write,
+    F: ?Sized + Formatter,
+{
+    formatter.begin_string(writer)?;
+    let mut start = 0;
+    for (i, _) in crate::escape_positions(s) {
+        if start < i {
+            formatter.write_string_fragment(writer, &s[start..i])?;
+        }
+        formatter.write_char_escape(writer, char_escape(s.as_bytes()[i]))?;
+        start = i + 1;
+    }
+    if start < s.len() {
+        formatter.write_string_fragment(writer, &s[start..])?;
+    }
+    formatter.end_string(writer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::std::prelude::v1::*;
+    use super::*;
+    use serde_json::ser::{CompactFormatter, PrettyFormatter};
+
+    #[test]
+    fn test_format_escaped_str() {
+        let mut inputs: Vec<String> = (0..0x80u8).map(|b| char::from(b).to_string()).collect();
+        for len in [0, 1, 15, 16, 31, 32, 33, 64, 200] {
+            inputs.push(
+                "a\"\\é\n\x01\x1F/😀\r\t"
+                    .chars()
+                    .cycle()
+                    .take(len)
+                    .collect(),
+            );
+        }
+        for s in &inputs {
+            let expected = serde_json::to_vec(s).unwrap();
+            let mut out = Vec::new();
+            format_escaped_str(&mut out, &mut CompactFormatter, s).unwrap();
+            assert_eq!(out, expected, "{:?}", s);
+            out.clear();
+            format_escaped_str(&mut out, &mut PrettyFormatter::new(), s).unwrap();
+            assert_eq!(out, expected, "{:?}", s);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Json escape sequences mapped back to their characters
+//!
+//! The `\` of the sequences are looked for with an escaper of their own, so the
+//! runs between them are skipped with the same vector loops as `escape`
+use v_escape::{
+    unescape::{unescape_lossy_with, unescape_with, Decoded},
+    writer::Cow,
+    UnescapeError, UnescapeStream,
+};
+
+#[allow(dead_code)]
+mod backslash {
+    macro_rules! build_backslash {
+        ($($t:tt)*) => {
+            v_escape::new!(Backslash, "0x5C->\\\\", $($t)*);
+        };
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(v_jsonescape_simd, v_jsonescape_avx))] {
+            build_backslash!(simd = true, avx = true);
+        } else if #[cfg(all(v_jsonescape_simd, v_jsonescape_sse))] {
+            build_backslash!(simd = true, avx = false);
+        } else {
+            build_backslash!(simd = false);
+        }
+    }
+}
+
+/// Code unit of the `\uXXXX` starting `rest`
+#[inline]
+fn code_unit(rest: &[u8]) -> Result<u16, Decoded> {
+    let mut unit = 0;
+    for i in 0..6 {
+        let b = *rest.get(i).ok_or(Decoded::Partial)?;
+        match (i, b) {
+            (0, b'\\') | (1, b'u') => (),
+            (2..=5, _) => {
+                let d = char::from(b).to_digit(16).ok_or(Decoded::Unknown)?;
+                unit = unit << 4 | d as u16;
+            }
+            _ => return Err(Decoded::Unknown),
+        }
+    }
+    Ok(unit)
+}
+
+/// Character of the `\uXXXX` starting `rest`, or of the surrogate pair
+#[inline]
+fn unicode(rest: &[u8]) -> Result<Decoded, Decoded> {
+    let unit = code_unit(rest)?;
+    if !(0xD800..=0xDBFF).contains(&unit) {
+        // A lone low surrogate isn't a char
+        return Ok(char::from_u32(unit.into()).map_or(Decoded::Invalid(6), |c| Decoded::Char(c, 6)));
+    }
+
+    // A high surrogate has to be followed by a low one
+    let low = match code_unit(&rest[6..]) {
+        Ok(low @ 0xDC00..=0xDFFF) => low,
+        Ok(_) | Err(Decoded::Unknown) => return Err(Decoded::Invalid(6)),
+        Err(read) => return Err(read),
+    };
+    let c = 0x10000 + ((u32::from(unit) - 0xD800) << 10 | (u32::from(low) - 0xDC00));
+    Ok(char::from_u32(c).map_or(Decoded::Invalid(12), |c| Decoded::Char(c, 12)))
+}
+
+/// Character of the escape sequence starting `rest` and its length
+#[inline]
+fn decode(rest: &[u8]) -> Decoded {
+    let c = match rest.get(1) {
+        Some(b'"') => '"',
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\x08',
+        Some(b'f') => '\x0C',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => return unicode(rest).unwrap_or_else(|read| read),
+        Some(_) => return Decoded::Unknown,
+        None => return Decoded::Partial,
+    };
+    Decoded::Char(c, 2)
+}
+
+/// `s` with the json escape sequences replaced by their characters, borrowing
+/// it when there is none
+///
+/// Decodes the short escapes, like `\n` and `\"`, and the `\uXXXX` ones, a
+/// surrogate pair being one character. Any other `\`, or a lone surrogate, is
+/// an error at its position, of [`kind`](UnescapeError::kind) `Unknown` or
+/// `Invalid`
+///
+/// ```
+/// use v_jsonescape::{escape, unescape};
+///
+/// let s = "\"quoted\"\n\tC:\\";
+/// assert_eq!(unescape(&escape(s).to_string()).unwrap(), s);
+/// assert_eq!(unescape("\\u00e9 \\ud83d\\ude00").unwrap(), "é 😀");
+/// assert_eq!(unescape("a \\x").unwrap_err().position(), 2);
+/// ```
+#[inline]
+pub fn unescape(s: &str) -> Result<Cow<'_, str>, UnescapeError> {
+    unescape_with(s, backslash::find_first_escape_bytes, decode)
+}
+
+/// `s` with the json escape sequences replaced by their characters and
+/// `U+FFFD` in place of the errors of [`unescape`]
+///
+/// A lone surrogate is replaced whole, of any other `\` only the `\` is
+///
+/// ```
+/// use v_jsonescape::unescape_lossy;
+///
+/// assert_eq!(unescape_lossy("\\ud83d \\x"), "\u{FFFD} \u{FFFD}x");
+/// ```
+#[inline]
+pub fn unescape_lossy(s: &str) -> Cow<'_, str> {
+    unescape_lossy_with(s, backslash::find_first_escape_bytes, decode)
+}
+
+/// Stream unescaping json given in chunks, the escape sequences can be cut
+/// between them. With [`lossy`](UnescapeStream::lossy) it writes `U+FFFD` in
+/// place of the errors like [`unescape_lossy`]
+///
+/// ```
+/// let mut stream = v_jsonescape::unescape_stream();
+/// let mut out = Vec::new();
+/// stream.feed(b"a\\u00", &mut out).unwrap();
+/// assert_eq!(out, b"a");
+/// stream.feed(b"3c", &mut out).unwrap();
+/// stream.finish(&mut out).unwrap();
+/// assert_eq!(out, b"a<");
+/// ```
+#[inline]
+pub fn unescape_stream() -> UnescapeStream {
+    UnescapeStream::new(backslash::find_first_escape_bytes, decode)
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{borrow::Cow, prelude::v1::*};
+    use super::{unescape, unescape_lossy, unescape_stream};
+    use v_escape::UnescapeErrorKind;
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(unescape(""), Ok(Cow::Borrowed(""))));
+        assert!(matches!(unescape("foo ✓"), Ok(Cow::Borrowed("foo ✓"))));
+        assert_eq!(
+            unescape("\\\"\\\\\\/\\b\\f\\n\\r\\t").unwrap(),
+            "\"\\/\x08\x0C\n\r\t"
+        );
+        assert_eq!(
+            unescape("\\u0000\\u001f\\u00E9\\uffff").unwrap(),
+            "\0\x1F\u{e9}\u{ffff}"
+        );
+        assert_eq!(
+            unescape("\\ud83d\\ude00\\uDBFF\\uDFFF").unwrap(),
+            "😀\u{10ffff}"
+        );
+        assert_eq!(unescape("é\\\\n✓").unwrap(), "é\\n✓");
+
+        use UnescapeErrorKind::*;
+        for (s, position, kind) in [
+            ("\\", 0, Incomplete),
+            ("a\\x", 1, Unknown),
+            ("\\n\\u12", 2, Incomplete),
+            ("\\u12g4", 0, Unknown),
+            ("\\u+123", 0, Unknown),
+            ("\\ud83d", 0, Incomplete),
+            ("\\ud83d\\u", 0, Incomplete),
+            ("\\ud83dx", 0, Invalid),
+            ("\\ud83d\\n", 0, Invalid),
+            ("\\ud83d\\ud83d", 0, Invalid),
+            ("\\ude00", 0, Invalid),
+            ("\\U0041", 0, Unknown),
+        ] {
+            let err = unescape(s).unwrap_err();
+            assert_eq!((err.position(), err.kind()), (position, kind), "{}", s);
+        }
+
+        for len in 0..100 {
+            let s: String = "a\"\\é\n\x01😀".chars().cycle().take(len).collect();
+            assert_eq!(unescape(&crate::escape(&s).to_string()).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_unescape_lossy() {
+        assert!(matches!(unescape_lossy("foo ✓"), Cow::Borrowed("foo ✓")));
+        assert_eq!(unescape_lossy("\\\"a\\\""), "\"a\"");
+        assert_eq!(unescape_lossy("\\ud83d\\ud83d\\ude00"), "\u{FFFD}😀");
+        assert_eq!(unescape_lossy("\\ude00\\n"), "\u{FFFD}\n");
+        assert_eq!(unescape_lossy("a\\q é\\u00"), "a\u{FFFD}q é\u{FFFD}");
+    }
+
+    #[test]
+    fn test_unescape_stream() {
+        let s = "\\\"key\\\": \\ud83d\\ude00é\\n\\u003c✓\\\\";
+        let expected = unescape(s).unwrap();
+        for size in 1..s.len() {
+            let mut stream = unescape_stream();
+            let mut out = Vec::new();
+            for chunk in s.as_bytes().chunks(size) {
+                stream.feed(chunk, &mut out).unwrap();
+            }
+            stream.finish(&mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected, "{}", size);
+        }
+
+        let mut stream = unescape_stream();
+        let mut out = Vec::new();
+        stream.feed(b"a\\n\\ud83d", &mut out).unwrap();
+        stream.feed(b"\\u", &mut out).unwrap();
+        assert_eq!(out, b"a\n");
+        assert_eq!(stream.finish(&mut out).unwrap_err().position(), 3);
+
+        let mut stream = unescape_stream();
+        stream.feed(b"ab", &mut out).unwrap();
+        assert_eq!(stream.feed(b"c\\x", &mut out).unwrap_err().position(), 3);
+
+        let mut stream = unescape_stream().lossy();
+        let mut out = Vec::new();
+        stream.feed(b"\\x\\ud8", &mut out).unwrap();
+        stream.feed(b"3d\\n\\u00", &mut out).unwrap();
+        stream.finish(&mut out).unwrap();
+        assert_eq!(out, "\u{FFFD}x\u{FFFD}\n\u{FFFD}".as_bytes());
+    }
+}
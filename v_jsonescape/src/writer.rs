@@ -0,0 +1,134 @@
+//! Json strings written whole, quotes included, to `io::Write`
+//!
+//! The escaped runs are written as they are found, without an intermediate
+//! buffer, by `escape_to_writer`
+extern crate std;
+
+use self::std::io::{self, Write};
+
+/// Write `s` as a json string to `out`, escaped between quotes
+///
+/// ```
+/// let mut out = Vec::new();
+/// v_jsonescape::write_json_string(&mut out, "a \"b\"").unwrap();
+/// assert_eq!(out, br#""a \"b\"""#);
+/// ```
+#[inline]
+pub fn write_json_string<W: Write + ?Sized>(out: &mut W, s: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    crate::escape_to_writer(s, out)?;
+    out.write_all(b"\"")
+}
+
+/// Writer of newline delimited json, a string per line
+///
+/// ```
+/// use v_jsonescape::NdjsonWriter;
+///
+/// let mut writer = NdjsonWriter::new(Vec::new());
+/// writer.write_string("a\nb").unwrap();
+/// writer.write_string("c").unwrap();
+/// assert_eq!(writer.into_inner(), b"\"a\\nb\"\n\"c\"\n");
+/// ```
+#[derive(Debug)]
+pub struct NdjsonWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter { writer }
+    }
+
+    /// Write `s` as a json string on a line of its own
+    #[inline]
+    pub fn write_string(&mut self, s: &str) -> io::Result<()> {
+        write_json_string(&mut self.writer, s)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// The underlying writer
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Unwrap the underlying writer
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writer of a json array of strings
+///
+/// `[` is written with the first string, or by `finish` when there is none
+///
+/// ```
+/// use v_jsonescape::JsonArrayWriter;
+///
+/// let mut writer = JsonArrayWriter::new(Vec::new());
+/// writer.write_string("a").unwrap();
+/// writer.write_string("\"b\"").unwrap();
+/// assert_eq!(writer.finish().unwrap(), br#"["a","\"b\""]"#);
+/// ```
+#[derive(Debug)]
+pub struct JsonArrayWriter<W> {
+    writer: W,
+    started: bool,
+}
+
+impl<W: Write> JsonArrayWriter<W> {
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        JsonArrayWriter {
+            writer,
+            started: false,
+        }
+    }
+
+    /// Write `s` as the next string of the array
+    #[inline]
+    pub fn write_string(&mut self, s: &str) -> io::Result<()> {
+        self.writer
+            .write_all(if self.started { b"," } else { b"[" })?;
+        self.started = true;
+        write_json_string(&mut self.writer, s)
+    }
+
+    /// Close the array and return the underlying writer
+    #[inline]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer
+            .write_all(if self.started { b"]" } else { b"[]" })?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::std::prelude::v1::*;
+    use super::*;
+
+    #[test]
+    fn test_writers() {
+        let mut out = Vec::new();
+        write_json_string(&mut out, "").unwrap();
+        write_json_string(&mut out, "é\t\\").unwrap();
+        assert_eq!(out, b"\"\"\"\xC3\xA9\\t\\\\\"");
+
+        let s = "\"key\": \u{1}\n".repeat(10);
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write_string(&s).unwrap();
+        writer.write_string("").unwrap();
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines, [format!("\"{}\"", crate::escape(&s)), "\"\"".into()]);
+
+        assert_eq!(JsonArrayWriter::new(Vec::new()).finish().unwrap(), b"[]");
+        let mut writer = JsonArrayWriter::new(Vec::new());
+        writer.write_string("\n").unwrap();
+        assert_eq!(writer.finish().unwrap(), b"[\"\\n\"]");
+    }
+}
@@ -1,9 +1,21 @@
 use std::env;
 
 fn main() {
+    declare_cfgs();
     enable_simd_optimizations();
 }
 
+fn declare_cfgs() {
+    for name in &[
+        "v_latexescape_simd",
+        "v_latexescape_sse",
+        "v_latexescape_avx",
+        "v_escape_nosimd",
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({})", name);
+    }
+}
+
 fn enable_simd_optimizations() {
     if is_env_set("CARGO_CFG_LATEXESCAPE_DISABLE_AUTO_SIMD") {
         return;
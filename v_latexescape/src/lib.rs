@@ -6,6 +6,26 @@
 //! print!("{}", escape("# Header"));
 //! ```
 //!
+//! Text in math mode is escaped with `escape_math`, or with `mode::escape`
+//! choosing the mode at runtime:
+//!
+//! ```rust
+//! use v_latexescape::mode::{escape, Mode};
+//!
+//! assert_eq!(escape("a^b", Mode::Text).to_string(), "a\\textasciicircum{}b");
+//! assert_eq!(escape("a^b", Mode::Math).to_string(), "a\\hat{}b");
+//! ```
+//!
+//! For `pdflatex`, `escape_unicode` writes the common non-ascii characters as
+//! LaTeX macros too:
+//!
+//! ```rust
+//! use v_latexescape::escape_unicode;
+//!
+//! assert_eq!(escape_unicode("déjà vu").to_string(), "d\\'ej\\`a vu");
+//! ```
+//!
+#![no_std]
 
 macro_rules! build {
     ($($t:tt)*) => {
@@ -15,11 +35,26 @@ macro_rules! build {
              94->\\textasciicircum{} || 95->\\_ || 123->\\{ || 125->\\} || 126->\\textasciitilde{}",
             $($t)*
         );
+
+        // The text commands of `\`, `^` and `~` are errors in math mode
+        v_escape::new!(
+            LateXMathEscape,
+            "35->\\# || 36->\\$ || 37->\\% || 38->\\& || 92->\\backslash{} || \
+             94->\\hat{} || 95->\\_ || 123->\\{ || 125->\\} || 126->\\sim{}",
+            module = math,
+            $($t)*
+        );
+
+        /// Escape `s` for math mode, inside `$...$` or an equation
+        #[inline]
+        pub fn escape_math(s: &str) -> LateXMathEscape<'_> {
+            math::escape(s)
+        }
     };
 }
 
 /// Without simd optimizations
-mod fallback {
+pub mod fallback {
     build!(simd = false);
 }
 
@@ -33,8 +68,25 @@ cfg_if::cfg_if! {
     }
 }
 
+pub mod mode;
+mod unicode;
+
+pub use self::unicode::{escape_unicode, unicode_macro, LateXUnicodeEscape};
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("latex", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"latex"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
 #[cfg(test)]
 mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+
     #[test]
     fn test_escape() {
         use super::LateXEscape;
@@ -70,4 +122,13 @@ mod test {
                 .repeat(10_000)
         );
     }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("latex").unwrap();
+        escaper.escape_to("#a", &mut out).unwrap();
+        assert_eq!(out, "\\#a");
+    }
 }
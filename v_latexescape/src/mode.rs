@@ -0,0 +1,86 @@
+//! Escaping for the mode of the text in the document
+//!
+//! Text and math mode have escapers of their own, the text commands of some
+//! characters are errors in math mode
+use core::fmt::{self, Display};
+
+use crate::{math, LateXEscape, LateXMathEscape};
+
+/// Mode the escaped text is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Paragraph text, as `escape`
+    Text,
+    /// Inside `$...$` or an equation, as `escape_math`
+    Math,
+}
+
+impl Mode {
+    /// Escaper of this mode, to choose it at runtime
+    #[inline]
+    pub fn escaper(self) -> &'static dyn v_escape::Escaper {
+        match self {
+            Mode::Text => crate::ESCAPER,
+            Mode::Math => math::ESCAPER,
+        }
+    }
+}
+
+/// `s` escaped for `mode`, the escaping happens when it is formatted
+pub enum ModeEscape<'a> {
+    Text(LateXEscape<'a>),
+    Math(LateXMathEscape<'a>),
+}
+
+impl Display for ModeEscape<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModeEscape::Text(e) => e.fmt(fmt),
+            ModeEscape::Math(e) => e.fmt(fmt),
+        }
+    }
+}
+
+/// Escape `s` for `mode`
+#[inline]
+pub fn escape(s: &str, mode: Mode) -> ModeEscape<'_> {
+    match mode {
+        Mode::Text => ModeEscape::Text(crate::escape(s)),
+        Mode::Math => ModeEscape::Math(math::escape(s)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+    use super::{escape, Mode};
+
+    #[test]
+    fn test_escape() {
+        let s = "x_1^2 \\leq {a} ~ 50% & $5 #1";
+        for (mode, e) in [
+            (
+                Mode::Text,
+                "x\\_1\\textasciicircum{}2 \\textbackslash{}leq \\{a\\} \\textasciitilde{} \
+                 50\\% \\& \\$5 \\#1",
+            ),
+            (
+                Mode::Math,
+                "x\\_1\\hat{}2 \\backslash{}leq \\{a\\} \\sim{} 50\\% \\& \\$5 \\#1",
+            ),
+        ] {
+            assert_eq!(escape(s, mode).to_string(), e, "{:?}", mode);
+            let mut out = String::new();
+            mode.escaper().escape_to(s, &mut out).unwrap();
+            assert_eq!(out, e, "{:?}", mode);
+        }
+
+        let s = "a^~".repeat(30);
+        assert_eq!(
+            escape(&s, Mode::Math).to_string(),
+            "a\\hat{}\\sim{}".repeat(30)
+        );
+    }
+}
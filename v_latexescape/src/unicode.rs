@@ -0,0 +1,221 @@
+//! Non-ascii characters written as LaTeX macros, for `pdflatex`
+//!
+//! The first bytes of the non-ascii characters are looked for with the other
+//! escaped characters, by an escaper of their own, so the ascii runs between
+//! them are skipped with the same vector loops as `escape`. The characters
+//! without a macro are written as they are
+use core::fmt::{self, Display, Formatter};
+
+// The multi-byte characters are only looked for, `LateXUnicodeEscape` writes
+// them
+macro_rules! build_escaped {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            escaped,
+            "35->\\# || 36->\\$ || 37->\\% || 38->\\& || 92->\\textbackslash{} || \
+             94->\\textasciicircum{} || 95->\\_ || 123->\\{ || 125->\\} || \
+             126->\\textasciitilde{} || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_latexescape_simd, v_latexescape_avx))] {
+        build_escaped!(simd = true, avx = true);
+    } else if #[cfg(all(v_latexescape_simd, v_latexescape_sse))] {
+        build_escaped!(simd = true, avx = false);
+    } else {
+        build_escaped!(simd = false);
+    }
+}
+
+/// Macros of the non-ascii characters, sorted by character
+static MACROS: &[(char, &str)] = &[
+    ('\u{a0}', "~"),
+    ('¡', "!`"),
+    ('£', "\\pounds{}"),
+    ('§', "\\S{}"),
+    ('©', "\\copyright{}"),
+    ('«', "\\guillemotleft{}"),
+    ('®', "\\textregistered{}"),
+    ('°', "\\textdegree{}"),
+    ('±', "\\textpm{}"),
+    ('¶', "\\P{}"),
+    ('»', "\\guillemotright{}"),
+    ('¿', "?`"),
+    ('À', "\\`A"),
+    ('Á', "\\'A"),
+    ('Â', "\\^A"),
+    ('Ã', "\\~A"),
+    ('Ä', "\\\"A"),
+    ('Å', "\\AA{}"),
+    ('Æ', "\\AE{}"),
+    ('Ç', "\\c{C}"),
+    ('È', "\\`E"),
+    ('É', "\\'E"),
+    ('Ê', "\\^E"),
+    ('Ë', "\\\"E"),
+    ('Ì', "\\`I"),
+    ('Í', "\\'I"),
+    ('Î', "\\^I"),
+    ('Ï', "\\\"I"),
+    ('Ñ', "\\~N"),
+    ('Ò', "\\`O"),
+    ('Ó', "\\'O"),
+    ('Ô', "\\^O"),
+    ('Õ', "\\~O"),
+    ('Ö', "\\\"O"),
+    ('×', "\\texttimes{}"),
+    ('Ø', "\\O{}"),
+    ('Ù', "\\`U"),
+    ('Ú', "\\'U"),
+    ('Û', "\\^U"),
+    ('Ü', "\\\"U"),
+    ('Ý', "\\'Y"),
+    ('ß', "\\ss{}"),
+    ('à', "\\`a"),
+    ('á', "\\'a"),
+    ('â', "\\^a"),
+    ('ã', "\\~a"),
+    ('ä', "\\\"a"),
+    ('å', "\\aa{}"),
+    ('æ', "\\ae{}"),
+    ('ç', "\\c{c}"),
+    ('è', "\\`e"),
+    ('é', "\\'e"),
+    ('ê', "\\^e"),
+    ('ë', "\\\"e"),
+    ('ì', "\\`i"),
+    ('í', "\\'i"),
+    ('î', "\\^i"),
+    ('ï', "\\\"i"),
+    ('ñ', "\\~n"),
+    ('ò', "\\`o"),
+    ('ó', "\\'o"),
+    ('ô', "\\^o"),
+    ('õ', "\\~o"),
+    ('ö', "\\\"o"),
+    ('÷', "\\textdiv{}"),
+    ('ø', "\\o{}"),
+    ('ù', "\\`u"),
+    ('ú', "\\'u"),
+    ('û', "\\^u"),
+    ('ü', "\\\"u"),
+    ('ý', "\\'y"),
+    ('ÿ', "\\\"y"),
+    ('Č', "\\v{C}"),
+    ('č', "\\v{c}"),
+    ('Ł', "\\L{}"),
+    ('ł', "\\l{}"),
+    ('Ő', "\\H{O}"),
+    ('ő', "\\H{o}"),
+    ('Œ', "\\OE{}"),
+    ('œ', "\\oe{}"),
+    ('Š', "\\v{S}"),
+    ('š', "\\v{s}"),
+    ('Ű', "\\H{U}"),
+    ('ű', "\\H{u}"),
+    ('Ÿ', "\\\"Y"),
+    ('Ž', "\\v{Z}"),
+    ('ž', "\\v{z}"),
+    ('–', "--"),
+    ('—', "---"),
+    ('‘', "`"),
+    ('’', "'"),
+    ('“', "``"),
+    ('”', "''"),
+    ('†', "\\dag{}"),
+    ('‡', "\\ddag{}"),
+    ('•', "\\textbullet{}"),
+    ('…', "\\ldots{}"),
+    ('€', "\\texteuro{}"),
+    ('™', "\\texttrademark{}"),
+];
+
+/// Macro of `c` in text mode, `None` when it has none
+///
+/// ```
+/// use v_latexescape::unicode_macro;
+///
+/// assert_eq!(unicode_macro('é'), Some("\\'e"));
+/// assert_eq!(unicode_macro('—'), Some("---"));
+/// assert_eq!(unicode_macro('a'), None);
+/// ```
+#[inline]
+pub fn unicode_macro(c: char) -> Option<&'static str> {
+    MACROS
+        .binary_search_by_key(&c, |&(c, _)| c)
+        .ok()
+        .map(|i| MACROS[i].1)
+}
+
+/// Escaped `s` of [`escape_unicode`]
+pub struct LateXUnicodeEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> Display for LateXUnicodeEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        v_escape::pad::pad(fmt, |fmt| {
+            v_escape::scan::escape_found_chars(
+                self.s,
+                fmt,
+                escaped::find_first_escape_bytes,
+                escaped::escape_byte,
+                |c, fmt| unicode_macro(c).map(|m| fmt.write_str(m)),
+            )
+        })
+    }
+}
+
+/// Escape `s` like `escape` and the common non-ascii characters as LaTeX
+/// macros, `é` as `\'e` and `—` as `---`
+///
+/// It is for `pdflatex`, whose input is read byte by byte, `xelatex` and
+/// `lualatex` read the characters of `escape` as they are
+///
+/// ```
+/// use v_latexescape::escape_unicode;
+///
+/// assert_eq!(escape_unicode("Café — 5€").to_string(), "Caf\\'e --- 5\\texteuro{}");
+/// ```
+#[inline]
+pub fn escape_unicode(s: &str) -> LateXUnicodeEscape<'_> {
+    LateXUnicodeEscape { s }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape_unicode, MACROS};
+
+    #[test]
+    fn test_escape_unicode() {
+        assert!(MACROS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(MACROS.iter().all(|(c, _)| !c.is_ascii()));
+
+        assert_eq!(escape_unicode("").to_string(), "");
+        assert_eq!(escape_unicode("foo").to_string(), "foo");
+        assert_eq!(
+            escape_unicode("“Ça va?” — 100% naïve").to_string(),
+            "``\\c{C}a va?'' --- 100\\% na\\\"ive"
+        );
+        assert_eq!(
+            escape_unicode("ß~æ").to_string(),
+            "\\ss{}\\textasciitilde{}\\ae{}"
+        );
+        assert_eq!(escape_unicode("λ→😀ü").to_string(), "λ→😀\\\"u");
+        assert_eq!(format!("{:>6}", escape_unicode("é")), "   \\'e");
+
+        // Past the widest vector, the same as escaping character by character
+        let s: String = "aé#λ—😀_\u{a0}".chars().cycle().take(300).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| escape_unicode(&c.to_string()).to_string())
+            .collect();
+        assert_eq!(escape_unicode(&s).to_string(), expected);
+    }
+}
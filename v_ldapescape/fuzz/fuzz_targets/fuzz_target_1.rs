@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use v_ldapescape::{LDAPFilterEscape, b_escape};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = LDAPFilterEscape::new(data).to_string();
+    b_escape(data, &mut bytes::BytesMut::with_capacity(0));
+    // fuzzed code goes here
+});
@@ -0,0 +1,179 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_ldapescape::{escape, escape_dn};
+//!
+//! assert_eq!(escape("*)(uid=*").to_string(), "\\2a\\29\\28uid=\\2a");
+//! assert_eq!(escape_dn("Doe, John").to_string(), "Doe\\, John");
+//! ```
+//!
+//! `escape` is for a value of a search filter, as RFC 4515 says: `*`, `(`,
+//! `)`, `\` and `NUL` are written as a `\` and their two hexadecimal digits,
+//! so a value can't end its item nor be taken as a wildcard.
+//!
+//! `escape_dn` is for an attribute value of a distinguished name, as RFC 4514
+//! says: `"`, `+`, `,`, `;`, `<`, `=`, `>` and `\` are escaped with a `\`,
+//! `NUL` as `\00`, and a space or a `#` at the start and a space at the end
+//! too, as they would be dropped or read as a hexadecimal value:
+//!
+//! ```rust
+//! use v_ldapescape::escape_dn;
+//!
+//! assert_eq!(escape_dn("#1 ").to_string(), "\\#1\\ ");
+//! ```
+#![no_std]
+
+use core::fmt::{self, Display, Formatter};
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            LDAPFilterEscape,
+            "0x00->\\00 || 0x28->\\28 || 0x29->\\29 || 0x2A->\\2a || 0x5C->\\5c",
+            $($t)*
+        );
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_ldapescape_simd, v_ldapescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_ldapescape_simd, v_ldapescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+#[allow(dead_code)]
+mod dn {
+    // A value of a distinguished name without the spaces and `#` at its ends,
+    // which are written by `LDAPDNEscape`. `=` can be escaped, it is for the
+    // parsers that split at it
+    macro_rules! build_dn {
+        ($($t:tt)*) => {
+            v_escape::new!(
+                DN,
+                "0x00->\\00 || 0x22->\\\" || 0x2B->\\+ || 0x2C->\\, || 0x3B->\\; || \
+                 0x3C->\\< || 0x3D->\\= || 0x3E->\\> || 0x5C->\\\\",
+                $($t)*
+            );
+        };
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(v_ldapescape_simd, v_ldapescape_avx))] {
+            build_dn!(simd = true, avx = true);
+        } else if #[cfg(all(v_ldapescape_simd, v_ldapescape_sse))] {
+            build_dn!(simd = true, avx = false);
+        } else {
+            build_dn!(simd = false);
+        }
+    }
+}
+
+/// Escaped `s` of [`escape_dn`]
+pub struct LDAPDNEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> LDAPDNEscape<'a> {
+    #[inline]
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        let s = self.s;
+        let b = s.as_bytes();
+        let start = match b.first() {
+            Some(b' ') => {
+                fmt.write_str("\\ ")?;
+                1
+            }
+            Some(b'#') => {
+                fmt.write_str("\\#")?;
+                1
+            }
+            _ => 0,
+        };
+        // The first byte is already written when it is the only one
+        let trailing = b.len() > start && b[b.len() - 1] == b' ';
+        let end = b.len() - trailing as usize;
+        Display::fmt(&dn::escape(&s[start..end]), fmt)?;
+        if trailing {
+            fmt.write_str("\\ ")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for LDAPDNEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Escape `s` as an attribute value of a distinguished name
+#[inline]
+pub fn escape_dn(s: &str) -> LDAPDNEscape<'_> {
+    LDAPDNEscape { s }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("ldap", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"ldap"`, it escapes filter
+/// values as `escape`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_dn};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(
+            escape("john.doe@example.com").to_string(),
+            "john.doe@example.com"
+        );
+        assert_eq!(escape("\0()*\\").to_string(), "\\00\\28\\29\\2a\\5c");
+        assert_eq!(escape("a,b=c é").to_string(), "a,b=c é");
+    }
+
+    #[test]
+    fn test_escape_dn() {
+        assert_eq!(escape_dn("").to_string(), "");
+        assert_eq!(escape_dn(" ").to_string(), "\\ ");
+        assert_eq!(escape_dn("  ").to_string(), "\\ \\ ");
+        assert_eq!(escape_dn("#").to_string(), "\\#");
+        assert_eq!(escape_dn("# ").to_string(), "\\#\\ ");
+        assert_eq!(escape_dn("a#b c").to_string(), "a#b c");
+        assert_eq!(escape_dn(" a b ").to_string(), "\\ a b\\ ");
+        assert_eq!(
+            escape_dn("\"+,;<=>\\\0*()").to_string(),
+            "\\\"\\+\\,\\;\\<\\=\\>\\\\\\00*()"
+        );
+        assert_eq!(format!("{:>4}", escape_dn(" ")), "  \\ ");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("ldap").unwrap();
+        escaper.escape_to("a*", &mut out).unwrap();
+        assert_eq!(out, "a\\2a");
+    }
+}
@@ -0,0 +1,35 @@
+use v_ldapescape::{escape, escape_dn, LDAPFilterEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(LDAPFilterEscape::from("(*)").to_string(), "\\28\\2a\\29");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [1, 2, 15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        for skip in [0, 32, 35] {
+            let s: String = all.chars().cycle().skip(skip).take(len).collect();
+            let filter: String = s
+                .chars()
+                .map(|c| match c {
+                    '\0' | '(' | ')' | '*' | '\\' => format!("\\{:02x}", c as u32),
+                    c => c.to_string(),
+                })
+                .collect();
+            assert_eq!(escape(&s).to_string(), filter, "{:?}", s);
+
+            let last = s.chars().count() - 1;
+            let dn: String = s
+                .chars()
+                .enumerate()
+                .map(|(i, c)| match c {
+                    '\0' => "\\00".into(),
+                    '"' | '+' | ',' | ';' | '<' | '=' | '>' | '\\' => format!("\\{}", c),
+                    ' ' | '#' if i == 0 => format!("\\{}", c),
+                    ' ' if i == last => "\\ ".into(),
+                    c => c.to_string(),
+                })
+                .collect();
+            assert_eq!(escape_dn(&s).to_string(), dn, "{:?}", s);
+        }
+    }
+}
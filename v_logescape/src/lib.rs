@@ -0,0 +1,104 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_logescape::escape;
+//!
+//! let user = "eve\nINFO login ok user=admin";
+//! assert_eq!(
+//!     format!("user={}", escape(user)),
+//!     "user=eve\\nINFO login ok user=admin"
+//! );
+//! ```
+//!
+//! An untrusted value with a line end can forge the log lines after its own.
+//! `escape` keeps a value on its line: `\r`, `\n` and `\t` are escaped as
+//! such, the other control characters and `DEL` as `\xXX`, and `\` as `\\`,
+//! so an escaped line end never reads as the text `\n` of the value.
+//!
+//! The escaped value is `Display`, it is written straight to the formatter of
+//! the log record, as a field of `tracing` or an argument of `log`, without
+//! allocating. A value without a byte to escape is written in a single
+//! `write_str` after the simd scan:
+//!
+//! ```rust
+//! use v_logescape::escape;
+//!
+//! # let path = "/index.html";
+//! // With `tracing`, `info!(path = %escape(path), "request")`
+//! // With `log`, `info!("request path={}", escape(path))`
+//! assert_eq!(escape(path).to_string(), path);
+//! ```
+//!
+//! Non-ascii characters are written as they are, the line separators
+//! `U+0085`, `U+2028` and `U+2029` included, which most log readers don't
+//! take as line ends.
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            LogEscape,
+            "0x00-0x08->\\x{:02x} || 0x09->\\t || 0x0A->\\n || 0x0B-0x0C->\\x{:02x} || \
+             0x0D->\\r || 0x0E-0x1F->\\x{:02x} || 0x5C->\\\\ || 0x7F->\\x7f",
+            $($t)*
+        );
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_logescape_simd, v_logescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_logescape_simd, v_logescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("log", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"log"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, needs_escaping};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a=\"b\" é\u{2028}").to_string(), "a=\"b\" é\u{2028}");
+        assert_eq!(
+            escape("\0\t\n\u{b}\r\u{1b}\u{7f}").to_string(),
+            "\\x00\\t\\n\\x0b\\r\\x1b\\x7f"
+        );
+        assert_eq!(escape("a\\nb\nc").to_string(), "a\\\\nb\\nc");
+        assert_eq!(format!("{:>4}", escape("\n")), "  \\n");
+    }
+
+    #[test]
+    fn test_needs_escaping() {
+        assert!(!needs_escaping("GET /index.html 200"));
+        assert!(needs_escaping(&format!("{}\r", "a".repeat(40))));
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("log").unwrap();
+        escaper.escape_to("a\n", &mut out).unwrap();
+        assert_eq!(out, "a\\n");
+    }
+}
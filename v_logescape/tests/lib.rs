@@ -0,0 +1,25 @@
+use v_logescape::{escape, LogEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(LogEscape::from("a\nb").to_string(), "a\\nb");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\t' => "\\t".into(),
+                '\n' => "\\n".into(),
+                '\r' => "\\r".into(),
+                '\\' => "\\\\".into(),
+                c if c.is_ascii_control() => format!("\\x{:02x}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        let escaped = escape(&s).to_string();
+        assert_eq!(escaped, expected, "{}", len);
+        assert!(!escaped.chars().any(|c| c.is_ascii_control()));
+    }
+}
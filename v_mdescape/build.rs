@@ -0,0 +1,34 @@
+use std::env;
+
+fn main() {
+    declare_cfgs();
+    enable_simd_optimizations();
+}
+
+fn declare_cfgs() {
+    for name in &[
+        "v_mdescape_simd",
+        "v_mdescape_sse",
+        "v_mdescape_avx",
+        "v_escape_nosimd",
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({})", name);
+    }
+}
+
+fn enable_simd_optimizations() {
+    if is_env_set("CARGO_CFG_MDESCAPE_DISABLE_AUTO_SIMD") {
+        return;
+    }
+
+    println!("cargo:rustc-cfg=v_mdescape_simd");
+    println!("cargo:rustc-cfg=v_mdescape_sse");
+
+    if !is_env_set("CARGO_CFG_MDESCAPE_DISABLE_AUTO_AVX") {
+        println!("cargo:rustc-cfg=v_mdescape_avx");
+    }
+}
+
+fn is_env_set(name: &str) -> bool {
+    env::var(name).is_ok()
+}
@@ -0,0 +1,126 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_mdescape::escape;
+//!
+//! assert_eq!(escape("*not bold* [x](y)").to_string(), "\\*not bold\\* \\[x\\]\\(y\\)");
+//! ```
+//!
+//! `escape` writes a `\` before the punctuation that starts the inline
+//! formatting, the entities and the common blocks, `` &*_`~[]()#>!|\ ``. In
+//! GitHub flavored Markdown this is still active, written as it is:
+//!
+//! * the list markers `-`, `+` and `1.` or `1)` at the start of a line
+//! * the `=` and `-` lines of the setext headings and the `---` breaks
+//! * the `<` of raw html and autolinks, the bare urls GFM links too
+//! * the line breaks, a line ending with two spaces too
+//!
+//! `escape_commonmark` escapes every ascii punctuation character, all of them
+//! can be escaped in CommonMark, so raw html and the list markers are shown as
+//! text too:
+//!
+//! ```rust
+//! use v_mdescape::escape_commonmark;
+//!
+//! assert_eq!(escape_commonmark("- <b>").to_string(), "\\- \\<b\\>");
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            MDEscape,
+            "0x21->\\! || 0x23->\\# || 0x26->\\& || 0x28->\\( || 0x29->\\) || 0x2A->\\* || \
+             0x3E->\\> || 0x5B->\\[ || 0x5C->\\\\ || 0x5D->\\] || 0x5F->\\_ || 0x60->\\` || \
+             0x7C->\\| || 0x7E->\\~",
+            $($t)*
+        );
+
+        v_escape::new!(
+            MDCommonMarkEscape,
+            "0x21->\\! || 0x22->\\\" || 0x23->\\# || 0x24->\\$ || 0x25->\\% || \
+             0x26->\\& || 0x27->\\' || 0x28->\\( || 0x29->\\) || 0x2A->\\* || 0x2B->\\+ || \
+             0x2C->\\, || 0x2D->\\- || 0x2E->\\. || 0x2F->\\/ || 0x3A->\\: || 0x3B->\\; || \
+             0x3C->\\< || 0x3D->\\= || 0x3E->\\> || 0x3F->\\? || 0x40->\\@ || 0x5B->\\[ || \
+             0x5C->\\\\ || 0x5D->\\] || 0x5E->\\^ || 0x5F->\\_ || 0x60->\\` || \
+             0x7B->\\{ || 0x7C->\\| || 0x7D->\\} || 0x7E->\\~",
+            module = commonmark,
+            $($t)*
+        );
+
+        /// Escape `s` like `escape` and every other ascii punctuation character
+        /// too, as CommonMark allows
+        #[inline]
+        pub fn escape_commonmark(s: &str) -> MDCommonMarkEscape<'_> {
+            commonmark::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_mdescape_simd, v_mdescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_mdescape_simd, v_mdescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("markdown", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"markdown"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+    use super::{escape, escape_commonmark};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a - b + c < d").to_string(), "a - b + c < d");
+        assert_eq!(
+            escape("# Title\n> ![img](x) `code` |a|_b_ \\").to_string(),
+            "\\# Title\n\\> \\!\\[img\\]\\(x\\) \\`code\\` \\|a\\|\\_b\\_ \\\\"
+        );
+        assert_eq!(
+            escape("~~a~~ &amp; 1. b").to_string(),
+            "\\~\\~a\\~\\~ \\&amp; 1. b"
+        );
+    }
+
+    #[test]
+    fn test_escape_commonmark() {
+        for b in 0..0x80u8 {
+            let c = char::from(b);
+            let expected = if c.is_ascii_punctuation() {
+                ['\\', c].iter().collect()
+            } else {
+                c.to_string()
+            };
+            assert_eq!(escape_commonmark(&c.to_string()).to_string(), expected);
+        }
+        assert_eq!(escape_commonmark("1. é").to_string(), "1\\. é");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("markdown").unwrap();
+        escaper.escape_to("*a*", &mut out).unwrap();
+        assert_eq!(out, "\\*a\\*");
+    }
+}
@@ -0,0 +1,27 @@
+use v_mdescape::{escape, escape_commonmark, MDEscape};
+
+#[test]
+fn test_escape() {
+    let specials = "&*_`~[]()#>!|\\";
+    let escaped: String = specials.chars().flat_map(|c| ['\\', c]).collect();
+    assert_eq!(MDEscape::from(specials).to_string(), escaped);
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().skip(32).take(len).collect();
+        let expected: String = s
+            .chars()
+            .flat_map(|c| specials.contains(c).then_some('\\').into_iter().chain([c]))
+            .collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+
+        let expected: String = s
+            .chars()
+            .flat_map(|c| {
+                let punct = c.is_ascii_punctuation();
+                punct.then_some('\\').into_iter().chain([c])
+            })
+            .collect();
+        assert_eq!(escape_commonmark(&s).to_string(), expected, "{}", len);
+    }
+}
@@ -0,0 +1,107 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_pdfescape::escape;
+//!
+//! assert_eq!(escape("f(x) = 1\\2\n").to_string(), "f\\(x\\) = 1\\\\2\\n");
+//! ```
+//!
+//! `escape` is for the bytes between the parentheses of a literal string,
+//! `(...)`. `(`, `)` and `\` are escaped with a `\`, and the control bytes
+//! and `DEL` with their short escapes, `\n`, `\r`, `\t`, `\b` and `\f`, or else
+//! in octal as `\033`. A line end is escaped too, as a reader takes any of
+//! them in a literal string as a single `LF`.
+//!
+//! `escape_hex` writes every byte as two hexadecimal digits, for the text
+//! between the angle brackets of a hexadecimal string, `<...>`, which is
+//! plain ascii whatever the bytes are:
+//!
+//! ```rust
+//! use v_pdfescape::escape_hex;
+//!
+//! assert_eq!(escape_hex("Hé").to_string(), "48C3A9");
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            PDFEscape,
+            "0x00-0x07->\\{:03o} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+             0x0B->\\013 || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\{:03o} || 0x28->\\( || \
+             0x29->\\) || 0x5C->\\\\ || 0x7F->\\177",
+            $($t)*
+        );
+        v_escape::new!(
+            PDFHexEscape,
+            "0x00-0xFF->{:02X}",
+            module = hex,
+            $($t)*
+        );
+
+        /// Escape `s` for a hexadecimal string, `<...>`
+        #[inline]
+        pub fn escape_hex(s: &str) -> PDFHexEscape<'_> {
+            hex::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_pdfescape_simd, v_pdfescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_pdfescape_simd, v_pdfescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("pdf", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"pdf"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_hex};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a<b>[c] %d é").to_string(), "a<b>[c] %d é");
+        assert_eq!(escape("()\\").to_string(), "\\(\\)\\\\");
+        assert_eq!(
+            escape("\0\u{8}\t\n\u{b}\u{c}\r\u{1b}\u{7f}").to_string(),
+            "\\000\\b\\t\\n\\013\\f\\r\\033\\177"
+        );
+        assert_eq!(format!("{:>4}", escape("(")), "  \\(");
+    }
+
+    #[test]
+    fn test_escape_hex() {
+        assert_eq!(escape_hex("").to_string(), "");
+        assert_eq!(escape_hex("\0 ~\u{7f}").to_string(), "00207E7F");
+        assert_eq!(escape_hex("😀").to_string(), "F09F9880");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("pdf").unwrap();
+        escaper.escape_to("a(", &mut out).unwrap();
+        assert_eq!(out, "a\\(");
+    }
+}
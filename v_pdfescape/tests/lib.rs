@@ -0,0 +1,28 @@
+use v_pdfescape::{escape, escape_hex, PDFEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(PDFEscape::from("a(b").to_string(), "a\\(b");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\u{8}' => "\\b".into(),
+                '\t' => "\\t".into(),
+                '\n' => "\\n".into(),
+                '\u{c}' => "\\f".into(),
+                '\r' => "\\r".into(),
+                '(' | ')' | '\\' => format!("\\{}", c),
+                c if c.is_ascii_control() => format!("\\{:03o}", c as u32),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+
+        let expected: String = s.bytes().map(|b| format!("{:02X}", b)).collect();
+        assert_eq!(escape_hex(&s).to_string(), expected, "{}", len);
+    }
+}
@@ -0,0 +1,114 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_regexescape::escape;
+//!
+//! assert_eq!(escape("1+1=2?").to_string(), "1\\+1=2\\?");
+//! ```
+//!
+//! `escape` writes a `\` before every metacharacter, the same ones as
+//! `regex::escape`: `` \.+*?()|[]{}^$ `` and `#&-~` too, they are special in
+//! verbose mode and in the set operations of the classes. The output matches
+//! the string literally in the patterns of `regex` and of PCRE without its
+//! extended options, the engines it is checked with. Other engines may reject
+//! the escaped `#&-~`, JavaScript does with the `u` or `v` flag.
+//!
+//! `escape_class` is for the text inside `[...]`, where only `]`, `\`, `^` and
+//! `-` have a meaning in PCRE and JavaScript:
+//!
+//! ```rust
+//! use v_regexescape::escape_class;
+//!
+//! assert_eq!(escape_class("a-z]^").to_string(), "a\\-z\\]\\^");
+//! ```
+//!
+//! The classes of `regex` can be nested and have set operations, the text of
+//! those is escaped with `escape` instead.
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            RegexEscape,
+            "0x23->\\# || 0x24->\\$ || 0x26->\\& || 0x28->\\( || 0x29->\\) || 0x2A->\\* || \
+             0x2B->\\+ || 0x2D->\\- || 0x2E->\\. || 0x3F->\\? || 0x5B->\\[ || 0x5C->\\\\ || \
+             0x5D->\\] || 0x5E->\\^ || 0x7B->\\{ || 0x7C->\\| || 0x7D->\\} || 0x7E->\\~",
+            $($t)*
+        );
+
+        v_escape::new!(
+            RegexClassEscape,
+            "0x2D->\\- || 0x5C->\\\\ || 0x5D->\\] || 0x5E->\\^",
+            module = class,
+            $($t)*
+        );
+
+        /// Escape `s` to be written inside a character class, `[...]`
+        #[inline]
+        pub fn escape_class(s: &str) -> RegexClassEscape<'_> {
+            class::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_regexescape_simd, v_regexescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_regexescape_simd, v_regexescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("regex", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"regex"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+    use super::{escape, escape_class};
+
+    #[test]
+    fn test_escape() {
+        let metas = "\\.+*?()|[]{}^$#&-~";
+        for b in 0..0x80u8 {
+            let c = char::from(b);
+            let expected = if metas.contains(c) {
+                ['\\', c].iter().collect()
+            } else {
+                c.to_string()
+            };
+            assert_eq!(escape(&c.to_string()).to_string(), expected, "{:?}", c);
+        }
+        assert_eq!(escape("é.😀").to_string(), "é\\.😀");
+    }
+
+    #[test]
+    fn test_escape_class() {
+        assert_eq!(escape_class("").to_string(), "");
+        assert_eq!(escape_class("a.b*[").to_string(), "a.b*[");
+        assert_eq!(escape_class("\\]^-").to_string(), "\\\\\\]\\^\\-");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("regex").unwrap();
+        escaper.escape_to("a.b", &mut out).unwrap();
+        assert_eq!(out, "a\\.b");
+    }
+}
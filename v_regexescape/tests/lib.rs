@@ -0,0 +1,40 @@
+use v_regexescape::{escape, escape_class, RegexEscape};
+
+#[test]
+fn test_escape() {
+    let metas = "\\.+*?()|[]{}^$#&-~";
+    let escaped: String = metas.chars().flat_map(|c| ['\\', c]).collect();
+    assert_eq!(RegexEscape::from(metas).to_string(), escaped);
+
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = "a.b(c)é[d]-😀^$\\".chars().cycle().take(len).collect();
+        let expected: String = s
+            .chars()
+            .flat_map(|c| {
+                let meta = metas.contains(c);
+                meta.then_some('\\').into_iter().chain([c])
+            })
+            .collect();
+        assert_eq!(escape(&s).to_string(), expected, "{}", len);
+
+        let class: String = s
+            .chars()
+            .flat_map(|c| {
+                let meta = "]\\^-".contains(c);
+                meta.then_some('\\').into_iter().chain([c])
+            })
+            .collect();
+        assert_eq!(escape_class(&s).to_string(), class, "{}", len);
+    }
+}
+
+#[test]
+fn test_regex() {
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    assert_eq!(escape(&all).to_string(), regex::escape(&all));
+
+    let re = regex::Regex::new(&format!("^{}$", escape(&all))).unwrap();
+    assert!(re.is_match(&all));
+    let re = regex::Regex::new(&format!("^[{}]+$", escape(&all))).unwrap();
+    assert!(re.is_match(&all));
+}
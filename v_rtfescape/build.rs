@@ -0,0 +1,34 @@
+use std::env;
+
+fn main() {
+    declare_cfgs();
+    enable_simd_optimizations();
+}
+
+fn declare_cfgs() {
+    for name in &[
+        "v_rtfescape_simd",
+        "v_rtfescape_sse",
+        "v_rtfescape_avx",
+        "v_escape_nosimd",
+    ] {
+        println!("cargo:rustc-check-cfg=cfg({})", name);
+    }
+}
+
+fn enable_simd_optimizations() {
+    if is_env_set("CARGO_CFG_RTFESCAPE_DISABLE_AUTO_SIMD") {
+        return;
+    }
+
+    println!("cargo:rustc-cfg=v_rtfescape_simd");
+    println!("cargo:rustc-cfg=v_rtfescape_sse");
+
+    if !is_env_set("CARGO_CFG_RTFESCAPE_DISABLE_AUTO_AVX") {
+        println!("cargo:rustc-cfg=v_rtfescape_avx");
+    }
+}
+
+fn is_env_set(name: &str) -> bool {
+    env::var(name).is_ok()
+}
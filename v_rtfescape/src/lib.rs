@@ -0,0 +1,137 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_rtfescape::escape;
+//!
+//! assert_eq!(escape("Café {menu}").to_string(), "Caf\\u233? \\{menu\\}");
+//! ```
+//!
+//! `escape` is for the text of a group of an RTF document. `\`, `{` and `}`
+//! are escaped with a `\`, and every non-ascii character as `\uN?`, its code
+//! unit of UTF-16 in signed decimal followed by the `?` a reader without
+//! unicode shows instead, as `\uc1`, the default, says. A character past
+//! `U+FFFF` is written as its two surrogates:
+//!
+//! ```rust
+//! use v_rtfescape::escape;
+//!
+//! assert_eq!(escape("€😀").to_string(), "\\u8364?\\u-10179?\\u-8704?");
+//! ```
+//!
+//! The escaped text is ascii, which any code page of the document reads the
+//! same. The line ends are kept, they are ignored by the readers, a line
+//! break is a `\line` or a `\par` of the document.
+//!
+//! The first bytes of the non-ascii characters are looked for with the ascii
+//! escaped characters, so the ascii runs between them are skipped with the
+//! simd loops of the escapers.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter, Write};
+
+// The multi-byte characters are only looked for, `write_escaped` writes them
+macro_rules! build_escaped {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            escaped,
+            "0x5C->\\\\ || 0x7B->\\{ || 0x7D->\\} || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_rtfescape_simd, v_rtfescape_avx))] {
+        build_escaped!(simd = true, avx = true);
+    } else if #[cfg(all(v_rtfescape_simd, v_rtfescape_sse))] {
+        build_escaped!(simd = true, avx = false);
+    } else {
+        build_escaped!(simd = false);
+    }
+}
+
+fn write_escaped(s: &str, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+    v_escape::scan::escape_found_chars(
+        s,
+        out,
+        escaped::find_first_escape_bytes,
+        escaped::escape_byte,
+        |c, out| {
+            let mut units = [0; 2];
+            // The control word takes a signed 16-bit number
+            Some(
+                c.encode_utf16(&mut units)
+                    .iter()
+                    .try_for_each(|unit| write!(out, "\\u{}?", *unit as i16)),
+            )
+        },
+    )
+}
+
+/// Escaped `s` of [`escape`]
+pub struct RTFEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> From<&'a str> for RTFEscape<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        RTFEscape { s }
+    }
+}
+
+impl<'a> Display for RTFEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        v_escape::pad::pad(fmt, |fmt| write_escaped(self.s, fmt))
+    }
+}
+
+/// Escape `s` to be written as the text of a group
+#[inline]
+pub fn escape(s: &str) -> RTFEscape<'_> {
+    RTFEscape { s }
+}
+
+/// Escaper of `escape`, to choose it at runtime
+pub static ESCAPER: &dyn v_escape::Escaper = &v_escape::FnEscaper(|s, out| write_escaped(s, out));
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("rtf", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"rtf"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, ESCAPER};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a\tb\n(c) [d] ?").to_string(), "a\tb\n(c) [d] ?");
+        assert_eq!(escape("\\{}").to_string(), "\\\\\\{\\}");
+        assert_eq!(
+            escape("\u{80}é\u{7fff}\u{8000}\u{ffff}").to_string(),
+            "\\u128?\\u233?\\u32767?\\u-32768?\\u-1?"
+        );
+        assert_eq!(escape("\u{10000}").to_string(), "\\u-10240?\\u-9216?");
+        assert_eq!(format!("{:>7}", escape("é")), " \\u233?");
+    }
+
+    #[test]
+    fn test_escaper() {
+        let s: String = "a{é}\\😀".repeat(20);
+        let mut out = String::new();
+        ESCAPER.escape_to(&s, &mut out).unwrap();
+        assert_eq!(out, escape(&s).to_string());
+        assert_eq!(ESCAPER.escaped_len(&s), out.len());
+
+        super::register();
+        assert!(v_escape::registry().get("rtf").is_some());
+    }
+}
@@ -0,0 +1,42 @@
+use v_rtfescape::{escape, RTFEscape};
+
+/// `s` read back from its escapes
+fn unescape(s: &str) -> String {
+    let mut units = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            units.push(c as u16);
+            continue;
+        }
+        match chars.next().unwrap() {
+            'u' => {
+                let mut n = String::new();
+                while let Some(&c) = chars.peek().filter(|&&c| c == '-' || c.is_ascii_digit()) {
+                    n.push(c);
+                    chars.next();
+                }
+                assert_eq!(chars.next(), Some('?'));
+                units.push(n.parse::<i16>().unwrap() as u16);
+            }
+            c => units.push(c as u16),
+        }
+    }
+    String::from_utf16(&units).unwrap()
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(RTFEscape::from("a{b").to_string(), "a\\{b");
+
+    let all: String = (0..0x80u8)
+        .map(char::from)
+        .chain("\u{80}é€\u{8000}\u{ffff}😀".chars())
+        .collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let escaped = escape(&s).to_string();
+        assert!(escaped.is_ascii());
+        assert_eq!(unescape(&escaped), s, "{}", len);
+    }
+}
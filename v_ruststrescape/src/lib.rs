@@ -0,0 +1,83 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_ruststrescape::escape;
+//!
+//! assert_eq!(escape("say \"hi\"\n").to_string(), "say \\\"hi\\\"\\n");
+//! ```
+//!
+//! `escape` is for the text between the `"` of a string literal of generated
+//! Rust source, as a `const` of a build script or the output of a proc-macro.
+//! An ascii character is escaped as `str::escape_debug` does: `\0`, `\t`,
+//! `\n`, `\r`, `\'`, `\"` and `\\`, and the other control characters and `DEL`
+//! as `\u{1b}`. `'` is escaped too, so the output is a char literal as well.
+//!
+//! Non-ascii characters are written as they are, any of them can be in a
+//! literal. `escape_debug` escapes the non printable ones too, as `U+200B`,
+//! and a combining character at the start, so the output of both is the same
+//! for ascii and printable text only.
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            RustStrEscape,
+            "0x00->\\0 || 0x01-0x08->\\u{{:x}} || 0x09->\\t || 0x0A->\\n || \
+             0x0B-0x0C->\\u{{:x}} || 0x0D->\\r || 0x0E-0x1F->\\u{{:x}} || 0x22->\\\" || \
+             0x27->\\' || 0x5C->\\\\ || 0x7F->\\u{7f}",
+            $($t)*
+        );
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_ruststrescape_simd, v_ruststrescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_ruststrescape_simd, v_ruststrescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("rust", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"rust"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::escape;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a{b} é\u{200b}").to_string(), "a{b} é\u{200b}");
+        assert_eq!(
+            escape("\0\u{1}\t\n\r\u{1b}\u{7f}").to_string(),
+            "\\0\\u{1}\\t\\n\\r\\u{1b}\\u{7f}"
+        );
+        assert_eq!(escape("'\"\\").to_string(), "\\'\\\"\\\\");
+        assert_eq!(format!("{:>4}", escape("\n")), "  \\n");
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("rust").unwrap();
+        escaper.escape_to("a\"", &mut out).unwrap();
+        assert_eq!(out, "a\\\"");
+    }
+}
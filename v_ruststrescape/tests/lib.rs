@@ -0,0 +1,30 @@
+use v_ruststrescape::{escape, RustStrEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(RustStrEscape::from("a\nb").to_string(), "a\\nb");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀ß€".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        assert_eq!(
+            escape(&s).to_string(),
+            s.escape_debug().to_string(),
+            "{}",
+            len
+        );
+    }
+}
+
+#[test]
+fn test_escape_default() {
+    // `escape_default` differs only in writing `NUL` as `\u{0}`
+    for b in 0..0x80u8 {
+        let s = char::from(b).to_string();
+        let expected = match b {
+            0 => "\\0".into(),
+            _ => s.escape_default().to_string(),
+        };
+        assert_eq!(escape(&s).to_string(), expected, "{}", b);
+    }
+}
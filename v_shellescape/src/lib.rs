@@ -0,0 +1,241 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_shellescape::quote;
+//!
+//! assert_eq!(quote("file.txt").to_string(), "file.txt");
+//! assert_eq!(quote("it's $HOME").to_string(), "'it'\\''s $HOME'");
+//! ```
+//!
+//! The strings are quoted for POSIX `sh`, and so for `bash`, `dash` and `zsh`,
+//! between single quotes, where every character but `'` is taken as it is.
+//! A `'` ends the quotes, is written escaped and the quotes start again.
+//!
+//! The strings of only `[A-Za-z0-9_@%+=:,./-]`, as the ones `shlex.quote` of
+//! Python doesn't quote, are written as they are. `needs_quoting` looks for
+//! the other characters with the simd loops of the escapers:
+//!
+//! ```rust
+//! use v_shellescape::needs_quoting;
+//!
+//! assert!(!needs_quoting("--out=/tmp/a.txt"));
+//! assert!(needs_quoting("a b"));
+//! assert!(needs_quoting(""));
+//! ```
+//!
+//! With the `alloc` feature, `quote_cow` returns the string borrowed when it
+//! isn't quoted.
+//!
+//! `escape` only escapes the `'`, for strings written between single quotes
+//! that are already there.
+//!
+//! The Windows command lines have rules of their own, in `windows`:
+//!
+//! ```rust
+//! use v_shellescape::windows::{quote_arg, quote_cmd_arg, quote_powershell};
+//!
+//! assert_eq!(quote_arg("C:\\My Files\\").to_string(), "\"C:\\My Files\\\\\"");
+//! assert_eq!(quote_cmd_arg("a&b c").to_string(), "^\"a^&b c^\"");
+//! assert_eq!(quote_powershell("it's").to_string(), "'it''s'");
+//! ```
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt::{self, Display, Formatter};
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(ShellEscape, "39->'\\''", $($t)*);
+
+        // The metacharacters of `cmd.exe` are taken as they are after a `^`
+        v_escape::new!(
+            CmdEscape,
+            "0x21->^! || 0x22->^\" || 0x25->^% || 0x26->^& || 0x28->^( || 0x29->^) || \
+             0x3C->^< || 0x3E->^> || 0x5E->^^ || 0x7C->^|",
+            module = cmd,
+            $($t)*
+        );
+
+        /// Escape the metacharacters of `cmd.exe` in `s` with `^`, for a
+        /// command line run by `cmd /c`
+        #[inline]
+        pub fn escape_cmd(s: &str) -> CmdEscape<'_> {
+            cmd::escape(s)
+        }
+    };
+}
+
+// The characters the quoting looks for, only found, never escaped
+macro_rules! build_finders {
+    ($($t:tt)*) => {
+        // Every byte but `[A-Za-z0-9_@%+=:,./-]`
+        v_escape::finder!(
+            special,
+            "0x00-0x24 || 0x26-0x2A || 0x3B-0x3C || 0x3E-0x3F || 0x5B-0x5E || 0x60 || \
+             0x7B-0x7F || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+
+        // The bytes a Windows argument is quoted for
+        v_escape::finder!(blank, "0x09-0x0B || 0x20 || 0x22", $($t)*);
+
+        // The escapes of a quoted argument depend on the bytes after the
+        // backslashes, they are written by `windows::WindowsArg`
+        v_escape::finder!(slashes, "0x22 || 0x5C", $($t)*);
+
+        // PowerShell takes `‘`, `’`, `‚` and `‛` as `'` too, they are doubled
+        // by `windows::PowerShellQuote`
+        v_escape::finder!(quotes, "0x27 || 0x80-0xBF || 0xC2-0xF4", $($t)*);
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_shellescape_simd, v_shellescape_avx))] {
+        build!(simd = true, avx = true);
+        build_finders!(simd = true, avx = true);
+    } else if #[cfg(all(v_shellescape_simd, v_shellescape_sse))] {
+        build!(simd = true, avx = false);
+        build_finders!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+        build_finders!(simd = false);
+    }
+}
+
+/// Whether `s` has to be quoted to be a single word of the shell, it is empty
+/// or has a character out of `[A-Za-z0-9_@%+=:,./-]`
+#[inline]
+pub fn needs_quoting(s: &str) -> bool {
+    s.is_empty() || special::find_first_escape(s).is_some()
+}
+
+/// `s` quoted of [`quote`], as it is or between single quotes
+pub struct ShellQuote<'a> {
+    s: &'a str,
+}
+
+impl<'a> ShellQuote<'a> {
+    #[inline]
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        if needs_quoting(self.s) {
+            fmt.write_str("'")?;
+            Display::fmt(&escape(self.s), fmt)?;
+            fmt.write_str("'")
+        } else {
+            fmt.write_str(self.s)
+        }
+    }
+}
+
+impl<'a> Display for ShellQuote<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Quote `s` as a single word of the shell, only when it `needs_quoting`
+#[inline]
+pub fn quote(s: &str) -> ShellQuote<'_> {
+    ShellQuote { s }
+}
+
+/// Quote `s` as [`quote`], borrowed when it isn't quoted
+///
+/// ```
+/// use std::borrow::Cow;
+/// use v_shellescape::quote_cow;
+///
+/// assert!(matches!(quote_cow("a.txt"), Cow::Borrowed("a.txt")));
+/// assert_eq!(quote_cow("a b"), "'a b'");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn quote_cow(s: &str) -> alloc::borrow::Cow<'_, str> {
+    use alloc::string::String;
+
+    if needs_quoting(s) {
+        let mut out = String::with_capacity(s.len() + 2);
+        out.push('\'');
+        escape_into(s, &mut out);
+        out.push('\'');
+        out.into()
+    } else {
+        s.into()
+    }
+}
+
+pub mod windows;
+
+static ENTRY: v_escape::registry::Entry =
+    v_escape::registry::Entry::new("shell-single-quoted", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"shell-single-quoted"`, it
+/// escapes the `'` of a string between single quotes as `escape`. Not
+/// `"shell"`, which is the name of the `preset = "shell"` pairs
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{needs_quoting, quote};
+
+    #[test]
+    fn test_needs_quoting() {
+        for b in 0..0x80u8 {
+            let c = char::from(b);
+            let safe = c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+            assert_eq!(needs_quoting(&c.to_string()), !safe, "{:?}", c);
+            // Past the widest vector
+            let s = format!("{}{}", "a".repeat(70), c);
+            assert_eq!(needs_quoting(&s), !safe, "{:?}", c);
+        }
+        assert!(needs_quoting(""));
+        assert!(needs_quoting("é"));
+        assert!(!needs_quoting(&"a-b/c.d".repeat(20)));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("").to_string(), "''");
+        assert_eq!(quote("abc").to_string(), "abc");
+        assert_eq!(quote("a b").to_string(), "'a b'");
+        assert_eq!(quote("'").to_string(), "''\\'''");
+        assert_eq!(quote("$(rm -rf ~)").to_string(), "'$(rm -rf ~)'");
+        assert_eq!(quote("don't").to_string(), "'don'\\''t'");
+        assert_eq!(quote("é\n").to_string(), "'é\n'");
+        assert_eq!(format!("{:>6}", quote("a b")), " 'a b'");
+        assert_eq!(
+            quote(&"it's ".repeat(20)).to_string(),
+            format!("'{}'", "it'\\''s ".repeat(20))
+        );
+
+        #[cfg(feature = "alloc")]
+        for s in ["", "abc", "a'b", "x y"] {
+            assert_eq!(super::quote_cow(s), quote(s).to_string());
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("shell-single-quoted").unwrap();
+        escaper.escape_to("a'b", &mut out).unwrap();
+        assert_eq!(out, "a'\\''b");
+    }
+}
@@ -0,0 +1,286 @@
+//! Quoting of the Windows command lines
+//!
+//! A Windows program gets its command line as a single string and splits it
+//! itself, most of them with the rules of the C runtime of MSVC, the ones of
+//! `CommandLineToArgvW` too. [`quote_arg`] quotes a string as an argument of
+//! those rules.
+//!
+//! A command line run by `cmd /c` is read by `cmd.exe` first,
+//! [`quote_cmd_arg`] escapes the quoted argument for it with `^`. The lines of
+//! a `.bat` file aren't covered, a `%` there is written `%%` and `^%` doesn't
+//! escape it. PowerShell has its own strings, [`quote_powershell`] writes a
+//! string between its single quotes
+use core::fmt::{self, Display, Formatter};
+
+#[inline]
+fn write_slashes(fmt: &mut Formatter, n: usize) -> fmt::Result {
+    (0..n).try_for_each(|_| fmt.write_str("\\"))
+}
+
+/// `s` quoted of [`quote_arg`]
+pub struct WindowsArg<'a> {
+    s: &'a str,
+}
+
+impl<'a> WindowsArg<'a> {
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        let s = self.s;
+        if !s.is_empty() && crate::blank::find_first_escape(s).is_none() {
+            return fmt.write_str(s);
+        }
+
+        fmt.write_str("\"")?;
+        let bytes = s.as_bytes();
+        let mut start = 0;
+        while let Some(i) = crate::slashes::find_first_escape_bytes(&bytes[start..]) {
+            let i = start + i;
+            let end = i + bytes[i..].iter().take_while(|&&b| b == b'\\').count();
+            fmt.write_str(&s[start..i])?;
+            // The backslashes are only escapes before a `"`, the closing
+            // one too
+            match bytes.get(end) {
+                Some(b'"') => {
+                    write_slashes(fmt, 2 * (end - i) + 1)?;
+                    fmt.write_str("\"")?;
+                    start = end + 1;
+                }
+                None => {
+                    write_slashes(fmt, 2 * (end - i))?;
+                    start = end;
+                }
+                Some(_) => {
+                    write_slashes(fmt, end - i)?;
+                    start = end;
+                }
+            }
+        }
+        fmt.write_str(&s[start..])?;
+        fmt.write_str("\"")
+    }
+}
+
+impl<'a> Display for WindowsArg<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Quote `s` as a single argument of a Windows command line, split by the
+/// rules of the C runtime of MSVC
+///
+/// It is quoted when it is empty or has a space, a tab, a newline or a `"`,
+/// the backslashes are only doubled before a `"`
+///
+/// ```
+/// use v_shellescape::windows::quote_arg;
+///
+/// assert_eq!(quote_arg("C:\\Temp\\a.txt").to_string(), "C:\\Temp\\a.txt");
+/// assert_eq!(quote_arg("say \"hi\"").to_string(), "\"say \\\"hi\\\"\"");
+/// ```
+#[inline]
+pub fn quote_arg(s: &str) -> WindowsArg<'_> {
+    WindowsArg { s }
+}
+
+/// `s` quoted of [`quote_cmd_arg`]
+pub struct CmdArg<'a> {
+    s: &'a str,
+}
+
+impl<'a> CmdArg<'a> {
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        // Escaped as it is written, without an intermediate buffer
+        struct Caret<'a, 'b>(&'a mut Formatter<'b>);
+
+        impl fmt::Write for Caret<'_, '_> {
+            #[inline]
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                Display::fmt(&crate::escape_cmd(s), self.0)
+            }
+        }
+
+        fmt::write(&mut Caret(fmt), format_args!("{}", quote_arg(self.s)))
+    }
+}
+
+impl<'a> Display for CmdArg<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Quote `s` as [`quote_arg`] and escape it with `^` for the command line of
+/// `cmd /c`
+///
+/// Every metacharacter is escaped, the `"` too, so `cmd` never sees the
+/// quotes and the argument can't end them. It isn't for the lines of a `.bat`
+/// file, their `%` can't be escaped with `^`
+///
+/// ```
+/// use v_shellescape::windows::quote_cmd_arg;
+///
+/// assert_eq!(quote_cmd_arg("%PATH%").to_string(), "^%PATH^%");
+/// ```
+#[inline]
+pub fn quote_cmd_arg(s: &str) -> CmdArg<'_> {
+    CmdArg { s }
+}
+
+/// `s` quoted of [`quote_powershell`]
+pub struct PowerShellQuote<'a> {
+    s: &'a str,
+}
+
+impl<'a> PowerShellQuote<'a> {
+    fn write(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.write_str("'")?;
+        v_escape::scan::escape_found(
+            self.s,
+            fmt,
+            crate::quotes::find_first_escape_bytes,
+            |s, i, fmt| {
+                let c = v_escape::scan::char_at(s, i);
+                let end = i + c.len_utf8();
+                fmt.write_str(&s[i..end])?;
+                // A quote is doubled, whichever of them it is
+                if let '\'' | '\u{2018}'..='\u{201B}' = c {
+                    fmt.write_str(&s[i..end])?;
+                }
+                Ok(end)
+            },
+        )?;
+        fmt.write_str("'")
+    }
+}
+
+impl<'a> Display for PowerShellQuote<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Quote `s` as a single quoted string of PowerShell, where `$` and `` ` ``
+/// are taken as they are
+///
+/// ```
+/// use v_shellescape::windows::quote_powershell;
+///
+/// assert_eq!(quote_powershell("$env:PATH").to_string(), "'$env:PATH'");
+/// assert_eq!(quote_powershell("it’s").to_string(), "'it’’s'");
+/// ```
+#[inline]
+pub fn quote_powershell(s: &str) -> PowerShellQuote<'_> {
+    PowerShellQuote { s }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{quote_arg, quote_cmd_arg, quote_powershell};
+
+    /// Arguments of `cmdline` split as the C runtime of MSVC does
+    fn split_args(cmdline: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut chars = cmdline.chars().peekable();
+        loop {
+            while let Some(' ' | '\t' | '\n' | '\u{b}') = chars.peek() {
+                chars.next();
+            }
+            if chars.peek().is_none() {
+                return args;
+            }
+            let mut arg = String::new();
+            let mut quoted = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        let mut n = 1;
+                        while chars.next_if_eq(&'\\').is_some() {
+                            n += 1;
+                        }
+                        if chars.peek() == Some(&'"') {
+                            arg.push_str(&"\\".repeat(n / 2));
+                            if n % 2 == 1 {
+                                arg.push('"');
+                                chars.next();
+                            }
+                        } else {
+                            arg.push_str(&"\\".repeat(n));
+                        }
+                    }
+                    '"' => quoted = !quoted,
+                    ' ' | '\t' | '\n' | '\u{b}' if !quoted => break,
+                    c => arg.push(c),
+                }
+            }
+            args.push(arg);
+        }
+    }
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("").to_string(), "\"\"");
+        assert_eq!(quote_arg("a\\b").to_string(), "a\\b");
+        assert_eq!(quote_arg("a b\\").to_string(), "\"a b\\\\\"");
+        assert_eq!(quote_arg("\\\"").to_string(), "\"\\\\\\\"\"");
+        assert_eq!(quote_arg("a\\\\b c").to_string(), "\"a\\\\b c\"");
+        assert_eq!(format!("{:>5}", quote_arg("a b")), "\"a b\"");
+
+        let args = [
+            "",
+            "plain",
+            "two words",
+            "\"",
+            "\\",
+            "a\\\\\"b\\ c\\\\",
+            "tab\there\\",
+            "é \"😀\" \\\\",
+            &"\\\" x".repeat(20),
+        ];
+        let cmdline: Vec<_> = args.iter().map(|a| quote_arg(a).to_string()).collect();
+        assert_eq!(split_args(&cmdline.join(" ")), args);
+    }
+
+    #[test]
+    fn test_quote_cmd_arg() {
+        assert_eq!(quote_cmd_arg("abc").to_string(), "abc");
+        assert_eq!(
+            quote_cmd_arg("a & b | \"c\"").to_string(),
+            "^\"a ^& b ^| \\^\"c\\^\"^\""
+        );
+        assert_eq!(quote_cmd_arg("!x^(<y>)%").to_string(), "^!x^^^(^<y^>^)^%");
+        assert_eq!(
+            quote_cmd_arg(&"a&".repeat(40)).to_string(),
+            "a^&".repeat(40)
+        );
+    }
+
+    #[test]
+    fn test_quote_powershell() {
+        assert_eq!(quote_powershell("").to_string(), "''");
+        assert_eq!(quote_powershell("a'b").to_string(), "'a''b'");
+        assert_eq!(
+            quote_powershell("‘a’ ‚b‛ “c” é").to_string(),
+            "'‘‘a’’ ‚‚b‛‛ “c” é'"
+        );
+        assert_eq!(quote_powershell("`$x").to_string(), "'`$x'");
+        assert_eq!(
+            quote_powershell(&"it's é ".repeat(20)).to_string(),
+            format!("'{}'", "it''s é ".repeat(20))
+        );
+    }
+}
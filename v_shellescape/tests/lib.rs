@@ -0,0 +1,58 @@
+use v_shellescape::{escape, needs_quoting, quote, ShellEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(ShellEscape::from("").to_string(), "");
+    assert_eq!(ShellEscape::from("foo").to_string(), "foo");
+    assert_eq!(escape("a'b'").to_string(), "a'\\''b'\\''");
+    assert_eq!(escape(&"'".repeat(64)).to_string(), "'\\''".repeat(64));
+}
+
+#[test]
+fn test_quote() {
+    let safe = "ABCXYZabcxyz0189_@%+=:,./-";
+    assert!(!needs_quoting(safe));
+    assert_eq!(quote(safe).to_string(), safe);
+
+    for unsafe_char in " \t\n!\"#$&'()*;<>?[\\]^`{|}~\u{7f}é😀".chars() {
+        for len in [0, 15, 16, 31, 32, 63, 64, 200] {
+            let s = format!("{}{}{}", "a".repeat(len), unsafe_char, "b".repeat(len));
+            assert!(needs_quoting(&s), "{:?} at {}", unsafe_char, len);
+            let expected = format!("'{}'", s.replace('\'', "'\\''"));
+            assert_eq!(quote(&s).to_string(), expected);
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_sh() {
+    use std::process::Command;
+
+    let words = [
+        "",
+        "plain",
+        "two words",
+        "it's",
+        "''",
+        "$HOME `id` $(id) \\ \" * ? ~ ! # ;&|<>",
+        "new\nline\ttab",
+        "ünï😀",
+    ];
+    let script: Vec<_> = words
+        .iter()
+        .map(|w| format!("printf '%s\\0' {}", quote(w)))
+        .collect();
+    let out = match Command::new("sh").arg("-c").arg(script.join("\n")).output() {
+        Ok(out) => out,
+        // No `sh` to run
+        Err(_) => return,
+    };
+    assert!(out.status.success());
+    let printed: Vec<_> = out
+        .stdout
+        .split(|&b| b == 0)
+        .map(|w| String::from_utf8(w.to_vec()).unwrap())
+        .collect();
+    assert_eq!(printed[..words.len()], words);
+}
@@ -0,0 +1,129 @@
+//! Escaping with the dialect chosen at runtime
+//!
+//! The escapes of a literal depend on the database and on its settings, as
+//! `standard_conforming_strings` of PostgreSQL or `NO_BACKSLASH_ESCAPES` of
+//! MySQL, which a tool showing queries of several databases learns at runtime
+use core::fmt::{self, Display};
+
+use crate::{mysql, postgres, SQLEscape, SQLMySQLEscape, SQLPostgresEscape};
+
+/// Dialect of the string literal the escaped text is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// The standard literal, `'...'`, as `escape`
+    Standard,
+    /// The escape string literal of PostgreSQL, `E'...'`, as `escape_postgres`
+    Postgres,
+    /// A literal of MySQL with its backslash escapes, as `escape_mysql`
+    MySQL,
+}
+
+impl Dialect {
+    /// Escaper of this dialect, to choose it at runtime
+    #[inline]
+    pub fn escaper(self) -> &'static dyn v_escape::Escaper {
+        match self {
+            Dialect::Standard => crate::ESCAPER,
+            Dialect::Postgres => postgres::ESCAPER,
+            Dialect::MySQL => mysql::ESCAPER,
+        }
+    }
+
+    /// Opening quote of a literal of this dialect, the closing one is `'`
+    #[inline]
+    pub fn open_quote(self) -> &'static str {
+        match self {
+            Dialect::Postgres => "E'",
+            Dialect::Standard | Dialect::MySQL => "'",
+        }
+    }
+}
+
+/// `s` escaped for a dialect, the escaping happens when it is formatted
+pub enum DialectEscape<'a> {
+    Standard(SQLEscape<'a>),
+    Postgres(SQLPostgresEscape<'a>),
+    MySQL(SQLMySQLEscape<'a>),
+}
+
+impl Display for DialectEscape<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DialectEscape::Standard(e) => e.fmt(fmt),
+            DialectEscape::Postgres(e) => e.fmt(fmt),
+            DialectEscape::MySQL(e) => e.fmt(fmt),
+        }
+    }
+}
+
+/// Escape `s` for `dialect`
+#[inline]
+pub fn escape(s: &str, dialect: Dialect) -> DialectEscape<'_> {
+    match dialect {
+        Dialect::Standard => DialectEscape::Standard(crate::escape(s)),
+        Dialect::Postgres => DialectEscape::Postgres(postgres::escape(s)),
+        Dialect::MySQL => DialectEscape::MySQL(mysql::escape(s)),
+    }
+}
+
+/// Literal of [`quote`]
+pub struct DialectQuote<'a> {
+    s: &'a str,
+    dialect: Dialect,
+}
+
+impl<'a> DialectQuote<'a> {
+    #[inline]
+    fn write(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.dialect.open_quote())?;
+        escape(self.s, self.dialect).fmt(fmt)?;
+        fmt.write_str("'")
+    }
+}
+
+impl<'a> Display for DialectQuote<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if fmt.width().is_none() && fmt.precision().is_none() {
+            self.write(fmt)
+        } else {
+            v_escape::pad::pad(fmt, |fmt| self.write(fmt))
+        }
+    }
+}
+
+/// Write `s` as a string literal of `dialect`, with its quotes
+#[inline]
+pub fn quote(s: &str, dialect: Dialect) -> DialectQuote<'_> {
+    DialectQuote { s, dialect }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, quote, Dialect};
+
+    #[test]
+    fn test_escape() {
+        let s = "it's C:\\tmp\n";
+        for (dialect, e) in [
+            (Dialect::Standard, "it''s C:\\tmp\n"),
+            (Dialect::Postgres, "it''s C:\\\\tmp\\n"),
+            (Dialect::MySQL, "it\\'s C:\\\\tmp\\n"),
+        ] {
+            assert_eq!(escape(s, dialect).to_string(), e, "{:?}", dialect);
+            let mut out = String::new();
+            dialect.escaper().escape_to(s, &mut out).unwrap();
+            assert_eq!(out, e, "{:?}", dialect);
+            assert_eq!(
+                quote(s, dialect).to_string(),
+                format!("{}{}'", dialect.open_quote(), e),
+                "{:?}",
+                dialect
+            );
+        }
+        assert_eq!(quote("", Dialect::Postgres).to_string(), "E''");
+        assert_eq!(format!("{:>6}", quote("'", Dialect::Standard)), "  ''''");
+    }
+}
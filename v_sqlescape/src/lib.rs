@@ -0,0 +1,139 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_sqlescape::escape;
+//!
+//! assert_eq!(escape("O'Brien").to_string(), "O''Brien");
+//! ```
+//!
+//! The escapers write the text of a literal into a query shown in a log, a
+//! redacted statement or the output of `EXPLAIN`. A query run by a database
+//! binds its parameters instead, the escaping depends on the settings of the
+//! connection, which the escaped text can't know.
+//!
+//! `escape` is for the standard string literal, `'...'`, where only `'` is
+//! escaped, as `''`. It is the literal of PostgreSQL with
+//! `standard_conforming_strings`, its default, of SQLite and of MySQL with
+//! `NO_BACKSLASH_ESCAPES`.
+//!
+//! `escape_postgres` is for the escape string literal of PostgreSQL,
+//! `E'...'`, where `\` is escaped as `\\` too and the control characters with
+//! their short escapes, and `escape_mysql` for a literal of MySQL, which reads
+//! the escapes of `\` by default. It escapes as `mysql_real_escape_string`.
+//!
+//! `dialect::escape` and `dialect::quote` choose the dialect at runtime, the
+//! second writes the quotes of the literal too:
+//!
+//! ```rust
+//! use v_sqlescape::dialect::{quote, Dialect};
+//!
+//! assert_eq!(quote("a\\b\n", Dialect::Postgres).to_string(), "E'a\\\\b\\n'");
+//! assert_eq!(quote("it's", Dialect::MySQL).to_string(), "'it\\'s'");
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(SQLEscape, "0x27->''", $($t)*);
+
+        // The escapes of an `E'...'` literal, `'` kept as `''` as it is in
+        // the standard literal
+        v_escape::new!(
+            SQLPostgresEscape,
+            "0x08->\\b || 0x09->\\t || 0x0A->\\n || 0x0C->\\f || 0x0D->\\r || 0x27->'' || \
+             0x5C->\\\\",
+            module = postgres,
+            $($t)*
+        );
+
+        /// Escape `s` for an escape string literal of PostgreSQL, `E'...'`
+        #[inline]
+        pub fn escape_postgres(s: &str) -> SQLPostgresEscape<'_> {
+            postgres::escape(s)
+        }
+
+        v_escape::new!(
+            SQLMySQLEscape,
+            "0x00->\\0 || 0x0A->\\n || 0x0D->\\r || 0x1A->\\Z || 0x22->\\\" || 0x27->\\' || \
+             0x5C->\\\\",
+            module = mysql,
+            $($t)*
+        );
+
+        /// Escape `s` for a string literal of MySQL, as
+        /// `mysql_real_escape_string`
+        #[inline]
+        pub fn escape_mysql(s: &str) -> SQLMySQLEscape<'_> {
+            mysql::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_sqlescape_simd, v_sqlescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_sqlescape_simd, v_sqlescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+pub mod dialect;
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("sql", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"sql"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, escape_mysql, escape_postgres};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("a\"b\\c\n é").to_string(), "a\"b\\c\n é");
+        assert_eq!(escape("'';").to_string(), "'''';");
+        assert_eq!(format!("{:>4}", escape("'")), "  ''");
+    }
+
+    #[test]
+    fn test_escape_postgres() {
+        assert_eq!(escape_postgres("a\"b%_ é").to_string(), "a\"b%_ é");
+        assert_eq!(
+            escape_postgres("\u{8}\t\n\u{c}\r'\\").to_string(),
+            "\\b\\t\\n\\f\\r''\\\\"
+        );
+        assert_eq!(escape_postgres("\u{1}\u{b}").to_string(), "\u{1}\u{b}");
+    }
+
+    #[test]
+    fn test_escape_mysql() {
+        assert_eq!(escape_mysql("a\tb%_ é").to_string(), "a\tb%_ é");
+        assert_eq!(
+            escape_mysql("\0\n\r\u{1a}\"'\\").to_string(),
+            "\\0\\n\\r\\Z\\\"\\'\\\\"
+        );
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("sql").unwrap();
+        escaper.escape_to("a'", &mut out).unwrap();
+        assert_eq!(out, "a''");
+    }
+}
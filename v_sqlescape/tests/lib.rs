@@ -0,0 +1,41 @@
+use v_sqlescape::{escape, escape_mysql, escape_postgres, SQLEscape};
+
+#[test]
+fn test_escape() {
+    assert_eq!(SQLEscape::from("a'b").to_string(), "a''b");
+
+    let all: String = (0..0x80u8).map(char::from).chain("é😀".chars()).collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+
+        assert_eq!(escape(&s).to_string(), s.replace('\'', "''"), "{}", len);
+
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\u{8}' => "\\b".into(),
+                '\t' => "\\t".into(),
+                '\n' => "\\n".into(),
+                '\u{c}' => "\\f".into(),
+                '\r' => "\\r".into(),
+                '\'' => "''".into(),
+                '\\' => "\\\\".into(),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape_postgres(&s).to_string(), expected, "{}", len);
+
+        let expected: String = s
+            .chars()
+            .map(|c| match c {
+                '\0' => "\\0".into(),
+                '\n' => "\\n".into(),
+                '\r' => "\\r".into(),
+                '\u{1a}' => "\\Z".into(),
+                '"' | '\'' | '\\' => format!("\\{}", c),
+                c => c.to_string(),
+            })
+            .collect();
+        assert_eq!(escape_mysql(&s).to_string(), expected, "{}", len);
+    }
+}
@@ -0,0 +1,113 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_tomlescape::escape;
+//!
+//! assert_eq!(escape("C:\\tmp \"a\"\n").to_string(), "C:\\\\tmp \\\"a\\\"\\n");
+//! ```
+//!
+//! `escape` is for the text between the quotes of a basic string, `"..."`.
+//! `\` and `"` are escaped with a `\`, and the control characters and `DEL`,
+//! which can't be in a string as they are, as `\b`, `\t`, `\n`, `\f`, `\r` or
+//! `\uXXXX`.
+//!
+//! A literal string, `'...'`, has no escapes, `can_be_literal` checks if a
+//! string can be written as one, as a path with backslashes reads better:
+//!
+//! ```rust
+//! use v_tomlescape::can_be_literal;
+//!
+//! assert!(can_be_literal("C:\\Users\\me"));
+//! assert!(!can_be_literal("it's"));
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            TOMLEscape,
+            "0x00-0x07->\\u{:04X} || 0x08->\\b || 0x09->\\t || 0x0A->\\n || \
+             0x0B->\\u000B || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1F->\\u{:04X} || \
+             0x22->\\\" || 0x5C->\\\\ || 0x7F->\\u007F",
+            $($t)*
+        );
+    };
+}
+
+// The bytes a literal string can't have, only found, never escaped
+macro_rules! build_not_literal {
+    ($($t:tt)*) => {
+        v_escape::finder!(not_literal, "0x00-0x08 || 0x0A-0x1F || 0x27 || 0x7F", $($t)*);
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_tomlescape_simd, v_tomlescape_avx))] {
+        build!(simd = true, avx = true);
+        build_not_literal!(simd = true, avx = true);
+    } else if #[cfg(all(v_tomlescape_simd, v_tomlescape_sse))] {
+        build!(simd = true, avx = false);
+        build_not_literal!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+        build_not_literal!(simd = false);
+    }
+}
+
+/// Whether `s` can be written as it is between the `'` of a literal string,
+/// it has no `'` and no control character but tab
+#[inline]
+pub fn can_be_literal(s: &str) -> bool {
+    not_literal::find_first_escape(s).is_none()
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("toml", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"toml"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{can_be_literal, escape};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("key = 'value' é").to_string(), "key = 'value' é");
+        assert_eq!(
+            escape("\0\u{8}\t\n\u{b}\u{c}\r\u{1f}\u{7f}").to_string(),
+            "\\u0000\\b\\t\\n\\u000B\\f\\r\\u001F\\u007F"
+        );
+        assert_eq!(escape("\"\\").to_string(), "\\\"\\\\");
+    }
+
+    #[test]
+    fn test_can_be_literal() {
+        assert!(can_be_literal(""));
+        assert!(can_be_literal("a\tb \"c\" \\d é"));
+        for b in (0u8..0x20).chain([b'\'', 0x7F]).filter(|&b| b != b'\t') {
+            let s = format!("{}{}", "a".repeat(40), char::from(b));
+            assert!(!can_be_literal(&s));
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("toml").unwrap();
+        escaper.escape_to("a\"", &mut out).unwrap();
+        assert_eq!(out, "a\\\"");
+    }
+}
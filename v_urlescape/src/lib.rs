@@ -0,0 +1,143 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_urlescape::escape;
+//!
+//! assert_eq!(escape("a&b c/é").to_string(), "a%26b%20c%2F%C3%A9");
+//! ```
+//!
+//! The bytes are percent-encoded as `%XX`, with the encode sets of the URL
+//! standard of WHATWG. `escape` uses the component set, it leaves only the
+//! ascii alphanumerics and `` !'()*-._~ `` as they are, as `encodeURIComponent`
+//! of JavaScript:
+//!
+//! * `escape_path_segment`, for a segment of the path, keeps `/`, `\` and `%`
+//!   escaped but not `` !$&'()*+,:;=@[]^| ``, a `\` is a `/` in the path of
+//!   the special schemes, as `http` or `file`
+//! * `escape_query` and `escape_fragment` only escape what would end them or
+//!   can't be in a URL, a `%` is taken as already encoded
+//! * `escape_form` writes `application/x-www-form-urlencoded`, the space is
+//!   `+` and every byte but the alphanumerics and `*-._` is encoded
+//!
+//! ```rust
+//! use v_urlescape::{escape_form, escape_path_segment, escape_query};
+//!
+//! assert_eq!(escape_path_segment("a b/c?").to_string(), "a%20b%2Fc%3F");
+//! assert_eq!(escape_query("a=1&b=é#").to_string(), "a=1&b=%C3%A9%23");
+//! assert_eq!(escape_form("a b+c!").to_string(), "a+b%2Bc%21");
+//! ```
+//!
+//! `set::escape` chooses the set at runtime.
+#![no_std]
+
+// https://url.spec.whatwg.org/#percent-encoded-bytes
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            URLEscape,
+            "0x00-0x20->%{:02X} || 0x22-0x26->%{:02X} || 0x2B-0x2C->%{:02X} || \
+             0x2F->%{:02X} || 0x3A-0x40->%{:02X} || 0x5B-0x5E->%{:02X} || 0x60->%{:02X} || \
+             0x7B-0x7D->%{:02X} || 0x7F-0xFF->%{:02X}",
+            $($t)*
+        );
+
+        v_escape::new!(
+            URLPathSegmentEscape,
+            "0x00-0x20->%{:02X} || 0x22-0x23->%{:02X} || 0x25->%{:02X} || 0x2F->%{:02X} || \
+             0x3C->%{:02X} || 0x3E-0x3F->%{:02X} || 0x5C->%{:02X} || 0x60->%{:02X} || \
+             0x7B->%{:02X} || 0x7D->%{:02X} || 0x7F-0xFF->%{:02X}",
+            module = path_segment,
+            $($t)*
+        );
+
+        /// Escape `s` as a segment of the path, between two `/`
+        #[inline]
+        pub fn escape_path_segment(s: &str) -> URLPathSegmentEscape<'_> {
+            path_segment::escape(s)
+        }
+
+        v_escape::new!(
+            URLQueryEscape,
+            "0x00-0x20->%{:02X} || 0x22-0x23->%{:02X} || 0x3C->%{:02X} || 0x3E->%{:02X} || \
+             0x7F-0xFF->%{:02X}",
+            module = query,
+            $($t)*
+        );
+
+        /// Escape `s` as the query, after the `?`
+        #[inline]
+        pub fn escape_query(s: &str) -> URLQueryEscape<'_> {
+            query::escape(s)
+        }
+
+        v_escape::new!(
+            URLFragmentEscape,
+            "0x00-0x20->%{:02X} || 0x22->%{:02X} || 0x3C->%{:02X} || 0x3E->%{:02X} || \
+             0x60->%{:02X} || 0x7F-0xFF->%{:02X}",
+            module = fragment,
+            $($t)*
+        );
+
+        /// Escape `s` as the fragment, after the `#`
+        #[inline]
+        pub fn escape_fragment(s: &str) -> URLFragmentEscape<'_> {
+            fragment::escape(s)
+        }
+
+        v_escape::new!(
+            URLFormEscape,
+            "0x00-0x1F->%{:02X} || 0x20->+ || 0x21-0x29->%{:02X} || 0x2B-0x2C->%{:02X} || \
+             0x2F->%{:02X} || 0x3A-0x40->%{:02X} || 0x5B-0x5E->%{:02X} || 0x60->%{:02X} || \
+             0x7B-0xFF->%{:02X}",
+            module = form,
+            $($t)*
+        );
+
+        /// Escape `s` as a name or a value of `application/x-www-form-urlencoded`
+        #[inline]
+        pub fn escape_form(s: &str) -> URLFormEscape<'_> {
+            form::escape(s)
+        }
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_urlescape_simd, v_urlescape_avx))] {
+        build!(simd = true, avx = true);
+    } else if #[cfg(all(v_urlescape_simd, v_urlescape_sse))] {
+        build!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+    }
+}
+
+pub mod set;
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("url", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"url"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("url").unwrap();
+        escaper.escape_to("a b", &mut out).unwrap();
+        assert_eq!(out, "a%20b");
+    }
+}
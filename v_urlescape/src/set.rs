@@ -0,0 +1,130 @@
+//! Escaping with the encode set chosen at runtime
+use core::fmt::{self, Display};
+
+use crate::{
+    form, fragment, path_segment, query, URLEscape, URLFormEscape, URLFragmentEscape,
+    URLPathSegmentEscape, URLQueryEscape,
+};
+
+/// Encode set of the part of the URL the escaped text is written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EncodeSet {
+    /// Any part, as `escape`
+    Component,
+    /// A segment of the path, as `escape_path_segment`
+    PathSegment,
+    /// The query, as `escape_query`
+    Query,
+    /// The fragment, as `escape_fragment`
+    Fragment,
+    /// A form name or value, as `escape_form`
+    Form,
+}
+
+impl EncodeSet {
+    /// Escaper of this set, to choose it at runtime
+    #[inline]
+    pub fn escaper(self) -> &'static dyn v_escape::Escaper {
+        match self {
+            EncodeSet::Component => crate::ESCAPER,
+            EncodeSet::PathSegment => path_segment::ESCAPER,
+            EncodeSet::Query => query::ESCAPER,
+            EncodeSet::Fragment => fragment::ESCAPER,
+            EncodeSet::Form => form::ESCAPER,
+        }
+    }
+}
+
+/// `s` escaped for an encode set, the escaping happens when it is formatted
+pub enum SetEscape<'a> {
+    Component(URLEscape<'a>),
+    PathSegment(URLPathSegmentEscape<'a>),
+    Query(URLQueryEscape<'a>),
+    Fragment(URLFragmentEscape<'a>),
+    Form(URLFormEscape<'a>),
+}
+
+impl Display for SetEscape<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetEscape::Component(e) => e.fmt(fmt),
+            SetEscape::PathSegment(e) => e.fmt(fmt),
+            SetEscape::Query(e) => e.fmt(fmt),
+            SetEscape::Fragment(e) => e.fmt(fmt),
+            SetEscape::Form(e) => e.fmt(fmt),
+        }
+    }
+}
+
+/// Escape `s` with `set`
+///
+/// ```
+/// use v_urlescape::set::{escape, EncodeSet};
+///
+/// assert_eq!(escape("a b", EncodeSet::Component).to_string(), "a%20b");
+/// assert_eq!(escape("a b", EncodeSet::Form).to_string(), "a+b");
+/// ```
+#[inline]
+pub fn escape(s: &str, set: EncodeSet) -> SetEscape<'_> {
+    match set {
+        EncodeSet::Component => SetEscape::Component(crate::escape(s)),
+        EncodeSet::PathSegment => SetEscape::PathSegment(path_segment::escape(s)),
+        EncodeSet::Query => SetEscape::Query(query::escape(s)),
+        EncodeSet::Fragment => SetEscape::Fragment(fragment::escape(s)),
+        EncodeSet::Form => SetEscape::Form(form::escape(s)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, EncodeSet};
+
+    const SETS: [EncodeSet; 5] = [
+        EncodeSet::Component,
+        EncodeSet::PathSegment,
+        EncodeSet::Query,
+        EncodeSet::Fragment,
+        EncodeSet::Form,
+    ];
+
+    /// Whether `b` is encoded by `set`, with the sets of the URL standard
+    fn encoded(set: EncodeSet, b: u8) -> bool {
+        let c0 = !(0x20..=0x7E).contains(&b);
+        let fragment = c0 || b" \"<>`".contains(&b);
+        let query = c0 || b" \"#<>".contains(&b);
+        let path = query || b"?`{}".contains(&b);
+        let userinfo = path || b"/:;=@[\\]^|".contains(&b);
+        let component = userinfo || b"$%&+,".contains(&b);
+        match set {
+            EncodeSet::Component => component,
+            EncodeSet::PathSegment => path || b"/%\\".contains(&b),
+            EncodeSet::Query => query,
+            EncodeSet::Fragment => fragment,
+            EncodeSet::Form => component || b"!'()~".contains(&b),
+        }
+    }
+
+    #[test]
+    fn test_sets() {
+        for set in SETS {
+            for b in 0..0x80u8 {
+                let s = char::from(b).to_string();
+                let expected = if set == EncodeSet::Form && b == b' ' {
+                    "+".into()
+                } else if encoded(set, b) {
+                    format!("%{:02X}", b)
+                } else {
+                    s.clone()
+                };
+                assert_eq!(escape(&s, set).to_string(), expected, "{:?} {:?}", set, s);
+                let mut out = String::new();
+                set.escaper().escape_to(&s, &mut out).unwrap();
+                assert_eq!(out, expected, "{:?} {:?}", set, s);
+            }
+            assert_eq!(escape("é😀", set).to_string(), "%C3%A9%F0%9F%98%80");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use v_urlescape::{escape, escape_form, escape_fragment, escape_path_segment, escape_query};
+
+/// `s` decoded back from `%XX`, and `+` as a space when `plus`
+fn decode(s: &str, plus: bool) -> String {
+    let mut out = vec![];
+    let b = s.as_bytes();
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'%' => {
+                out.push(u8::from_str_radix(&s[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            }
+            b'+' if plus => {
+                out.push(b' ');
+                i += 1;
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape("").to_string(), "");
+    assert_eq!(escape("azAZ09-._~!'()*").to_string(), "azAZ09-._~!'()*");
+    assert_eq!(escape("100%").to_string(), "100%25");
+    assert_eq!(escape_path_segment("a;b=c@d").to_string(), "a;b=c@d");
+    assert_eq!(escape_path_segment("..\\a/b").to_string(), "..%5Ca%2Fb");
+    assert_eq!(escape_fragment("#a `b`").to_string(), "#a%20%60b%60");
+
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = "a b/c?d#e&f=g+h%éi😀\u{1}"
+            .chars()
+            .cycle()
+            .take(len)
+            .collect();
+        for escaped in [
+            escape(&s).to_string(),
+            escape_path_segment(&s).to_string(),
+            escape_query(&s).to_string(),
+            escape_fragment(&s).to_string(),
+        ] {
+            assert!(escaped.is_ascii());
+            assert!(!escaped.contains(' '));
+        }
+        assert_eq!(decode(&escape(&s).to_string(), false), s);
+        assert_eq!(decode(&escape_path_segment(&s).to_string(), false), s);
+        assert_eq!(decode(&escape_form(&s).to_string(), true), s);
+    }
+}
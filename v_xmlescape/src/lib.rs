@@ -0,0 +1,172 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_xmlescape::{escape, escape_attr};
+//!
+//! assert_eq!(escape("a < b & c").to_string(), "a &lt; b &amp; c");
+//! assert_eq!(
+//!     escape_attr("'a'\n\"b\"").to_string(),
+//!     "&apos;a&apos;&#10;&quot;b&quot;"
+//! );
+//! ```
+//!
+//! `escape` is for the text of an element and `escape_attr` for the value of
+//! an attribute, between either quote. Both write the carriage returns as
+//! `&#13;`, a parser would turn them into newlines. The attribute values have
+//! their tabs and newlines escaped too, a parser would read them as spaces.
+//!
+//! The control characters but tab, newline and carriage return can't be in an
+//! XML 1.0 document, not even as references, both escapers write them as
+//! `U+FFFD`. `find_invalid` finds them, and the noncharacters `U+FFFE` and
+//! `U+FFFF`, to reject the strings that have them instead:
+//!
+//! ```rust
+//! use v_xmlescape::{escape, find_invalid};
+//!
+//! assert_eq!(escape("a\u{1}b").to_string(), "a\u{FFFD}b");
+//! assert_eq!(find_invalid("a\u{1}b"), Some(1));
+//! assert_eq!(find_invalid("a\tb"), None);
+//! ```
+#![no_std]
+
+macro_rules! build {
+    ($($t:tt)*) => {
+        v_escape::new!(
+            XMLEscape,
+            "0x00-0x08->\u{FFFD} || 0x0B-0x0C->\u{FFFD} || 0x0D->&#13; || \
+             0x0E-0x1F->\u{FFFD} || 38->&amp; || 60->&lt; || 62->&gt;",
+            $($t)*
+        );
+
+        v_escape::new!(
+            XMLAttrEscape,
+            "0x00-0x08->\u{FFFD} || 0x09->&#9; || 0x0A->&#10; || 0x0B-0x0C->\u{FFFD} || \
+             0x0D->&#13; || 0x0E-0x1F->\u{FFFD} || 34->&quot; || 38->&amp; || 39->&apos; || \
+             60->&lt; || 62->&gt;",
+            module = attr,
+            $($t)*
+        );
+
+        /// Escape `s` as the value of an attribute, between `"` or `'`
+        #[inline]
+        pub fn escape_attr(s: &str) -> XMLAttrEscape<'_> {
+            attr::escape(s)
+        }
+    };
+}
+
+// The control characters XML 1.0 doesn't allow, only found, never escaped
+macro_rules! build_invalid {
+    ($($t:tt)*) => {
+        v_escape::finder!(invalid, "0x00-0x08 || 0x0B-0x0C || 0x0E-0x1F", $($t)*);
+    };
+}
+
+/// Without simd optimizations
+pub mod fallback {
+    build!(simd = false);
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_xmlescape_simd, v_xmlescape_avx))] {
+        build!(simd = true, avx = true);
+        build_invalid!(simd = true, avx = true);
+    } else if #[cfg(all(v_xmlescape_simd, v_xmlescape_sse))] {
+        build!(simd = true, avx = false);
+        build_invalid!(simd = true, avx = false);
+    } else {
+        pub use self::fallback::*;
+        build_invalid!(simd = false);
+    }
+}
+
+/// Index of the first character of `s` that can't be in an XML 1.0 document,
+/// a control character but tab, newline and carriage return or one of the
+/// noncharacters `U+FFFE` and `U+FFFF`
+#[inline]
+pub fn find_invalid(s: &str) -> Option<usize> {
+    let control = invalid::find_first_escape(s);
+    // Both are `EF BF BE` and `EF BF BF`, only in the part before a control
+    let rest = &s[..control.unwrap_or(s.len())];
+    let nonchar = rest
+        .find('\u{FFFE}')
+        .into_iter()
+        .chain(rest.find('\u{FFFF}'))
+        .min();
+    nonchar.or(control)
+}
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("xml", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"xml"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::prelude::v1::*;
+    use super::{escape, escape_attr, find_invalid};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("foo").to_string(), "foo");
+        assert_eq!(
+            escape("<a href=\"x\">'&'</a>").to_string(),
+            "&lt;a href=\"x\"&gt;'&amp;'&lt;/a&gt;"
+        );
+        assert_eq!(escape("a\tb\nc\rd").to_string(), "a\tb\nc&#13;d");
+        assert_eq!(escape("a\r\nb").to_string(), "a&#13;\nb");
+        assert_eq!(
+            escape("\0\u{8}\u{b}\u{c}\u{e}\u{1f}").to_string(),
+            "\u{FFFD}".repeat(6)
+        );
+        assert_eq!(
+            escape(&"é<&>".repeat(20)).to_string(),
+            "é&lt;&amp;&gt;".repeat(20)
+        );
+    }
+
+    #[test]
+    fn test_escape_attr() {
+        assert_eq!(escape_attr("").to_string(), "");
+        assert_eq!(
+            escape_attr("<\"a\" & 'b'>").to_string(),
+            "&lt;&quot;a&quot; &amp; &apos;b&apos;&gt;"
+        );
+        assert_eq!(
+            escape_attr("a\tb\nc\rd\u{1}").to_string(),
+            "a&#9;b&#10;c&#13;d\u{FFFD}"
+        );
+        assert_eq!(
+            escape_attr(&"x'\n".repeat(30)).to_string(),
+            "x&apos;&#10;".repeat(30)
+        );
+    }
+
+    #[test]
+    fn test_find_invalid() {
+        assert_eq!(find_invalid(""), None);
+        assert_eq!(find_invalid("a\t\n\rb é 😀 \u{FFFD}"), None);
+        for b in (0u8..0x20).filter(|b| !b"\t\n\r".contains(b)) {
+            let s = format!("{}{}", "a".repeat(40), char::from(b));
+            assert_eq!(find_invalid(&s), Some(40), "{:#04x}", b);
+        }
+        assert_eq!(find_invalid("ab\u{FFFF}"), Some(2));
+        assert_eq!(find_invalid("é\u{FFFE}\u{1}"), Some(2));
+        assert_eq!(find_invalid("\u{1}\u{FFFE}"), Some(0));
+    }
+
+    #[test]
+    fn test_register() {
+        super::register();
+        let mut out = String::new();
+        let escaper = v_escape::registry().get("xml").unwrap();
+        escaper.escape_to("<a", &mut out).unwrap();
+        assert_eq!(out, "&lt;a");
+    }
+}
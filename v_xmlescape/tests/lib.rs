@@ -0,0 +1,33 @@
+use v_xmlescape::{escape, escape_attr, XMLAttrEscape, XMLEscape};
+
+#[test]
+fn test_escape() {
+    let escapes = "<>&\"'\t\n\r\u{0}";
+    assert_eq!(XMLEscape::from("").to_string(), "");
+    assert_eq!(
+        XMLEscape::from(escapes).to_string(),
+        "&lt;&gt;&amp;\"'\t\n&#13;\u{FFFD}"
+    );
+    assert_eq!(
+        XMLAttrEscape::from(escapes).to_string(),
+        "&lt;&gt;&amp;&quot;&apos;&#9;&#10;&#13;\u{FFFD}"
+    );
+
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = "a<b>c&d\"e'f\ng\r\u{1}é"
+            .chars()
+            .cycle()
+            .take(len)
+            .collect();
+        let text: String = s
+            .chars()
+            .map(|c| escape(&c.to_string()).to_string())
+            .collect();
+        let attr: String = s
+            .chars()
+            .map(|c| escape_attr(&c.to_string()).to_string())
+            .collect();
+        assert_eq!(escape(&s).to_string(), text, "{}", len);
+        assert_eq!(escape_attr(&s).to_string(), attr, "{}", len);
+    }
+}
@@ -0,0 +1,150 @@
+//! # Quick start
+//!
+//! ```rust
+//! use v_yamlescape::escape;
+//!
+//! assert_eq!(escape("say \"hi\"\n").to_string(), "say \\\"hi\\\"\\n");
+//! ```
+//!
+//! The strings are escaped to be written between the quotes of a double quoted
+//! scalar, with the escapes of YAML 1.2 that YAML 1.1 has too:
+//!
+//! * `\` and `"` as `\\` and `\"`
+//! * the control characters as `\0`, `\a`, `\b`, `\t`, `\n`, `\v`, `\f`,
+//!   `\r`, `\e` or `\xXX`, the C1 ones too and `U+0085` as `\N`
+//! * the line and paragraph separators, `U+2028` and `U+2029`, as `\L` and
+//!   `\P`, YAML 1.1 takes them as line breaks
+//! * the byte order mark and the noncharacters `U+FFFE` and `U+FFFF` as
+//!   `\uXXXX`
+//!
+//! ```rust
+//! use v_yamlescape::escape;
+//!
+//! assert_eq!(escape("a\u{2028}b\u{85}").to_string(), "a\\Lb\\N");
+//! ```
+//!
+//! The first bytes of the non-ascii characters are looked for with the ascii
+//! escaped characters, so the ascii runs between them are skipped with the
+//! simd loops of the escapers.
+#![no_std]
+
+use core::fmt::{self, Display, Formatter, Write};
+
+// The multi-byte characters are only looked for, `write_escaped` writes them
+macro_rules! build_escaped {
+    ($($t:tt)*) => {
+        v_escape::finder!(
+            escaped,
+            "0x00->\\0 || 0x01-0x06->\\x{:02X} || 0x07->\\a || 0x08->\\b || 0x09->\\t || \
+             0x0A->\\n || 0x0B->\\v || 0x0C->\\f || 0x0D->\\r || 0x0E-0x1A->\\x{:02X} || \
+             0x1B->\\e || 0x1C-0x1F->\\x{:02X} || 0x22->\\\" || 0x5C->\\\\ || \
+             0x7F->\\x7F || 0x80-0xBF || 0xC2-0xF4",
+            $($t)*
+        );
+    };
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(v_yamlescape_simd, v_yamlescape_avx))] {
+        build_escaped!(simd = true, avx = true);
+    } else if #[cfg(all(v_yamlescape_simd, v_yamlescape_sse))] {
+        build_escaped!(simd = true, avx = false);
+    } else {
+        build_escaped!(simd = false);
+    }
+}
+
+/// Escape of a non-ascii character, `None` when it is written as it is
+#[inline]
+fn escape_non_ascii(c: char, out: &mut (impl Write + ?Sized)) -> Option<fmt::Result> {
+    Some(match c {
+        '\u{85}' => out.write_str("\\N"),
+        '\u{80}'..='\u{9F}' => write!(out, "\\x{:02X}", c as u32),
+        '\u{2028}' => out.write_str("\\L"),
+        '\u{2029}' => out.write_str("\\P"),
+        '\u{FEFF}' | '\u{FFFE}' | '\u{FFFF}' => write!(out, "\\u{:04X}", c as u32),
+        _ => return None,
+    })
+}
+
+fn write_escaped(s: &str, out: &mut (impl Write + ?Sized)) -> fmt::Result {
+    v_escape::scan::escape_found_chars(
+        s,
+        out,
+        escaped::find_first_escape_bytes,
+        escaped::escape_byte,
+        escape_non_ascii,
+    )
+}
+
+/// Escaped `s` of [`escape`]
+pub struct YAMLEscape<'a> {
+    s: &'a str,
+}
+
+impl<'a> From<&'a str> for YAMLEscape<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        YAMLEscape { s }
+    }
+}
+
+impl<'a> Display for YAMLEscape<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        v_escape::pad::pad(fmt, |fmt| write_escaped(self.s, fmt))
+    }
+}
+
+/// Escape `s` to be written between the quotes of a double quoted scalar
+#[inline]
+pub fn escape(s: &str) -> YAMLEscape<'_> {
+    YAMLEscape { s }
+}
+
+/// Escaper of `escape`, to choose it at runtime
+pub static ESCAPER: &dyn v_escape::Escaper = &v_escape::FnEscaper(|s, out| write_escaped(s, out));
+
+static ENTRY: v_escape::registry::Entry = v_escape::registry::Entry::new("yaml", ESCAPER);
+
+/// Add this escaper to `v_escape::registry()` as `"yaml"`
+#[inline]
+pub fn register() {
+    v_escape::registry().register(&ENTRY);
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use self::std::{format, prelude::v1::*};
+    use super::{escape, ESCAPER};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("").to_string(), "");
+        assert_eq!(escape("foo: 'bar' # baz").to_string(), "foo: 'bar' # baz");
+        assert_eq!(
+            escape("\0\u{7}\u{8}\t\n\u{b}\u{c}\r\u{1b}\u{1}\u{1f}\u{7f}").to_string(),
+            "\\0\\a\\b\\t\\n\\v\\f\\r\\e\\x01\\x1F\\x7F"
+        );
+        assert_eq!(escape("\"\\").to_string(), "\\\"\\\\");
+        assert_eq!(
+            escape("\u{80}\u{85}\u{9f}\u{a0}é\u{2028}\u{2029}\u{feff}\u{fffe}\u{ffff}😀")
+                .to_string(),
+            "\\x80\\N\\x9F\u{a0}é\\L\\P\\uFEFF\\uFFFE\\uFFFF😀"
+        );
+        assert_eq!(format!("{:>4}", escape("\n")), "  \\n");
+    }
+
+    #[test]
+    fn test_escaper() {
+        let s: String = "a\"\u{2028}\u{1}é\\".repeat(20);
+        let mut out = String::new();
+        ESCAPER.escape_to(&s, &mut out).unwrap();
+        assert_eq!(out, escape(&s).to_string());
+        assert_eq!(ESCAPER.escaped_len(&s), out.len());
+
+        super::register();
+        assert!(v_escape::registry().get("yaml").is_some());
+    }
+}
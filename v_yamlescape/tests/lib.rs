@@ -0,0 +1,51 @@
+use v_yamlescape::{escape, YAMLEscape};
+
+/// `s` read back from the escapes of a double quoted scalar
+fn unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let hex = |chars: &mut std::str::Chars, n| {
+            let h: String = chars.take(n).collect();
+            char::from_u32(u32::from_str_radix(&h, 16).unwrap()).unwrap()
+        };
+        out.push(match chars.next().unwrap() {
+            '0' => '\0',
+            'a' => '\u{7}',
+            'b' => '\u{8}',
+            't' => '\t',
+            'n' => '\n',
+            'v' => '\u{b}',
+            'f' => '\u{c}',
+            'r' => '\r',
+            'e' => '\u{1b}',
+            'N' => '\u{85}',
+            'L' => '\u{2028}',
+            'P' => '\u{2029}',
+            'x' => hex(&mut chars, 2),
+            'u' => hex(&mut chars, 4),
+            c => c,
+        });
+    }
+    out
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(YAMLEscape::from("a\"b").to_string(), "a\\\"b");
+
+    let all: String = (0..0x80u8)
+        .map(char::from)
+        .chain("\u{85}\u{9b}\u{2028}\u{2029}\u{feff}é😀".chars())
+        .collect();
+    for len in [15, 16, 31, 32, 33, 63, 64, 65, 1024] {
+        let s: String = all.chars().cycle().take(len).collect();
+        let escaped = escape(&s).to_string();
+        assert!(!escaped.chars().any(|c| c.is_control() || c == '\u{2028}'));
+        assert_eq!(unescape(&escaped), s, "{}", len);
+    }
+}